@@ -0,0 +1,81 @@
+use crate::commands::config::notify_config_changed;
+use crate::models::backup::{BackupInfo, BackupKind, BackupStatus, RestoreReport, RestoreScope};
+use crate::services::backup::BackupService;
+use crate::services::config::ConfigService;
+use crate::error::{AppError, AppResult};
+use tauri::{AppHandle, Manager, State};
+
+// 定时备份检查间隔（秒），实际备份频率由配置中的 backup_interval 决定
+const BACKUP_CHECK_INTERVAL_SECS: u64 = 60;
+
+#[tauri::command]
+pub async fn list_backups(backup_service: State<'_, BackupService>) -> AppResult<Vec<BackupInfo>> {
+    backup_service.list_backups().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn create_backup(backup_service: State<'_, BackupService>) -> AppResult<BackupInfo> {
+    backup_service
+        .create_backup(BackupKind::Manual)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_backup(backup_service: State<'_, BackupService>, backup_id: String) -> AppResult<()> {
+    backup_service
+        .delete_backup(&backup_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 从备份恢复，默认整体恢复。整体恢复后重新加载配置并通知前端
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    backup_service: State<'_, BackupService>,
+    config_service: State<'_, ConfigService>,
+    backup_id: String,
+    scope: Option<RestoreScope>,
+) -> AppResult<RestoreReport> {
+    let report = backup_service
+        .restore_backup(&backup_id, scope.unwrap_or_default())
+        .await?;
+
+    if matches!(report.scope, RestoreScope::Full) {
+        let config = config_service.reload().await?;
+        notify_config_changed(&app, &config);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_backup_status(
+    backup_service: State<'_, BackupService>,
+    config_service: State<'_, ConfigService>,
+) -> AppResult<BackupStatus> {
+    let config = config_service.get_config().await?;
+    backup_service
+        .status(&config.general)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 后台定时备份。每次检查都重新读取配置，修改间隔或开关后无需重启
+pub fn spawn_scheduled_backups(app: AppHandle) {
+    let backup_service = app.state::<BackupService>().inner().clone();
+    let config_service = app.state::<ConfigService>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match config_service.get_config().await {
+                Ok(config) => match backup_service.run_scheduled(&config.general).await {
+                    Ok(Some(backup)) => println!("Created scheduled backup {}", backup.id),
+                    Ok(None) => {}
+                    Err(e) => println!("Scheduled backup failed: {}", e),
+                },
+                Err(e) => println!("Failed to load config for scheduled backup: {}", e),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(BACKUP_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
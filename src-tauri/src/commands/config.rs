@@ -1,49 +1,64 @@
-use crate::models::config::{AppConfig, AIProvider, MCPServer};
+use crate::models::config::{AppConfig, MCPServer};
 use crate::services::config::ConfigService;
+use crate::error::{AppError, AppResult};
+use tauri::{AppHandle, Emitter, State};
 
-#[tauri::command]
-pub async fn get_config() -> Result<AppConfig, String> {
-    ConfigService::get_config().await.map_err(|e| e.to_string())
+// 配置保存后向所有窗口广播，载荷为新的 AppConfig
+pub const CONFIG_CHANGED_EVENT: &str = "config-changed";
+
+pub fn notify_config_changed(app: &AppHandle, config: &AppConfig) {
+    if let Err(e) = app.emit(CONFIG_CHANGED_EVENT, config) {
+        println!("Failed to emit {}: {}", CONFIG_CHANGED_EVENT, e);
+    }
 }
 
 #[tauri::command]
-pub async fn save_config(config: AppConfig) -> Result<(), String> {
-    ConfigService::save_config(config)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn get_config(config_service: State<'_, ConfigService>) -> AppResult<AppConfig> {
+    config_service.get_config().await.map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn import_config(file_path: String) -> Result<AppConfig, String> {
-    ConfigService::import_config(&file_path)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn save_config(
+    app: AppHandle,
+    config_service: State<'_, ConfigService>,
+    config: AppConfig,
+) -> AppResult<()> {
+    let saved = config_service.save_config(config).await?;
+    notify_config_changed(&app, &saved);
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn export_config(config: AppConfig, file_path: String) -> Result<(), String> {
-    ConfigService::export_config(config, &file_path)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn import_config(
+    app: AppHandle,
+    config_service: State<'_, ConfigService>,
+    file_path: String,
+) -> AppResult<AppConfig> {
+    let config = config_service.import_config(&file_path).await?;
+    notify_config_changed(&app, &config);
+    Ok(config)
 }
 
 #[tauri::command]
-pub async fn test_ai_provider(provider: AIProvider) -> Result<bool, String> {
-    ConfigService::test_ai_provider(provider)
+pub async fn export_config(config: AppConfig, file_path: String) -> AppResult<()> {
+    ConfigService::export_config(config, &file_path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn test_mcp_server(server: MCPServer) -> Result<bool, String> {
+pub async fn test_mcp_server(server: MCPServer) -> AppResult<bool> {
     ConfigService::test_mcp_server(server)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn reset_config() -> Result<AppConfig, String> {
-    ConfigService::reset_config()
-        .await
-        .map_err(|e| e.to_string())
-}
\ No newline at end of file
+pub async fn reset_config(
+    app: AppHandle,
+    config_service: State<'_, ConfigService>,
+) -> AppResult<AppConfig> {
+    let config = config_service.reset_config().await?;
+    notify_config_changed(&app, &config);
+    Ok(config)
+}
@@ -1,38 +1,43 @@
-use crate::models::document::{Document, CreateDocumentData};
+use crate::models::document::{
+    Document, CreateDocumentData, DocumentListQuery, DocumentListResult, DocumentSearchResult, DocumentSummary,
+};
+use crate::models::revision::{DocumentRevision, DocumentRevisionDetail, RevisionDiff};
 use crate::services::database::Database;
+use crate::services::links;
+use crate::error::{AppError, AppResult};
 use tauri::State;
 
 #[tauri::command]
 pub async fn create_document(
     database: State<'_, Database>,
     document_data: CreateDocumentData,
-) -> Result<Document, String> {
+) -> AppResult<Document> {
     database
         .create_document(document_data)
         .await
-        .map_err(|e| format!("创建文档失败: {}", e))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_documents(
     database: State<'_, Database>,
     project_id: String,
-) -> Result<Vec<Document>, String> {
+) -> AppResult<Vec<DocumentSummary>> {
     database
         .get_documents_by_project(&project_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_document_by_id(
     database: State<'_, Database>,
     document_id: String,
-) -> Result<Option<Document>, String> {
+) -> AppResult<Option<Document>> {
     database
         .get_document_by_id(&document_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -40,22 +45,22 @@ pub async fn update_document(
     database: State<'_, Database>,
     document_id: String,
     document_data: Document,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .update_document(&document_id, document_data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn delete_document(
     database: State<'_, Database>,
     document_id: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .delete_document(&document_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -63,22 +68,33 @@ pub async fn save_document(
     database: State<'_, Database>,
     document_id: String,
     content: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .save_document_content(&document_id, &content)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_documents_by_project(
     database: State<'_, Database>,
     project_id: String,
-) -> Result<Vec<Document>, String> {
+) -> AppResult<Vec<DocumentSummary>> {
     database
         .get_documents_by_project(&project_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_documents(
+    database: State<'_, Database>,
+    query: Option<DocumentListQuery>,
+) -> AppResult<DocumentListResult> {
+    database
+        .list_documents(&query.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -86,9 +102,91 @@ pub async fn update_document_content(
     database: State<'_, Database>,
     document_id: String,
     content: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .save_document_content(&document_id, &content)
         .await
-        .map_err(|e| e.to_string())
-}
\ No newline at end of file
+        .map_err(AppError::from)
+}
+#[tauri::command]
+pub async fn list_document_revisions(
+    database: State<'_, Database>,
+    document_id: String,
+) -> AppResult<Vec<DocumentRevision>> {
+    database
+        .list_document_revisions(&document_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_document_revision(
+    database: State<'_, Database>,
+    document_id: String,
+    version: u32,
+) -> AppResult<Option<DocumentRevisionDetail>> {
+    database
+        .get_document_revision(&document_id, version)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn diff_document_revisions(
+    database: State<'_, Database>,
+    document_id: String,
+    from_version: u32,
+    to_version: Option<u32>,
+) -> AppResult<RevisionDiff> {
+    database
+        .diff_document_revisions(&document_id, from_version, to_version)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn restore_document_revision(
+    database: State<'_, Database>,
+    document_id: String,
+    version: u32,
+) -> AppResult<Document> {
+    database
+        .restore_document_revision(&document_id, version)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_documents(
+    database: State<'_, Database>,
+    query: Option<String>,
+    workspace_id: Option<String>,
+    project_id: Option<String>,
+    tags: Option<Vec<String>>,
+    status: Option<String>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> AppResult<DocumentSearchResult> {
+    database
+        .search_documents(
+            query.as_deref(),
+            workspace_id.as_deref(),
+            project_id.as_deref(),
+            &tags.unwrap_or_default(),
+            status.as_deref(),
+            page.unwrap_or(1),
+            page_size.unwrap_or(20),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// 读取 writeflow://attachment/ 链接指向的附件，返回 data URI 供编辑器预览
+#[tauri::command]
+pub async fn read_attachment(url: String) -> AppResult<String> {
+    tokio::task::spawn_blocking(move || links::attachment_data_uri(&url))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::from)
+}
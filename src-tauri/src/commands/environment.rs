@@ -1,42 +1,43 @@
 use crate::models::environment::{EnvironmentStatus, InstallationResult, EnvSummary};
 use crate::services::environment::EnvironmentService;
+use crate::error::{AppError, AppResult};
 
 #[tauri::command]
-pub async fn check_environment() -> Result<EnvironmentStatus, String> {
+pub async fn check_environment() -> AppResult<EnvironmentStatus> {
     let service = EnvironmentService::new();
     service.check_environment()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_environment_summary() -> Result<EnvSummary, String> {
+pub async fn get_environment_summary() -> AppResult<EnvSummary> {
     let service = EnvironmentService::new();
     service.get_summary()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn install_nodejs() -> Result<InstallationResult, String> {
+pub async fn install_nodejs() -> AppResult<InstallationResult> {
     let service = EnvironmentService::new();
     service.install_nodejs()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn install_writeflow_cli() -> Result<InstallationResult, String> {
+pub async fn install_writeflow_cli() -> AppResult<InstallationResult> {
     let service = EnvironmentService::new();
     service.install_writeflow_cli()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn repair_environment() -> Result<InstallationResult, String> {
+pub async fn repair_environment() -> AppResult<InstallationResult> {
     let service = EnvironmentService::new();
     service.repair_environment()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
@@ -0,0 +1,216 @@
+use crate::services::config::ConfigService;
+use crate::services::database::Database;
+use crate::services::{docx, epub, export, markdown, pdf};
+use crate::error::{AppError, AppResult};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+// 指定了输出路径时写入文件，目录不存在则创建
+async fn write_output(output_path: Option<&str>, contents: &[u8]) -> AppResult<()> {
+    let Some(path) = output_path.map(Path::new) else { return Ok(()) };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, contents).await?;
+    Ok(())
+}
+
+// 文档中的相对图片路径按导出文件所在目录解析
+fn image_base_dir(output_path: &str) -> Option<PathBuf> {
+    Path::new(output_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+}
+
+// 排版、字体子集化和打包比较耗时，放到阻塞线程池执行
+async fn render_blocking(render: impl FnOnce() -> anyhow::Result<Vec<u8>> + Send + 'static) -> AppResult<Vec<u8>> {
+    tokio::task::spawn_blocking(render)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::from)
+}
+
+/// 导出单个文档为 HTML，返回渲染结果
+#[tauri::command]
+pub async fn export_document_html(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    document_id: String,
+    output_path: Option<String>,
+) -> AppResult<String> {
+    let document = database
+        .get_document_by_id(&document_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Document", &document_id))?;
+    let config = config_service.get_config().await?;
+
+    let html = export::document_to_html(&document, &config.export.html_options);
+    write_output(output_path.as_deref(), html.as_bytes()).await?;
+    Ok(html)
+}
+
+/// 按目录顺序把整个项目导出为一个 HTML
+#[tauri::command]
+pub async fn export_project_html(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    project_id: String,
+    output_path: Option<String>,
+) -> AppResult<String> {
+    let project = database
+        .get_project_by_id(&project_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Project", &project_id))?;
+    let documents = database.get_project_documents_ordered(&project_id).await?;
+    let config = config_service.get_config().await?;
+
+    let html = export::project_to_html(&project, &documents, &config.export.html_options);
+    write_output(output_path.as_deref(), html.as_bytes()).await?;
+    Ok(html)
+}
+
+/// 导出单个文档为 PDF 并写入 output_path
+#[tauri::command]
+pub async fn export_document_pdf(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    document_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let document = database
+        .get_document_by_id(&document_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Document", &document_id))?;
+    let options = config_service.get_config().await?.export.pdf_options;
+
+    let base_dir = image_base_dir(&output_path);
+    let bytes = render_blocking(move || pdf::document_to_pdf(&document, &options, base_dir.as_deref())).await?;
+    write_output(Some(&output_path), &bytes).await
+}
+
+/// 按目录顺序把整个项目编排为一个 PDF 并写入 output_path
+#[tauri::command]
+pub async fn export_project_pdf(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    project_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let project = database
+        .get_project_by_id(&project_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Project", &project_id))?;
+    let documents = database.get_project_documents_ordered(&project_id).await?;
+    let options = config_service.get_config().await?.export.pdf_options;
+
+    let base_dir = image_base_dir(&output_path);
+    let bytes = render_blocking(move || pdf::project_to_pdf(&project, &documents, &options, base_dir.as_deref())).await?;
+    write_output(Some(&output_path), &bytes).await
+}
+
+/// 导出单个文档为 DOCX 并写入 output_path
+#[tauri::command]
+pub async fn export_document_docx(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    document_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let document = database
+        .get_document_by_id(&document_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Document", &document_id))?;
+    let options = config_service.get_config().await?.export.docx_options;
+
+    let base_dir = image_base_dir(&output_path);
+    let bytes = render_blocking(move || docx::document_to_docx(&document, &options, base_dir.as_deref())).await?;
+    write_output(Some(&output_path), &bytes).await
+}
+
+/// 按目录顺序把整个项目导出为一个 DOCX 并写入 output_path
+#[tauri::command]
+pub async fn export_project_docx(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    project_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let project = database
+        .get_project_by_id(&project_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Project", &project_id))?;
+    let documents = database.get_project_documents_ordered(&project_id).await?;
+    let options = config_service.get_config().await?.export.docx_options;
+
+    let base_dir = image_base_dir(&output_path);
+    let bytes = render_blocking(move || docx::project_to_docx(&project, &documents, &options, base_dir.as_deref())).await?;
+    write_output(Some(&output_path), &bytes).await
+}
+
+/// 把项目导出为 EPUB 3 电子书并写入 output_path，章节顺序和目录层级取自项目目录树
+#[tauri::command]
+pub async fn export_project_epub(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    project_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let project = database
+        .get_project_by_id(&project_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Project", &project_id))?;
+    let tree = database.get_project_tree(&project_id).await?;
+    let documents = database.get_project_documents_ordered(&project_id).await?;
+    let options = config_service.get_config().await?.export.epub_options;
+
+    let base_dir = image_base_dir(&output_path);
+    let bytes = render_blocking(move || epub::project_to_epub(&project, &tree, &documents, &options, base_dir.as_deref())).await?;
+    write_output(Some(&output_path), &bytes).await
+}
+
+/// 导出单个文档为 Markdown，按配置附带 YAML front matter 并转换换行符
+#[tauri::command]
+pub async fn export_document_markdown(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    document_id: String,
+    output_path: Option<String>,
+) -> AppResult<String> {
+    let document = database
+        .get_document_by_id(&document_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Document", &document_id))?;
+    let config = config_service.get_config().await?;
+
+    let text = markdown::document_to_markdown(&document, &config.export.markdown_options);
+    write_output(output_path.as_deref(), text.as_bytes()).await?;
+    Ok(text)
+}
+
+/// 把项目导出为与目录树同构的 Markdown 文件；output_path 以 .zip 结尾时打包为 zip，否则写入该目录
+#[tauri::command]
+pub async fn export_project_markdown(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    project_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let tree = database.get_project_tree(&project_id).await?;
+    let documents = database.get_project_documents_ordered(&project_id).await?;
+    let options = config_service.get_config().await?.export.markdown_options;
+
+    let archive = markdown::project_to_markdown_archive(&tree, &documents, &options);
+    let is_zip = Path::new(&output_path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    if is_zip {
+        let bytes = render_blocking(move || markdown::archive_to_zip(&archive)).await?;
+        write_output(Some(&output_path), &bytes).await
+    } else {
+        tokio::task::spawn_blocking(move || markdown::archive_to_dir(&archive, Path::new(&output_path)))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?
+            .map_err(AppError::from)
+    }
+}
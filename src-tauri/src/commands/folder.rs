@@ -0,0 +1,66 @@
+use crate::models::folder::{CreateFolderData, Folder, ProjectTree};
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_folder(
+    database: State<'_, Database>,
+    data: CreateFolderData,
+) -> AppResult<Folder> {
+    database.create_folder(data).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rename_folder(
+    database: State<'_, Database>,
+    folder_id: String,
+    name: String,
+) -> AppResult<Folder> {
+    database
+        .rename_folder(&folder_id, &name)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn move_folder(
+    database: State<'_, Database>,
+    folder_id: String,
+    parent_id: Option<String>,
+    position: Option<u32>,
+) -> AppResult<()> {
+    database
+        .move_folder(&folder_id, parent_id.as_deref(), position)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_folder(database: State<'_, Database>, folder_id: String) -> AppResult<()> {
+    database.delete_folder(&folder_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn move_document(
+    database: State<'_, Database>,
+    document_id: String,
+    folder_id: Option<String>,
+    position: Option<u32>,
+) -> AppResult<()> {
+    database
+        .move_document(&document_id, folder_id.as_deref(), position)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_project_tree(
+    database: State<'_, Database>,
+    project_id: String,
+) -> AppResult<ProjectTree> {
+    database
+        .get_project_tree(&project_id)
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,90 @@
+use crate::models::project::{GoalStatus, Project, ProjectForecast, ProjectGoals};
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+// 后台检查项目目标的间隔
+const REMINDER_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+#[tauri::command]
+pub async fn set_project_goals(
+    database: State<'_, Database>,
+    project_id: String,
+    goals: ProjectGoals,
+) -> AppResult<Project> {
+    database
+        .set_project_goals(&project_id, goals)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_project_forecast(
+    database: State<'_, Database>,
+    project_id: String,
+) -> AppResult<ProjectForecast> {
+    database
+        .get_project_forecast(&project_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_project_forecasts(database: State<'_, Database>) -> AppResult<Vec<ProjectForecast>> {
+    database.list_project_forecasts().await.map_err(AppError::from)
+}
+
+fn reminder_text(forecast: &ProjectForecast) -> Option<(String, String)> {
+    match forecast.status {
+        GoalStatus::Overdue => Some((
+            "项目已逾期".to_string(),
+            format!(
+                "「{}」已超过截止日期，当前进度 {}%",
+                forecast.project_name, forecast.progress
+            ),
+        )),
+        GoalStatus::AtRisk => Some((
+            "项目可能无法按时完成".to_string(),
+            match forecast.required_words_per_day {
+                Some(required) => format!(
+                    "「{}」需要每天写 {} 字才能按时完成，最近平均每天 {:.0} 字",
+                    forecast.project_name, required, forecast.average_words_per_day
+                ),
+                None => format!(
+                    "「{}」最近平均每天 {:.0} 字，低于每日目标",
+                    forecast.project_name, forecast.average_words_per_day
+                ),
+            },
+        )),
+        _ => None,
+    }
+}
+
+/// 定期检查项目目标，对逾期或有风险的项目发送系统通知。同一项目的同一状态每天最多提醒一次
+pub fn spawn_goal_reminders(app: AppHandle) {
+    let database = app.state::<Database>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let mut notified: HashMap<String, (GoalStatus, chrono::NaiveDate)> = HashMap::new();
+        loop {
+            match database.list_project_forecasts().await {
+                Ok(forecasts) => {
+                    let today = chrono::Local::now().date_naive();
+                    for forecast in forecasts {
+                        let Some((title, body)) = reminder_text(&forecast) else { continue };
+                        if notified.get(&forecast.project_id) == Some(&(forecast.status, today)) {
+                            continue;
+                        }
+                        if let Err(e) = app.notification().builder().title(title).body(body).show() {
+                            println!("Failed to show goal reminder: {}", e);
+                        }
+                        notified.insert(forecast.project_id.clone(), (forecast.status, today));
+                    }
+                }
+                Err(e) => println!("Failed to check project goals: {}", e),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(REMINDER_INTERVAL_SECS)).await;
+        }
+    });
+}
@@ -0,0 +1,154 @@
+use crate::models::document::{CreateDocumentData, Document, DocumentType};
+use crate::models::import::{
+    FolderImportFinished, FolderImportOptions, FolderImportProgress, FolderImportReport, VaultImportOptions, VaultImportReport,
+};
+use crate::services::database::Database;
+use crate::services::{docx, folder_import, markdown, notion, obsidian};
+use crate::error::{AppError, AppResult};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+
+// 目录导入的进度和结束事件，载荷分别为 FolderImportProgress 和 FolderImportFinished
+pub const FOLDER_IMPORT_PROGRESS_EVENT: &str = "folder-import-progress";
+pub const FOLDER_IMPORT_FINISHED_EVENT: &str = "folder-import-finished";
+
+/// 把 Word 文档导入为 Markdown 文档；标题依次取文档中的标题样式段落、文档属性和文件名
+#[tauri::command]
+pub async fn import_docx(
+    database: State<'_, Database>,
+    project_id: String,
+    folder_id: Option<String>,
+    file_path: String,
+) -> AppResult<Document> {
+    let path = Path::new(&file_path);
+    let fallback_title = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("未命名文档")
+        .to_string();
+    let bytes = tokio::fs::read(path).await?;
+    let imported = tokio::task::spawn_blocking(move || docx::docx_to_markdown(&bytes))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    let mut document = database
+        .create_document(CreateDocumentData {
+            title: imported.title.unwrap_or(fallback_title),
+            content: Some(imported.markdown),
+            content_type: DocumentType::Markdown,
+            project_id,
+            folder_path: None,
+            folder_id,
+            tags: None,
+            template_id: None,
+            template_variables: None,
+        })
+        .await?;
+
+    // 作者不在 CreateDocumentData 中，创建后再写入元数据
+    if let Some(author) = imported.author {
+        document.metadata.author = Some(author);
+        database.update_document(&document.id, document.clone()).await?;
+    }
+    Ok(document)
+}
+
+/// 导入 Markdown 归档（zip 或目录）到项目中，还原文件夹结构和 front matter 中的文档信息。
+/// 与 export_project_markdown 的输出互为逆操作；id 未被占用时沿用原 id
+#[tauri::command]
+pub async fn import_markdown_archive(
+    database: State<'_, Database>,
+    project_id: String,
+    path: String,
+) -> AppResult<Vec<Document>> {
+    let source = PathBuf::from(&path);
+    let archive = tokio::task::spawn_blocking(move || {
+        if source.is_dir() {
+            markdown::read_dir_archive(&source)
+        } else {
+            markdown::read_zip_archive(&std::fs::read(&source)?)
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+    // 归档应当能完整还原，有文件读不出来时整体放弃
+    if let Some((file, reason)) = archive.errors.first() {
+        return Err(AppError::validation(format!("Cannot read {}: {}", file, reason)));
+    }
+
+    let mut documents: Vec<Document> = archive
+        .files
+        .iter()
+        .map(|file| markdown::file_to_document(file, &project_id))
+        .collect();
+    // 按导出时记录的顺序排列；没有 order 的文件保持文件名顺序
+    documents.sort_by_key(|document| document.sort_order);
+    Ok(database.import_documents(&project_id, &archive.folders, documents).await?)
+}
+
+/// 预览目录导入：列出将要新建的文件夹以及每个文件会被新建、更新、跳过还是读取失败，不写入数据
+#[tauri::command]
+pub async fn preview_folder_import(
+    database: State<'_, Database>,
+    options: FolderImportOptions,
+) -> AppResult<FolderImportReport> {
+    Ok(folder_import::plan(&database, &options).await?.report)
+}
+
+/// 在后台把目录导入为项目（或重新导入到 options.project_id），立即返回 import_id。
+/// 扫描目录和执行导入都在后台进行，目录不存在等错误也通过结束事件通知
+#[tauri::command]
+pub async fn start_folder_import(
+    app: AppHandle,
+    database: State<'_, Database>,
+    options: FolderImportOptions,
+) -> AppResult<String> {
+    let import_id = uuid::Uuid::new_v4().to_string();
+    let database = database.inner().clone();
+
+    let id = import_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let progress = |processed, total, path: &str| {
+            let payload = FolderImportProgress { import_id: id.clone(), processed, total, path: path.to_string() };
+            if let Err(e) = app.emit(FOLDER_IMPORT_PROGRESS_EVENT, payload) {
+                println!("Failed to emit {}: {}", FOLDER_IMPORT_PROGRESS_EVENT, e);
+            }
+        };
+        let result = async {
+            let plan = folder_import::plan(&database, &options).await?;
+            folder_import::execute(&database, &options, plan, progress).await
+        }
+        .await;
+        let finished = match result {
+            Ok(report) => FolderImportFinished { import_id: id.clone(), report: Some(report), error: None },
+            Err(e) => {
+                println!("Folder import {} failed: {}", id, e);
+                FolderImportFinished { import_id: id.clone(), report: None, error: Some(AppError::from(e)) }
+            }
+        };
+        if let Err(e) = app.emit(FOLDER_IMPORT_FINISHED_EVENT, &finished) {
+            println!("Failed to emit {}: {}", FOLDER_IMPORT_FINISHED_EVENT, e);
+        }
+    });
+    Ok(import_id)
+}
+
+/// 导入 Obsidian 库（目录或 zip）：wikilink 和笔记链接改写为文档链接，引用的附件复制到应用数据目录，
+/// 无法解析的链接保留原文并列在报告中
+#[tauri::command]
+pub async fn import_obsidian_vault(
+    database: State<'_, Database>,
+    options: VaultImportOptions,
+) -> AppResult<VaultImportReport> {
+    Ok(obsidian::import_vault(&database, &options).await?)
+}
+
+/// 导入 Notion 导出的 Markdown & CSV（目录或 zip）：数据库的列映射为文档字段和自定义属性，
+/// 页面链接改写为文档链接，引用的附件复制到应用数据目录
+#[tauri::command]
+pub async fn import_notion_export(
+    database: State<'_, Database>,
+    options: VaultImportOptions,
+) -> AppResult<VaultImportReport> {
+    Ok(notion::import_export(&database, &options).await?)
+}
@@ -6,3 +6,13 @@ pub mod provider;
 pub mod project;
 pub mod system;
 pub mod workspace;
+pub mod trash;
+pub mod folder;
+pub mod tag;
+pub mod writing;
+pub mod goal;
+pub mod secret;
+pub mod backup;
+pub mod export;
+pub mod import;
+pub mod template;
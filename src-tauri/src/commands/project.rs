@@ -1,32 +1,33 @@
-use crate::models::project::{Project, CreateProjectData, ProjectStats, ProjectListResult};
+use crate::models::project::{Project, CreateProjectData, ProjectStats, ProjectListResult, ProjectWritingPreferences};
 use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
 use tauri::State;
 
 #[tauri::command]
 pub async fn create_project(
     database: State<'_, Database>,
     project_data: CreateProjectData,
-) -> Result<Project, String> {
+) -> AppResult<Project> {
     database
         .create_project(project_data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_projects(database: State<'_, Database>) -> Result<Vec<Project>, String> {
-    database.get_projects().await.map_err(|e| e.to_string())
+pub async fn get_projects(database: State<'_, Database>) -> AppResult<Vec<Project>> {
+    database.get_projects().await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_project_by_id(
     database: State<'_, Database>,
     project_id: String,
-) -> Result<Option<Project>, String> {
+) -> AppResult<Option<Project>> {
     database
         .get_project_by_id(&project_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -34,38 +35,38 @@ pub async fn update_project(
     database: State<'_, Database>,
     project_id: String,
     project_data: Project,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .update_project(&project_id, project_data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn delete_project(
     database: State<'_, Database>,
     project_id: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .delete_project(&project_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_project_stats(database: State<'_, Database>) -> Result<ProjectStats, String> {
-    database.get_project_stats().await.map_err(|e| e.to_string())
+pub async fn get_project_stats(database: State<'_, Database>) -> AppResult<ProjectStats> {
+    database.get_project_stats().await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_projects_by_workspace(
     database: State<'_, Database>,
     workspace_id: String,
-) -> Result<Vec<Project>, String> {
+) -> AppResult<Vec<Project>> {
     database
         .get_projects_by_workspace(&workspace_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -78,7 +79,7 @@ pub async fn search_projects(
     order: Option<String>,
     page: Option<u32>,
     page_size: Option<u32>,
-) -> Result<ProjectListResult, String> {
+) -> AppResult<ProjectListResult> {
     database
         .search_projects(
             workspace_id.as_deref(),
@@ -90,5 +91,17 @@ pub async fn search_projects(
             page_size.unwrap_or(9),
         )
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_project_writing_preferences(
+    database: State<'_, Database>,
+    project_id: String,
+    preferences: ProjectWritingPreferences,
+) -> AppResult<Project> {
+    database
+        .set_project_writing_preferences(&project_id, preferences)
+        .await
+        .map_err(AppError::from)
 }
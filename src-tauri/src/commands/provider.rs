@@ -1,24 +1,52 @@
+use crate::services::config::ConfigService;
 use crate::services::database::Database;
-use crate::models::provider::{AIProvider, CreateAIProviderInput};
+use crate::services::provider;
+use crate::models::provider::{AIProvider, AIProviderStats, CreateAIProviderInput};
+use crate::error::{AppError, AppResult};
 use tauri::State;
 
 #[tauri::command]
-pub async fn list_ai_providers(database: State<'_, Database>) -> Result<Vec<AIProvider>, String> {
-    database.list_ai_providers().await.map_err(|e| e.to_string())
+pub async fn list_ai_providers(database: State<'_, Database>) -> AppResult<Vec<AIProvider>> {
+    database.list_ai_providers().await.map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn create_ai_provider(database: State<'_, Database>, input: CreateAIProviderInput) -> Result<AIProvider, String> {
-    database.create_ai_provider(input).await.map_err(|e| e.to_string())
+pub async fn create_ai_provider(database: State<'_, Database>, input: CreateAIProviderInput) -> AppResult<AIProvider> {
+    database.create_ai_provider(input).await.map_err(AppError::from)
 }
 
+/// 删除提供商，如果它是默认提供商则同时清除配置中的默认值
 #[tauri::command]
-pub async fn delete_ai_provider(database: State<'_, Database>, id: String) -> Result<(), String> {
-    database.delete_ai_provider(&id).await.map_err(|e| e.to_string())
+pub async fn delete_ai_provider(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+    id: String,
+) -> AppResult<()> {
+    database.delete_ai_provider(&id).await?;
+
+    let mut config = config_service.get_config().await?;
+    if config.ai_providers.default_provider.as_deref() == Some(id.as_str()) {
+        config.ai_providers.default_provider = None;
+        config_service.save_config(config).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_ai_provider(database: State<'_, Database>, provider: AIProvider) -> AppResult<()> {
+    database.update_ai_provider(&provider).await.map_err(AppError::from)
 }
 
+/// 测试提供商连接；已保存的提供商会同时更新状态
 #[tauri::command]
-pub async fn update_ai_provider(database: State<'_, Database>, provider: AIProvider) -> Result<(), String> {
-    database.update_ai_provider(&provider).await.map_err(|e| e.to_string())
+pub async fn test_ai_provider(database: State<'_, Database>, provider: AIProvider) -> AppResult<bool> {
+    let api_key = database.resolve_secret(&provider.api_key).await?;
+    let connected = provider::test_connection(&provider, &api_key).await;
+    database.record_ai_provider_test(&provider.id, connected).await?;
+    Ok(connected)
 }
 
+#[tauri::command]
+pub async fn get_ai_provider_stats(database: State<'_, Database>) -> AppResult<AIProviderStats> {
+    database.get_ai_provider_stats().await.map_err(AppError::from)
+}
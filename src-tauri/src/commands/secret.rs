@@ -0,0 +1,26 @@
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use tauri::State;
+
+/// 加密保存密钥，返回可写入配置的 `secret://` 引用
+#[tauri::command]
+pub async fn set_secret(database: State<'_, Database>, value: String) -> AppResult<String> {
+    database.store_secret(&value).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rotate_secret(
+    database: State<'_, Database>,
+    reference: String,
+    value: String,
+) -> AppResult<()> {
+    database
+        .rotate_secret(&reference, &value)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn reveal_secret(database: State<'_, Database>, reference: String) -> AppResult<String> {
+    database.reveal_secret(&reference).await.map_err(AppError::from)
+}
@@ -1,7 +1,8 @@
 use crate::models::system::SystemInfo;
+use crate::error::AppResult;
 
 #[tauri::command]
-pub async fn get_system_info() -> Result<SystemInfo, String> {
+pub async fn get_system_info() -> AppResult<SystemInfo> {
     Ok(SystemInfo {
         platform: std::env::consts::OS.to_string(),
         arch: std::env::consts::ARCH.to_string(),
@@ -13,7 +14,7 @@ pub async fn get_system_info() -> Result<SystemInfo, String> {
 pub async fn show_notification(
     title: String,
     body: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     // This will be implemented when notification plugin is properly set up
     println!("Notification: {} - {}", title, body);
     Ok(())
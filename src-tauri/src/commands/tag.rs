@@ -0,0 +1,74 @@
+use crate::models::document::DocumentSummary;
+use crate::models::tag::{Tag, TagUsage};
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_tags(database: State<'_, Database>) -> AppResult<Vec<TagUsage>> {
+    database.list_tags().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    database: State<'_, Database>,
+    tag_id: String,
+    name: String,
+) -> AppResult<Tag> {
+    database
+        .rename_tag(&tag_id, &name)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn merge_tags(
+    database: State<'_, Database>,
+    source_id: String,
+    target_id: String,
+) -> AppResult<Tag> {
+    database
+        .merge_tags(&source_id, &target_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_tag(database: State<'_, Database>, tag_id: String) -> AppResult<()> {
+    database.delete_tag(&tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_documents_by_tags(
+    database: State<'_, Database>,
+    tags: Vec<String>,
+    match_all: Option<bool>,
+) -> AppResult<Vec<DocumentSummary>> {
+    database
+        .get_documents_by_tags(&tags, match_all.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_project_tags(
+    database: State<'_, Database>,
+    project_id: String,
+) -> AppResult<Vec<String>> {
+    database
+        .get_project_tags(&project_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_project_tags(
+    database: State<'_, Database>,
+    project_id: String,
+    tags: Vec<String>,
+) -> AppResult<Vec<String>> {
+    database
+        .set_project_tags(&project_id, &tags)
+        .await
+        .map_err(AppError::from)
+}
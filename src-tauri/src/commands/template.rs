@@ -0,0 +1,160 @@
+use crate::models::document::DocumentType;
+use crate::models::template::{DocumentTemplate, ProjectTemplate, ProjectTemplateData, TemplateData};
+use crate::services::database::Database;
+use crate::services::templates;
+use crate::error::{AppError, AppResult};
+use std::path::Path;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_templates(
+    database: State<'_, Database>,
+    category: Option<String>,
+) -> AppResult<Vec<DocumentTemplate>> {
+    database
+        .list_templates(category.as_deref())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_template(
+    database: State<'_, Database>,
+    template_id: String,
+) -> AppResult<DocumentTemplate> {
+    database
+        .get_template(&template_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Template", template_id))
+}
+
+#[tauri::command]
+pub async fn create_template(
+    database: State<'_, Database>,
+    template_data: TemplateData,
+) -> AppResult<DocumentTemplate> {
+    database
+        .create_template(template_data)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_template(
+    database: State<'_, Database>,
+    template_id: String,
+    template_data: TemplateData,
+) -> AppResult<DocumentTemplate> {
+    database
+        .update_template(&template_id, template_data)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_template(database: State<'_, Database>, template_id: String) -> AppResult<()> {
+    database.delete_template(&template_id).await.map_err(AppError::from)
+}
+
+/// 导入模板文件。.json 为 export_templates 导出的文件；.md 和 .txt 整个作为模板正文，文件名作为模板名称
+#[tauri::command]
+pub async fn import_templates(
+    database: State<'_, Database>,
+    file_path: String,
+) -> AppResult<Vec<DocumentTemplate>> {
+    let path = Path::new(&file_path);
+    let text = tokio::fs::read_to_string(path).await?;
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let templates = match extension.as_str() {
+        "md" | "markdown" | "txt" => vec![TemplateData {
+            name: path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+            description: String::new(),
+            category: String::new(),
+            title: String::new(),
+            content: text,
+            content_type: if extension == "txt" { DocumentType::PlainText } else { DocumentType::Markdown },
+            tags: Vec::new(),
+            variables: Vec::new(),
+        }],
+        _ => templates::from_bundle(&text)?,
+    };
+    Ok(database.import_templates(templates).await?)
+}
+
+/// 把指定的模板导出为 JSON 文件，内置模板导出后在其他设备上作为用户模板导入
+#[tauri::command]
+pub async fn export_templates(
+    database: State<'_, Database>,
+    template_ids: Vec<String>,
+    file_path: String,
+) -> AppResult<()> {
+    let mut selected = Vec::new();
+    for template_id in template_ids {
+        let template = database
+            .get_template(&template_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Template", template_id))?;
+        selected.push(template);
+    }
+    tokio::fs::write(&file_path, templates::to_bundle(selected)?).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_project_templates(database: State<'_, Database>) -> AppResult<Vec<ProjectTemplate>> {
+    database.list_project_templates().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_project_template(
+    database: State<'_, Database>,
+    template_id: String,
+) -> AppResult<ProjectTemplate> {
+    database
+        .get_project_template(&template_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Project template", template_id))
+}
+
+#[tauri::command]
+pub async fn create_project_template(
+    database: State<'_, Database>,
+    template_data: ProjectTemplateData,
+) -> AppResult<ProjectTemplate> {
+    database
+        .create_project_template(template_data)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_project_template(
+    database: State<'_, Database>,
+    template_id: String,
+    template_data: ProjectTemplateData,
+) -> AppResult<ProjectTemplate> {
+    database
+        .update_project_template(&template_id, template_data)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_project_template(database: State<'_, Database>, template_id: String) -> AppResult<()> {
+    database.delete_project_template(&template_id).await.map_err(AppError::from)
+}
+
+/// 把项目的文件夹结构、文档、标签、目标和写作偏好另存为项目模板，include_content 为 false 时只保留文档标题
+#[tauri::command]
+pub async fn save_project_as_template(
+    database: State<'_, Database>,
+    project_id: String,
+    name: Option<String>,
+    description: Option<String>,
+    include_content: bool,
+) -> AppResult<ProjectTemplate> {
+    database
+        .save_project_as_template(&project_id, name.as_deref(), description.as_deref(), include_content)
+        .await
+        .map_err(AppError::from)
+}
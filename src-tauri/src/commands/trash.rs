@@ -0,0 +1,77 @@
+use crate::models::trash::{TrashItem, TrashItemKind};
+use crate::services::config::ConfigService;
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use tauri::{AppHandle, Manager, State};
+
+// 后台清理过期回收站条目的间隔
+const PURGE_INTERVAL_SECS: u64 = 60 * 60;
+
+async fn trash_retention_days(config_service: &ConfigService) -> u32 {
+    config_service
+        .get_config()
+        .await
+        .unwrap_or_default()
+        .general
+        .trash_retention_days
+}
+
+#[tauri::command]
+pub async fn list_trash(
+    database: State<'_, Database>,
+    config_service: State<'_, ConfigService>,
+) -> AppResult<Vec<TrashItem>> {
+    let retention_days = trash_retention_days(&config_service).await;
+    database.list_trash(retention_days).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn restore_trash_item(
+    database: State<'_, Database>,
+    kind: TrashItemKind,
+    id: String,
+) -> AppResult<()> {
+    database
+        .restore_trash_item(kind, &id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn purge_trash_item(
+    database: State<'_, Database>,
+    kind: TrashItemKind,
+    id: String,
+) -> AppResult<()> {
+    database
+        .purge_trash_item(kind, &id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn empty_trash(database: State<'_, Database>) -> AppResult<()> {
+    database.empty_trash().await.map_err(AppError::from)
+}
+
+/// 清理超过保留期的回收站条目
+async fn purge_expired(database: &Database, config_service: &ConfigService) {
+    let retention_days = trash_retention_days(config_service).await;
+    match database.purge_expired_trash(retention_days).await {
+        Ok(0) => {}
+        Ok(count) => println!("Purged {} expired trash items", count),
+        Err(e) => println!("Failed to purge expired trash: {}", e),
+    }
+}
+
+/// 启动时立即清理一次，之后定时清理，应用长时间运行时过期条目也会按时删除。每次都重新读取保留天数
+pub fn spawn_trash_purge(app: AppHandle) {
+    let database = app.state::<Database>().inner().clone();
+    let config_service = app.state::<ConfigService>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            purge_expired(&database, &config_service).await;
+            tokio::time::sleep(std::time::Duration::from_secs(PURGE_INTERVAL_SECS)).await;
+        }
+    });
+}
@@ -1,44 +1,45 @@
 use crate::models::workspace::{Workspace, CreateWorkspaceData};
 use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
 use tauri::State;
 
 #[tauri::command]
 pub async fn create_workspace(
     database: State<'_, Database>,
     workspace_data: CreateWorkspaceData,
-) -> Result<Workspace, String> {
+) -> AppResult<Workspace> {
     database
         .create_workspace(workspace_data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_workspaces(database: State<'_, Database>) -> Result<Vec<Workspace>, String> {
-    database.get_workspaces().await.map_err(|e| e.to_string())
+pub async fn get_workspaces(database: State<'_, Database>) -> AppResult<Vec<Workspace>> {
+    database.get_workspaces().await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn switch_workspace(
     database: State<'_, Database>,
     workspace_id: String,
-) -> Result<Workspace, String> {
+) -> AppResult<Workspace> {
     database
         .get_workspace_by_id(&workspace_id)
         .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Workspace not found".to_string())
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::not_found("Workspace", workspace_id))
 }
 
 #[tauri::command]
 pub async fn delete_workspace(
     database: State<'_, Database>,
     workspace_id: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     database
         .delete_workspace(&workspace_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -47,9 +48,9 @@ pub async fn update_workspace(
     workspace_id: String,
     name: String,
     description: String,
-) -> Result<Workspace, String> {
+) -> AppResult<Workspace> {
     database
         .update_workspace(&workspace_id, &name, &description)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
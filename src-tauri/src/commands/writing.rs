@@ -0,0 +1,30 @@
+use crate::models::writing::{StatsPeriod, WritingStats, WritingStatsFilter, WritingStreak};
+use crate::services::database::Database;
+use crate::error::{AppError, AppResult};
+use chrono::NaiveDate;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_writing_stats(
+    database: State<'_, Database>,
+    period: StatsPeriod,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    filter: Option<WritingStatsFilter>,
+) -> AppResult<WritingStats> {
+    database
+        .get_writing_stats(period, from, to, &filter.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_writing_streak(
+    database: State<'_, Database>,
+    filter: Option<WritingStatsFilter>,
+) -> AppResult<WritingStreak> {
+    database
+        .get_writing_streak(&filter.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
+}
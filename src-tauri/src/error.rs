@@ -0,0 +1,127 @@
+// 命令层统一错误类型
+// 序列化为 `{ code, message, details }`，前端根据稳定的 code 区分错误类别
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{entity} not found: {id}")]
+    NotFound { entity: &'static str, id: String },
+
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("Database is busy, please try again")]
+    DatabaseLocked(#[source] sqlx::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[source] sqlx::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn not_found(entity: &'static str, id: impl Into<String>) -> Self {
+        Self::NotFound { entity, id: id.into() }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "NOT_FOUND",
+            Self::Validation(_) => "VALIDATION_FAILED",
+            Self::Conflict(_) => "CONFLICT",
+            Self::DatabaseLocked(_) => "DATABASE_LOCKED",
+            Self::Database(_) => "DATABASE_ERROR",
+            Self::Io(_) => "IO_ERROR",
+            Self::Serialization(_) => "SERIALIZATION_ERROR",
+            Self::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::NotFound { entity, id } => Some(serde_json::json!({ "entity": entity, "id": id })),
+            Self::DatabaseLocked(e) | Self::Database(e) => e
+                .as_database_error()
+                .and_then(|db| db.code())
+                .map(|code| serde_json::json!({ "sqlite_code": code })),
+            Self::Io(e) => Some(serde_json::json!({ "kind": format!("{:?}", e.kind()) })),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::PoolTimedOut = err {
+            return Self::DatabaseLocked(err);
+        }
+
+        // SQLite 主错误码为扩展错误码的低 8 位：5 = SQLITE_BUSY，6 = SQLITE_LOCKED，19 = SQLITE_CONSTRAINT
+        let primary_code = err
+            .as_database_error()
+            .and_then(|db| db.code())
+            .and_then(|code| code.parse::<i32>().ok())
+            .map(|code| code & 0xff);
+        match primary_code {
+            Some(5) | Some(6) => Self::DatabaseLocked(err),
+            Some(19) if err.as_database_error().is_some_and(|db| db.is_unique_violation()) => {
+                Self::Conflict(err.to_string())
+            }
+            _ => Self::Database(err),
+        }
+    }
+}
+
+// 服务层使用 anyhow，这里尽量还原出具体的错误类别
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<AppError>() {
+            Ok(app_err) => return app_err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<sqlx::Error>() {
+            Ok(sqlx_err) => return sqlx_err.into(),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<std::io::Error>() {
+            Ok(io_err) => return io_err.into(),
+            Err(err) => err,
+        };
+        match err.downcast::<serde_json::Error>() {
+            Ok(json_err) => json_err.into(),
+            Err(err) => Self::Internal(format!("{:#}", err)),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
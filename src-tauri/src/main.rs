@@ -2,24 +2,37 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
+mod error;
 mod models;
 mod services;
 mod utils;
 
 use commands::*;
+use services::backup::BackupService;
+use services::config::ConfigService;
 use services::database::Database;
 
 #[tokio::main]
 async fn main() {
     // Initialize database
     let database = Database::new().await.expect("Failed to initialize database");
-    
+    let config_service = ConfigService::new(database.clone());
+    let backup_service = BackupService::new(database.clone()).expect("Failed to locate backups directory");
+
     tauri::Builder::default()
         .manage(database)
+        .manage(config_service)
+        .manage(backup_service)
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            goal::spawn_goal_reminders(app.handle().clone());
+            backup::spawn_scheduled_backups(app.handle().clone());
+            trash::spawn_trash_purge(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Project management
             project::create_project,
@@ -30,6 +43,7 @@ async fn main() {
             project::get_project_stats,
             project::get_projects_by_workspace,
             project::search_projects,
+            project::set_project_writing_preferences,
             
             // Workspace management
             workspace::create_workspace,
@@ -42,12 +56,43 @@ async fn main() {
             document::create_document,
             document::get_documents,
             document::get_documents_by_project,
+            document::list_documents,
             document::get_document_by_id,
             document::update_document,
             document::update_document_content,
             document::delete_document,
             document::save_document,
+            document::list_document_revisions,
+            document::get_document_revision,
+            document::diff_document_revisions,
+            document::restore_document_revision,
+            document::search_documents,
+            document::read_attachment,
             
+            // Trash
+            trash::list_trash,
+            trash::restore_trash_item,
+            trash::purge_trash_item,
+            trash::empty_trash,
+            folder::create_folder,
+            folder::rename_folder,
+            folder::move_folder,
+            folder::delete_folder,
+            folder::move_document,
+            folder::get_project_tree,
+            tag::list_tags,
+            tag::rename_tag,
+            tag::merge_tags,
+            tag::delete_tag,
+            tag::get_documents_by_tags,
+            tag::get_project_tags,
+            tag::set_project_tags,
+            writing::get_writing_stats,
+            writing::get_writing_streak,
+            goal::set_project_goals,
+            goal::get_project_forecast,
+            goal::list_project_forecasts,
+
             // Environment management
             environment::check_environment,
             environment::get_environment_summary,
@@ -67,13 +112,58 @@ async fn main() {
             provider::create_ai_provider,
             provider::delete_ai_provider,
             provider::update_ai_provider,
+            provider::test_ai_provider,
+            provider::get_ai_provider_stats,
+            secret::set_secret,
+            secret::rotate_secret,
+            secret::reveal_secret,
+
+            // Backups
+            backup::list_backups,
+            backup::create_backup,
+            backup::delete_backup,
+            backup::restore_backup,
+            backup::get_backup_status,
+
+            // Export
+            export::export_document_html,
+            export::export_project_html,
+            export::export_document_pdf,
+            export::export_project_pdf,
+            export::export_document_docx,
+            export::export_project_docx,
+            export::export_project_epub,
+            export::export_document_markdown,
+            export::export_project_markdown,
+
+            // Import
+            import::import_docx,
+            import::import_markdown_archive,
+            import::preview_folder_import,
+            import::start_folder_import,
+            import::import_obsidian_vault,
+            import::import_notion_export,
+
+            // Templates
+            template::list_templates,
+            template::get_template,
+            template::create_template,
+            template::update_template,
+            template::delete_template,
+            template::import_templates,
+            template::export_templates,
+            template::list_project_templates,
+            template::get_project_template,
+            template::create_project_template,
+            template::update_project_template,
+            template::delete_project_template,
+            template::save_project_as_template,
             
             // Configuration
             config::get_config,
             config::save_config,
             config::import_config,
             config::export_config,
-            config::test_ai_provider,
             config::test_mcp_server,
             config::reset_config,
             
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BackupKind {
+    Scheduled,
+    Manual,
+    PreMigration,
+    PreRestore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String, // 备份文件名
+    pub kind: BackupKind,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupStatus {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    pub last_backup: Option<DateTime<Utc>>,
+    pub next_backup: Option<DateTime<Utc>>,
+    pub backup_count: u32,
+    pub total_size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub enum RestoreScope {
+    #[default]
+    Full,
+    Project(String),
+    Document(String),
+}
+
+// 未删除的实体数量，恢复前后各统计一次
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EntityCounts {
+    pub workspaces: u32,
+    pub projects: u32,
+    pub documents: u32,
+    pub folders: u32,
+    pub tags: u32,
+    pub revisions: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub backup_id: String,
+    pub scope: RestoreScope,
+    pub schema_version: i64,
+    // 恢复前自动创建的安全快照
+    pub safety_backup_id: String,
+    pub before: EntityCounts,
+    pub after: EntityCounts,
+}
@@ -25,6 +25,12 @@ pub struct GeneralConfig {
     pub backup_interval: u32, // minutes
     pub default_workspace: Option<String>,
     pub recent_files_limit: u32,
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +74,10 @@ pub struct ExportConfig {
     pub pdf_options: PdfExportOptions,
     pub html_options: HtmlExportOptions,
     pub markdown_options: MarkdownExportOptions,
+    #[serde(default)]
+    pub docx_options: DocxExportOptions,
+    #[serde(default)]
+    pub epub_options: EpubExportOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +103,18 @@ pub struct MarkdownExportOptions {
     pub line_ending: String,
 }
 
+// 参考文档中的样式覆盖默认样式，缺少的样式仍使用内置定义
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocxExportOptions {
+    pub reference_docx: Option<String>,
+}
+
+// 未指定封面图片时生成只有书名和作者的文字封面
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpubExportOptions {
+    pub cover_image: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WriteFlowConfig {
     pub cli_path: Option<String>,
@@ -108,24 +130,12 @@ pub struct PluginConfig {
     pub plugin_settings: HashMap<String, serde_json::Value>,
 }
 
+// 提供商本身保存在 ai_providers 表中，这里只记录默认提供商的 id
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIProvidersConfig {
-    pub providers: HashMap<String, AIProvider>,
     pub default_provider: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AIProvider {
-    pub name: String,
-    pub provider_type: String,
-    pub api_key: Option<String>,
-    pub api_base: Option<String>,
-    pub model: Option<String>,
-    pub max_tokens: Option<u32>,
-    pub temperature: Option<f32>,
-    pub enabled: bool,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPServersConfig {
     pub servers: HashMap<String, MCPServer>,
@@ -178,6 +188,7 @@ impl Default for AppConfig {
                 backup_interval: 10,
                 default_workspace: None,
                 recent_files_limit: 10,
+                trash_retention_days: default_trash_retention_days(),
             },
             editor: EditorConfig {
                 theme: "default".to_string(),
@@ -222,6 +233,8 @@ impl Default for AppConfig {
                     include_metadata: true,
                     line_ending: "LF".to_string(),
                 },
+                docx_options: DocxExportOptions::default(),
+                epub_options: EpubExportOptions::default(),
             },
             writeflow: WriteFlowConfig {
                 cli_path: None,
@@ -235,7 +248,6 @@ impl Default for AppConfig {
                 plugin_settings: HashMap::new(),
             },
             ai_providers: AIProvidersConfig {
-                providers: HashMap::new(),
                 default_provider: None,
             },
             mcp_servers: MCPServersConfig {
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +14,10 @@ pub struct Document {
     pub char_count: u32,
     pub project_id: String,
     pub folder_path: Option<String>,
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    #[serde(default)]
+    pub sort_order: i64,
     pub tags: Vec<String>,
     pub metadata: DocumentMetadata,
     pub created_at: DateTime<Utc>,
@@ -44,6 +49,9 @@ pub struct DocumentMetadata {
     pub reading_time: u32,
     pub export_formats: Vec<String>,
     pub version: u32,
+    // 自定义属性，如 front matter 中的其他字段、Notion 数据库的列
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,8 +61,13 @@ pub struct CreateDocumentData {
     pub content_type: DocumentType,
     pub project_id: String,
     pub folder_path: Option<String>,
+    #[serde(default)]
+    pub folder_id: Option<String>,
     pub tags: Option<Vec<String>>,
     pub template_id: Option<String>,
+    // 模板变量的取值，只在指定 template_id 时使用
+    #[serde(default)]
+    pub template_variables: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +79,67 @@ pub struct DocumentStats {
     pub recent_count: u32,
 }
 
+// 列表使用的文档摘要，不包含正文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSummary {
+    pub id: String,
+    pub title: String,
+    pub content_type: DocumentType,
+    pub status: DocumentStatus,
+    pub word_count: u32,
+    pub char_count: u32,
+    pub project_id: String,
+    pub folder_path: Option<String>,
+    pub folder_id: Option<String>,
+    pub sort_order: i64,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentListQuery {
+    pub project_id: Option<String>,
+    pub workspace_id: Option<String>,
+    pub folder_id: Option<String>,
+    pub status: Option<String>,
+    pub tags: Option<Vec<String>>,
+    // 按标题模糊匹配，全文搜索请使用 search_documents
+    pub query: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentListResult {
+    pub items: Vec<DocumentSummary>,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSearchHit {
+    pub id: String,
+    pub title: String,
+    pub title_highlight: String,
+    pub snippet: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub status: DocumentStatus,
+    pub tags: Vec<String>,
+    pub word_count: u32,
+    pub updated_at: DateTime<Utc>,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSearchResult {
+    pub items: Vec<DocumentSearchHit>,
+    pub total: u32,
+}
+
 impl Document {
     pub fn new(data: CreateDocumentData) -> Self {
         let now = Utc::now();
@@ -84,6 +158,8 @@ impl Document {
             char_count,
             project_id: data.project_id,
             folder_path: data.folder_path,
+            folder_id: data.folder_id,
+            sort_order: 0,
             tags: data.tags.unwrap_or_default(),
             metadata: DocumentMetadata {
                 author: None,
@@ -91,6 +167,7 @@ impl Document {
                 reading_time,
                 export_formats: vec!["markdown".to_string(), "pdf".to_string()],
                 version: 1,
+                properties: BTreeMap::new(),
             },
             created_at: now,
             updated_at: now,
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::document::DocumentStatus;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: String,
+    pub project_id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub sort_order: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateFolderData {
+    pub project_id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeDocument {
+    pub id: String,
+    pub title: String,
+    pub status: DocumentStatus,
+    pub word_count: u32,
+    pub sort_order: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderNode {
+    #[serde(flatten)]
+    pub folder: Folder,
+    pub folders: Vec<FolderNode>,
+    pub documents: Vec<TreeDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTree {
+    pub project_id: String,
+    pub folders: Vec<FolderNode>,
+    pub documents: Vec<TreeDocument>,
+}
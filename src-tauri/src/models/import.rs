@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use super::document::DocumentType;
+use crate::error::AppError;
+
+// 目标项目中已有同一文档（front matter 中的 id 相同，或同一文件夹下标题相同）时的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum DuplicateStrategy {
+    #[default]
+    Skip,
+    Update,
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderImportOptions {
+    pub source_path: String,
+    pub workspace_id: String,
+    // 指定时导入到已有项目（重新导入），否则新建项目
+    #[serde(default)]
+    pub project_id: Option<String>,
+    // 新建项目的名称，默认取目录名
+    #[serde(default)]
+    pub project_name: Option<String>,
+    #[serde(default)]
+    pub duplicates: DuplicateStrategy,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ImportAction {
+    Create,
+    Update,
+    Skip,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFileResult {
+    pub path: String,
+    pub title: String,
+    pub folder_path: Option<String>,
+    pub content_type: DocumentType,
+    pub action: ImportAction,
+    // 新建或更新后的文档；跳过时为已存在的同名文档
+    pub document_id: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderImportReport {
+    pub dry_run: bool,
+    // 预览且需要新建项目时为 None
+    pub project_id: Option<String>,
+    pub project_name: String,
+    // 需要新建的文件夹路径
+    pub new_folders: Vec<String>,
+    pub files: Vec<ImportFileResult>,
+    pub created: u32,
+    pub updated: u32,
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderImportProgress {
+    pub import_id: String,
+    pub processed: u32,
+    pub total: u32,
+    pub path: String,
+}
+
+// 导入结束事件的载荷，成功时带报告，失败时带与命令返回值相同的 { code, message, details } 错误
+#[derive(Debug, Serialize)]
+pub struct FolderImportFinished {
+    pub import_id: String,
+    pub report: Option<FolderImportReport>,
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultImportOptions {
+    // 目录，Notion 导出也可以直接是 zip 文件
+    pub source_path: String,
+    pub workspace_id: String,
+    // 指定时导入到已有项目，否则新建项目
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub project_name: Option<String>,
+}
+
+// 指向导入范围之外、无法改写的内部链接
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnresolvedLink {
+    pub path: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultImportReport {
+    pub project_id: String,
+    pub project_name: String,
+    pub files: Vec<ImportFileResult>,
+    pub created: u32,
+    pub failed: u32,
+    pub attachments_copied: u32,
+    pub unresolved_links: Vec<UnresolvedLink>,
+}
@@ -6,3 +6,11 @@ pub mod provider;
 pub mod project;
 pub mod system;
 pub mod workspace;
+pub mod revision;
+pub mod trash;
+pub mod folder;
+pub mod tag;
+pub mod writing;
+pub mod backup;
+pub mod import;
+pub mod template;
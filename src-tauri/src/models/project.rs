@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -14,6 +14,16 @@ pub struct Project {
     pub documents_count: u32,
     pub words_count: u32,
     pub workspace_id: String,
+    #[serde(default)]
+    pub target_word_count: Option<u32>,
+    #[serde(default)]
+    pub deadline: Option<NaiveDate>,
+    #[serde(default)]
+    pub daily_word_quota: Option<u32>,
+    #[serde(default)]
+    pub progress_mode: ProgressMode,
+    #[serde(default)]
+    pub writing_preferences: ProjectWritingPreferences,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -25,6 +35,63 @@ pub enum ProjectStatus {
     Archived,
 }
 
+// 进度计算方式：按目标字数，或按文档状态加权
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    #[default]
+    WordCount,
+    DocumentStatus,
+}
+
+// 项目级写作偏好，未设置的项沿用全局配置中的 writing_preferences
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectWritingPreferences {
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub writing_style: Option<String>,
+    #[serde(default)]
+    pub tone: Option<String>,
+    #[serde(default)]
+    pub target_audience: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectGoals {
+    pub target_word_count: Option<u32>,
+    pub deadline: Option<NaiveDate>,
+    pub daily_word_quota: Option<u32>,
+    #[serde(default)]
+    pub progress_mode: ProgressMode,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GoalStatus {
+    NoGoal,
+    OnTrack,
+    AtRisk,
+    Overdue,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectForecast {
+    pub project_id: String,
+    pub project_name: String,
+    pub progress: u32,
+    pub words_count: u32,
+    pub target_word_count: Option<u32>,
+    pub remaining_words: Option<u32>,
+    pub deadline: Option<NaiveDate>,
+    pub days_remaining: Option<i64>,
+    pub daily_word_quota: Option<u32>,
+    pub required_words_per_day: Option<u32>,
+    // 最近一段时间内平均每天净增字数
+    pub average_words_per_day: f64,
+    pub projected_completion_date: Option<NaiveDate>,
+    pub status: GoalStatus,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateProjectData {
     pub name: String,
@@ -64,6 +131,11 @@ impl Project {
             documents_count: 0,
             words_count: 0,
             workspace_id: data.workspace_id,
+            target_word_count: None,
+            deadline: None,
+            daily_word_quota: None,
+            progress_mode: ProgressMode::default(),
+            writing_preferences: ProjectWritingPreferences::default(),
             created_at: now,
             updated_at: now,
         }
@@ -1,11 +1,14 @@
 use serde::{Serialize, Deserialize};
 
+// AI 提供商统一保存在 ai_providers 表中，配置中只记录默认提供商的 id
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIProvider {
     pub id: String,
     pub name: String,
+    #[serde(default = "default_provider_type")]
+    pub provider_type: String,  // openai/anthropic/deepseek/kimi/qwen/zhipu/custom
     pub model_name: String,
-    pub api_key: String,
+    pub api_key: String,        // secret:// 引用
     pub base_url: Option<String>,
     pub icon: String,
     pub bg_color: String,
@@ -13,6 +16,10 @@ pub struct AIProvider {
     pub status_text: String,
     pub max_tokens: i64,
     pub context_length: i64,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
     pub last_tested: String,
     pub description: Option<String>,
     pub priority: i64,
@@ -22,6 +29,8 @@ pub struct AIProvider {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateAIProviderInput {
     pub name: String,
+    #[serde(default = "default_provider_type")]
+    pub provider_type: String,
     pub model_name: String,
     pub api_key: String,
     pub base_url: Option<String>,
@@ -29,8 +38,25 @@ pub struct CreateAIProviderInput {
     pub bg_color: String,
     pub max_tokens: i64,
     pub context_length: i64,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
     pub description: Option<String>,
     pub priority: i64,
     pub model_pointer: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AIProviderStats {
+    pub connected: u32,
+    pub total: u32,
+}
+
+fn default_provider_type() -> String {
+    "custom".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
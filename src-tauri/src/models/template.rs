@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::document::{DocumentStatus, DocumentType};
+use super::project::{ProgressMode, ProjectWritingPreferences};
+
+// 模板中的 {{name}} 占位符，创建文档时向用户询问，未填写时使用默认值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub default_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    // 标题和正文都可以包含占位符
+    pub title: String,
+    pub content: String,
+    pub content_type: DocumentType,
+    pub tags: Vec<String>,
+    pub variables: Vec<TemplateVariable>,
+    // 内置模板随应用更新，不能修改或删除
+    pub built_in: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 新建、更新和导入模板使用的数据；正文中未声明的占位符会自动补为变量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateData {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub content_type: DocumentType,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+}
+
+// 导出文件的格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateBundle {
+    pub version: u32,
+    pub templates: Vec<TemplateData>,
+}
+
+impl From<DocumentTemplate> for TemplateData {
+    fn from(template: DocumentTemplate) -> Self {
+        Self {
+            name: template.name,
+            description: template.description,
+            category: template.category,
+            title: template.title,
+            content: template.content,
+            content_type: template.content_type,
+            tags: template.tags,
+            variables: template.variables,
+        }
+    }
+}
+
+// 项目模板中的初始文档，标题和正文可以使用内置变量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateSeedDocument {
+    pub title: String,
+    #[serde(default)]
+    pub folder_path: Option<String>,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub content_type: DocumentType,
+    #[serde(default)]
+    pub status: DocumentStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTemplateData {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // 创建项目时未选择图标和颜色才使用
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub color: String,
+    // 文件夹路径，如 "正文/第一卷"，按顺序创建
+    #[serde(default)]
+    pub folders: Vec<String>,
+    #[serde(default)]
+    pub documents: Vec<TemplateSeedDocument>,
+    // 项目标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub target_word_count: Option<u32>,
+    #[serde(default)]
+    pub daily_word_quota: Option<u32>,
+    #[serde(default)]
+    pub progress_mode: ProgressMode,
+    #[serde(default)]
+    pub writing_preferences: ProjectWritingPreferences,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub id: String,
+    #[serde(flatten)]
+    pub template: ProjectTemplateData,
+    pub built_in: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingActivity {
+    pub document_id: String,
+    pub project_id: String,
+    pub workspace_id: String,
+    pub recorded_at: DateTime<Utc>,
+    pub words_delta: i64,
+    pub active_seconds: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WritingStatsFilter {
+    pub workspace_id: Option<String>,
+    pub project_id: Option<String>,
+    pub document_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingStatsBucket {
+    // 按用户本地时区计算的区间起始日期
+    pub start: NaiveDate,
+    pub words_added: i64,
+    pub words_deleted: i64,
+    pub net_words: i64,
+    pub active_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingStats {
+    pub period: StatsPeriod,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub buckets: Vec<WritingStatsBucket>,
+    pub words_added: i64,
+    pub words_deleted: i64,
+    pub net_words: i64,
+    pub active_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingStreak {
+    pub current_days: u32,
+    pub longest_days: u32,
+    pub last_writing_day: Option<NaiveDate>,
+}
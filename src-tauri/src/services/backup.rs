@@ -1,21 +1,312 @@
 // 备份服务模块
-// 用于自动备份和文档恢复
+// 定时把 writeflow.db 快照到备份目录，并按小时、天、周槽位清理旧的定时备份；
+// 恢复前校验备份并先为当前数据库创建安全快照
 
-#[allow(dead_code)]
-pub struct BackupService;
+use crate::error::AppError;
+use crate::models::backup::{BackupInfo, BackupKind, BackupStatus, RestoreReport, RestoreScope};
+use crate::models::config::GeneralConfig;
+use crate::services::database::Database;
+use crate::services::migration;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use tokio::fs;
+
+// 定时备份的保留槽位：最近 24 个小时各一份，最近 7 天各一份，最近 4 周各一份。
+// 手动备份以及迁移、恢复前的安全快照不会被自动清理
+pub const HOURLY_SLOTS: usize = 24;
+pub const DAILY_SLOTS: usize = 7;
+pub const WEEKLY_SLOTS: usize = 4;
+
+const BACKUP_EXTENSION: &str = "db";
+
+fn kind_prefix(kind: BackupKind) -> &'static str {
+    match kind {
+        BackupKind::Scheduled => "scheduled-",
+        BackupKind::Manual => "manual-",
+        BackupKind::PreMigration => "pre-migration-",
+        BackupKind::PreRestore => "pre-restore-",
+    }
+}
+
+fn kind_of(file_name: &str) -> Option<BackupKind> {
+    if !file_name.ends_with(&format!(".{}", BACKUP_EXTENSION)) {
+        return None;
+    }
+    [BackupKind::Scheduled, BackupKind::Manual, BackupKind::PreMigration, BackupKind::PreRestore]
+        .into_iter()
+        .find(|kind| file_name.starts_with(kind_prefix(*kind)))
+}
+
+/// 返回按保留策略应删除的定时备份 id，`backups` 的顺序无要求
+pub fn expired_backups(backups: &[BackupInfo]) -> Vec<String> {
+    let mut scheduled: Vec<&BackupInfo> = backups.iter().filter(|b| b.kind == BackupKind::Scheduled).collect();
+    scheduled.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+
+    let mut hours = HashSet::new();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut expired = Vec::new();
+
+    // 从新到旧遍历，每个时间段内最新的一份占用该段的槽位
+    for backup in scheduled {
+        let local = backup.created_at.with_timezone(&Local);
+        let day = local.date_naive();
+        let week = day.iso_week();
+
+        let mut keep = false;
+        if hours.insert((day, local.hour())) && hours.len() <= HOURLY_SLOTS {
+            keep = true;
+        }
+        if days.insert(day) && days.len() <= DAILY_SLOTS {
+            keep = true;
+        }
+        if weeks.insert((week.year(), week.week())) && weeks.len() <= WEEKLY_SLOTS {
+            keep = true;
+        }
+        if !keep {
+            expired.push(backup.id.clone());
+        }
+    }
+    expired
+}
+
+#[derive(Clone)]
+pub struct BackupService {
+    database: Database,
+    directory: PathBuf,
+}
 
-#[allow(dead_code)]
 impl BackupService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(database: Database) -> Result<Self> {
+        Ok(Self {
+            database,
+            directory: Database::backups_directory()?,
+        })
     }
-    
-    // TODO: 实现备份功能
-    pub async fn create_backup(&self) -> anyhow::Result<String> {
-        Ok("backup-placeholder".to_string())
+
+    /// 列出备份目录中的所有备份，最新的在前
+    pub async fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        let mut entries = match fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(kind) = kind_of(&file_name) else { continue };
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            backups.push(BackupInfo {
+                id: file_name,
+                kind,
+                path: entry.path().display().to_string(),
+                size_bytes: metadata.len(),
+                created_at: DateTime::<Utc>::from(metadata.modified()?),
+            });
+        }
+
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        Ok(backups)
     }
-    
-    pub async fn restore_backup(&self, _backup_id: &str) -> anyhow::Result<()> {
+
+    /// 先快照到临时文件再重命名，列表中不会出现写了一半的备份
+    pub async fn create_backup(&self, kind: BackupKind) -> Result<BackupInfo> {
+        fs::create_dir_all(&self.directory).await?;
+
+        let file_name = format!(
+            "{}{}.{}",
+            kind_prefix(kind),
+            Utc::now().format("%Y%m%dT%H%M%S%3fZ"),
+            BACKUP_EXTENSION
+        );
+        let path = self.directory.join(&file_name);
+        let partial = self.directory.join(format!("{}.partial", file_name));
+        if fs::try_exists(&partial).await? {
+            fs::remove_file(&partial).await?;
+        }
+
+        self.database.snapshot_to(&partial).await?;
+        fs::rename(&partial, &path).await?;
+
+        let metadata = fs::metadata(&path).await?;
+        Ok(BackupInfo {
+            id: file_name,
+            kind,
+            path: path.display().to_string(),
+            size_bytes: metadata.len(),
+            created_at: DateTime::<Utc>::from(metadata.modified()?),
+        })
+    }
+
+    // 只接受列表中存在的文件，避免 id 中带路径
+    async fn find_backup(&self, backup_id: &str) -> Result<BackupInfo> {
+        self.list_backups()
+            .await?
+            .into_iter()
+            .find(|b| b.id == backup_id)
+            .ok_or_else(|| AppError::not_found("Backup", backup_id).into())
+    }
+
+    pub async fn delete_backup(&self, backup_id: &str) -> Result<()> {
+        let backup = self.find_backup(backup_id).await?;
+        fs::remove_file(&backup.path).await?;
         Ok(())
     }
+
+    /// 校验备份的完整性与结构版本，创建安全快照后按范围恢复，并返回恢复前后的实体数量
+    pub async fn restore_backup(&self, backup_id: &str, scope: RestoreScope) -> Result<RestoreReport> {
+        let backup = self.find_backup(backup_id).await?;
+        let path = PathBuf::from(&backup.path);
+
+        let schema_version = Database::inspect_snapshot(&path).await?;
+        let latest = migration::latest_version();
+        if schema_version == 0 {
+            return Err(AppError::validation(format!("Backup {} is not a WriteFlow database", backup_id)).into());
+        }
+        if schema_version > latest {
+            return Err(AppError::validation(format!(
+                "Backup {} has schema version {}, newer than this build supports ({})",
+                backup_id, schema_version, latest
+            ))
+            .into());
+        }
+        // 部分恢复直接按列读取备份，要求结构一致；旧版本备份只能整体恢复后由迁移升级
+        if !matches!(scope, RestoreScope::Full) && schema_version != latest {
+            return Err(AppError::validation(format!(
+                "Backup {} has schema version {}; partial restore needs version {}, restore the full backup instead",
+                backup_id, schema_version, latest
+            ))
+            .into());
+        }
+
+        let safety = self.create_backup(BackupKind::PreRestore).await?;
+        let before = self.database.entity_counts().await?;
+        match &scope {
+            RestoreScope::Full => self.database.replace_with_snapshot(&path).await?,
+            RestoreScope::Project(project_id) => self.database.restore_project_from_snapshot(&path, project_id).await?,
+            RestoreScope::Document(document_id) => self.database.restore_document_from_snapshot(&path, document_id).await?,
+        }
+        let after = self.database.entity_counts().await?;
+
+        Ok(RestoreReport {
+            backup_id: backup.id,
+            scope,
+            schema_version,
+            safety_backup_id: safety.id,
+            before,
+            after,
+        })
+    }
+
+    /// 删除超出保留槽位的定时备份，返回被删除的 id
+    pub async fn apply_retention(&self) -> Result<Vec<String>> {
+        let expired = expired_backups(&self.list_backups().await?);
+        for backup_id in &expired {
+            fs::remove_file(self.directory.join(backup_id)).await?;
+        }
+        Ok(expired)
+    }
+
+    pub async fn status(&self, general: &GeneralConfig) -> Result<BackupStatus> {
+        let backups = self.list_backups().await?;
+        let last_backup = backups.first().map(|b| b.created_at);
+        let next_backup = general.backup_enabled.then(|| match last_backup {
+            Some(last) => last + Self::interval(general),
+            None => Utc::now(),
+        });
+
+        Ok(BackupStatus {
+            enabled: general.backup_enabled,
+            interval_minutes: general.backup_interval,
+            last_backup,
+            next_backup,
+            backup_count: backups.len() as u32,
+            total_size_bytes: backups.iter().map(|b| b.size_bytes).sum(),
+        })
+    }
+
+    fn interval(general: &GeneralConfig) -> chrono::Duration {
+        chrono::Duration::minutes(general.backup_interval.max(1) as i64)
+    }
+
+    /// 距离上次备份超过配置的间隔时创建定时备份并执行保留策略
+    pub async fn run_scheduled(&self, general: &GeneralConfig) -> Result<Option<BackupInfo>> {
+        if !general.backup_enabled {
+            return Ok(None);
+        }
+
+        let last_backup = self
+            .list_backups()
+            .await?
+            .into_iter()
+            .find(|b| b.kind != BackupKind::PreMigration)
+            .map(|b| b.created_at);
+        if last_backup.is_some_and(|last| Utc::now() < last + Self::interval(general)) {
+            return Ok(None);
+        }
+
+        let backup = self.create_backup(BackupKind::Scheduled).await?;
+        let expired = self.apply_retention().await?;
+        if !expired.is_empty() {
+            println!("Removed {} expired backups", expired.len());
+        }
+        Ok(Some(backup))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn backup(id: String, kind: BackupKind, created_at: DateTime<Local>) -> BackupInfo {
+        BackupInfo { id, kind, path: String::new(), size_bytes: 0, created_at: created_at.with_timezone(&Utc) }
+    }
+
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, month, day, hour, minute, 0).single().unwrap()
+    }
+
+    #[test]
+    fn scheduled_backups_keep_the_newest_per_hour_day_and_week_slot() {
+        let mut backups = Vec::new();
+        let mut expected = Vec::new();
+        let mut scheduled = |id: String, created_at: DateTime<Local>, expires: bool| {
+            if expires {
+                expected.push(id.clone());
+            }
+            backups.push(backup(id, BackupKind::Scheduled, created_at));
+        };
+
+        // 2026-06-17 是周三；同一小时内只保留最新的一份
+        scheduled("same-hour-25".into(), at(6, 17, 12, 25), false);
+        scheduled("same-hour-20".into(), at(6, 17, 12, 20), true);
+        scheduled("same-hour-10".into(), at(6, 17, 12, 10), true);
+        // 再往前 23 个小时占满小时槽位，之后同一天内的备份被清理
+        for hours in 1..=30 {
+            scheduled(format!("hourly-{hours}"), at(6, 17, 12, 5) - Duration::hours(hours), hours > 23);
+        }
+        // 天槽位共 7 个：今天、昨天以及 15 日到 11 日；之后同一周内的备份被清理
+        for day in (8..=15).rev() {
+            scheduled(format!("daily-{day}"), at(6, day, 9, 0), day < 11);
+        }
+        // 周槽位共 4 个：本周、上周以及 6 月 3 日和 5 月 27 日所在的周
+        scheduled("weekly-06-03".into(), at(6, 3, 9, 0), false);
+        scheduled("weekly-05-27".into(), at(5, 27, 9, 0), false);
+        scheduled("weekly-05-20".into(), at(5, 20, 9, 0), true);
+        backups.push(backup("manual".into(), BackupKind::Manual, at(5, 1, 9, 0)));
+        backups.push(backup("pre-migration".into(), BackupKind::PreMigration, at(4, 1, 9, 0)));
+        backups.reverse();
+
+        let mut expired = expired_backups(&backups);
+        expired.sort();
+        expected.sort();
+        assert_eq!(expired, expected);
+    }
 }
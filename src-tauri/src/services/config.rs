@@ -1,57 +1,78 @@
-use crate::models::config::{AppConfig, AIProvider, MCPServer, MCPConnectionType};
+use crate::models::config::{AppConfig, MCPServer, MCPConnectionType};
 use crate::services::database::Database;
 use anyhow::Result;
 use tokio::fs;
+use tokio::sync::RwLock;
 use std::path::Path;
+use std::sync::Arc;
 
-pub struct ConfigService;
+// 配置服务：基于应用共享的数据库连接，并在内存中缓存当前配置。
+// 所有写入都持有缓存写锁，读写顺序一致，不会出现并发加载互相覆盖的问题
+#[derive(Clone)]
+pub struct ConfigService {
+    database: Database,
+    cache: Arc<RwLock<Option<AppConfig>>>,
+}
 
 impl ConfigService {
-    pub async fn get_config() -> Result<AppConfig> {
-        let db = Database::new().await?;
-        
-        if let Some(mut config) = db.get_config().await? {
-            // 检查并迁移配置以确保包含所有必需字段
-            let mut needs_update = false;
-            
-            // 确保 ai_providers 字段存在
-            if config.ai_providers.providers.is_empty() && config.ai_providers.default_provider.is_none() {
-                // 这可能是旧配置，需要初始化新字段
-                needs_update = true;
-            }
-            
-            // 如果需要更新，保存迁移后的配置
-            if needs_update {
-                config.updated_at = chrono::Utc::now();
-                db.save_config(config.clone()).await?;
-                println!("Configuration migrated to include new fields");
-            }
-            
+    pub fn new(database: Database) -> Self {
+        Self {
+            database,
+            cache: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub async fn get_config(&self) -> Result<AppConfig> {
+        if let Some(config) = self.cache.read().await.as_ref() {
+            return Ok(config.clone());
+        }
+
+        let mut cache = self.cache.write().await;
+        // 等待写锁期间可能已有其他调用完成了加载
+        if let Some(config) = cache.as_ref() {
+            return Ok(config.clone());
+        }
+
+        let config = self.load_config().await?;
+        *cache = Some(config.clone());
+        Ok(config)
+    }
+
+    /// 丢弃缓存并从数据库重新加载，用于数据库被整体替换之后
+    pub async fn reload(&self) -> Result<AppConfig> {
+        let mut cache = self.cache.write().await;
+        let config = self.load_config().await?;
+        *cache = Some(config.clone());
+        Ok(config)
+    }
+
+    async fn load_config(&self) -> Result<AppConfig> {
+        if let Some(config) = self.database.get_config().await? {
             Ok(config)
         } else {
             let default_config = AppConfig::default();
-            db.save_config(default_config.clone()).await?;
+            self.database.save_config(default_config.clone()).await?;
             println!("Created default configuration");
             Ok(default_config)
         }
     }
 
-    pub async fn save_config(mut config: AppConfig) -> Result<()> {
+    /// 保存配置并更新缓存，返回实际保存的配置
+    pub async fn save_config(&self, mut config: AppConfig) -> Result<AppConfig> {
+        let mut cache = self.cache.write().await;
         config.updated_at = chrono::Utc::now();
         
-        let db = Database::new().await?;
-        db.save_config(config).await?;
+        self.database.save_config(config.clone()).await?;
+        *cache = Some(config.clone());
         
-        Ok(())
+        Ok(config)
     }
 
-    pub async fn import_config(file_path: &str) -> Result<AppConfig> {
+    pub async fn import_config(&self, file_path: &str) -> Result<AppConfig> {
         let content = fs::read_to_string(file_path).await?;
         let config: AppConfig = serde_json::from_str(&content)?;
         
-        Self::save_config(config.clone()).await?;
-        
-        Ok(config)
+        self.save_config(config).await
     }
 
     pub async fn export_config(config: AppConfig, file_path: &str) -> Result<()> {
@@ -66,39 +87,8 @@ impl ConfigService {
         Ok(())
     }
 
-    pub async fn reset_config() -> Result<AppConfig> {
-        let default_config = AppConfig::default();
-        Self::save_config(default_config.clone()).await?;
-        Ok(default_config)
-    }
-
-    pub async fn test_ai_provider(provider: AIProvider) -> Result<bool> {
-        // 基本验证
-        if provider.api_key.is_none() || provider.api_key.as_ref().unwrap().is_empty() {
-            return Ok(false);
-        }
-
-        // 简单的 HTTP 请求测试（这里可以根据具体提供商实现更详细的测试）
-        match provider.provider_type.as_str() {
-            "openai" => {
-                let client = reqwest::Client::new();
-                let base_url = provider.api_base.unwrap_or_else(|| "https://api.openai.com/v1".to_string());
-                let response = client
-                    .get(format!("{}/models", base_url))
-                    .header("Authorization", format!("Bearer {}", provider.api_key.unwrap()))
-                    .send()
-                    .await;
-                
-                match response {
-                    Ok(resp) => Ok(resp.status().is_success()),
-                    Err(_) => Ok(false),
-                }
-            }
-            _ => {
-                // 对于其他提供商，暂时只检查 API key 是否存在
-                Ok(provider.api_key.is_some() && !provider.api_key.unwrap().is_empty())
-            }
-        }
+    pub async fn reset_config(&self) -> Result<AppConfig> {
+        self.save_config(AppConfig::default()).await
     }
 
     pub async fn test_mcp_server(server: MCPServer) -> Result<bool> {
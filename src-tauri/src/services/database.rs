@@ -1,19 +1,42 @@
 use crate::models::{
-    project::{Project, CreateProjectData, ProjectListResult, ProjectStatus},
+    project::{Project, CreateProjectData, ProjectForecast, ProjectGoals, ProjectListResult, ProjectStatus, ProjectWritingPreferences},
     workspace::{Workspace, CreateWorkspaceData},
-    document::{Document, CreateDocumentData},
+    document::{
+        Document, CreateDocumentData, DocumentListQuery, DocumentListResult, DocumentMetadata, DocumentSearchHit,
+        DocumentSearchResult, DocumentStatus, DocumentSummary,
+    },
     config::AppConfig,
     agent::{AgentModel, InstallAgentInput},
-    provider::{AIProvider, CreateAIProviderInput},
+    backup::EntityCounts,
+    provider::{AIProvider, AIProviderStats, CreateAIProviderInput},
+    revision::{DocumentRevision, DocumentRevisionDetail, RevisionDiff},
+    trash::{TrashItem, TrashItemKind},
+    folder::{CreateFolderData, Folder, FolderNode, ProjectTree, TreeDocument},
+    tag::{Tag, TagUsage},
+    template::{DocumentTemplate, ProjectTemplate, ProjectTemplateData, TemplateData},
+    writing::{StatsPeriod, WritingActivity, WritingStats, WritingStatsFilter, WritingStreak},
 };
-use sqlx::{SqlitePool, Row};
+use crate::error::AppError;
+use crate::services::{goals, migration, revision, search, secrets, templates, writing_stats};
+use crate::services::templates::TemplateContext;
+use sqlx::{sqlite::{SqliteConnectOptions, SqliteRow}, Connection, Row, Sqlite, SqliteConnection, SqlitePool, Transaction};
 use tokio::fs;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
 use anyhow::Result;
 
+const MAX_PAGE_SIZE: u32 = 500;
+
 #[derive(Clone)]
 pub struct Database {
-    pool: SqlitePool,
+    // 从备份整体恢复时会替换为新的连接池，所有克隆共享同一个槽位
+    pool: Arc<RwLock<SqlitePool>>,
+    db_path: PathBuf,
+    master_key: secrets::MasterKey,
+    // 串行化整库恢复
+    restore_lock: Arc<Mutex<()>>,
 }
 
 impl Database {
@@ -26,16 +49,53 @@ impl Database {
             return Err(e.into());
         }
         
-        let db_path = db_dir.join("writeflow.db");
+        // 访问系统钥匙串可能阻塞，放到阻塞线程中执行
+        let key_dir = db_dir.clone();
+        let master_key = tokio::task::spawn_blocking(move || secrets::load_or_create_master_key(&key_dir)).await??;
+        println!("Using master key from {:?}", master_key.source);
+
+        Self::open(db_dir.join("writeflow.db"), master_key).await
+    }
+
+    // 打开指定位置的数据库，测试中使用临时目录
+    async fn open(db_path: PathBuf, master_key: secrets::MasterKey) -> Result<Self> {
+        let pool = Self::open_pool(&db_path).await?;
+        let database = Self {
+            pool: Arc::new(RwLock::new(pool)),
+            db_path,
+            master_key,
+            restore_lock: Arc::new(Mutex::new(())),
+        };
+        database.ensure_search_index().await?;
+        database.seal_plaintext_provider_keys().await?;
+        database.sync_built_in_templates().await?;
+
+        Ok(database)
+    }
+
+    async fn open_pool(db_path: &Path) -> Result<SqlitePool> {
         let database_url = format!("sqlite:{}?mode=rwc", db_path.display());
         
         println!("Connecting to database: {}", database_url);
         let pool = SqlitePool::connect(&database_url).await?;
         
-        let database = Self { pool };
-        database.init_tables().await?;
-        
-        Ok(database)
+        // 迁移前的备份放在数据库所在目录的 backups 下，与 backups_directory() 一致
+        migration::run_migrations(&pool, &db_path.with_file_name("backups")).await?;
+        Ok(pool)
+    }
+
+    // 返回读锁，在一条语句内使用（作为执行器或开启事务），不要跨语句持有。
+    // 整库恢复期间持有写锁，此时的调用会等到新连接池就位，不会用到已关闭的连接池
+    async fn pool(&self) -> RwLockReadGuard<'_, SqlitePool> {
+        self.pool.read().await
+    }
+
+    /// 分页参数来自前端：页大小限制在 1..=MAX_PAGE_SIZE，偏移量用 i64 饱和计算，超大页码不会溢出。
+    /// 返回 (LIMIT, OFFSET)
+    fn page_bounds(page: u32, page_size: u32) -> (i64, i64) {
+        let limit = page_size.clamp(1, MAX_PAGE_SIZE) as i64;
+        let offset = (page.max(1) as i64 - 1).saturating_mul(limit);
+        (limit, offset)
     }
 
     fn get_data_directory() -> Result<PathBuf> {
@@ -47,129 +107,13 @@ impl Database {
         Ok(base.join("writeflow-studio"))
     }
 
-    async fn init_tables(&self) -> Result<()> {
-        // Workspaces table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS workspaces (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL,
-                projects_count INTEGER NOT NULL DEFAULT 0,
-                status TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                last_accessed TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Projects table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS projects (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL,
-                icon TEXT NOT NULL,
-                color TEXT NOT NULL,
-                status TEXT NOT NULL,
-                progress INTEGER NOT NULL DEFAULT 0,
-                documents_count INTEGER NOT NULL DEFAULT 0,
-                words_count INTEGER NOT NULL DEFAULT 0,
-                workspace_id TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (workspace_id) REFERENCES workspaces (id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Documents table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS documents (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                content TEXT NOT NULL,
-                content_type TEXT NOT NULL,
-                status TEXT NOT NULL,
-                word_count INTEGER NOT NULL DEFAULT 0,
-                char_count INTEGER NOT NULL DEFAULT 0,
-                project_id TEXT NOT NULL,
-                folder_path TEXT,
-                tags TEXT, -- JSON array
-                metadata TEXT NOT NULL, -- JSON
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                last_accessed TEXT NOT NULL,
-                FOREIGN KEY (project_id) REFERENCES projects (id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Config table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS config (
-                id INTEGER PRIMARY KEY,
-                config_data TEXT NOT NULL, -- JSON
-                updated_at TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Agents table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS agents (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                category TEXT NOT NULL,
-                version TEXT NOT NULL,
-                enabled INTEGER NOT NULL DEFAULT 1,
-                description TEXT,
-                tags TEXT NOT NULL -- JSON array
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // AI Providers table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS ai_providers (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                model_name TEXT NOT NULL,
-                api_key TEXT NOT NULL,
-                base_url TEXT,
-                icon TEXT NOT NULL,
-                bg_color TEXT NOT NULL,
-                status TEXT NOT NULL,
-                status_text TEXT NOT NULL,
-                max_tokens INTEGER NOT NULL,
-                context_length INTEGER NOT NULL,
-                last_tested TEXT NOT NULL,
-                description TEXT,
-                priority INTEGER NOT NULL,
-                model_pointer TEXT
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    pub fn backups_directory() -> Result<PathBuf> {
+        Ok(Self::get_data_directory()?.join("backups"))
+    }
 
-        Ok(())
+    // 导入时复制的图片和附件，按项目分子目录存放
+    pub fn attachments_directory() -> Result<PathBuf> {
+        Ok(Self::get_data_directory()?.join("attachments"))
     }
 
     // Workspace operations
@@ -190,15 +134,15 @@ impl Database {
         .bind(workspace.created_at.to_rfc3339())
         .bind(workspace.updated_at.to_rfc3339())
         .bind(workspace.last_accessed.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&*self.pool().await)
         .await?;
 
         Ok(workspace)
     }
 
     pub async fn get_workspaces(&self) -> Result<Vec<Workspace>> {
-        let rows = sqlx::query("SELECT * FROM workspaces ORDER BY updated_at DESC")
-            .fetch_all(&self.pool)
+        let rows = sqlx::query("SELECT * FROM workspaces WHERE deleted_at IS NULL ORDER BY updated_at DESC")
+            .fetch_all(&*self.pool().await)
             .await?;
 
         let mut workspaces = Vec::new();
@@ -220,13 +164,36 @@ impl Database {
     }
 
     // Project operations
+    /// 创建项目。指定 template_id 时在同一事务中建好模板中的文件夹、初始文档和项目标签，并应用字数目标和写作偏好
     pub async fn create_project(&self, data: CreateProjectData) -> Result<Project> {
-        let project = Project::new(data);
-        
+        let template = match data.template_id.as_deref().filter(|id| !id.is_empty()) {
+            Some(template_id) => Some(
+                self.get_project_template(template_id)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Project template", template_id))?,
+            ),
+            None => None,
+        };
+        let mut project = Project::new(data);
+        let mut seeds = None;
+        if let Some(template) = &template {
+            templates::apply_project_template(&mut project, &template.template);
+            let workspace = self.get_workspace_by_id(&project.workspace_id).await?;
+            let mut context = TemplateContext {
+                title: String::new(),
+                project: project.name.clone(),
+                workspace: workspace.map(|w| w.name).unwrap_or_default(),
+                now: chrono::Local::now(),
+            };
+            seeds = Some(templates::seed_documents(&template.template, &project.id, &mut context)?);
+        }
+        let mut tx = self.pool().await.begin().await?;
+
         sqlx::query(
             r#"
-            INSERT INTO projects (id, name, description, icon, color, status, progress, documents_count, words_count, workspace_id, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            INSERT INTO projects (id, name, description, icon, color, status, progress, documents_count, words_count, workspace_id,
+                target_word_count, deadline, daily_word_quota, progress_mode, writing_preferences, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
             "#,
         )
         .bind(&project.id)
@@ -239,43 +206,41 @@ impl Database {
         .bind(project.documents_count as i64)
         .bind(project.words_count as i64)
         .bind(&project.workspace_id)
+        .bind(project.target_word_count.map(|v| v as i64))
+        .bind(project.deadline.map(|d| d.format("%Y-%m-%d").to_string()))
+        .bind(project.daily_word_quota.map(|v| v as i64))
+        .bind(serde_json::to_string(&project.progress_mode)?)
+        .bind(serde_json::to_string(&project.writing_preferences)?)
         .bind(project.created_at.to_rfc3339())
         .bind(project.updated_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
-        // Update workspace projects count
-        sqlx::query("UPDATE workspaces SET projects_count = projects_count + 1 WHERE id = ?1")
-            .bind(&project.workspace_id)
-            .execute(&self.pool)
-            .await?;
+        if let (Some(template), Some(seeds)) = (&template, seeds) {
+            Self::replace_project_tags(&mut tx, &project.id, &template.template.tags).await?;
+            Self::insert_documents(&mut tx, &project.id, &template.template.folders, seeds).await?;
+        }
+        Self::refresh_workspace_counters(&mut tx, &project.workspace_id).await?;
 
-        Ok(project)
+        tx.commit().await?;
+        if template.is_none() {
+            return Ok(project);
+        }
+        // 文档数、字数和进度已在事务中刷新，重新读取
+        self.get_project_by_id(&project.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", &project.id).into())
     }
 
     pub async fn get_projects_by_workspace(&self, workspace_id: &str) -> Result<Vec<Project>> {
-        let rows = sqlx::query("SELECT * FROM projects WHERE workspace_id = ?1 ORDER BY updated_at DESC")
+        let rows = sqlx::query("SELECT * FROM projects WHERE workspace_id = ?1 AND deleted_at IS NULL ORDER BY updated_at DESC")
             .bind(workspace_id)
-            .fetch_all(&self.pool)
+            .fetch_all(&*self.pool().await)
             .await?;
 
         let mut projects = Vec::new();
         for row in rows {
-            let project = Project {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                icon: row.get("icon"),
-                color: row.get("color"),
-                status: serde_json::from_str(&row.get::<String, _>("status"))?,
-                progress: row.get::<i64, _>("progress") as u32,
-                documents_count: row.get::<i64, _>("documents_count") as u32,
-                words_count: row.get::<i64, _>("words_count") as u32,
-                workspace_id: row.get("workspace_id"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
-            };
-            projects.push(project);
+            projects.push(Self::row_to_project(&row)?);
         }
 
         Ok(projects)
@@ -291,7 +256,7 @@ impl Database {
         page: u32,
         page_size: u32,
     ) -> Result<ProjectListResult> {
-        let mut where_clauses: Vec<String> = Vec::new();
+        let mut where_clauses: Vec<String> = vec!["deleted_at IS NULL".to_string()];
         let mut args: Vec<String> = Vec::new();
 
         if let Some(wid) = workspace_id {
@@ -327,14 +292,13 @@ impl Database {
             _ => ("updated_at", "DESC"),
         };
 
-        let limit = page_size.max(1) as i64;
-        let offset = ((page.max(1) - 1) * page_size) as i64;
+        let (limit, offset) = Self::page_bounds(page, page_size);
 
         // Build count query
         let count_sql = format!("SELECT COUNT(*) as count FROM projects{}", where_sql);
         let mut count_q = sqlx::query(&count_sql);
         for a in &args { count_q = count_q.bind(a); }
-        let count_row = count_q.fetch_one(&self.pool).await?;
+        let count_row = count_q.fetch_one(&*self.pool().await).await?;
         let total: u32 = count_row.get::<i64, _>("count") as u32;
 
         // Build list query
@@ -342,25 +306,11 @@ impl Database {
         let mut list_q = sqlx::query(&list_sql);
         for a in &args { list_q = list_q.bind(a); }
         list_q = list_q.bind(limit).bind(offset);
-        let rows = list_q.fetch_all(&self.pool).await?;
+        let rows = list_q.fetch_all(&*self.pool().await).await?;
 
         let mut items = Vec::new();
         for row in rows {
-            let project = Project {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                icon: row.get("icon"),
-                color: row.get("color"),
-                status: serde_json::from_str(&row.get::<String, _>("status"))?,
-                progress: row.get::<i64, _>("progress") as u32,
-                documents_count: row.get::<i64, _>("documents_count") as u32,
-                words_count: row.get::<i64, _>("words_count") as u32,
-                workspace_id: row.get("workspace_id"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
-            };
-            items.push(project);
+            items.push(Self::row_to_project(&row)?);
         }
 
         Ok(ProjectListResult { items, total })
@@ -369,16 +319,52 @@ impl Database {
     // Document operations
     pub async fn create_document(&self, data: CreateDocumentData) -> Result<Document> {
         // 验证项目是否存在
-        if self.get_project_by_id(&data.project_id).await?.is_none() {
-            anyhow::bail!(format!("Project not found: {}", data.project_id));
+        let project = self
+            .get_project_by_id(&data.project_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", &data.project_id))?;
+
+        let data = match data.template_id.clone().filter(|id| !id.is_empty()) {
+            Some(template_id) => {
+                let template = self
+                    .get_template(&template_id)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Template", &template_id))?;
+                let workspace = self.get_workspace_by_id(&project.workspace_id).await?;
+                let mut context = TemplateContext {
+                    title: String::new(),
+                    project: project.name,
+                    workspace: workspace.map(|w| w.name).unwrap_or_default(),
+                    now: chrono::Local::now(),
+                };
+                templates::instantiate(&template, data, &mut context)?
+            }
+            None => data,
+        };
+        let mut document = Document::new(data);
+        let mut tx = self.pool().await.begin().await?;
+
+        // 优先使用 folder_id；只提供 folder_path 时按路径查找或创建对应的文件夹
+        if let Some(folder_id) = &document.folder_id {
+            Self::ensure_folder_in_project(&mut tx, folder_id, &document.project_id).await?;
+        } else if let Some(path) = document.folder_path.as_deref() {
+            document.folder_id = Self::ensure_folder_path(&mut tx, &document.project_id, path).await?;
         }
+        document.folder_path = Self::folder_path_of(&mut tx, document.folder_id.as_deref()).await?;
+        document.sort_order = Self::next_document_sort_order(&mut tx, &document.project_id, document.folder_id.as_deref()).await?;
+        Self::insert_document(&mut tx, &mut document).await?;
+        Self::refresh_project_counters(&mut tx, &document.project_id).await?;
 
-        let document = Document::new(data);
-        
+        tx.commit().await?;
+        Ok(document)
+    }
+
+    // 写入文档行并同步标签和全文索引；文件夹、排序和项目计数由调用方处理
+    async fn insert_document(conn: &mut SqliteConnection, document: &mut Document) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO documents (id, title, content, content_type, status, word_count, char_count, project_id, folder_path, tags, metadata, created_at, updated_at, last_accessed)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            INSERT INTO documents (id, title, content, content_type, status, word_count, char_count, project_id, folder_path, folder_id, sort_order, tags, metadata, created_at, updated_at, last_accessed)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
             "#,
         )
         .bind(&document.id)
@@ -390,231 +376,1392 @@ impl Database {
         .bind(document.char_count as i64)
         .bind(&document.project_id)
         .bind(&document.folder_path)
+        .bind(&document.folder_id)
+        .bind(document.sort_order)
         .bind(serde_json::to_string(&document.tags)?)
         .bind(serde_json::to_string(&document.metadata)?)
         .bind(document.created_at.to_rfc3339())
         .bind(document.updated_at.to_rfc3339())
         .bind(document.last_accessed.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| {
             println!("SQL error inserting document (project_id={}): {}", document.project_id, e);
             e
         })?;
 
-        // Update project documents count and words count
-        sqlx::query("UPDATE projects SET documents_count = documents_count + 1, words_count = words_count + ?1 WHERE id = ?2")
-            .bind(document.word_count as i64)
-            .bind(&document.project_id)
-            .execute(&self.pool)
-            .await?;
+        document.tags = Self::set_document_tags(conn, &document.id, &document.tags).await?;
+        Self::reindex_document(conn, &document.id).await?;
+        Ok(())
+    }
 
-        Ok(document)
+    /// 在一个事务中批量写入导入的文档，保留其中的状态、标签、元数据和时间戳。
+    /// 按 folder_path 查找或创建文件夹，同一文件夹内按传入顺序排列；id 已被占用时换用新 id
+    pub async fn import_documents(&self, project_id: &str, folders: &[String], documents: Vec<Document>) -> Result<Vec<Document>> {
+        if self.get_project_by_id(project_id).await?.is_none() {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        let mut tx = self.pool().await.begin().await?;
+        let imported = Self::insert_documents(&mut tx, project_id, folders, documents).await?;
+        tx.commit().await?;
+        Ok(imported)
     }
 
-    pub async fn get_documents_by_project(&self, project_id: &str) -> Result<Vec<Document>> {
-        let rows = sqlx::query("SELECT * FROM documents WHERE project_id = ?1 ORDER BY updated_at DESC")
-            .bind(project_id)
-            .fetch_all(&self.pool)
-            .await?;
+    // import_documents 和按模板创建项目共用，最后刷新项目计数
+    async fn insert_documents(conn: &mut SqliteConnection, project_id: &str, folders: &[String], documents: Vec<Document>) -> Result<Vec<Document>> {
+        // 先按顺序建好文件夹，空文件夹和文件夹的先后次序也能还原
+        for path in folders {
+            Self::ensure_folder_path(&mut *conn, project_id, path).await?;
+        }
+
+        let mut imported = Vec::with_capacity(documents.len());
+        for mut document in documents {
+            let taken = sqlx::query("SELECT 1 FROM documents WHERE id = ?1")
+                .bind(&document.id)
+                .fetch_optional(&mut *conn)
+                .await?
+                .is_some();
+            if taken || document.id.trim().is_empty() {
+                document.id = uuid::Uuid::new_v4().to_string();
+            }
+            document.project_id = project_id.to_string();
+            document.folder_id = match document.folder_path.as_deref() {
+                Some(path) => Self::ensure_folder_path(&mut *conn, project_id, path).await?,
+                None => None,
+            };
+            document.folder_path = Self::folder_path_of(&mut *conn, document.folder_id.as_deref()).await?;
+            document.sort_order = Self::next_document_sort_order(&mut *conn, project_id, document.folder_id.as_deref()).await?;
+            Self::insert_document(&mut *conn, &mut document).await?;
+            imported.push(document);
+        }
+        Self::refresh_project_counters(&mut *conn, project_id).await?;
+        Ok(imported)
+    }
+
+    pub async fn get_documents_by_project(&self, project_id: &str) -> Result<Vec<DocumentSummary>> {
+        let sql = format!(
+            "SELECT {} FROM documents d WHERE d.project_id = ?1 AND d.deleted_at IS NULL ORDER BY d.updated_at DESC",
+            Self::DOCUMENT_SUMMARY_COLUMNS
+        );
+        let rows = sqlx::query(&sql).bind(project_id).fetch_all(&*self.pool().await).await?;
 
         let mut documents = Vec::new();
         for row in rows {
-            let document = Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_type: serde_json::from_str(&row.get::<String, _>("content_type"))?,
-                status: serde_json::from_str(&row.get::<String, _>("status"))?,
-                word_count: row.get::<i64, _>("word_count") as u32,
-                char_count: row.get::<i64, _>("char_count") as u32,
-                project_id: row.get("project_id"),
-                folder_path: row.get("folder_path"),
-                tags: serde_json::from_str(&row.get::<String, _>("tags"))?,
-                metadata: serde_json::from_str(&row.get::<String, _>("metadata"))?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
-                last_accessed: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("last_accessed"))?.with_timezone(&chrono::Utc),
-            };
-            documents.push(document);
+            documents.push(Self::row_to_document_summary(&row)?);
         }
 
         Ok(documents)
     }
 
-    // Config operations
-    pub async fn get_config(&self) -> Result<Option<AppConfig>> {
-        let row = sqlx::query("SELECT config_data FROM config WHERE id = 1")
-            .fetch_optional(&self.pool)
-            .await?;
+    // 列表查询只读取这些列，避免把正文传给前端
+    const DOCUMENT_SUMMARY_COLUMNS: &'static str = "d.id, d.title, d.content_type, d.status, d.word_count, d.char_count, d.project_id, \
+        d.folder_path, d.folder_id, d.sort_order, d.tags, d.created_at, d.updated_at, d.last_accessed";
+
+    fn row_to_document_summary(row: &SqliteRow) -> Result<DocumentSummary> {
+        Ok(DocumentSummary {
+            id: row.get("id"),
+            title: row.get("title"),
+            content_type: serde_json::from_str(&row.get::<String, _>("content_type")).unwrap_or_default(),
+            status: serde_json::from_str(&row.get::<String, _>("status")).unwrap_or_default(),
+            word_count: row.get::<i64, _>("word_count") as u32,
+            char_count: row.get::<i64, _>("char_count") as u32,
+            project_id: row.get("project_id"),
+            folder_path: row.get("folder_path"),
+            folder_id: row.get("folder_id"),
+            sort_order: row.get::<i64, _>("sort_order"),
+            tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+            last_accessed: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("last_accessed"))?.with_timezone(&chrono::Utc),
+        })
+    }
 
-        if let Some(row) = row {
-            let config_str: String = row.get("config_data");
-            println!("Loading config from database, length: {} chars", config_str.len());
-            
-            match serde_json::from_str::<AppConfig>(&config_str) {
-                Ok(config) => {
-                    println!("Successfully parsed config with {} AI providers, {} MCP servers", 
-                        config.ai_providers.providers.len(),
-                        config.mcp_servers.servers.len()
-                    );
-                    Ok(Some(config))
-                },
-                Err(e) => {
-                    println!("Failed to parse config from database: {}. Using default config.", e);
-                    // 如果解析失败，可能是旧版本的配置，返回 None 让上层创建默认配置
-                    Ok(None)
-                }
+    /// 分页列出文档摘要，支持按项目、工作区、文件夹、状态、标签和标题筛选
+    pub async fn list_documents(&self, list_query: &DocumentListQuery) -> Result<DocumentListResult> {
+        let mut where_clauses: Vec<String> = vec!["d.deleted_at IS NULL".to_string(), "p.deleted_at IS NULL".to_string()];
+        let mut args: Vec<String> = Vec::new();
+
+        if let Some(pid) = &list_query.project_id {
+            where_clauses.push("d.project_id = ?".to_string());
+            args.push(pid.clone());
+        }
+
+        if let Some(wid) = &list_query.workspace_id {
+            where_clauses.push("p.workspace_id = ?".to_string());
+            args.push(wid.clone());
+        }
+
+        if let Some(fid) = &list_query.folder_id {
+            where_clauses.push("d.folder_id = ?".to_string());
+            args.push(fid.clone());
+        }
+
+        if let Some(st) = &list_query.status {
+            if let Ok(parsed) = serde_json::from_value::<DocumentStatus>(serde_json::Value::String(st.clone())) {
+                where_clauses.push("d.status = ?".to_string());
+                args.push(serde_json::to_string(&parsed)?);
             }
-        } else {
-            println!("No config found in database, will create default");
-            Ok(None)
         }
-    }
 
-    pub async fn save_config(&self, config: AppConfig) -> Result<()> {
-        let config_data = serde_json::to_string(&config)?;
-        
-        sqlx::query(
-            r#"
-            INSERT OR REPLACE INTO config (id, config_data, updated_at)
-            VALUES (1, ?1, ?2)
-            "#,
-        )
-        .bind(&config_data)
-        .bind(config.updated_at.to_rfc3339())
-        .execute(&self.pool)
-        .await?;
+        if let Some(tags) = list_query.tags.as_ref().filter(|t| !t.is_empty()) {
+            let placeholders = vec!["?"; tags.len()].join(", ");
+            where_clauses.push(format!(
+                "EXISTS (SELECT 1 FROM document_tags dt JOIN tags t ON t.id = dt.tag_id WHERE dt.document_id = d.id AND t.name IN ({}))",
+                placeholders
+            ));
+            args.extend(tags.iter().cloned());
+        }
 
-        Ok(())
-    }
+        if let Some(q) = list_query.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            where_clauses.push("d.title LIKE ?".to_string());
+            args.push(format!("%{}%", q));
+        }
 
-    // Additional project methods
-    pub async fn get_projects(&self) -> Result<Vec<Project>> {
-        let rows = sqlx::query("SELECT * FROM projects ORDER BY updated_at DESC")
-            .fetch_all(&self.pool)
-            .await?;
+        let from_sql = format!(" FROM documents d JOIN projects p ON p.id = d.project_id WHERE {}", where_clauses.join(" AND "));
+
+        let sort_dir = if list_query.order.as_deref().is_some_and(|o| o.eq_ignore_ascii_case("ASC")) { "ASC" } else { "DESC" };
+        // 手动顺序先按文件夹分组，排序方向同时作用于两列
+        let sort_cols: &[&str] = match list_query.sort.as_deref().unwrap_or("updated_at") {
+            "title" => &["d.title"],
+            "created_at" => &["d.created_at"],
+            "word_count" => &["d.word_count"],
+            "status" => &["d.status"],
+            "sort_order" => &["d.folder_id", "d.sort_order"],
+            _ => &["d.updated_at"],
+        };
+        let order_by: Vec<String> = sort_cols.iter().map(|col| format!("{} {}", col, sort_dir)).collect();
 
-        let mut projects = Vec::new();
+        let (limit, offset) = Self::page_bounds(list_query.page.unwrap_or(1), list_query.page_size.unwrap_or(50));
+
+        let count_sql = format!("SELECT COUNT(*) as count{}", from_sql);
+        let mut count_q = sqlx::query(&count_sql);
+        for a in &args { count_q = count_q.bind(a); }
+        let total = count_q.fetch_one(&*self.pool().await).await?.get::<i64, _>("count") as u32;
+
+        let list_sql = format!(
+            "SELECT {}{} ORDER BY {}, d.id LIMIT ? OFFSET ?",
+            Self::DOCUMENT_SUMMARY_COLUMNS, from_sql, order_by.join(", ")
+        );
+        let mut list_q = sqlx::query(&list_sql);
+        for a in &args { list_q = list_q.bind(a); }
+        let rows = list_q.bind(limit).bind(offset).fetch_all(&*self.pool().await).await?;
+
+        let mut items = Vec::new();
         for row in rows {
-            let project = Project {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                icon: row.get("icon"),
-                color: row.get("color"),
-                status: serde_json::from_str(&row.get::<String, _>("status"))?,
-                progress: row.get::<i64, _>("progress") as u32,
-                documents_count: row.get::<i64, _>("documents_count") as u32,
-                words_count: row.get::<i64, _>("words_count") as u32,
-                workspace_id: row.get("workspace_id"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
-            };
-            projects.push(project);
+            items.push(Self::row_to_document_summary(&row)?);
         }
 
-        Ok(projects)
+        Ok(DocumentListResult { items, total })
     }
 
-    pub async fn get_project_by_id(&self, project_id: &str) -> Result<Option<Project>> {
-        let row = sqlx::query("SELECT * FROM projects WHERE id = ?1")
-            .bind(project_id)
-            .fetch_optional(&self.pool)
+    // Folder operations
+
+    fn row_to_folder(row: &SqliteRow) -> Result<Folder> {
+        Ok(Folder {
+            id: row.get("id"),
+            project_id: row.get("project_id"),
+            parent_id: row.get("parent_id"),
+            name: row.get("name"),
+            sort_order: row.get::<i64, _>("sort_order"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    pub async fn get_folder_by_id(&self, folder_id: &str) -> Result<Option<Folder>> {
+        let row = sqlx::query("SELECT * FROM folders WHERE id = ?1")
+            .bind(folder_id)
+            .fetch_optional(&*self.pool().await)
             .await?;
+        row.map(|r| Self::row_to_folder(&r)).transpose()
+    }
 
-        if let Some(row) = row {
-            let project = Project {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                icon: row.get("icon"),
-                color: row.get("color"),
-                status: serde_json::from_str(&row.get::<String, _>("status"))?,
-                progress: row.get::<i64, _>("progress") as u32,
-                documents_count: row.get::<i64, _>("documents_count") as u32,
-                words_count: row.get::<i64, _>("words_count") as u32,
-                workspace_id: row.get("workspace_id"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+    async fn ensure_folder_in_project(conn: &mut SqliteConnection, folder_id: &str, project_id: &str) -> Result<()> {
+        let exists = sqlx::query("SELECT 1 FROM folders WHERE id = ?1 AND project_id = ?2")
+            .bind(folder_id)
+            .bind(project_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .is_some();
+        if !exists {
+            return Err(AppError::not_found("Folder", folder_id).into());
+        }
+        Ok(())
+    }
+
+    /// 按 `a/b/c` 形式的路径逐级查找文件夹，不存在的层级会被创建。空路径返回 None
+    async fn ensure_folder_path(conn: &mut SqliteConnection, project_id: &str, path: &str) -> Result<Option<String>, sqlx::Error> {
+        let mut parent_id: Option<String> = None;
+        for name in path.split(['/', '\\']).map(str::trim).filter(|n| !n.is_empty()) {
+            let existing: Option<String> = sqlx::query("SELECT id FROM folders WHERE project_id = ?1 AND parent_id IS ?2 AND name = ?3")
+                .bind(project_id)
+                .bind(&parent_id)
+                .bind(name)
+                .fetch_optional(&mut *conn)
+                .await?
+                .map(|r| r.get("id"));
+
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = format!("folder-{}", uuid::Uuid::new_v4());
+                    let now = chrono::Utc::now().to_rfc3339();
+                    sqlx::query(
+                        r#"
+                        INSERT INTO folders (id, project_id, parent_id, name, sort_order, created_at, updated_at)
+                        VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM folders WHERE project_id = ?2 AND parent_id IS ?3), ?5, ?5)
+                        "#,
+                    )
+                    .bind(&id)
+                    .bind(project_id)
+                    .bind(&parent_id)
+                    .bind(name)
+                    .bind(&now)
+                    .execute(&mut *conn)
+                    .await?;
+                    id
+                }
             };
-            Ok(Some(project))
-        } else {
-            Ok(None)
+            parent_id = Some(id);
         }
+        Ok(parent_id)
     }
 
-    pub async fn update_project(&self, project_id: &str, mut project: Project) -> Result<()> {
-        project.updated_at = chrono::Utc::now();
-        
+    // 由文件夹层级拼出兼容旧字段的 folder_path
+    async fn folder_path_of(conn: &mut SqliteConnection, folder_id: Option<&str>) -> Result<Option<String>> {
+        let Some(folder_id) = folder_id else { return Ok(None) };
+        let row = sqlx::query(
+            r#"
+            WITH RECURSIVE chain(id, parent_id, name, depth) AS (
+                SELECT id, parent_id, name, 0 FROM folders WHERE id = ?1
+                UNION ALL
+                SELECT f.id, f.parent_id, f.name, chain.depth + 1 FROM folders f JOIN chain ON f.id = chain.parent_id
+            )
+            SELECT group_concat(name, '/') AS path FROM (SELECT name FROM chain ORDER BY depth DESC)
+            "#,
+        )
+        .bind(folder_id)
+        .fetch_one(&mut *conn)
+        .await?;
+        Ok(row.get("path"))
+    }
+
+    // 文件夹改名或移动后刷新项目内所有文档的 folder_path
+    async fn refresh_folder_paths(conn: &mut SqliteConnection, project_id: &str) -> Result<()> {
         sqlx::query(
             r#"
-            UPDATE projects 
-            SET name = ?1, description = ?2, icon = ?3, color = ?4, status = ?5, progress = ?6, updated_at = ?7
-            WHERE id = ?8
+            WITH RECURSIVE paths(id, path) AS (
+                SELECT id, name FROM folders WHERE project_id = ?1 AND parent_id IS NULL
+                UNION ALL
+                SELECT f.id, paths.path || '/' || f.name FROM folders f JOIN paths ON f.parent_id = paths.id
+            )
+            UPDATE documents SET folder_path = (SELECT path FROM paths WHERE paths.id = documents.folder_id)
+            WHERE project_id = ?1
             "#,
         )
-        .bind(&project.name)
-        .bind(&project.description)
-        .bind(&project.icon)
-        .bind(&project.color)
-        .bind(serde_json::to_string(&project.status)?)
-        .bind(project.progress as i64)
-        .bind(project.updated_at.to_rfc3339())
         .bind(project_id)
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
-
         Ok(())
     }
 
-    pub async fn delete_project(&self, project_id: &str) -> Result<()> {
-        // First delete all documents in this project
-        sqlx::query("DELETE FROM documents WHERE project_id = ?1")
+    async fn next_document_sort_order(conn: &mut SqliteConnection, project_id: &str, folder_id: Option<&str>) -> Result<i64> {
+        let row = sqlx::query("SELECT COALESCE(MAX(sort_order) + 1, 0) AS next FROM documents WHERE project_id = ?1 AND folder_id IS ?2")
             .bind(project_id)
-            .execute(&self.pool)
+            .bind(folder_id)
+            .fetch_one(&mut *conn)
             .await?;
+        Ok(row.get("next"))
+    }
 
-        // Then delete the project
-        sqlx::query("DELETE FROM projects WHERE id = ?1")
+    /// 把条目放到同级列表的指定位置（None 表示末尾），并为同级条目重新编号
+    async fn place_in_siblings(
+        conn: &mut SqliteConnection,
+        table: &str,
+        parent_column: &str,
+        project_id: &str,
+        parent_id: Option<&str>,
+        item_id: &str,
+        position: Option<u32>,
+    ) -> Result<()> {
+        // 前端传来的位置只计可见的文档，回收站中的文档不参与排序
+        let visible = if table == "documents" { " AND deleted_at IS NULL" } else { "" };
+        let sql = format!(
+            "SELECT id FROM {} WHERE project_id = ?1 AND {} IS ?2 AND id != ?3{} ORDER BY sort_order ASC",
+            table, parent_column, visible
+        );
+        let mut ids: Vec<String> = sqlx::query(&sql)
             .bind(project_id)
-            .execute(&self.pool)
-            .await?;
-
+            .bind(parent_id)
+            .bind(item_id)
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+
+        let index = position.map(|p| (p as usize).min(ids.len())).unwrap_or(ids.len());
+        ids.insert(index, item_id.to_string());
+
+        let update = format!("UPDATE {} SET {} = ?2, sort_order = ?3 WHERE id = ?1", table, parent_column);
+        for (order, id) in ids.iter().enumerate() {
+            sqlx::query(&update)
+                .bind(id)
+                .bind(parent_id)
+                .bind(order as i64)
+                .execute(&mut *conn)
+                .await?;
+        }
         Ok(())
     }
 
-    pub async fn get_project_stats(&self) -> Result<crate::models::project::ProjectStats> {
-        use crate::models::project::{ProjectStats, ProjectStatus};
-        
-        let total_row = sqlx::query("SELECT COUNT(*) as count FROM projects")
-            .fetch_one(&self.pool)
-            .await?;
-        let total: u32 = total_row.get::<i64, _>("count") as u32;
+    pub async fn create_folder(&self, data: CreateFolderData) -> Result<Folder> {
+        if self.get_project_by_id(&data.project_id).await?.is_none() {
+            return Err(AppError::not_found("Project", &data.project_id).into());
+        }
+        let name = data.name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Folder name cannot be empty").into());
+        }
 
-        let active_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1")
-            .bind(serde_json::to_string(&ProjectStatus::Active)?)
-            .fetch_one(&self.pool)
-            .await?;
-        let active: u32 = active_row.get::<i64, _>("count") as u32;
+        let mut tx = self.pool().await.begin().await?;
+        if let Some(parent_id) = &data.parent_id {
+            Self::ensure_folder_in_project(&mut tx, parent_id, &data.project_id).await?;
+        }
 
-        let completed_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1")
-            .bind(serde_json::to_string(&ProjectStatus::Completed)?)
-            .fetch_one(&self.pool)
-            .await?;
-        let completed: u32 = completed_row.get::<i64, _>("count") as u32;
+        let id = format!("folder-{}", uuid::Uuid::new_v4());
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO folders (id, project_id, parent_id, name, sort_order, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM folders WHERE project_id = ?2 AND parent_id IS ?3), ?5, ?5)
+            "#,
+        )
+        .bind(&id)
+        .bind(&data.project_id)
+        .bind(&data.parent_id)
+        .bind(name)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
 
-        let archived_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1")
-            .bind(serde_json::to_string(&ProjectStatus::Archived)?)
-            .fetch_one(&self.pool)
-            .await?;
-        let archived: u32 = archived_row.get::<i64, _>("count") as u32;
+        tx.commit().await?;
+        self.get_folder_by_id(&id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Folder", &id).into())
+    }
 
-        // Projects created this week
-        let week_ago = chrono::Utc::now() - chrono::Duration::weeks(1);
-        let this_week_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE created_at > ?1")
+    pub async fn rename_folder(&self, folder_id: &str, name: &str) -> Result<Folder> {
+        let folder = self
+            .get_folder_by_id(folder_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Folder", folder_id))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Folder name cannot be empty").into());
+        }
+
+        let mut tx = self.pool().await.begin().await?;
+        sqlx::query("UPDATE folders SET name = ?2, updated_at = ?3 WHERE id = ?1")
+            .bind(folder_id)
+            .bind(name)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_folder_paths(&mut tx, &folder.project_id).await?;
+        tx.commit().await?;
+
+        self.get_folder_by_id(folder_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Folder", folder_id).into())
+    }
+
+    /// 移动文件夹到新的父级（None 为项目根目录）下的指定位置
+    pub async fn move_folder(&self, folder_id: &str, parent_id: Option<&str>, position: Option<u32>) -> Result<()> {
+        let folder = self
+            .get_folder_by_id(folder_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Folder", folder_id))?;
+
+        let mut tx = self.pool().await.begin().await?;
+        if let Some(parent_id) = parent_id {
+            Self::ensure_folder_in_project(&mut tx, parent_id, &folder.project_id).await?;
+
+            // 不能移动到自身或自己的子孙文件夹下
+            let creates_cycle = sqlx::query(
+                r#"
+                WITH RECURSIVE ancestors(id, parent_id) AS (
+                    SELECT id, parent_id FROM folders WHERE id = ?1
+                    UNION ALL
+                    SELECT f.id, f.parent_id FROM folders f JOIN ancestors a ON f.id = a.parent_id
+                )
+                SELECT 1 FROM ancestors WHERE id = ?2
+                "#,
+            )
+            .bind(parent_id)
+            .bind(folder_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+            if creates_cycle {
+                return Err(AppError::validation("Cannot move a folder into itself or one of its subfolders").into());
+            }
+        }
+
+        Self::place_in_siblings(&mut tx, "folders", "parent_id", &folder.project_id, parent_id, folder_id, position).await?;
+        sqlx::query("UPDATE folders SET updated_at = ?2 WHERE id = ?1")
+            .bind(folder_id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_folder_paths(&mut tx, &folder.project_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 删除文件夹。其中的文档和子文件夹会移到被删除文件夹的父级末尾，不会删除任何文档
+    pub async fn delete_folder(&self, folder_id: &str) -> Result<()> {
+        let folder = self
+            .get_folder_by_id(folder_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Folder", folder_id))?;
+
+        let mut tx = self.pool().await.begin().await?;
+        let parent_id = folder.parent_id.as_deref();
+
+        let document_ids: Vec<String> = sqlx::query("SELECT id FROM documents WHERE folder_id = ?1 ORDER BY sort_order ASC")
+            .bind(folder_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        for id in &document_ids {
+            Self::place_in_siblings(&mut tx, "documents", "folder_id", &folder.project_id, parent_id, id, None).await?;
+        }
+
+        let child_ids: Vec<String> = sqlx::query("SELECT id FROM folders WHERE parent_id = ?1 ORDER BY sort_order ASC")
+            .bind(folder_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        for id in &child_ids {
+            Self::place_in_siblings(&mut tx, "folders", "parent_id", &folder.project_id, parent_id, id, None).await?;
+        }
+
+        sqlx::query("DELETE FROM folders WHERE id = ?1")
+            .bind(folder_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_folder_paths(&mut tx, &folder.project_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 移动文档到指定文件夹（None 为项目根目录）下的指定位置，也用于同一文件夹内调整顺序
+    pub async fn move_document(&self, document_id: &str, folder_id: Option<&str>, position: Option<u32>) -> Result<()> {
+        let project_id: String = sqlx::query("SELECT project_id FROM documents WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(document_id)
+            .fetch_optional(&*self.pool().await)
+            .await?
+            .ok_or_else(|| AppError::not_found("Document", document_id))?
+            .get("project_id");
+
+        let mut tx = self.pool().await.begin().await?;
+        if let Some(folder_id) = folder_id {
+            Self::ensure_folder_in_project(&mut tx, folder_id, &project_id).await?;
+        }
+        Self::place_in_siblings(&mut tx, "documents", "folder_id", &project_id, folder_id, document_id, position).await?;
+        sqlx::query("UPDATE documents SET folder_path = ?2, updated_at = ?3 WHERE id = ?1")
+            .bind(document_id)
+            .bind(Self::folder_path_of(&mut tx, folder_id).await?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 一次返回项目的完整文件夹树，文档只包含列表展示所需字段
+    pub async fn get_project_tree(&self, project_id: &str) -> Result<ProjectTree> {
+        if self.get_project_by_id(project_id).await?.is_none() {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        let folder_rows = sqlx::query("SELECT * FROM folders WHERE project_id = ?1 ORDER BY sort_order ASC, name ASC")
+            .bind(project_id)
+            .fetch_all(&*self.pool().await)
+            .await?;
+        let document_rows = sqlx::query(
+            "SELECT id, title, status, word_count, sort_order, updated_at, folder_id FROM documents WHERE project_id = ?1 AND deleted_at IS NULL ORDER BY sort_order ASC, created_at ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&*self.pool().await)
+        .await?;
+
+        let mut folders: Vec<Folder> = Vec::new();
+        for row in &folder_rows {
+            folders.push(Self::row_to_folder(row)?);
+        }
+
+        let mut documents_by_folder: HashMap<Option<String>, Vec<TreeDocument>> = HashMap::new();
+        for row in &document_rows {
+            documents_by_folder
+                .entry(row.get("folder_id"))
+                .or_default()
+                .push(TreeDocument {
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    status: serde_json::from_str(&row.get::<String, _>("status")).unwrap_or_default(),
+                    word_count: row.get::<i64, _>("word_count") as u32,
+                    sort_order: row.get::<i64, _>("sort_order"),
+                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+                });
+        }
+
+        fn build(parent_id: Option<&str>, folders: &[Folder], documents: &mut HashMap<Option<String>, Vec<TreeDocument>>) -> Vec<FolderNode> {
+            folders
+                .iter()
+                .filter(|f| f.parent_id.as_deref() == parent_id)
+                .map(|f| FolderNode {
+                    folder: f.clone(),
+                    folders: build(Some(&f.id), folders, documents),
+                    documents: documents.remove(&Some(f.id.clone())).unwrap_or_default(),
+                })
+                .collect()
+        }
+
+        let root_folders = build(None, &folders, &mut documents_by_folder);
+        Ok(ProjectTree {
+            project_id: project_id.to_string(),
+            folders: root_folders,
+            documents: documents_by_folder.remove(&None).unwrap_or_default(),
+        })
+    }
+
+    // Tag operations
+    // document_tags 是文档标签的唯一来源，documents.tags 列只作为按顺序排列的冗余缓存
+
+    fn row_to_tag(row: &SqliteRow) -> Result<Tag> {
+        Ok(Tag {
+            id: row.get("id"),
+            name: row.get("name"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    // 去除首尾空白和空标签，大小写不同的重复标签只保留第一个
+    fn normalize_tags(tags: &[String]) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        tags.iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    async fn ensure_tag(conn: &mut SqliteConnection, name: &str) -> Result<String, sqlx::Error> {
+        // name 列使用 NOCASE 排序规则，大小写不同视为同一标签
+        if let Some(row) = sqlx::query("SELECT id FROM tags WHERE name = ?1")
+            .bind(name)
+            .fetch_optional(&mut *conn)
+            .await?
+        {
+            return Ok(row.get("id"));
+        }
+
+        let id = format!("tag-{}", uuid::Uuid::new_v4());
+        sqlx::query("INSERT INTO tags (id, name, created_at) VALUES (?1, ?2, ?3)")
+            .bind(&id)
+            .bind(name)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *conn)
+            .await?;
+        Ok(id)
+    }
+
+    // 按 document_tags 重新生成 documents.tags 缓存
+    async fn refresh_document_tags_cache(conn: &mut SqliteConnection, filter: &str, arg: &str) -> Result<(), sqlx::Error> {
+        let sql = format!(
+            r#"
+            UPDATE documents SET tags = (
+                SELECT json_group_array(name) FROM (
+                    SELECT t.name FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                    WHERE dt.document_id = documents.id ORDER BY dt.position
+                )
+            )
+            WHERE {}
+            "#,
+            filter
+        );
+        sqlx::query(&sql).bind(arg).execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// 用给定的标签列表替换文档的全部标签，返回规范化后的标签名
+    async fn set_document_tags(conn: &mut SqliteConnection, document_id: &str, tags: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query("DELETE FROM document_tags WHERE document_id = ?1")
+            .bind(document_id)
+            .execute(&mut *conn)
+            .await?;
+
+        for (position, name) in Self::normalize_tags(tags).iter().enumerate() {
+            let tag_id = Self::ensure_tag(&mut *conn, name).await?;
+            sqlx::query("INSERT INTO document_tags (document_id, tag_id, position) VALUES (?1, ?2, ?3)")
+                .bind(document_id)
+                .bind(&tag_id)
+                .bind(position as i64)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Self::refresh_document_tags_cache(&mut *conn, "id = ?1", document_id).await?;
+        let row = sqlx::query("SELECT tags FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default())
+    }
+
+    pub async fn get_project_tags(&self, project_id: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT t.name FROM project_tags pt JOIN tags t ON t.id = pt.tag_id WHERE pt.project_id = ?1 ORDER BY pt.position",
+        )
+        .bind(project_id)
+        .fetch_all(&*self.pool().await)
+        .await?;
+        Ok(rows.iter().map(|r| r.get("name")).collect())
+    }
+
+    pub async fn set_project_tags(&self, project_id: &str, tags: &[String]) -> Result<Vec<String>> {
+        if self.get_project_by_id(project_id).await?.is_none() {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        let mut tx = self.pool().await.begin().await?;
+        Self::replace_project_tags(&mut tx, project_id, tags).await?;
+        tx.commit().await?;
+
+        self.get_project_tags(project_id).await
+    }
+
+    async fn replace_project_tags(conn: &mut SqliteConnection, project_id: &str, tags: &[String]) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM project_tags WHERE project_id = ?1")
+            .bind(project_id)
+            .execute(&mut *conn)
+            .await?;
+        for (position, name) in Self::normalize_tags(tags).iter().enumerate() {
+            let tag_id = Self::ensure_tag(&mut *conn, name).await?;
+            sqlx::query("INSERT INTO project_tags (project_id, tag_id, position) VALUES (?1, ?2, ?3)")
+                .bind(project_id)
+                .bind(&tag_id)
+                .bind(position as i64)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// 列出所有标签及其在未删除文档和项目中的使用次数
+    pub async fn list_tags(&self) -> Result<Vec<TagUsage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.id, t.name, t.created_at,
+                (SELECT COUNT(*) FROM document_tags dt JOIN documents d ON d.id = dt.document_id
+                    WHERE dt.tag_id = t.id AND d.deleted_at IS NULL) AS document_count,
+                (SELECT COUNT(*) FROM project_tags pt JOIN projects p ON p.id = pt.project_id
+                    WHERE pt.tag_id = t.id AND p.deleted_at IS NULL) AS project_count
+            FROM tags t
+            ORDER BY t.name COLLATE NOCASE
+            "#,
+        )
+        .fetch_all(&*self.pool().await)
+        .await?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            tags.push(TagUsage {
+                tag: Self::row_to_tag(&row)?,
+                document_count: row.get::<i64, _>("document_count") as u32,
+                project_count: row.get::<i64, _>("project_count") as u32,
+            });
+        }
+        Ok(tags)
+    }
+
+    async fn get_tag(conn: &mut SqliteConnection, tag_id: &str) -> Result<Tag> {
+        let row = sqlx::query("SELECT * FROM tags WHERE id = ?1")
+            .bind(tag_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Tag", tag_id))?;
+        Self::row_to_tag(&row)
+    }
+
+    /// 重命名标签。新名称已被其他标签使用时返回错误，应改用合并
+    pub async fn rename_tag(&self, tag_id: &str, name: &str) -> Result<Tag> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Tag name cannot be empty").into());
+        }
+
+        let mut tx = self.pool().await.begin().await?;
+        Self::get_tag(&mut tx, tag_id).await?;
+        let conflict = sqlx::query("SELECT 1 FROM tags WHERE name = ?1 AND id != ?2")
+            .bind(name)
+            .bind(tag_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if conflict {
+            return Err(AppError::conflict(format!("Tag \"{}\" already exists", name)).into());
+        }
+
+        sqlx::query("UPDATE tags SET name = ?2 WHERE id = ?1")
+            .bind(tag_id)
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_document_tags_cache(&mut tx, "id IN (SELECT document_id FROM document_tags WHERE tag_id = ?1)", tag_id).await?;
+
+        let tag = Self::get_tag(&mut tx, tag_id).await?;
+        tx.commit().await?;
+        Ok(tag)
+    }
+
+    /// 把 source 标签合并到 target：所有使用 source 的文档和项目改用 target，然后删除 source
+    pub async fn merge_tags(&self, source_id: &str, target_id: &str) -> Result<Tag> {
+        if source_id == target_id {
+            return Err(AppError::validation("Cannot merge a tag into itself").into());
+        }
+
+        let mut tx = self.pool().await.begin().await?;
+        Self::get_tag(&mut tx, source_id).await?;
+        Self::get_tag(&mut tx, target_id).await?;
+
+        for table in ["document_tags", "project_tags"] {
+            // 已同时带有两个标签的条目只保留 target
+            sqlx::query(&format!("UPDATE OR IGNORE {} SET tag_id = ?2 WHERE tag_id = ?1", table))
+                .bind(source_id)
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!("DELETE FROM {} WHERE tag_id = ?1", table))
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM tags WHERE id = ?1")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_document_tags_cache(&mut tx, "id IN (SELECT document_id FROM document_tags WHERE tag_id = ?1)", target_id).await?;
+
+        let tag = Self::get_tag(&mut tx, target_id).await?;
+        tx.commit().await?;
+        Ok(tag)
+    }
+
+    /// 从所有文档和项目中移除标签并删除
+    pub async fn delete_tag(&self, tag_id: &str) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+        Self::get_tag(&mut tx, tag_id).await?;
+
+        let document_ids: Vec<String> = sqlx::query("SELECT document_id FROM document_tags WHERE tag_id = ?1")
+            .bind(tag_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|r| r.get("document_id"))
+            .collect();
+
+        for table in ["document_tags", "project_tags"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE tag_id = ?1", table))
+                .bind(tag_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM tags WHERE id = ?1")
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+        for document_id in &document_ids {
+            Self::refresh_document_tags_cache(&mut tx, "id = ?1", document_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 按标签名查找文档。match_all 为 true 时要求同时带有全部标签，否则带有任意一个即可
+    pub async fn get_documents_by_tags(&self, tags: &[String], match_all: bool) -> Result<Vec<DocumentSummary>> {
+        let tags = Self::normalize_tags(tags);
+        if tags.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let required = if match_all { tags.len() } else { 1 };
+        let sql = format!(
+            r#"
+            SELECT {} FROM documents d
+            JOIN projects p ON p.id = d.project_id
+            WHERE d.deleted_at IS NULL AND p.deleted_at IS NULL AND d.id IN (
+                SELECT dt.document_id FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                WHERE t.name IN ({})
+                GROUP BY dt.document_id
+                HAVING COUNT(DISTINCT dt.tag_id) >= ?
+            )
+            ORDER BY d.updated_at DESC
+            "#,
+            Self::DOCUMENT_SUMMARY_COLUMNS,
+            vec!["?"; tags.len()].join(", ")
+        );
+
+        let mut query = sqlx::query(&sql);
+        for tag in &tags {
+            query = query.bind(tag);
+        }
+        let rows = query.bind(required as i64).fetch_all(&*self.pool().await).await?;
+
+        let mut documents = Vec::new();
+        for row in rows {
+            documents.push(Self::row_to_document_summary(&row)?);
+        }
+        Ok(documents)
+    }
+
+    // Writing activity operations
+
+    /// 在内容写入前调用，记录本次保存相对于上次的字数变化和编辑时长
+    async fn record_writing_activity(conn: &mut SqliteConnection, document_id: &str, new_content: &str, new_word_count: u32) -> Result<()> {
+        let Some(row) = sqlx::query(
+            r#"
+            SELECT d.content, d.word_count, d.project_id, p.workspace_id,
+                (SELECT MAX(recorded_at) FROM writing_activity WHERE document_id = d.id) AS last_recorded
+            FROM documents d JOIN projects p ON p.id = d.project_id
+            WHERE d.id = ?1
+            "#,
+        )
+        .bind(document_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        else {
+            return Ok(());
+        };
+
+        if row.get::<String, _>("content") == new_content {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now();
+        let last_recorded = row
+            .get::<Option<String>, _>("last_recorded")
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|t| t.with_timezone(&chrono::Utc));
+
+        sqlx::query(
+            r#"
+            INSERT INTO writing_activity (document_id, project_id, workspace_id, recorded_at, words_delta, active_seconds)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+        )
+        .bind(document_id)
+        .bind(row.get::<String, _>("project_id"))
+        .bind(row.get::<String, _>("workspace_id"))
+        .bind(now.to_rfc3339())
+        .bind(new_word_count as i64 - row.get::<i64, _>("word_count"))
+        .bind(writing_stats::active_seconds_since(last_recorded, now))
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_writing_activity(
+        &self,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        filter: &WritingStatsFilter,
+    ) -> Result<Vec<WritingActivity>> {
+        let mut where_clauses: Vec<&str> = Vec::new();
+        let mut args: Vec<String> = Vec::new();
+
+        if let Some(from) = from {
+            where_clauses.push("recorded_at >= ?");
+            args.push(from.to_rfc3339());
+        }
+        if let Some(to) = to {
+            where_clauses.push("recorded_at < ?");
+            args.push(to.to_rfc3339());
+        }
+        if let Some(wid) = &filter.workspace_id {
+            where_clauses.push("workspace_id = ?");
+            args.push(wid.clone());
+        }
+        if let Some(pid) = &filter.project_id {
+            where_clauses.push("project_id = ?");
+            args.push(pid.clone());
+        }
+        if let Some(did) = &filter.document_id {
+            where_clauses.push("document_id = ?");
+            args.push(did.clone());
+        }
+
+        let where_sql = if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", where_clauses.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT document_id, project_id, workspace_id, recorded_at, words_delta, active_seconds FROM writing_activity{} ORDER BY recorded_at ASC",
+            where_sql
+        );
+
+        let mut query = sqlx::query(&sql);
+        for arg in &args {
+            query = query.bind(arg);
+        }
+
+        let rows = query.fetch_all(&*self.pool().await).await?;
+        let mut activities = Vec::new();
+        for row in rows {
+            activities.push(WritingActivity {
+                document_id: row.get("document_id"),
+                project_id: row.get("project_id"),
+                workspace_id: row.get("workspace_id"),
+                recorded_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("recorded_at"))?.with_timezone(&chrono::Utc),
+                words_delta: row.get("words_delta"),
+                active_seconds: row.get("active_seconds"),
+            });
+        }
+        Ok(activities)
+    }
+
+    /// 按本地日期统计 [from, to] 内每日、每周或每月的写作字数和编辑时长
+    pub async fn get_writing_stats(
+        &self,
+        period: StatsPeriod,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+        filter: &WritingStatsFilter,
+    ) -> Result<WritingStats> {
+        let to = to.unwrap_or_else(|| writing_stats::local_day(chrono::Utc::now()));
+        let from = from.unwrap_or_else(|| writing_stats::default_from(to, period));
+        if from > to {
+            return Err(AppError::validation(format!("Invalid range: {} is after {}", from, to)).into());
+        }
+
+        let activities = self
+            .list_writing_activity(
+                Some(writing_stats::local_day_start(from)),
+                Some(writing_stats::local_day_start(to + chrono::Duration::days(1))),
+                filter,
+            )
+            .await?;
+        Ok(writing_stats::aggregate(&activities, period, from, to))
+    }
+
+    pub async fn get_writing_streak(&self, filter: &WritingStatsFilter) -> Result<WritingStreak> {
+        let activities = self.list_writing_activity(None, None, filter).await?;
+        Ok(writing_stats::streak(&activities, writing_stats::local_day(chrono::Utc::now())))
+    }
+
+    // Goal operations
+
+    /// 设置项目的目标字数、截止日期和每日配额，并重新计算进度
+    pub async fn set_project_goals(&self, project_id: &str, project_goals: ProjectGoals) -> Result<Project> {
+        let mut tx = self.pool().await.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE projects
+            SET target_word_count = ?2, deadline = ?3, daily_word_quota = ?4, progress_mode = ?5, updated_at = ?6
+            WHERE id = ?1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(project_id)
+        .bind(project_goals.target_word_count.map(|v| v as i64))
+        .bind(project_goals.deadline.map(|d| d.format("%Y-%m-%d").to_string()))
+        .bind(project_goals.daily_word_quota.map(|v| v as i64))
+        .bind(serde_json::to_string(&project_goals.progress_mode)?)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        Self::refresh_project_counters(&mut tx, project_id).await?;
+        tx.commit().await?;
+
+        self.get_project_by_id(project_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", project_id).into())
+    }
+
+    pub async fn set_project_writing_preferences(&self, project_id: &str, preferences: ProjectWritingPreferences) -> Result<Project> {
+        let result = sqlx::query("UPDATE projects SET writing_preferences = ?2, updated_at = ?3 WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(project_id)
+            .bind(serde_json::to_string(&preferences)?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&*self.pool().await)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        self.get_project_by_id(project_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", project_id).into())
+    }
+
+    async fn forecast_project(&self, project: &Project) -> Result<ProjectForecast> {
+        let today = writing_stats::local_day(chrono::Utc::now());
+        let filter = WritingStatsFilter { project_id: Some(project.id.clone()), ..Default::default() };
+        let recent = self
+            .get_writing_stats(StatsPeriod::Day, Some(goals::recent_window_start(today)), Some(today), &filter)
+            .await?;
+        Ok(goals::forecast(project, &recent, today))
+    }
+
+    pub async fn get_project_forecast(&self, project_id: &str) -> Result<ProjectForecast> {
+        let project = self
+            .get_project_by_id(project_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", project_id))?;
+        self.forecast_project(&project).await
+    }
+
+    /// 返回所有设置了目标字数或截止日期的项目的预测
+    pub async fn list_project_forecasts(&self) -> Result<Vec<ProjectForecast>> {
+        let mut forecasts = Vec::new();
+        for project in self.get_projects().await? {
+            if project.target_word_count.is_some() || project.deadline.is_some() {
+                forecasts.push(self.forecast_project(&project).await?);
+            }
+        }
+        Ok(forecasts)
+    }
+
+    // Search operations
+
+    /// 用文档表当前内容刷新一条全文索引；文档不存在时只删除索引
+    async fn reindex_document(conn: &mut SqliteConnection, document_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM documents_fts WHERE document_id = ?1")
+            .bind(document_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let row = sqlx::query("SELECT title, content FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        if let Some(row) = row {
+            sqlx::query("INSERT INTO documents_fts (title, content, document_id) VALUES (?1, ?2, ?3)")
+                .bind(search::index_text(&row.get::<String, _>("title")))
+                .bind(search::index_text(&row.get::<String, _>("content")))
+                .bind(document_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // 索引与文档数量不一致时（首次迁移或异常退出后）重建全部索引
+    async fn ensure_search_index(&self) -> Result<()> {
+        let row = sqlx::query("SELECT (SELECT COUNT(*) FROM documents) AS docs, (SELECT COUNT(*) FROM documents_fts) AS indexed")
+            .fetch_one(&*self.pool().await)
+            .await?;
+        if row.get::<i64, _>("docs") == row.get::<i64, _>("indexed") {
+            return Ok(());
+        }
+
+        println!("Rebuilding document search index");
+        let mut tx = self.pool().await.begin().await?;
+        sqlx::query("DELETE FROM documents_fts").execute(&mut *tx).await?;
+        let ids: Vec<String> = sqlx::query("SELECT id FROM documents")
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        for id in ids {
+            Self::reindex_document(&mut tx, &id).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_documents(
+        &self,
+        query: Option<&str>,
+        workspace_id: Option<&str>,
+        project_id: Option<&str>,
+        tags: &[String],
+        status: Option<&str>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<DocumentSearchResult> {
+        let match_expr = query.and_then(search::match_query);
+        let terms = query.map(search::highlight_terms).unwrap_or_default();
+
+        let mut where_clauses: Vec<String> = vec!["d.deleted_at IS NULL".to_string(), "p.deleted_at IS NULL".to_string()];
+        let mut args: Vec<String> = Vec::new();
+
+        if let Some(expr) = &match_expr {
+            where_clauses.push("documents_fts MATCH ?".to_string());
+            args.push(expr.clone());
+        }
+
+        if let Some(wid) = workspace_id {
+            where_clauses.push("p.workspace_id = ?".to_string());
+            args.push(wid.to_string());
+        }
+
+        if let Some(pid) = project_id {
+            where_clauses.push("d.project_id = ?".to_string());
+            args.push(pid.to_string());
+        }
+
+        if !tags.is_empty() {
+            let placeholders = vec!["?"; tags.len()].join(", ");
+            where_clauses.push(format!(
+                "EXISTS (SELECT 1 FROM document_tags dt JOIN tags t ON t.id = dt.tag_id WHERE dt.document_id = d.id AND t.name IN ({}))",
+                placeholders
+            ));
+            args.extend(tags.iter().cloned());
+        }
+
+        if let Some(st) = status {
+            if let Ok(parsed) = serde_json::from_value::<DocumentStatus>(serde_json::Value::String(st.to_string())) {
+                where_clauses.push("d.status = ?".to_string());
+                args.push(serde_json::to_string(&parsed)?);
+            }
+        }
+
+        let where_sql = if where_clauses.is_empty() { String::new() } else { format!(" WHERE {}", where_clauses.join(" AND ")) };
+        let (score_sql, order_sql) = if match_expr.is_some() {
+            ("bm25(documents_fts, 10.0, 1.0)", "score ASC, d.updated_at DESC")
+        } else {
+            ("0.0", "d.updated_at DESC")
+        };
+        let from_sql = "FROM documents_fts JOIN documents d ON d.id = documents_fts.document_id JOIN projects p ON p.id = d.project_id";
+
+        let (limit, offset) = Self::page_bounds(page, page_size);
+
+        let count_sql = format!("SELECT COUNT(*) as count {}{}", from_sql, where_sql);
+        let mut count_q = sqlx::query(&count_sql);
+        for a in &args { count_q = count_q.bind(a); }
+        let total: u32 = count_q.fetch_one(&*self.pool().await).await?.get::<i64, _>("count") as u32;
+
+        let list_sql = format!(
+            "SELECT d.id, d.title, d.content, d.project_id, d.status, d.tags, d.word_count, d.updated_at, p.name AS project_name, {} AS score {}{} ORDER BY {} LIMIT ? OFFSET ?",
+            score_sql, from_sql, where_sql, order_sql
+        );
+        let mut list_q = sqlx::query(&list_sql);
+        for a in &args { list_q = list_q.bind(a); }
+        list_q = list_q.bind(limit).bind(offset);
+        let rows = list_q.fetch_all(&*self.pool().await).await?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let title: String = row.get("title");
+            let content: String = row.get("content");
+            items.push(DocumentSearchHit {
+                id: row.get("id"),
+                title_highlight: search::highlight(&title, &terms),
+                title,
+                snippet: search::snippet(&content, &terms),
+                project_id: row.get("project_id"),
+                project_name: row.get("project_name"),
+                status: serde_json::from_str(&row.get::<String, _>("status")).unwrap_or_default(),
+                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+                word_count: row.get::<i64, _>("word_count") as u32,
+                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+                score: -row.get::<f64, _>("score"),
+            });
+        }
+
+        Ok(DocumentSearchResult { items, total })
+    }
+
+    // Config operations
+    pub async fn get_config(&self) -> Result<Option<AppConfig>> {
+        let row = sqlx::query("SELECT config_data FROM config WHERE id = 1")
+            .fetch_optional(&*self.pool().await)
+            .await?;
+
+        if let Some(row) = row {
+            let config_str: String = row.get("config_data");
+            println!("Loading config from database, length: {} chars", config_str.len());
+            
+            match serde_json::from_str::<AppConfig>(&config_str) {
+                Ok(config) => {
+                    println!("Successfully parsed config with {} MCP servers", config.mcp_servers.servers.len());
+                    Ok(Some(config))
+                },
+                Err(e) => {
+                    println!("Failed to parse config from database: {}. Using default config.", e);
+                    // 如果解析失败，可能是旧版本的配置，返回 None 让上层创建默认配置
+                    Ok(None)
+                }
+            }
+        } else {
+            println!("No config found in database, will create default");
+            Ok(None)
+        }
+    }
+
+    pub async fn save_config(&self, config: AppConfig) -> Result<()> {
+        let config_data = serde_json::to_string(&config)?;
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO config (id, config_data, updated_at)
+            VALUES (1, ?1, ?2)
+            "#,
+        )
+        .bind(&config_data)
+        .bind(config.updated_at.to_rfc3339())
+        .execute(&*self.pool().await)
+        .await?;
+
+        Ok(())
+    }
+
+    // Additional project methods
+    pub async fn get_projects(&self) -> Result<Vec<Project>> {
+        let rows = sqlx::query("SELECT * FROM projects WHERE deleted_at IS NULL ORDER BY updated_at DESC")
+            .fetch_all(&*self.pool().await)
+            .await?;
+
+        let mut projects = Vec::new();
+        for row in rows {
+            projects.push(Self::row_to_project(&row)?);
+        }
+
+        Ok(projects)
+    }
+
+    pub async fn get_project_by_id(&self, project_id: &str) -> Result<Option<Project>> {
+        let row = sqlx::query("SELECT * FROM projects WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(project_id)
+            .fetch_optional(&*self.pool().await)
+            .await?;
+
+        row.map(|r| Self::row_to_project(&r)).transpose()
+    }
+
+    fn row_to_project(row: &SqliteRow) -> Result<Project> {
+        Ok(Project {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            icon: row.get("icon"),
+            color: row.get("color"),
+            status: serde_json::from_str(&row.get::<String, _>("status"))?,
+            progress: row.get::<i64, _>("progress") as u32,
+            documents_count: row.get::<i64, _>("documents_count") as u32,
+            words_count: row.get::<i64, _>("words_count") as u32,
+            workspace_id: row.get("workspace_id"),
+            target_word_count: row.get::<Option<i64>, _>("target_word_count").map(|v| v as u32),
+            deadline: row
+                .get::<Option<String>, _>("deadline")
+                .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+            daily_word_quota: row.get::<Option<i64>, _>("daily_word_quota").map(|v| v as u32),
+            progress_mode: serde_json::from_str(&row.get::<String, _>("progress_mode")).unwrap_or_default(),
+            writing_preferences: serde_json::from_str(&row.get::<String, _>("writing_preferences")).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    pub async fn update_project(&self, project_id: &str, mut project: Project) -> Result<()> {
+        project.updated_at = chrono::Utc::now();
+        
+        // 回收站中的项目不能修改
+        let result = sqlx::query(
+            r#"
+            UPDATE projects 
+            SET name = ?1, description = ?2, icon = ?3, color = ?4, status = ?5, updated_at = ?6
+            WHERE id = ?7 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(&project.name)
+        .bind(&project.description)
+        .bind(&project.icon)
+        .bind(&project.color)
+        .bind(serde_json::to_string(&project.status)?)
+        .bind(project.updated_at.to_rfc3339())
+        .bind(project_id)
+        .execute(&*self.pool().await)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Project", project_id).into());
+        }
+
+        Ok(())
+    }
+
+    /// 将项目及其文档移入回收站
+    pub async fn delete_project(&self, project_id: &str) -> Result<()> {
+        let deleted_at = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool().await.begin().await?;
+
+        sqlx::query("UPDATE documents SET deleted_at = ?2 WHERE project_id = ?1 AND deleted_at IS NULL")
+            .bind(project_id)
+            .bind(&deleted_at)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE projects SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(project_id)
+            .bind(&deleted_at)
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_project_counters(&mut tx, project_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_project_stats(&self) -> Result<crate::models::project::ProjectStats> {
+        use crate::models::project::{ProjectStats, ProjectStatus};
+        
+        let total_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE deleted_at IS NULL")
+            .fetch_one(&*self.pool().await)
+            .await?;
+        let total: u32 = total_row.get::<i64, _>("count") as u32;
+
+        let active_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1 AND deleted_at IS NULL")
+            .bind(serde_json::to_string(&ProjectStatus::Active)?)
+            .fetch_one(&*self.pool().await)
+            .await?;
+        let active: u32 = active_row.get::<i64, _>("count") as u32;
+
+        let completed_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1 AND deleted_at IS NULL")
+            .bind(serde_json::to_string(&ProjectStatus::Completed)?)
+            .fetch_one(&*self.pool().await)
+            .await?;
+        let completed: u32 = completed_row.get::<i64, _>("count") as u32;
+
+        let archived_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE status = ?1 AND deleted_at IS NULL")
+            .bind(serde_json::to_string(&ProjectStatus::Archived)?)
+            .fetch_one(&*self.pool().await)
+            .await?;
+        let archived: u32 = archived_row.get::<i64, _>("count") as u32;
+
+        // Projects created this week
+        let week_ago = chrono::Utc::now() - chrono::Duration::weeks(1);
+        let this_week_row = sqlx::query("SELECT COUNT(*) as count FROM projects WHERE created_at > ?1 AND deleted_at IS NULL")
             .bind(week_ago.to_rfc3339())
-            .fetch_one(&self.pool)
+            .fetch_one(&*self.pool().await)
             .await?;
         let this_week: u32 = this_week_row.get::<i64, _>("count") as u32;
 
@@ -627,87 +1774,579 @@ impl Database {
         })
     }
 
-    // Additional document methods
-    pub async fn get_document_by_id(&self, document_id: &str) -> Result<Option<Document>> {
-        let row = sqlx::query("SELECT * FROM documents WHERE id = ?1")
+    // Additional document methods
+    pub async fn get_document_by_id(&self, document_id: &str) -> Result<Option<Document>> {
+        let row = sqlx::query("SELECT * FROM documents WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(document_id)
+            .fetch_optional(&*self.pool().await)
+            .await?;
+        row.map(|r| Self::row_to_document(&r)).transpose()
+    }
+
+    /// 按目录路径和排序值返回项目下的完整文档，供整项目导出使用
+    pub async fn get_project_documents_ordered(&self, project_id: &str) -> Result<Vec<Document>> {
+        let rows = sqlx::query(
+            "SELECT * FROM documents WHERE project_id = ?1 AND deleted_at IS NULL \
+             ORDER BY COALESCE(folder_path, ''), sort_order, created_at, id",
+        )
+        .bind(project_id)
+        .fetch_all(&*self.pool().await)
+        .await?;
+        rows.iter().map(Self::row_to_document).collect()
+    }
+
+    fn row_to_document(row: &SqliteRow) -> Result<Document> {
+        Ok(Document {
+            id: row.get("id"),
+            title: row.get("title"),
+            content: row.get("content"),
+            content_type: serde_json::from_str(&row.get::<String, _>("content_type")).unwrap_or_default(),
+            status: serde_json::from_str(&row.get::<String, _>("status")).unwrap_or_default(),
+            word_count: row.get::<i64, _>("word_count") as u32,
+            char_count: row.get::<i64, _>("char_count") as u32,
+            project_id: row.get("project_id"),
+            folder_path: row.get("folder_path"),
+            folder_id: row.get("folder_id"),
+            sort_order: row.get::<i64, _>("sort_order"),
+            tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+            metadata: serde_json::from_str(&row.get::<String, _>("metadata")).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+            last_accessed: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("last_accessed"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    pub async fn update_document(&self, document_id: &str, document_data: Document) -> Result<()> {
+        self.write_document(document_id, document_data, true).await
+    }
+
+    /// 重新导入时覆盖已有文档：与 update_document 相同，但不记入写作活动，导入的字数不算进统计和目标
+    pub async fn update_imported_document(&self, document_id: &str, document_data: Document) -> Result<()> {
+        self.write_document(document_id, document_data, false).await
+    }
+
+    async fn write_document(&self, document_id: &str, document_data: Document, record_activity: bool) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+        if record_activity {
+            Self::record_writing_activity(&mut tx, document_id, &document_data.content, document_data.word_count).await?;
+        }
+        let mut metadata = document_data.metadata.clone();
+        metadata.version = Self::snapshot_if_changed(&mut tx, document_id, &document_data.content)
+            .await?
+            .max(metadata.version);
+
+        // 回收站中的文档不能修改，更新不到时事务回滚，之前记录的活动和修订一并撤销
+        let result = sqlx::query(
+            "UPDATE documents SET title = ?2, content = ?3, status = ?4, word_count = ?5, char_count = ?6, metadata = ?7, updated_at = ?8 WHERE id = ?1 AND deleted_at IS NULL"
+        )
+        .bind(document_id)
+        .bind(&document_data.title)
+        .bind(&document_data.content)
+        .bind(serde_json::to_string(&document_data.status)?)
+        .bind(document_data.word_count as i64)
+        .bind(document_data.char_count as i64)
+        .bind(serde_json::to_string(&metadata)?)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Document", document_id).into());
+        }
+
+        Self::set_document_tags(&mut tx, document_id, &document_data.tags).await?;
+
+        Self::reindex_document(&mut tx, document_id).await?;
+        Self::refresh_document_project_counters(&mut tx, document_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 将文档移入回收站
+    pub async fn delete_document(&self, document_id: &str) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+
+        sqlx::query("UPDATE documents SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(document_id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_document_project_counters(&mut tx, document_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn save_document_content(&self, document_id: &str, content: &str) -> Result<()> {
+        let word_count = content.split_whitespace().count() as u32;
+        let char_count = content.chars().count() as u32;
+
+        let mut tx = self.pool().await.begin().await?;
+        Self::record_writing_activity(&mut tx, document_id, content, word_count).await?;
+        let version = Self::snapshot_if_changed(&mut tx, document_id, content).await?;
+
+        let result = sqlx::query(
+            "UPDATE documents SET content = ?2, word_count = ?3, char_count = ?4, metadata = json_set(metadata, '$.version', ?5), updated_at = ?6, last_accessed = ?7 WHERE id = ?1 AND deleted_at IS NULL"
+        )
+        .bind(document_id)
+        .bind(content)
+        .bind(word_count as i64)
+        .bind(char_count as i64)
+        .bind(version as i64)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Document", document_id).into());
+        }
+
+        Self::reindex_document(&mut tx, document_id).await?;
+        Self::refresh_document_project_counters(&mut tx, document_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Revision operations
+
+    /// 内容发生变化时把当前内容存为一个修订，返回文档保存后的版本号
+    async fn snapshot_if_changed(tx: &mut Transaction<'_, Sqlite>, document_id: &str, new_content: &str) -> Result<u32> {
+        let row = sqlx::query("SELECT title, content, word_count, char_count, metadata FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Document", document_id))?;
+
+        let metadata: DocumentMetadata = serde_json::from_str(&row.get::<String, _>("metadata")).unwrap_or_default();
+        let current_version = metadata.version.max(1);
+        let old_content: String = row.get("content");
+        if old_content == new_content {
+            return Ok(current_version);
+        }
+
+        let hash = revision::content_hash(&old_content);
+        sqlx::query("INSERT OR IGNORE INTO revision_blobs (hash, content) VALUES (?1, ?2)")
+            .bind(&hash)
+            .bind(&old_content)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO document_revisions (id, document_id, version, title, content_hash, word_count, char_count, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(format!("rev-{}", uuid::Uuid::new_v4()))
+        .bind(document_id)
+        .bind(current_version as i64)
+        .bind(row.get::<String, _>("title"))
+        .bind(&hash)
+        .bind(row.get::<i64, _>("word_count"))
+        .bind(row.get::<i64, _>("char_count"))
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut **tx)
+        .await?;
+
+        Self::prune_revisions(tx, document_id).await?;
+
+        Ok(current_version + 1)
+    }
+
+    // 保留最近的修订，更早的按时间分桶抽稀，超出保留期的全部删除
+    async fn prune_revisions(tx: &mut Transaction<'_, Sqlite>, document_id: &str) -> Result<()> {
+        let rows = sqlx::query("SELECT id, content_hash, created_at FROM document_revisions WHERE document_id = ?1 ORDER BY version DESC")
+            .bind(document_id)
+            .fetch_all(&mut **tx)
+            .await?;
+        if rows.len() <= revision::MAX_RECENT_REVISIONS {
+            return Ok(());
+        }
+
+        let mut revisions = Vec::with_capacity(rows.len());
+        let mut hashes = HashMap::new();
+        for row in &rows {
+            let id: String = row.get("id");
+            let created_at = chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc);
+            hashes.insert(id.clone(), row.get::<String, _>("content_hash"));
+            revisions.push((id, created_at));
+        }
+        let prune = revision::revisions_to_prune(&revisions, chrono::Utc::now());
+        if prune.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query("DELETE FROM document_revisions WHERE id IN (SELECT value FROM json_each(?1))")
+            .bind(serde_json::to_string(&prune)?)
+            .execute(&mut **tx)
+            .await?;
+        let pruned_hashes: Vec<&String> = prune.iter().filter_map(|id| hashes.get(id)).collect();
+        Self::delete_unreferenced_blobs(tx, &pruned_hashes).await
+    }
+
+    // 只检查给定的哈希，避免每次保存都扫描整张 revision_blobs
+    async fn delete_unreferenced_blobs(tx: &mut Transaction<'_, Sqlite>, hashes: &[&String]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"
+            DELETE FROM revision_blobs
+            WHERE hash IN (SELECT value FROM json_each(?1))
+              AND NOT EXISTS (SELECT 1 FROM document_revisions WHERE content_hash = revision_blobs.hash)
+            "#,
+        )
+        .bind(serde_json::to_string(hashes)?)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_document_revisions(&self, document_id: &str) -> Result<Vec<DocumentRevision>> {
+        let rows = sqlx::query("SELECT * FROM document_revisions WHERE document_id = ?1 ORDER BY version DESC")
             .bind(document_id)
-            .fetch_optional(&self.pool)
+            .fetch_all(&*self.pool().await)
             .await?;
 
-        if let Some(row) = row {
-            let document = Document {
-                id: row.get("id"),
-                title: row.get("title"),
+        let mut revisions = Vec::new();
+        for row in rows {
+            revisions.push(Self::row_to_revision(&row)?);
+        }
+        Ok(revisions)
+    }
+
+    pub async fn get_document_revision(&self, document_id: &str, version: u32) -> Result<Option<DocumentRevisionDetail>> {
+        let row = sqlx::query(
+            r#"
+            SELECT r.*, b.content FROM document_revisions r
+            JOIN revision_blobs b ON b.hash = r.content_hash
+            WHERE r.document_id = ?1 AND r.version = ?2
+            "#,
+        )
+        .bind(document_id)
+        .bind(version as i64)
+        .fetch_optional(&*self.pool().await)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(DocumentRevisionDetail {
+                revision: Self::row_to_revision(&row)?,
                 content: row.get("content"),
-                content_type: serde_json::from_str(&row.get::<String, _>("content_type")).unwrap_or_default(),
-                status: serde_json::from_str(&row.get::<String, _>("status")).unwrap_or_default(),
-                word_count: row.get::<i64, _>("word_count") as u32,
-                char_count: row.get::<i64, _>("char_count") as u32,
-                project_id: row.get("project_id"),
-                folder_path: row.get("folder_path"),
-                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
-                metadata: serde_json::from_str(&row.get::<String, _>("metadata")).unwrap_or_default(),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
-                last_accessed: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("last_accessed"))?.with_timezone(&chrono::Utc),
-            };
-            Ok(Some(document))
-        } else {
-            Ok(None)
+            })),
+            None => Ok(None),
         }
     }
 
-    pub async fn update_document(&self, document_id: &str, document_data: Document) -> Result<()> {
+    /// 比较两个修订；`to_version` 为空时与文档当前内容比较
+    pub async fn diff_document_revisions(&self, document_id: &str, from_version: u32, to_version: Option<u32>) -> Result<RevisionDiff> {
+        let from = self
+            .get_document_revision(document_id, from_version)
+            .await?
+            .ok_or_else(|| AppError::not_found("Revision", format!("{} v{}", document_id, from_version)))?;
+
+        match to_version {
+            Some(to_version) => {
+                let to = self
+                    .get_document_revision(document_id, to_version)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Revision", format!("{} v{}", document_id, to_version)))?;
+                Ok(revision::diff_texts(&from.content, &to.content, from_version, to_version, false))
+            }
+            None => {
+                let current = self
+                    .get_document_by_id(document_id)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Document", document_id))?;
+                Ok(revision::diff_texts(&from.content, &current.content, from_version, current.metadata.version, true))
+            }
+        }
+    }
+
+    /// 恢复到指定修订。恢复前的内容会作为新修订保留，因此恢复操作本身可以撤销
+    pub async fn restore_document_revision(&self, document_id: &str, version: u32) -> Result<Document> {
+        let revision = self
+            .get_document_revision(document_id, version)
+            .await?
+            .ok_or_else(|| AppError::not_found("Revision", format!("{} v{}", document_id, version)))?;
+
+        self.save_document_content(document_id, &revision.content).await?;
+
+        self.get_document_by_id(document_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Document", document_id).into())
+    }
+
+    fn row_to_revision(row: &SqliteRow) -> Result<DocumentRevision> {
+        Ok(DocumentRevision {
+            id: row.get("id"),
+            document_id: row.get("document_id"),
+            version: row.get::<i64, _>("version") as u32,
+            title: row.get("title"),
+            word_count: row.get::<i64, _>("word_count") as u32,
+            char_count: row.get::<i64, _>("char_count") as u32,
+            content_hash: row.get("content_hash"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    // Template operations
+    fn row_to_template(row: &SqliteRow) -> Result<DocumentTemplate> {
+        Ok(DocumentTemplate {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            category: row.get("category"),
+            title: row.get("title"),
+            content: row.get("content"),
+            content_type: serde_json::from_str(&row.get::<String, _>("content_type")).unwrap_or_default(),
+            tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+            variables: serde_json::from_str(&row.get::<String, _>("variables")).unwrap_or_default(),
+            built_in: row.get::<i64, _>("built_in") == 1,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    // 同一 id 已存在时覆盖内容，保留创建时间
+    async fn upsert_template(conn: &mut SqliteConnection, id: &str, data: TemplateData, built_in: bool) -> Result<DocumentTemplate> {
+        let now = chrono::Utc::now();
         sqlx::query(
-            "UPDATE documents SET title = ?2, content = ?3, status = ?4, word_count = ?5, char_count = ?6, tags = ?7, metadata = ?8, updated_at = ?9 WHERE id = ?1"
+            r#"
+            INSERT INTO document_templates (id, name, description, category, title, content, content_type, tags, variables, built_in, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name, description = excluded.description, category = excluded.category, title = excluded.title,
+                content = excluded.content, content_type = excluded.content_type, tags = excluded.tags, variables = excluded.variables,
+                built_in = excluded.built_in, updated_at = excluded.updated_at
+            "#,
         )
-        .bind(document_id)
-        .bind(&document_data.title)
-        .bind(&document_data.content)
-        .bind(serde_json::to_string(&document_data.status)?)
-        .bind(document_data.word_count as i64)
-        .bind(document_data.char_count as i64)
-        .bind(serde_json::to_string(&document_data.tags)?)
-        .bind(serde_json::to_string(&document_data.metadata)?)
-        .bind(chrono::Utc::now().to_rfc3339())
-        .execute(&self.pool)
+        .bind(id)
+        .bind(&data.name)
+        .bind(&data.description)
+        .bind(&data.category)
+        .bind(&data.title)
+        .bind(&data.content)
+        .bind(serde_json::to_string(&data.content_type)?)
+        .bind(serde_json::to_string(&Self::normalize_tags(&data.tags))?)
+        .bind(serde_json::to_string(&data.variables)?)
+        .bind(if built_in { 1 } else { 0 })
+        .bind(now.to_rfc3339())
+        .execute(&mut *conn)
         .await?;
-        
+
+        let row = sqlx::query("SELECT * FROM document_templates WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+        Self::row_to_template(&row)
+    }
+
+    // 内置的文档模板和项目模板按固定 id 写入，应用更新后内容随之更新；新版本中已去掉的内置模板一并删除
+    async fn sync_built_in_templates(&self) -> Result<()> {
+        let built_in = templates::built_in_templates();
+        let ids: Vec<&str> = built_in.iter().map(|(id, _)| id.as_str()).collect();
+        let ids = serde_json::to_string(&ids)?;
+
+        let mut tx = self.pool().await.begin().await?;
+        for (id, data) in built_in {
+            Self::upsert_template(&mut tx, &id, data, true).await?;
+        }
+        sqlx::query("DELETE FROM document_templates WHERE built_in = 1 AND id NOT IN (SELECT value FROM json_each(?1))")
+            .bind(ids)
+            .execute(&mut *tx)
+            .await?;
+
+        let built_in = templates::built_in_project_templates();
+        let ids: Vec<&str> = built_in.iter().map(|(id, _)| id.as_str()).collect();
+        let ids = serde_json::to_string(&ids)?;
+        for (id, data) in built_in {
+            Self::upsert_project_template(&mut tx, &id, data, true).await?;
+        }
+        sqlx::query("DELETE FROM project_templates WHERE built_in = 1 AND id NOT IN (SELECT value FROM json_each(?1))")
+            .bind(ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
         Ok(())
     }
 
-    pub async fn delete_document(&self, document_id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM documents WHERE id = ?1")
-            .bind(document_id)
-            .execute(&self.pool)
+    pub async fn list_templates(&self, category: Option<&str>) -> Result<Vec<DocumentTemplate>> {
+        let rows = sqlx::query(
+            "SELECT * FROM document_templates WHERE ?1 IS NULL OR category = ?1 ORDER BY built_in DESC, category ASC, name ASC",
+        )
+        .bind(category)
+        .fetch_all(&*self.pool().await)
+        .await?;
+        rows.iter().map(Self::row_to_template).collect()
+    }
+
+    pub async fn get_template(&self, template_id: &str) -> Result<Option<DocumentTemplate>> {
+        let row = sqlx::query("SELECT * FROM document_templates WHERE id = ?1")
+            .bind(template_id)
+            .fetch_optional(&*self.pool().await)
+            .await?;
+        row.as_ref().map(Self::row_to_template).transpose()
+    }
+
+    // 只允许修改和删除用户模板
+    async fn get_user_template(&self, template_id: &str) -> Result<DocumentTemplate> {
+        let template = self
+            .get_template(template_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Template", template_id))?;
+        if template.built_in {
+            return Err(AppError::validation(format!("Built-in template \"{}\" cannot be modified", template.name)).into());
+        }
+        Ok(template)
+    }
+
+    pub async fn create_template(&self, data: TemplateData) -> Result<DocumentTemplate> {
+        let data = templates::normalize(data)?;
+        let mut conn = self.pool().await.acquire().await?;
+        Self::upsert_template(&mut conn, &uuid::Uuid::new_v4().to_string(), data, false).await
+    }
+
+    pub async fn update_template(&self, template_id: &str, data: TemplateData) -> Result<DocumentTemplate> {
+        let data = templates::normalize(data)?;
+        let template = self.get_user_template(template_id).await?;
+        let mut conn = self.pool().await.acquire().await?;
+        Self::upsert_template(&mut conn, &template.id, data, false).await
+    }
+
+    pub async fn delete_template(&self, template_id: &str) -> Result<()> {
+        let template = self.get_user_template(template_id).await?;
+        sqlx::query("DELETE FROM document_templates WHERE id = ?1")
+            .bind(&template.id)
+            .execute(&*self.pool().await)
             .await?;
-        
         Ok(())
     }
 
-    pub async fn save_document_content(&self, document_id: &str, content: &str) -> Result<()> {
-        let word_count = content.split_whitespace().count() as u32;
-        let char_count = content.chars().count() as u32;
-        
+    /// 导入的模板一律作为用户模板新建；任何一个无效时整体放弃
+    pub async fn import_templates(&self, templates: Vec<TemplateData>) -> Result<Vec<DocumentTemplate>> {
+        let templates = templates.into_iter().map(templates::normalize).collect::<Result<Vec<_>>>()?;
+        let mut tx = self.pool().await.begin().await?;
+        let mut imported = Vec::new();
+        for data in templates {
+            imported.push(Self::upsert_template(&mut tx, &uuid::Uuid::new_v4().to_string(), data, false).await?);
+        }
+        tx.commit().await?;
+        Ok(imported)
+    }
+
+    fn row_to_project_template(row: &SqliteRow) -> Result<ProjectTemplate> {
+        Ok(ProjectTemplate {
+            id: row.get("id"),
+            template: serde_json::from_str(&row.get::<String, _>("data"))?,
+            built_in: row.get::<i64, _>("built_in") == 1,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&chrono::Utc),
+        })
+    }
+
+    async fn upsert_project_template(conn: &mut SqliteConnection, id: &str, data: ProjectTemplateData, built_in: bool) -> Result<ProjectTemplate> {
         sqlx::query(
-            "UPDATE documents SET content = ?2, word_count = ?3, char_count = ?4, updated_at = ?5, last_accessed = ?6 WHERE id = ?1"
+            r#"
+            INSERT INTO project_templates (id, name, data, built_in, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name, data = excluded.data, built_in = excluded.built_in, updated_at = excluded.updated_at
+            "#,
         )
-        .bind(document_id)
-        .bind(content)
-        .bind(word_count as i64)
-        .bind(char_count as i64)
+        .bind(id)
+        .bind(&data.name)
+        .bind(serde_json::to_string(&data)?)
+        .bind(if built_in { 1 } else { 0 })
         .bind(chrono::Utc::now().to_rfc3339())
-        .bind(chrono::Utc::now().to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
-        
+
+        let row = sqlx::query("SELECT * FROM project_templates WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+        Self::row_to_project_template(&row)
+    }
+
+    pub async fn list_project_templates(&self) -> Result<Vec<ProjectTemplate>> {
+        let rows = sqlx::query("SELECT * FROM project_templates ORDER BY built_in DESC, name ASC")
+            .fetch_all(&*self.pool().await)
+            .await?;
+        rows.iter().map(Self::row_to_project_template).collect()
+    }
+
+    pub async fn get_project_template(&self, template_id: &str) -> Result<Option<ProjectTemplate>> {
+        let row = sqlx::query("SELECT * FROM project_templates WHERE id = ?1")
+            .bind(template_id)
+            .fetch_optional(&*self.pool().await)
+            .await?;
+        row.as_ref().map(Self::row_to_project_template).transpose()
+    }
+
+    async fn get_user_project_template(&self, template_id: &str) -> Result<ProjectTemplate> {
+        let template = self
+            .get_project_template(template_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project template", template_id))?;
+        if template.built_in {
+            return Err(AppError::validation(format!("Built-in template \"{}\" cannot be modified", template.template.name)).into());
+        }
+        Ok(template)
+    }
+
+    pub async fn create_project_template(&self, data: ProjectTemplateData) -> Result<ProjectTemplate> {
+        let data = templates::normalize_project(data)?;
+        let mut conn = self.pool().await.acquire().await?;
+        Self::upsert_project_template(&mut conn, &uuid::Uuid::new_v4().to_string(), data, false).await
+    }
+
+    pub async fn update_project_template(&self, template_id: &str, data: ProjectTemplateData) -> Result<ProjectTemplate> {
+        let data = templates::normalize_project(data)?;
+        let template = self.get_user_project_template(template_id).await?;
+        let mut conn = self.pool().await.acquire().await?;
+        Self::upsert_project_template(&mut conn, &template.id, data, false).await
+    }
+
+    pub async fn delete_project_template(&self, template_id: &str) -> Result<()> {
+        let template = self.get_user_project_template(template_id).await?;
+        sqlx::query("DELETE FROM project_templates WHERE id = ?1")
+            .bind(&template.id)
+            .execute(&*self.pool().await)
+            .await?;
         Ok(())
     }
 
+    /// 把已有项目另存为用户模板，名称和描述为空时沿用项目的
+    pub async fn save_project_as_template(
+        &self,
+        project_id: &str,
+        name: Option<&str>,
+        description: Option<&str>,
+        include_content: bool,
+    ) -> Result<ProjectTemplate> {
+        let project = self
+            .get_project_by_id(project_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", project_id))?;
+        let tree = self.get_project_tree(project_id).await?;
+        let documents = self.get_project_documents_ordered(project_id).await?;
+        let tags = self.get_project_tags(project_id).await?;
+
+        let mut data = templates::project_to_template(&project, &tree, documents, tags, include_content);
+        if let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) {
+            data.name = name.to_string();
+        }
+        if let Some(description) = description {
+            data.description = description.to_string();
+        }
+        self.create_project_template(data).await
+    }
+
     // Agent operations
     pub async fn list_agents(&self) -> Result<Vec<AgentModel>> {
         let rows = sqlx::query("SELECT * FROM agents ORDER BY name ASC")
-            .fetch_all(&self.pool)
+            .fetch_all(&*self.pool().await)
             .await?;
 
         let mut agents = Vec::new();
@@ -738,7 +2377,7 @@ impl Database {
         .bind(&input.version)
         .bind(&input.description)
         .bind(&tags)
-        .execute(&self.pool)
+        .execute(&*self.pool().await)
         .await?;
         Ok(AgentModel { id, name: input.name, category: input.category, version: input.version, enabled: true, description: input.description, tags: serde_json::from_str(&tags).unwrap_or_default() })
     }
@@ -746,64 +2385,594 @@ impl Database {
     pub async fn set_agent_enabled(&self, id: &str, enabled: bool) -> Result<()> {
         sqlx::query("UPDATE agents SET enabled = ?2 WHERE id = ?1")
             .bind(id)
-            .bind(if enabled {1} else {0})
-            .execute(&self.pool)
+            .bind(if enabled {1} else {0})
+            .execute(&*self.pool().await)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn uninstall_agent(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM agents WHERE id = ?1")
+            .bind(id)
+            .execute(&*self.pool().await)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_agent_version(&self, id: &str, version: &str) -> Result<()> {
+        sqlx::query("UPDATE agents SET version = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(version)
+            .execute(&*self.pool().await)
+            .await?;
+        Ok(())
+    }
+
+    // Backup operations
+    /// 用 `VACUUM INTO` 生成一致的在线快照，目标文件不能已存在
+    pub async fn snapshot_to(&self, path: &Path) -> Result<()> {
+        let target = path.display().to_string().replace('\'', "''");
+        sqlx::query(&format!("VACUUM INTO '{}'", target))
+            .execute(&*self.pool().await)
+            .await?;
+        Ok(())
+    }
+
+    // Restore operations
+
+    /// 检查备份的完整性并返回其结构版本。
+    /// FTS5 表的完整性检查需要写权限，因此在备份的临时副本上以读写方式检查，备份文件本身不会被改动
+    pub async fn inspect_snapshot(path: &Path) -> Result<i64> {
+        let copy = std::env::temp_dir().join(format!("writeflow-inspect-{}.db", uuid::Uuid::new_v4()));
+        if let Err(e) = fs::copy(path, &copy).await {
+            let _ = fs::remove_file(&copy).await;
+            return Err(e.into());
+        }
+        let result = Self::inspect_database(&copy).await;
+        let _ = fs::remove_file(&copy).await;
+        result
+    }
+
+    async fn inspect_database(path: &Path) -> Result<i64> {
+        let mut conn = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(path)).await?;
+
+        let problems: Vec<String> = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&mut conn)
+            .await?
+            .iter()
+            .map(|r| r.get(0))
+            .collect();
+        if problems.len() != 1 || problems[0] != "ok" {
+            conn.close().await?;
+            return Err(AppError::validation(format!("Backup failed integrity check: {}", problems.join("; "))).into());
+        }
+
+        let version = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+            .fetch_one(&mut conn)
+            .await
+            .map(|r| r.get::<i64, _>("version"))
+            .unwrap_or(0);
+        conn.close().await?;
+        Ok(version)
+    }
+
+    pub async fn entity_counts(&self) -> Result<EntityCounts> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM workspaces WHERE deleted_at IS NULL) AS workspaces,
+                (SELECT COUNT(*) FROM projects WHERE deleted_at IS NULL) AS projects,
+                (SELECT COUNT(*) FROM documents WHERE deleted_at IS NULL) AS documents,
+                (SELECT COUNT(*) FROM folders) AS folders,
+                (SELECT COUNT(*) FROM tags) AS tags,
+                (SELECT COUNT(*) FROM document_revisions) AS revisions
+            "#,
+        )
+        .fetch_one(&*self.pool().await)
+        .await?;
+
+        Ok(EntityCounts {
+            workspaces: row.get::<i64, _>("workspaces") as u32,
+            projects: row.get::<i64, _>("projects") as u32,
+            documents: row.get::<i64, _>("documents") as u32,
+            folders: row.get::<i64, _>("folders") as u32,
+            tags: row.get::<i64, _>("tags") as u32,
+            revisions: row.get::<i64, _>("revisions") as u32,
+        })
+    }
+
+    /// 用备份整体替换当前数据库。旧连接池关闭时会等待进行中的查询完成，
+    /// 新库打开（并按需迁移）后替换进共享槽位；任何一步失败都换回原文件并重新打开。
+    /// 替换期间持有槽位的写锁，其他请求等到新连接池就位后再继续
+    pub async fn replace_with_snapshot(&self, snapshot: &Path) -> Result<()> {
+        let _guard = self.restore_lock.lock().await;
+        let staged = self.db_path.with_extension("db.restore");
+        let previous = self.db_path.with_extension("db.pre-restore");
+        if let Err(e) = fs::copy(snapshot, &staged).await {
+            let _ = fs::remove_file(&staged).await;
+            return Err(e.into());
+        }
+
+        let mut slot = self.pool.write().await;
+        slot.close().await;
+
+        // 关闭连接池之后的步骤放在一起，失败时统一回滚，槽位中不能留下已关闭的连接池
+        let mut moved = false;
+        let replaced = async {
+            for suffix in ["-wal", "-shm", "-journal"] {
+                let sidecar = PathBuf::from(format!("{}{}", self.db_path.display(), suffix));
+                if fs::try_exists(&sidecar).await? {
+                    fs::remove_file(&sidecar).await?;
+                }
+            }
+            fs::rename(&self.db_path, &previous).await?;
+            moved = true;
+            fs::rename(&staged, &self.db_path).await?;
+            Self::open_pool(&self.db_path).await
+        }
+        .await;
+
+        match replaced {
+            Ok(pool) => *slot = pool,
+            Err(e) => {
+                let _ = fs::remove_file(&staged).await;
+                // 原文件没能换回时不再打开，避免在原位置新建一个空库
+                let reopened = async {
+                    if moved {
+                        fs::rename(&previous, &self.db_path).await?;
+                    }
+                    Self::open_pool(&self.db_path).await
+                }
+                .await;
+                return match reopened {
+                    Ok(pool) => {
+                        *slot = pool;
+                        Err(e)
+                    }
+                    Err(reopen_error) => Err(e.context(format!("Failed to reopen the original database: {}", reopen_error))),
+                };
+            }
+        }
+        drop(slot);
+        fs::remove_file(&previous).await?;
+
+        self.ensure_search_index().await?;
+        self.seal_plaintext_provider_keys().await?;
+        Ok(())
+    }
+
+    /// 从备份中恢复单个项目：项目信息、文件夹、标签以及备份中该项目的全部文档。
+    /// 备份之后新建的文档保持不变
+    pub async fn restore_project_from_snapshot(&self, snapshot: &Path, project_id: &str) -> Result<()> {
+        let mut conn = self.pool().await.acquire().await?;
+        Self::attach_snapshot(&mut conn, snapshot).await?;
+        let result = async {
+            let mut tx = conn.begin().await?;
+            Self::restore_project(&mut tx, project_id).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        Self::detach_snapshot(conn).await;
+        result
+    }
+
+    /// 从备份中恢复单个文档，当前内容会先保存为一个修订
+    pub async fn restore_document_from_snapshot(&self, snapshot: &Path, document_id: &str) -> Result<()> {
+        let mut conn = self.pool().await.acquire().await?;
+        Self::attach_snapshot(&mut conn, snapshot).await?;
+        let result = async {
+            let mut tx = conn.begin().await?;
+            let project_id = Self::restore_document(&mut tx, document_id, true).await?;
+            Self::refresh_folder_paths(&mut tx, &project_id).await?;
+            Self::refresh_project_counters(&mut tx, &project_id).await?;
+            tx.commit().await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        Self::detach_snapshot(conn).await;
+        result
+    }
+
+    // ATTACH 不能在事务内执行，需要在取得的连接上先挂载再开启事务
+    async fn attach_snapshot(conn: &mut SqliteConnection, snapshot: &Path) -> Result<()> {
+        sqlx::query("ATTACH DATABASE ?1 AS snapshot")
+            .bind(snapshot.display().to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    // 卸载失败时丢弃该连接，避免挂载着备份的连接回到连接池
+    async fn detach_snapshot(mut conn: sqlx::pool::PoolConnection<Sqlite>) {
+        if let Err(e) = sqlx::query("DETACH DATABASE snapshot").execute(&mut *conn).await {
+            println!("Failed to detach snapshot: {}", e);
+            drop(conn.detach());
+        }
+    }
+
+    async fn restore_project(tx: &mut Transaction<'_, Sqlite>, project_id: &str) -> Result<()> {
+        // 文件夹之间的父子引用要等全部写入后再检查
+        sqlx::query("PRAGMA defer_foreign_keys = ON").execute(&mut **tx).await?;
+
+        let row = sqlx::query("SELECT workspace_id FROM snapshot.projects WHERE id = ?1")
+            .bind(project_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Project", format!("{} (in backup)", project_id)))?;
+        let workspace_id: String = row.get("workspace_id");
+        if sqlx::query("SELECT 1 FROM workspaces WHERE id = ?1").bind(&workspace_id).fetch_optional(&mut **tx).await?.is_none() {
+            return Err(AppError::validation(format!("Workspace {} of project {} no longer exists; restore the full backup instead", workspace_id, project_id)).into());
+        }
+
+        let exists = sqlx::query("SELECT 1 FROM projects WHERE id = ?1").bind(project_id).fetch_optional(&mut **tx).await?.is_some();
+        let sql = if exists {
+            r#"
+            UPDATE projects SET (name, description, icon, color, status, target_word_count, deadline, daily_word_quota, progress_mode, created_at) =
+                (SELECT name, description, icon, color, status, target_word_count, deadline, daily_word_quota, progress_mode, created_at
+                 FROM snapshot.projects WHERE id = ?1),
+                deleted_at = NULL, updated_at = ?2
+            WHERE id = ?1
+            "#
+        } else {
+            r#"
+            INSERT INTO projects (id, name, description, icon, color, status, workspace_id, target_word_count, deadline, daily_word_quota, progress_mode, created_at, updated_at)
+            SELECT id, name, description, icon, color, status, workspace_id, target_word_count, deadline, daily_word_quota, progress_mode, created_at, ?2
+            FROM snapshot.projects WHERE id = ?1
+            "#
+        };
+        sqlx::query(sql)
+            .bind(project_id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO folders (id, project_id, parent_id, name, sort_order, created_at, updated_at)
+            SELECT id, project_id, parent_id, name, sort_order, created_at, updated_at FROM snapshot.folders WHERE project_id = ?1
+            "#,
+        )
+        .bind(project_id)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE folders SET (parent_id, name, sort_order, updated_at) =
+                (SELECT s.parent_id, s.name, s.sort_order, s.updated_at FROM snapshot.folders s WHERE s.id = folders.id)
+            WHERE project_id = ?1 AND id IN (SELECT id FROM snapshot.folders WHERE project_id = ?1)
+            "#,
+        )
+        .bind(project_id)
+        .execute(&mut **tx)
+        .await?;
+
+        let tags: Vec<String> = sqlx::query(
+            "SELECT t.name FROM snapshot.project_tags pt JOIN snapshot.tags t ON t.id = pt.tag_id WHERE pt.project_id = ?1 ORDER BY pt.position",
+        )
+        .bind(project_id)
+        .fetch_all(&mut **tx)
+        .await?
+        .iter()
+        .map(|r| r.get("name"))
+        .collect();
+        Self::replace_project_tags(tx, project_id, &tags).await?;
+
+        let document_ids: Vec<String> = sqlx::query("SELECT id FROM snapshot.documents WHERE project_id = ?1")
+            .bind(project_id)
+            .fetch_all(&mut **tx)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        for document_id in &document_ids {
+            Self::restore_document(tx, document_id, false).await?;
+        }
+
+        Self::refresh_folder_paths(tx, project_id).await?;
+        Self::refresh_project_counters(tx, project_id).await
+    }
+
+    // 返回文档所属项目 ID。`undelete` 为 false 时保留备份中的回收站状态
+    async fn restore_document(tx: &mut Transaction<'_, Sqlite>, document_id: &str, undelete: bool) -> Result<String> {
+        let row = sqlx::query("SELECT project_id, folder_id, folder_path, content, metadata, deleted_at FROM snapshot.documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Document", format!("{} (in backup)", document_id)))?;
+        let project_id: String = row.get("project_id");
+        if sqlx::query("SELECT 1 FROM projects WHERE id = ?1").bind(&project_id).fetch_optional(&mut **tx).await?.is_none() {
+            return Err(AppError::validation(format!("Project {} of document {} no longer exists; restore the project first", project_id, document_id)).into());
+        }
+
+        // 备份中的文件夹已不存在时按路径重新创建
+        let snapshot_folder: Option<String> = row.get("folder_id");
+        let folder_id = match snapshot_folder {
+            Some(id) if sqlx::query("SELECT 1 FROM folders WHERE id = ?1 AND project_id = ?2").bind(&id).bind(&project_id).fetch_optional(&mut **tx).await?.is_some() => Some(id),
+            _ => match row.get::<Option<String>, _>("folder_path") {
+                Some(path) => Self::ensure_folder_path(tx, &project_id, &path).await?,
+                None => None,
+            },
+        };
+
+        let current = sqlx::query("SELECT folder_id, sort_order FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let content: String = row.get("content");
+        let mut metadata: DocumentMetadata = serde_json::from_str(&row.get::<String, _>("metadata")).unwrap_or_default();
+        let deleted_at: Option<String> = if undelete { None } else { row.get("deleted_at") };
+        let now = chrono::Utc::now().to_rfc3339();
+
+        match current {
+            Some(current) => {
+                metadata.version = Self::snapshot_if_changed(tx, document_id, &content).await?;
+                let sort_order = if current.get::<Option<String>, _>("folder_id") == folder_id {
+                    current.get::<i64, _>("sort_order")
+                } else {
+                    Self::next_document_sort_order(tx, &project_id, folder_id.as_deref()).await?
+                };
+                sqlx::query(
+                    r#"
+                    UPDATE documents SET (title, content, content_type, status, word_count, char_count, created_at) =
+                        (SELECT title, content, content_type, status, word_count, char_count, created_at FROM snapshot.documents WHERE id = ?1),
+                        metadata = ?2, folder_id = ?3, sort_order = ?4, deleted_at = ?5, updated_at = ?6
+                    WHERE id = ?1
+                    "#,
+                )
+                .bind(document_id)
+                .bind(serde_json::to_string(&metadata)?)
+                .bind(&folder_id)
+                .bind(sort_order)
+                .bind(&deleted_at)
+                .bind(&now)
+                .execute(&mut **tx)
+                .await?;
+            }
+            None => {
+                let sort_order = Self::next_document_sort_order(tx, &project_id, folder_id.as_deref()).await?;
+                sqlx::query(
+                    r#"
+                    INSERT INTO documents (id, title, content, content_type, status, word_count, char_count, project_id, tags, metadata, created_at, updated_at, last_accessed, folder_id, sort_order, deleted_at)
+                    SELECT id, title, content, content_type, status, word_count, char_count, project_id, tags, metadata, created_at, ?2, last_accessed, ?3, ?4, ?5
+                    FROM snapshot.documents WHERE id = ?1
+                    "#,
+                )
+                .bind(document_id)
+                .bind(&now)
+                .bind(&folder_id)
+                .bind(sort_order)
+                .bind(&deleted_at)
+                .execute(&mut **tx)
+                .await?;
+
+                // 当前库中没有的文档连同修订历史一起恢复
+                sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO revision_blobs (hash, content)
+                    SELECT b.hash, b.content FROM snapshot.revision_blobs b
+                    WHERE b.hash IN (SELECT content_hash FROM snapshot.document_revisions WHERE document_id = ?1)
+                    "#,
+                )
+                .bind(document_id)
+                .execute(&mut **tx)
+                .await?;
+                sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO document_revisions (id, document_id, version, title, content_hash, word_count, char_count, created_at)
+                    SELECT id, document_id, version, title, content_hash, word_count, char_count, created_at
+                    FROM snapshot.document_revisions WHERE document_id = ?1
+                    "#,
+                )
+                .bind(document_id)
+                .execute(&mut **tx)
+                .await?;
+            }
+        }
+
+        let tags: Vec<String> = sqlx::query(
+            "SELECT t.name FROM snapshot.document_tags dt JOIN snapshot.tags t ON t.id = dt.tag_id WHERE dt.document_id = ?1 ORDER BY dt.position",
+        )
+        .bind(document_id)
+        .fetch_all(&mut **tx)
+        .await?
+        .iter()
+        .map(|r| r.get("name"))
+        .collect();
+        Self::set_document_tags(tx, document_id, &tags).await?;
+        Self::reindex_document(tx, document_id).await?;
+
+        Ok(project_id)
+    }
+
+    // Secret operations
+    /// 加密保存新的密钥，返回 `secret://` 引用
+    pub async fn store_secret(&self, value: &str) -> Result<String> {
+        let mut conn = self.pool().await.acquire().await?;
+        self.insert_secret(&mut conn, value).await
+    }
+
+    async fn insert_secret(&self, conn: &mut SqliteConnection, value: &str) -> Result<String> {
+        let id = format!("sec-{}", uuid::Uuid::new_v4());
+        let encrypted = secrets::encrypt(&self.master_key, &id, value)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query("INSERT INTO secrets (id, ciphertext, nonce, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)")
+            .bind(&id)
+            .bind(&encrypted.ciphertext)
+            .bind(&encrypted.nonce)
+            .bind(&now)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(secrets::reference_for(&id))
+    }
+
+    fn parse_secret_reference(reference: &str) -> Result<&str> {
+        secrets::reference_id(reference)
+            .ok_or_else(|| AppError::validation(format!("Invalid secret reference: {}", reference)).into())
+    }
+
+    /// 替换引用指向的密钥内容，引用本身保持不变
+    pub async fn rotate_secret(&self, reference: &str, value: &str) -> Result<()> {
+        let mut conn = self.pool().await.acquire().await?;
+        self.rewrite_secret(&mut conn, reference, value).await
+    }
+
+    async fn rewrite_secret(&self, conn: &mut SqliteConnection, reference: &str, value: &str) -> Result<()> {
+        let id = Self::parse_secret_reference(reference)?;
+        let encrypted = secrets::encrypt(&self.master_key, id, value)?;
+
+        let result = sqlx::query("UPDATE secrets SET ciphertext = ?2, nonce = ?3, updated_at = ?4 WHERE id = ?1")
+            .bind(id)
+            .bind(&encrypted.ciphertext)
+            .bind(&encrypted.nonce)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *conn)
             .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Secret", reference).into());
+        }
         Ok(())
     }
 
-    pub async fn uninstall_agent(&self, id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM agents WHERE id = ?1")
+    pub async fn reveal_secret(&self, reference: &str) -> Result<String> {
+        let id = Self::parse_secret_reference(reference)?;
+        let row = sqlx::query("SELECT ciphertext, nonce FROM secrets WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&*self.pool().await)
+            .await?
+            .ok_or_else(|| AppError::not_found("Secret", reference))?;
+
+        secrets::decrypt(
+            &self.master_key,
+            id,
+            &secrets::EncryptedSecret { ciphertext: row.get("ciphertext"), nonce: row.get("nonce") },
+        )
+    }
+
+    pub async fn delete_secret(&self, reference: &str) -> Result<()> {
+        let mut conn = self.pool().await.acquire().await?;
+        Self::remove_secret(&mut conn, reference).await
+    }
+
+    async fn remove_secret(conn: &mut SqliteConnection, reference: &str) -> Result<()> {
+        let id = Self::parse_secret_reference(reference)?;
+        sqlx::query("DELETE FROM secrets WHERE id = ?1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
         Ok(())
     }
 
-    pub async fn update_agent_version(&self, id: &str, version: &str) -> Result<()> {
-        sqlx::query("UPDATE agents SET version = ?2 WHERE id = ?1")
-            .bind(id)
-            .bind(version)
-            .execute(&self.pool)
+    /// 明文转为引用；空值和已经是引用的值原样返回
+    pub async fn seal_secret(&self, value: &str) -> Result<String> {
+        if value.is_empty() || secrets::is_reference(value) {
+            return Ok(value.to_string());
+        }
+        self.store_secret(value).await
+    }
+
+    /// 引用原样返回，其余值视为明文（兼容尚未迁移的旧数据）
+    pub async fn resolve_secret(&self, value: &str) -> Result<String> {
+        if secrets::is_reference(value) {
+            self.reveal_secret(value).await
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    // 启动时把旧版本以明文保存的 API Key 转存为密钥引用
+    async fn seal_plaintext_provider_keys(&self) -> Result<()> {
+        let rows = sqlx::query("SELECT id, api_key FROM ai_providers WHERE api_key != '' AND api_key NOT LIKE 'secret://%'")
+            .fetch_all(&*self.pool().await)
             .await?;
+
+        // 密钥和引用在同一事务中写入，中途失败不会留下没有引用的密钥
+        let mut tx = self.pool().await.begin().await?;
+        for row in &rows {
+            let reference = self.insert_secret(&mut tx, &row.get::<String, _>("api_key")).await?;
+            sqlx::query("UPDATE ai_providers SET api_key = ?2 WHERE id = ?1")
+                .bind(row.get::<String, _>("id"))
+                .bind(&reference)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        if !rows.is_empty() {
+            println!("Encrypted {} plaintext provider API keys", rows.len());
+        }
         Ok(())
     }
 
     // AI Provider operations
+    fn row_to_ai_provider(row: &SqliteRow) -> AIProvider {
+        AIProvider {
+            id: row.get("id"),
+            name: row.get("name"),
+            provider_type: row.get("provider_type"),
+            model_name: row.get("model_name"),
+            api_key: row.get("api_key"),
+            base_url: row.get("base_url"),
+            icon: row.get("icon"),
+            bg_color: row.get("bg_color"),
+            status: row.get("status"),
+            status_text: row.get("status_text"),
+            max_tokens: row.get::<i64, _>("max_tokens"),
+            context_length: row.get::<i64, _>("context_length"),
+            temperature: row.get("temperature"),
+            enabled: row.get::<bool, _>("enabled"),
+            last_tested: row.get("last_tested"),
+            description: row.get("description"),
+            priority: row.get::<i64, _>("priority"),
+            model_pointer: row.get("model_pointer"),
+        }
+    }
+
     pub async fn list_ai_providers(&self) -> Result<Vec<AIProvider>> {
         let rows = sqlx::query("SELECT * FROM ai_providers ORDER BY priority ASC, name ASC")
-            .fetch_all(&self.pool)
+            .fetch_all(&*self.pool().await)
             .await?;
-        let mut items = Vec::new();
-        for row in rows {
-            items.push(AIProvider {
-                id: row.get("id"),
-                name: row.get("name"),
-                model_name: row.get("model_name"),
-                api_key: row.get("api_key"),
-                base_url: row.get("base_url"),
-                icon: row.get("icon"),
-                bg_color: row.get("bg_color"),
-                status: row.get("status"),
-                status_text: row.get("status_text"),
-                max_tokens: row.get::<i64, _>("max_tokens"),
-                context_length: row.get::<i64, _>("context_length"),
-                last_tested: row.get("last_tested"),
-                description: row.get("description"),
-                priority: row.get::<i64, _>("priority"),
-                model_pointer: row.get("model_pointer"),
-            });
-        }
-        Ok(items)
+        Ok(rows.iter().map(Self::row_to_ai_provider).collect())
+    }
+
+    pub async fn get_ai_provider_stats(&self) -> Result<AIProviderStats> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS total, COALESCE(SUM(status = 'connected'), 0) AS connected FROM ai_providers WHERE enabled = 1",
+        )
+        .fetch_one(&*self.pool().await)
+        .await?;
+        Ok(AIProviderStats {
+            connected: row.get::<i64, _>("connected") as u32,
+            total: row.get::<i64, _>("total") as u32,
+        })
+    }
+
+    /// 记录连接测试结果，提供商尚未保存时不做任何事
+    pub async fn record_ai_provider_test(&self, id: &str, connected: bool) -> Result<()> {
+        let (status, status_text) = if connected { ("connected", "已连接") } else { ("error", "连接失败") };
+        sqlx::query("UPDATE ai_providers SET status = ?2, status_text = ?3, last_tested = ?4 WHERE id = ?1")
+            .bind(id)
+            .bind(status)
+            .bind(status_text)
+            .bind(chrono::Local::now().format("%Y-%m-%d %H:%M").to_string())
+            .execute(&*self.pool().await)
+            .await?;
+        Ok(())
     }
 
     pub async fn create_ai_provider(&self, input: CreateAIProviderInput) -> Result<AIProvider> {
+        // 不接受引用，否则新提供商会与其他提供商共用同一个密钥
+        if secrets::is_reference(&input.api_key) {
+            return Err(AppError::validation("API key must be plaintext, not a secret reference").into());
+        }
         let id = format!("prov-{}", uuid::Uuid::new_v4());
         let provider = AIProvider {
             id: id.clone(),
             name: input.name,
+            provider_type: input.provider_type,
             model_name: input.model_name,
-            api_key: input.api_key,
+            api_key: self.seal_secret(&input.api_key).await?,
             base_url: input.base_url,
             icon: input.icon,
             bg_color: input.bg_color,
@@ -811,6 +2980,8 @@ impl Database {
             status_text: "测试中...".to_string(),
             max_tokens: input.max_tokens,
             context_length: input.context_length,
+            temperature: input.temperature,
+            enabled: input.enabled,
             last_tested: "从未".to_string(),
             description: input.description,
             priority: input.priority,
@@ -818,8 +2989,8 @@ impl Database {
         };
 
         sqlx::query(
-            r#"INSERT INTO ai_providers (id, name, model_name, api_key, base_url, icon, bg_color, status, status_text, max_tokens, context_length, last_tested, description, priority, model_pointer)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"#
+            r#"INSERT INTO ai_providers (id, name, model_name, api_key, base_url, icon, bg_color, status, status_text, max_tokens, context_length, last_tested, description, priority, model_pointer, provider_type, temperature, enabled)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)"#
         )
         .bind(&provider.id)
         .bind(&provider.name)
@@ -836,29 +3007,71 @@ impl Database {
         .bind(&provider.description)
         .bind(provider.priority)
         .bind(&provider.model_pointer)
-        .execute(&self.pool)
+        .bind(&provider.provider_type)
+        .bind(provider.temperature)
+        .bind(provider.enabled)
+        .execute(&*self.pool().await)
         .await?;
 
         Ok(provider)
     }
 
+    async fn get_ai_provider_key(conn: &mut SqliteConnection, id: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT api_key FROM ai_providers WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(row.map(|row| row.get("api_key")))
+    }
+
     pub async fn delete_ai_provider(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+        if let Some(key) = Self::get_ai_provider_key(&mut tx, id).await? {
+            if secrets::is_reference(&key) {
+                Self::remove_secret(&mut tx, &key).await?;
+            }
+        }
         sqlx::query("DELETE FROM ai_providers WHERE id = ?1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(())
     }
 
+    /// 传入明文 API Key 时更新原有密钥（没有则新建），传入空值时删除密钥。
+    /// 引用只接受该提供商自己的原有引用，密钥的变更与提供商的更新在同一事务中完成
     pub async fn update_ai_provider(&self, provider: &AIProvider) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+        let existing = Self::get_ai_provider_key(&mut tx, &provider.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("AIProvider", &provider.id))?;
+        let api_key = if provider.api_key.is_empty() {
+            if secrets::is_reference(&existing) {
+                Self::remove_secret(&mut tx, &existing).await?;
+            }
+            String::new()
+        } else if secrets::is_reference(&provider.api_key) {
+            if provider.api_key != existing {
+                return Err(AppError::validation("API key reference does not belong to this provider").into());
+            }
+            existing
+        } else if secrets::is_reference(&existing) {
+            self.rewrite_secret(&mut tx, &existing, &provider.api_key).await?;
+            existing
+        } else {
+            self.insert_secret(&mut tx, &provider.api_key).await?
+        };
+
         sqlx::query(
             r#"UPDATE ai_providers SET name=?2, model_name=?3, api_key=?4, base_url=?5, icon=?6, bg_color=?7,
-                status=?8, status_text=?9, max_tokens=?10, context_length=?11, last_tested=?12, description=?13, priority=?14, model_pointer=?15 WHERE id=?1"#
+                status=?8, status_text=?9, max_tokens=?10, context_length=?11, last_tested=?12, description=?13, priority=?14, model_pointer=?15,
+                provider_type=?16, temperature=?17, enabled=?18 WHERE id=?1"#
         )
         .bind(&provider.id)
         .bind(&provider.name)
         .bind(&provider.model_name)
-        .bind(&provider.api_key)
+        .bind(&api_key)
         .bind(&provider.base_url)
         .bind(&provider.icon)
         .bind(&provider.bg_color)
@@ -870,16 +3083,20 @@ impl Database {
         .bind(&provider.description)
         .bind(provider.priority)
         .bind(&provider.model_pointer)
-        .execute(&self.pool)
+        .bind(&provider.provider_type)
+        .bind(provider.temperature)
+        .bind(provider.enabled)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
         Ok(())
     }
 
     // Additional workspace methods
     pub async fn get_workspace_by_id(&self, workspace_id: &str) -> Result<Option<Workspace>> {
-        let row = sqlx::query("SELECT * FROM workspaces WHERE id = ?1")
+        let row = sqlx::query("SELECT * FROM workspaces WHERE id = ?1 AND deleted_at IS NULL")
             .bind(workspace_id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&*self.pool().await)
             .await?;
 
         if let Some(row) = row {
@@ -900,24 +3117,875 @@ impl Database {
     }
 
     pub async fn update_workspace(&self, workspace_id: &str, name: &str, description: &str) -> Result<Workspace> {
-        sqlx::query("UPDATE workspaces SET name = ?2, description = ?3, updated_at = ?4 WHERE id = ?1")
+        let result = sqlx::query("UPDATE workspaces SET name = ?2, description = ?3, updated_at = ?4 WHERE id = ?1 AND deleted_at IS NULL")
             .bind(workspace_id)
             .bind(name)
             .bind(description)
             .bind(chrono::Utc::now().to_rfc3339())
-            .execute(&self.pool)
+            .execute(&*self.pool().await)
             .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Workspace", workspace_id).into());
+        }
 
         // return updated row
-        Ok(self.get_workspace_by_id(workspace_id).await?.expect("workspace should exist"))
+        self.get_workspace_by_id(workspace_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Workspace", workspace_id).into())
     }
 
+    /// 将工作区及其下的项目、文档移入回收站
     pub async fn delete_workspace(&self, workspace_id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM workspaces WHERE id = ?1")
+        let deleted_at = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool().await.begin().await?;
+
+        sqlx::query(
+            "UPDATE documents SET deleted_at = ?2 WHERE deleted_at IS NULL AND project_id IN (SELECT id FROM projects WHERE workspace_id = ?1 AND deleted_at IS NULL)",
+        )
+        .bind(workspace_id)
+        .bind(&deleted_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE projects SET deleted_at = ?2 WHERE workspace_id = ?1 AND deleted_at IS NULL")
             .bind(workspace_id)
-            .execute(&self.pool)
+            .bind(&deleted_at)
+            .execute(&mut *tx)
             .await?;
-        
+
+        sqlx::query("UPDATE workspaces SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(workspace_id)
+            .bind(&deleted_at)
+            .execute(&mut *tx)
+            .await?;
+        Self::refresh_workspace_counters(&mut tx, workspace_id).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Counter operations
+    // 计数字段始终在修改数据的同一事务内由实际数据重新计算，避免增量更新累积误差
+
+    // 进度按目标字数计算；未设置目标或选择按状态计算时，取各文档状态权重的平均值
+    const PROJECT_COUNTERS_SQL: &'static str = r#"
+        UPDATE projects SET
+            documents_count = (SELECT COUNT(*) FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL),
+            words_count = (SELECT COALESCE(SUM(d.word_count), 0) FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL),
+            progress = CASE
+                WHEN progress_mode = '"WordCount"' AND COALESCE(target_word_count, 0) > 0 THEN
+                    MIN(100, (SELECT COALESCE(SUM(d.word_count), 0) FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL) * 100 / target_word_count)
+                ELSE COALESCE((
+                    SELECT CAST(ROUND(AVG(CASE d.status WHEN '"Final"' THEN 100 WHEN '"Review"' THEN 80 WHEN '"InProgress"' THEN 50 ELSE 0 END)) AS INTEGER)
+                    FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL
+                ), 0)
+            END
+    "#;
+
+    const WORKSPACE_COUNTERS_SQL: &'static str = r#"
+        UPDATE workspaces SET
+            projects_count = (SELECT COUNT(*) FROM projects p WHERE p.workspace_id = workspaces.id AND p.deleted_at IS NULL)
+    "#;
+
+    async fn refresh_project_counters(conn: &mut SqliteConnection, project_id: &str) -> Result<()> {
+        sqlx::query(&format!("{} WHERE id = ?1", Self::PROJECT_COUNTERS_SQL))
+            .bind(project_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(&format!("{} WHERE id = (SELECT workspace_id FROM projects WHERE id = ?1)", Self::WORKSPACE_COUNTERS_SQL))
+            .bind(project_id)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn refresh_document_project_counters(conn: &mut SqliteConnection, document_id: &str) -> Result<()> {
+        let project_id: Option<String> = sqlx::query("SELECT project_id FROM documents WHERE id = ?1")
+            .bind(document_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|r| r.get("project_id"));
+        if let Some(project_id) = project_id {
+            Self::refresh_project_counters(conn, &project_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn refresh_workspace_counters(conn: &mut SqliteConnection, workspace_id: &str) -> Result<()> {
+        sqlx::query(&format!("{} WHERE workspace_id = ?1", Self::PROJECT_COUNTERS_SQL))
+            .bind(workspace_id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(&format!("{} WHERE id = ?1", Self::WORKSPACE_COUNTERS_SQL))
+            .bind(workspace_id)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn trash_item_workspace(conn: &mut SqliteConnection, kind: TrashItemKind, id: &str) -> Result<Option<String>> {
+        let sql = match kind {
+            TrashItemKind::Workspace => "SELECT id AS workspace_id FROM workspaces WHERE id = ?1",
+            TrashItemKind::Project => "SELECT workspace_id FROM projects WHERE id = ?1",
+            TrashItemKind::Document => "SELECT p.workspace_id FROM documents d JOIN projects p ON p.id = d.project_id WHERE d.id = ?1",
+        };
+        Ok(sqlx::query(sql)
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|r| r.get("workspace_id")))
+    }
+
+    // Trash operations
+
+    /// 列出回收站中被直接删除的条目；随父级一起删除的子项不单独列出
+    pub async fn list_trash(&self, retention_days: u32) -> Result<Vec<TrashItem>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 'Workspace' AS kind, w.id, w.name, NULL AS parent_id, NULL AS parent_name, w.deleted_at
+            FROM workspaces w
+            WHERE w.deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'Project' AS kind, p.id, p.name, p.workspace_id, w.name, p.deleted_at
+            FROM projects p LEFT JOIN workspaces w ON w.id = p.workspace_id
+            WHERE p.deleted_at IS NOT NULL AND (w.deleted_at IS NULL OR w.deleted_at != p.deleted_at)
+            UNION ALL
+            SELECT 'Document' AS kind, d.id, d.title, d.project_id, p.name, d.deleted_at
+            FROM documents d LEFT JOIN projects p ON p.id = d.project_id
+            WHERE d.deleted_at IS NOT NULL AND (p.deleted_at IS NULL OR p.deleted_at != d.deleted_at)
+            ORDER BY deleted_at DESC
+            "#,
+        )
+        .fetch_all(&*self.pool().await)
+        .await?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let deleted_at = chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("deleted_at"))?.with_timezone(&chrono::Utc);
+            items.push(TrashItem {
+                id: row.get("id"),
+                kind: serde_json::from_value(serde_json::Value::String(row.get("kind")))?,
+                name: row.get("name"),
+                parent_id: row.get("parent_id"),
+                parent_name: row.get("parent_name"),
+                deleted_at,
+                purge_at: deleted_at + chrono::Duration::days(retention_days as i64),
+            });
+        }
+        Ok(items)
+    }
+
+    /// 从回收站恢复条目及与其一同删除的子项；若父级也在回收站中，父级会一并恢复
+    pub async fn restore_trash_item(&self, kind: TrashItemKind, id: &str) -> Result<()> {
+        let mut tx = self.pool().await.begin().await?;
+        let workspace_id = Self::trash_item_workspace(&mut tx, kind, id).await?;
+
+        match kind {
+            TrashItemKind::Workspace => {
+                Self::restore_workspace_row(&mut tx, id).await?;
+            }
+            TrashItemKind::Project => {
+                let workspace_id: String = sqlx::query("SELECT workspace_id FROM projects WHERE id = ?1")
+                    .bind(id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Project", id))?
+                    .get("workspace_id");
+                Self::restore_project_row(&mut tx, id).await?;
+                sqlx::query("UPDATE workspaces SET deleted_at = NULL WHERE id = ?1")
+                    .bind(&workspace_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            TrashItemKind::Document => {
+                let project_id: String = sqlx::query("SELECT project_id FROM documents WHERE id = ?1")
+                    .bind(id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| AppError::not_found("Document", id))?
+                    .get("project_id");
+                sqlx::query("UPDATE documents SET deleted_at = NULL WHERE id = ?1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE projects SET deleted_at = NULL WHERE id = ?1")
+                    .bind(&project_id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE workspaces SET deleted_at = NULL WHERE id = (SELECT workspace_id FROM projects WHERE id = ?1)")
+                    .bind(&project_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        if let Some(workspace_id) = workspace_id {
+            Self::refresh_workspace_counters(&mut tx, &workspace_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore_workspace_row(tx: &mut Transaction<'_, Sqlite>, workspace_id: &str) -> Result<()> {
+        let deleted_at: Option<String> = sqlx::query("SELECT deleted_at FROM workspaces WHERE id = ?1")
+            .bind(workspace_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("Workspace", workspace_id))?
+            .get("deleted_at");
+        let Some(deleted_at) = deleted_at else { return Ok(()) };
+
+        let project_ids: Vec<String> = sqlx::query("SELECT id FROM projects WHERE workspace_id = ?1 AND deleted_at = ?2")
+            .bind(workspace_id)
+            .bind(&deleted_at)
+            .fetch_all(&mut **tx)
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        for project_id in project_ids {
+            Self::restore_project_row(tx, &project_id).await?;
+        }
+
+        sqlx::query("UPDATE workspaces SET deleted_at = NULL WHERE id = ?1")
+            .bind(workspace_id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn restore_project_row(tx: &mut Transaction<'_, Sqlite>, project_id: &str) -> Result<()> {
+        sqlx::query("UPDATE documents SET deleted_at = NULL WHERE project_id = ?1 AND deleted_at = (SELECT deleted_at FROM projects WHERE id = ?1)")
+            .bind(project_id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("UPDATE projects SET deleted_at = NULL WHERE id = ?1")
+            .bind(project_id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    /// 永久删除回收站中的条目及其所有子项
+    pub async fn purge_trash_item(&self, kind: TrashItemKind, id: &str) -> Result<()> {
+        if !self.purge_if_trashed(kind, id).await? {
+            return Err(AppError::not_found("Trash item", id).into());
+        }
+        Ok(())
+    }
+
+    // 条目已不在回收站（例如已随父级一起清理）时返回 false
+    async fn purge_if_trashed(&self, kind: TrashItemKind, id: &str) -> Result<bool> {
+        let mut tx = self.pool().await.begin().await?;
+        let document_filter = match kind {
+            TrashItemKind::Workspace => "project_id IN (SELECT id FROM projects WHERE workspace_id = ?1)",
+            TrashItemKind::Project => "project_id = ?1",
+            TrashItemKind::Document => "id = ?1",
+        };
+        let table = match kind {
+            TrashItemKind::Workspace => "workspaces",
+            TrashItemKind::Project => "projects",
+            TrashItemKind::Document => "documents",
+        };
+
+        let in_trash = sqlx::query(&format!("SELECT 1 FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL", table))
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if !in_trash {
+            return Ok(false);
+        }
+        let workspace_id = Self::trash_item_workspace(&mut tx, kind, id).await?;
+
+        Self::purge_documents_where(&mut tx, document_filter, id).await?;
+        if kind != TrashItemKind::Document {
+            sqlx::query(&format!("DELETE FROM folders WHERE {}", document_filter))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!("DELETE FROM project_tags WHERE {}", document_filter))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if kind == TrashItemKind::Workspace {
+            sqlx::query("DELETE FROM projects WHERE workspace_id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(&format!("DELETE FROM {} WHERE id = ?1", table))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(workspace_id) = workspace_id {
+            Self::refresh_workspace_counters(&mut tx, &workspace_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // 删除满足条件的文档及其修订历史和全文索引
+    async fn purge_documents_where(tx: &mut Transaction<'_, Sqlite>, filter: &str, arg: &str) -> Result<()> {
+        let ids_sql = format!("SELECT id FROM documents WHERE {}", filter);
+        let hashes: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT content_hash FROM document_revisions WHERE document_id IN ({})",
+            ids_sql
+        ))
+        .bind(arg)
+        .fetch_all(&mut **tx)
+        .await?;
+        sqlx::query(&format!("DELETE FROM document_tags WHERE document_id IN ({})", ids_sql))
+            .bind(arg)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(&format!("DELETE FROM document_revisions WHERE document_id IN ({})", ids_sql))
+            .bind(arg)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(&format!("DELETE FROM documents_fts WHERE document_id IN ({})", ids_sql))
+            .bind(arg)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(&format!("DELETE FROM documents WHERE {}", filter))
+            .bind(arg)
+            .execute(&mut **tx)
+            .await?;
+        Self::delete_unreferenced_blobs(tx, &hashes.iter().collect::<Vec<_>>()).await
+    }
+
+    pub async fn empty_trash(&self) -> Result<()> {
+        for item in self.list_trash(0).await? {
+            self.purge_if_trashed(item.kind, &item.id).await?;
+        }
         Ok(())
     }
+
+    /// 永久删除在回收站中超过保留天数的条目，返回清理的数量
+    pub async fn purge_expired_trash(&self, retention_days: u32) -> Result<u32> {
+        let now = chrono::Utc::now();
+        let mut purged = 0;
+        for item in self.list_trash(retention_days).await? {
+            if item.purge_at <= now && self.purge_if_trashed(item.kind, &item.id).await? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::document::DocumentType;
+
+    // 临时目录中的数据库，离开作用域时删除目录
+    struct TestDatabase {
+        database: Database,
+        dir: PathBuf,
+    }
+
+    impl std::ops::Deref for TestDatabase {
+        type Target = Database;
+
+        fn deref(&self) -> &Database {
+            &self.database
+        }
+    }
+
+    impl Drop for TestDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    async fn open_database() -> TestDatabase {
+        let dir = std::env::temp_dir().join(format!("writeflow-database-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let database = Database::open(dir.join("writeflow.db"), secrets::ephemeral_master_key()).await.unwrap();
+        TestDatabase { database, dir }
+    }
+
+    async fn create_workspace(database: &Database, name: &str) -> Workspace {
+        database
+            .create_workspace(CreateWorkspaceData { name: name.to_string(), description: String::new() })
+            .await
+            .unwrap()
+    }
+
+    async fn create_project(database: &Database, workspace_id: &str) -> Project {
+        database
+            .create_project(CreateProjectData {
+                name: "Project".to_string(),
+                description: String::new(),
+                icon: String::new(),
+                color: String::new(),
+                workspace_id: workspace_id.to_string(),
+                template_id: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn create_document(database: &Database, project_id: &str, title: &str, content: &str) -> Document {
+        database
+            .create_document(CreateDocumentData {
+                title: title.to_string(),
+                content: Some(content.to_string()),
+                content_type: DocumentType::Markdown,
+                project_id: project_id.to_string(),
+                folder_path: None,
+                folder_id: None,
+                tags: None,
+                template_id: None,
+                template_variables: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    fn is_not_found(error: &anyhow::Error) -> bool {
+        matches!(error.downcast_ref::<AppError>(), Some(AppError::NotFound { .. }))
+    }
+
+    async fn workspace_names(database: &Database) -> Vec<String> {
+        let mut names: Vec<String> = database.get_workspaces().await.unwrap().into_iter().map(|w| w.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn replace_with_snapshot_swaps_in_the_backup() {
+        let database = open_database().await;
+        create_workspace(&database, "before").await;
+        let snapshot = database.db_path.with_file_name("snapshot.db");
+        database.snapshot_to(&snapshot).await.unwrap();
+        create_workspace(&database, "after").await;
+
+        database.replace_with_snapshot(&snapshot).await.unwrap();
+
+        assert_eq!(workspace_names(&database).await, ["before"]);
+        assert!(!database.db_path.with_extension("db.pre-restore").exists());
+        assert!(!database.db_path.with_extension("db.restore").exists());
+    }
+
+    #[tokio::test]
+    async fn replace_with_snapshot_reopens_the_database_when_a_rename_fails() {
+        let database = open_database().await;
+        create_workspace(&database, "before").await;
+        let snapshot = database.db_path.with_file_name("snapshot.db");
+        database.snapshot_to(&snapshot).await.unwrap();
+        create_workspace(&database, "after").await;
+
+        // 占用 .pre-restore 的位置，把原文件移开的那一步会失败
+        let previous = database.db_path.with_extension("db.pre-restore");
+        std::fs::create_dir_all(previous.join("occupied")).unwrap();
+
+        assert!(database.replace_with_snapshot(&snapshot).await.is_err());
+        assert_eq!(workspace_names(&database).await, ["after", "before"]);
+        assert!(!database.db_path.with_extension("db.restore").exists());
+    }
+
+    #[tokio::test]
+    async fn replace_with_snapshot_moves_the_original_back_when_the_backup_cannot_be_opened() {
+        let database = open_database().await;
+        create_workspace(&database, "before").await;
+        let snapshot = database.db_path.with_file_name("snapshot.db");
+        database.snapshot_to(&snapshot).await.unwrap();
+        create_workspace(&database, "after").await;
+
+        // 结构版本比当前程序新的备份无法打开
+        let mut conn = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&snapshot)).await.unwrap();
+        sqlx::query("INSERT INTO schema_version (version, name, applied_at) VALUES (9999, 'future', '')")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+
+        assert!(database.replace_with_snapshot(&snapshot).await.is_err());
+        assert_eq!(workspace_names(&database).await, ["after", "before"]);
+        assert!(!database.db_path.with_extension("db.pre-restore").exists());
+        assert!(!database.db_path.with_extension("db.restore").exists());
+    }
+
+    #[tokio::test]
+    async fn trashed_or_missing_projects_and_documents_cannot_be_edited() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let document = create_document(&database, &project.id, "Chapter", "draft").await;
+
+        database.delete_document(&document.id).await.unwrap();
+        assert!(is_not_found(&database.save_document_content(&document.id, "edited").await.unwrap_err()));
+        assert!(is_not_found(&database.update_document(&document.id, document.clone()).await.unwrap_err()));
+        assert!(is_not_found(&database.save_document_content("missing", "edited").await.unwrap_err()));
+
+        database.delete_project(&project.id).await.unwrap();
+        assert!(is_not_found(&database.update_project(&project.id, project.clone()).await.unwrap_err()));
+        assert!(is_not_found(&database.update_project("missing", project.clone()).await.unwrap_err()));
+
+        // 被拒绝的修改不留下修订
+        assert!(database.list_document_revisions(&document.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn inspect_snapshot_checks_a_copy_and_reports_the_schema_version() {
+        let database = open_database().await;
+        create_workspace(&database, "workspace").await;
+        let snapshot = database.db_path.with_file_name("snapshot.db");
+        database.snapshot_to(&snapshot).await.unwrap();
+        let before = std::fs::read(&snapshot).unwrap();
+
+        assert_eq!(Database::inspect_snapshot(&snapshot).await.unwrap(), migration::latest_version());
+        assert_eq!(std::fs::read(&snapshot).unwrap(), before);
+    }
+
+    #[tokio::test]
+    async fn inspect_snapshot_rejects_a_corrupted_search_index() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        create_document(&database, &project.id, "Chapter", "some searchable words").await;
+        let snapshot = database.db_path.with_file_name("snapshot.db");
+        database.snapshot_to(&snapshot).await.unwrap();
+
+        // 删掉索引中的一行，让倒排索引与文档对不上
+        let mut conn = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&snapshot)).await.unwrap();
+        sqlx::query("DELETE FROM documents_fts_data WHERE id = (SELECT MAX(id) FROM documents_fts_data)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+
+        let error = Database::inspect_snapshot(&snapshot).await.unwrap_err();
+        assert!(error.to_string().contains("FTS5"), "{}", error);
+    }
+
+    fn provider_input(name: &str, api_key: &str) -> CreateAIProviderInput {
+        CreateAIProviderInput {
+            name: name.to_string(),
+            provider_type: "openai".to_string(),
+            model_name: "model".to_string(),
+            api_key: api_key.to_string(),
+            base_url: None,
+            icon: String::new(),
+            bg_color: String::new(),
+            max_tokens: 4096,
+            context_length: 8192,
+            temperature: None,
+            enabled: true,
+            description: None,
+            priority: 0,
+            model_pointer: None,
+        }
+    }
+
+    async fn create_provider(database: &Database, name: &str, api_key: &str) -> AIProvider {
+        database.create_ai_provider(provider_input(name, api_key)).await.unwrap()
+    }
+
+    async fn secret_count(database: &Database) -> i64 {
+        sqlx::query("SELECT COUNT(*) AS count FROM secrets")
+            .fetch_one(&*database.pool().await)
+            .await
+            .unwrap()
+            .get("count")
+    }
+
+    #[tokio::test]
+    async fn provider_keys_are_sealed_rotated_and_deleted_with_the_provider() {
+        let database = open_database().await;
+        let mut provider = create_provider(&database, "first", "sk-first").await;
+        let reference = provider.api_key.clone();
+        assert!(secrets::is_reference(&reference));
+        assert_eq!(database.reveal_secret(&reference).await.unwrap(), "sk-first");
+
+        // 传回自己的引用时密钥不变，传入明文时原引用下的密钥被替换
+        database.update_ai_provider(&provider).await.unwrap();
+        assert_eq!(database.reveal_secret(&reference).await.unwrap(), "sk-first");
+        provider.api_key = "sk-rotated".to_string();
+        database.update_ai_provider(&provider).await.unwrap();
+        assert_eq!(database.list_ai_providers().await.unwrap()[0].api_key, reference);
+        assert_eq!(database.reveal_secret(&reference).await.unwrap(), "sk-rotated");
+        assert_eq!(secret_count(&database).await, 1);
+
+        // 清空后删除密钥
+        provider.api_key = String::new();
+        database.update_ai_provider(&provider).await.unwrap();
+        assert_eq!(database.list_ai_providers().await.unwrap()[0].api_key, "");
+        assert_eq!(secret_count(&database).await, 0);
+
+        provider.api_key = "sk-again".to_string();
+        database.update_ai_provider(&provider).await.unwrap();
+        assert_eq!(secret_count(&database).await, 1);
+        database.delete_ai_provider(&provider.id).await.unwrap();
+        assert_eq!(secret_count(&database).await, 0);
+    }
+
+    #[tokio::test]
+    async fn providers_cannot_take_another_providers_key_reference() {
+        let database = open_database().await;
+        let first = create_provider(&database, "first", "sk-first").await;
+        let mut second = create_provider(&database, "second", "sk-second").await;
+        let second_reference = second.api_key.clone();
+
+        second.api_key = first.api_key.clone();
+        let error = database.update_ai_provider(&second).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<AppError>(), Some(AppError::Validation(_))));
+        // 被拒绝的更新不动原有密钥
+        assert_eq!(database.reveal_secret(&second_reference).await.unwrap(), "sk-second");
+        assert_eq!(database.reveal_secret(&first.api_key).await.unwrap(), "sk-first");
+        assert!(database.create_ai_provider(provider_input("third", &first.api_key)).await.is_err());
+    }
+
+    #[test]
+    fn page_bounds_clamp_page_size_and_never_overflow() {
+        assert_eq!(Database::page_bounds(0, 0), (1, 0));
+        assert_eq!(Database::page_bounds(3, 20), (20, 40));
+        assert_eq!(Database::page_bounds(2, u32::MAX), (MAX_PAGE_SIZE as i64, MAX_PAGE_SIZE as i64));
+        assert_eq!(Database::page_bounds(u32::MAX, 500), (500, (u32::MAX as i64 - 1) * 500));
+    }
+
+    #[tokio::test]
+    async fn list_and_search_documents_accept_out_of_range_pages() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        create_document(&database, &project.id, "Chapter", "words").await;
+
+        let query = DocumentListQuery { page: Some(u32::MAX), page_size: Some(u32::MAX), ..Default::default() };
+        let result = database.list_documents(&query).await.unwrap();
+        assert!(result.items.is_empty());
+        assert_eq!(result.total, 1);
+
+        let result = database.search_documents(Some("words"), None, None, &[], None, u32::MAX, u32::MAX).await.unwrap();
+        assert!(result.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn manual_order_applies_the_direction_to_folders_and_positions() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let folder = database
+            .create_folder(CreateFolderData { project_id: project.id.clone(), parent_id: None, name: "Part".to_string() })
+            .await
+            .unwrap();
+        create_document(&database, &project.id, "loose", "").await;
+        for title in ["first", "second"] {
+            let document = create_document(&database, &project.id, title, "").await;
+            database.move_document(&document.id, Some(&folder.id), None).await.unwrap();
+        }
+
+        let titles = |order: &str| {
+            let query = DocumentListQuery {
+                project_id: Some(project.id.clone()),
+                sort: Some("sort_order".to_string()),
+                order: Some(order.to_string()),
+                ..Default::default()
+            };
+            let database = &database;
+            async move { database.list_documents(&query).await.unwrap().items.into_iter().map(|d| d.title).collect::<Vec<_>>() }
+        };
+        assert_eq!(titles("ASC").await, ["loose", "first", "second"]);
+        assert_eq!(titles("DESC").await, ["second", "first", "loose"]);
+    }
+
+    #[tokio::test]
+    async fn single_cjk_characters_match_anywhere_in_a_run() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        create_document(&database, &project.id, "恐龙", "我喜欢恐龙。").await;
+        create_document(&database, &project.id, "天气", "今天天气很好").await;
+        let trashed = create_document(&database, &project.id, "回收站", "龙在回收站里").await;
+        database.delete_document(&trashed.id).await.unwrap();
+
+        let titles = |query: &'static str| {
+            let database = &database;
+            async move {
+                let result = database.search_documents(Some(query), None, None, &[], None, 1, 20).await.unwrap();
+                let mut titles: Vec<String> = result.items.into_iter().map(|hit| hit.title).collect();
+                titles.sort();
+                titles
+            }
+        };
+        // 段首、段中、段尾的单字都能命中
+        assert_eq!(titles("我").await, ["恐龙"]);
+        assert_eq!(titles("欢").await, ["恐龙"]);
+        assert_eq!(titles("龙").await, ["恐龙"]);
+        assert_eq!(titles("好").await, ["天气"]);
+        assert_eq!(titles("天气").await, ["天气"]);
+        assert!(titles("龙天").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn saving_records_revisions_and_restoring_keeps_the_replaced_content() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let document = create_document(&database, &project.id, "doc", "first").await;
+
+        database.save_document_content(&document.id, "second").await.unwrap();
+        database.save_document_content(&document.id, "second").await.unwrap();
+        database.save_document_content(&document.id, "third").await.unwrap();
+        let restored = database.restore_document_revision(&document.id, 1).await.unwrap();
+        assert_eq!(restored.content, "first");
+        assert_eq!(restored.metadata.version, 4);
+
+        // 未变化的保存不产生修订，相同内容共用一个 blob
+        let versions: Vec<u32> = database.list_document_revisions(&document.id).await.unwrap().iter().map(|r| r.version).collect();
+        assert_eq!(versions, [3, 2, 1]);
+        let third = database.get_document_revision(&document.id, 3).await.unwrap().unwrap();
+        assert_eq!(third.content, "third");
+        let blobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revision_blobs").fetch_one(&*database.pool().await).await.unwrap();
+        assert_eq!(blobs, 3);
+    }
+
+    #[tokio::test]
+    async fn trash_restores_cascaded_children_and_purges_their_history() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let trashed_first = create_document(&database, &project.id, "trashed first", "old").await;
+        let kept = create_document(&database, &project.id, "kept", "body").await;
+        database.save_document_content(&trashed_first.id, "new").await.unwrap();
+        database.delete_document(&trashed_first.id).await.unwrap();
+        database.delete_project(&project.id).await.unwrap();
+
+        // 随项目一起删除的文档不单独列出
+        let mut trash: Vec<(TrashItemKind, String)> = database.list_trash(30).await.unwrap().into_iter().map(|item| (item.kind, item.id)).collect();
+        trash.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut expected = vec![(TrashItemKind::Project, project.id.clone()), (TrashItemKind::Document, trashed_first.id.clone())];
+        expected.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(trash, expected);
+
+        database.restore_trash_item(TrashItemKind::Project, &project.id).await.unwrap();
+        let active: Vec<String> = database.get_documents_by_project(&project.id).await.unwrap().into_iter().map(|d| d.id).collect();
+        assert_eq!(active, [kept.id.clone()]);
+        assert_eq!(database.get_project_by_id(&project.id).await.unwrap().unwrap().documents_count, 1);
+
+        database.purge_trash_item(TrashItemKind::Document, &trashed_first.id).await.unwrap();
+        assert!(database.get_document_by_id(&trashed_first.id).await.unwrap().is_none());
+        let pool = database.pool().await;
+        let revisions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM document_revisions").fetch_one(&*pool).await.unwrap();
+        let blobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revision_blobs").fetch_one(&*pool).await.unwrap();
+        drop(pool);
+        assert_eq!((revisions, blobs), (0, 0));
+        let error = database.purge_trash_item(TrashItemKind::Document, &trashed_first.id).await.unwrap_err();
+        assert!(is_not_found(&error));
+        let error = database.purge_trash_item(TrashItemKind::Document, &kept.id).await.unwrap_err();
+        assert!(is_not_found(&error));
+    }
+
+    #[tokio::test]
+    async fn expired_trash_is_purged_once_with_its_children() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let document = create_document(&database, &project.id, "doc", "body").await;
+        database.delete_workspace(&workspace.id).await.unwrap();
+
+        assert_eq!(database.purge_expired_trash(30).await.unwrap(), 0);
+        assert_eq!(database.list_trash(30).await.unwrap().len(), 1);
+
+        assert_eq!(database.purge_expired_trash(0).await.unwrap(), 1);
+        assert!(database.list_trash(0).await.unwrap().is_empty());
+        assert!(database.get_project_by_id(&project.id).await.unwrap().is_none());
+        assert!(database.get_document_by_id(&document.id).await.unwrap().is_none());
+        assert!(database.get_workspace_by_id(&workspace.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn counters_follow_every_document_and_project_change() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let other = create_project(&database, &workspace.id).await;
+        let first = create_document(&database, &project.id, "first", "one two three").await;
+        let second = create_document(&database, &project.id, "second", "four").await;
+
+        let counters = |project_id: String| {
+            let database = &database;
+            async move {
+                let project = database.get_project_by_id(&project_id).await.unwrap().unwrap();
+                (project.documents_count, project.words_count)
+            }
+        };
+        let projects_count = || async { database.get_workspace_by_id(&workspace.id).await.unwrap().unwrap().projects_count };
+        assert_eq!(counters(project.id.clone()).await, (2, 4));
+        assert_eq!(projects_count().await, 2);
+
+        database.save_document_content(&first.id, "one two").await.unwrap();
+        assert_eq!(counters(project.id.clone()).await, (2, 3));
+
+        database.delete_document(&second.id).await.unwrap();
+        assert_eq!(counters(project.id.clone()).await, (1, 2));
+        database.restore_trash_item(TrashItemKind::Document, &second.id).await.unwrap();
+        assert_eq!(counters(project.id.clone()).await, (2, 3));
+
+        database.delete_project(&other.id).await.unwrap();
+        assert_eq!(projects_count().await, 1);
+        database.restore_trash_item(TrashItemKind::Project, &other.id).await.unwrap();
+        assert_eq!(projects_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn merging_tags_moves_documents_and_projects_without_duplicates() {
+        let database = open_database().await;
+        let workspace = create_workspace(&database, "workspace").await;
+        let project = create_project(&database, &workspace.id).await;
+        let tagged = |title: &'static str, tags: &'static [&'static str]| {
+            let database = &database;
+            let project_id = project.id.clone();
+            async move {
+                let document = create_document(database, &project_id, title, "").await;
+                let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+                let mut conn = database.pool().await.acquire().await.unwrap();
+                Database::set_document_tags(&mut conn, &document.id, &tags).await.unwrap();
+                document
+            }
+        };
+        let both = tagged("both", &["Draft", "Intro"]).await;
+        let rough = tagged("rough", &["rough"]).await;
+        let mixed = tagged("mixed", &["rough", "draft"]).await;
+        database.set_project_tags(&project.id, &["rough".to_string(), "Draft".to_string()]).await.unwrap();
+
+        let tag_id = |name: &'static str| {
+            let database = &database;
+            async move { database.list_tags().await.unwrap().into_iter().find(|t| t.tag.name == name).unwrap().tag.id }
+        };
+        let merged = database.merge_tags(&tag_id("rough").await, &tag_id("Draft").await).await.unwrap();
+        assert_eq!(merged.name, "Draft");
+
+        let usage: Vec<(String, u32, u32)> = database
+            .list_tags()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.tag.name, t.document_count, t.project_count))
+            .collect();
+        assert_eq!(usage, [("Draft".to_string(), 3, 1), ("Intro".to_string(), 1, 0)]);
+        assert_eq!(database.get_project_tags(&project.id).await.unwrap(), ["Draft"]);
+        for (document, tags) in [(&both, vec!["Draft", "Intro"]), (&rough, vec!["Draft"]), (&mixed, vec!["Draft"])] {
+            assert_eq!(database.get_document_by_id(&document.id).await.unwrap().unwrap().tags, tags);
+        }
+
+        let titles = |match_all: bool| {
+            let database = &database;
+            async move {
+                let tags = ["draft".to_string(), "intro".to_string()];
+                let mut titles: Vec<String> = database.get_documents_by_tags(&tags, match_all).await.unwrap().into_iter().map(|d| d.title).collect();
+                titles.sort();
+                titles
+            }
+        };
+        assert_eq!(titles(true).await, ["both"]);
+        assert_eq!(titles(false).await, ["both", "mixed", "rough"]);
+
+        let error = database.merge_tags(&merged.id, &merged.id).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<AppError>(), Some(AppError::Validation { .. })));
+    }
 }
@@ -0,0 +1,1955 @@
+// DOCX 导入导出
+// 导出：用 comrak 解析 Markdown，把标题、强调、列表、表格、脚注和图片映射为 Word 内置样式，
+// 可选用参考 .docx 的样式表替换默认样式。导入：读取 document.xml 及样式、编号、脚注部件，转回 Markdown。
+// 两个方向都只处理内存中的字节，不依赖 Word 或 pandoc
+
+use crate::error::AppError;
+use crate::models::config::DocxExportOptions;
+use crate::models::document::{Document, DocumentType};
+use crate::models::project::Project;
+use crate::services::export::{markdown_options, GENERATOR};
+use crate::services::links;
+use crate::services::pdf::{html_to_text, load_portable_image};
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use comrak::nodes::{AstNode, ListType, NodeList, NodeValue, TableAlignment};
+use comrak::{parse_document, Arena};
+use image::ImageFormat;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const NS_W: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const NS_R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const REL_BASE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const CT_BASE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml";
+
+// A4 纵向，页边距 2cm，单位为 twip（1/20 pt）
+const PAGE_WIDTH: u32 = 11906;
+const PAGE_HEIGHT: u32 = 16838;
+const PAGE_MARGIN: u32 = 1134;
+const TEXT_WIDTH: u32 = PAGE_WIDTH - PAGE_MARGIN * 2;
+// 图片尺寸单位为 EMU：按 96 DPI 换算像素，宽度不超过版心
+const EMU_PER_PX: u64 = 9525;
+const EMU_PER_TWIP: u64 = 635;
+const MAX_IMAGE_WIDTH: u64 = TEXT_WIDTH as u64 * EMU_PER_TWIP;
+
+// 固定部件占用 rId1-rId5，超链接和图片从 rId10 开始编号
+const FIRST_DYNAMIC_RID: usize = 10;
+const BULLET_NUM_ID: u32 = 1;
+const LIST_INDENT: u32 = 720;
+
+// 导出使用的样式，id 与 Word 内置样式一致，方便参考文档直接覆盖
+const DEFAULT_STYLES: &[(&str, &str)] = &[
+    ("Normal", r#"<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="120" w:line="312" w:lineRule="auto"/></w:pPr></w:style>"#),
+    ("Title", r#"<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:before="240" w:after="240"/><w:jc w:val="center"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="44"/><w:szCs w:val="44"/></w:rPr></w:style>"#),
+    ("Subtitle", r#"<w:style w:type="paragraph" w:styleId="Subtitle"><w:name w:val="Subtitle"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:jc w:val="center"/></w:pPr><w:rPr><w:color w:val="595959"/><w:sz w:val="28"/><w:szCs w:val="28"/></w:rPr></w:style>"#),
+    ("Heading1", r#"<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="480" w:after="160"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="36"/><w:szCs w:val="36"/></w:rPr></w:style>"#),
+    ("Heading2", r#"<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="32"/><w:szCs w:val="32"/></w:rPr></w:style>"#),
+    ("Heading3", r#"<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="280" w:after="120"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="28"/><w:szCs w:val="28"/></w:rPr></w:style>"#),
+    ("Heading4", r#"<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="26"/><w:szCs w:val="26"/></w:rPr></w:style>"#),
+    ("Heading5", r#"<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="24"/><w:szCs w:val="24"/></w:rPr></w:style>"#),
+    ("Heading6", r#"<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:b/><w:bCs/><w:i/><w:iCs/><w:sz w:val="22"/><w:szCs w:val="22"/></w:rPr></w:style>"#),
+    ("Quote", r#"<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:pBdr><w:left w:val="single" w:sz="18" w:space="8" w:color="D0D7DE"/></w:pBdr><w:ind w:left="360"/></w:pPr><w:rPr><w:color w:val="57606A"/></w:rPr></w:style>"#),
+    ("ListParagraph", r#"<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="60"/><w:ind w:left="720"/></w:pPr></w:style>"#),
+    ("SourceCode", r#"<w:style w:type="paragraph" w:customStyle="1" w:styleId="SourceCode"><w:name w:val="Source Code"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F6F8FA"/><w:spacing w:after="160" w:line="264" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Consolas"/><w:sz w:val="20"/><w:szCs w:val="20"/></w:rPr></w:style>"#),
+    ("FootnoteText", r#"<w:style w:type="paragraph" w:styleId="FootnoteText"><w:name w:val="footnote text"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:sz w:val="18"/><w:szCs w:val="18"/></w:rPr></w:style>"#),
+    ("FootnoteReference", r#"<w:style w:type="character" w:styleId="FootnoteReference"><w:name w:val="footnote reference"/><w:rPr><w:vertAlign w:val="superscript"/></w:rPr></w:style>"#),
+    ("Hyperlink", r#"<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0969DA"/><w:u w:val="single"/></w:rPr></w:style>"#),
+    ("VerbatimChar", r#"<w:style w:type="character" w:customStyle="1" w:styleId="VerbatimChar"><w:name w:val="Verbatim Char"/><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Consolas"/><w:sz w:val="20"/><w:shd w:val="clear" w:color="auto" w:fill="F6F8FA"/></w:rPr></w:style>"#),
+    ("TableGrid", r#"<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:pPr><w:spacing w:after="0"/></w:pPr><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/><w:left w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/><w:right w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="D0D7DE"/></w:tblBorders><w:tblCellMar><w:top w:w="60" w:type="dxa"/><w:left w:w="108" w:type="dxa"/><w:bottom w:w="60" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr><w:tblStylePr w:type="firstRow"><w:rPr><w:b/><w:bCs/></w:rPr><w:tblPr/><w:tcPr><w:shd w:val="clear" w:color="auto" w:fill="F6F8FA"/></w:tcPr></w:tblStylePr></w:style>"#),
+];
+
+const DOC_DEFAULTS: &str = r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Microsoft YaHei" w:cs="Calibri"/><w:sz w:val="22"/><w:szCs w:val="22"/><w:lang w:val="en-US" w:eastAsia="zh-CN"/></w:rPr></w:rPrDefault><w:pPrDefault/></w:docDefaults>"#;
+
+// 导入时视为代码的等宽字体
+const MONOSPACE_FONTS: &[&str] = &[
+    "consolas", "courier", "courier new", "menlo", "monaco", "jetbrains mono", "source code pro", "sf mono", "lucida console",
+    "dejavu sans mono", "liberation mono", "noto sans mono",
+];
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // XML 1.0 不允许的控制字符直接丢弃
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn read_part<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>> {
+    match archive.by_name(name) {
+        Ok(mut file) => {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            Ok(Some(data))
+        }
+        Err(ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ---------- 导出 ----------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Marks {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: bool,
+}
+
+struct DocxImage {
+    name: String,
+    data: Vec<u8>,
+}
+
+struct Relationship {
+    kind: &'static str,
+    target: String,
+    external: bool,
+}
+
+struct CoreInfo {
+    title: String,
+    author: Option<String>,
+    keywords: Vec<String>,
+    language: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+struct ReferenceDocx {
+    styles: String,
+    theme: Option<Vec<u8>>,
+}
+
+struct DocxWriter<'a> {
+    base_dir: Option<&'a Path>,
+    body: String,
+    footnotes: String,
+    footnote_count: u32,
+    note_ids: HashMap<String, u32>,
+    pending_notes: Vec<(String, u32)>,
+    in_footnote: bool,
+    note_ref_pending: bool,
+    in_table: bool,
+    relationships: Vec<Relationship>,
+    link_ids: HashMap<String, String>,
+    images: Vec<DocxImage>,
+    // url -> (关系 id, 宽, 高)，加载失败的记为 None，避免重复读取
+    image_cache: HashMap<String, Option<(String, u64, u64)>>,
+    drawing_count: u32,
+    // 每个有序列表单独一个编号实例，以便从 start 重新计数：(层级, 起始值)
+    ordered_lists: Vec<(u32, usize)>,
+    list_levels: Vec<u32>,
+    pending_num: Option<(u32, u32)>,
+    task_marker: Option<bool>,
+    quote_depth: u32,
+    heading_offset: u8,
+    page_break: bool,
+    // 项目导出中的文档 id，指向它们的链接改为书签链接
+    documents: HashSet<String>,
+    bookmark_count: u32,
+}
+
+impl<'a> DocxWriter<'a> {
+    fn new(base_dir: Option<&'a Path>) -> Self {
+        Self {
+            base_dir,
+            body: String::new(),
+            footnotes: String::new(),
+            footnote_count: 0,
+            note_ids: HashMap::new(),
+            pending_notes: Vec::new(),
+            in_footnote: false,
+            note_ref_pending: false,
+            in_table: false,
+            relationships: Vec::new(),
+            link_ids: HashMap::new(),
+            images: Vec::new(),
+            image_cache: HashMap::new(),
+            drawing_count: 0,
+            ordered_lists: Vec::new(),
+            list_levels: Vec::new(),
+            pending_num: None,
+            task_marker: None,
+            quote_depth: 0,
+            heading_offset: 0,
+            page_break: false,
+            documents: HashSet::new(),
+            bookmark_count: 0,
+        }
+    }
+
+    fn relationship(&mut self, kind: &'static str, target: &str, external: bool) -> String {
+        self.relationships.push(Relationship { kind, target: target.to_string(), external });
+        format!("rId{}", FIRST_DYNAMIC_RID + self.relationships.len() - 1)
+    }
+
+    // ----- 段落与文字 -----
+
+    fn default_style(&self) -> Option<&'static str> {
+        if self.in_table {
+            None
+        } else if self.in_footnote {
+            Some("FootnoteText")
+        } else if self.quote_depth > 0 {
+            Some("Quote")
+        } else if !self.list_levels.is_empty() {
+            Some("ListParagraph")
+        } else {
+            None
+        }
+    }
+
+    fn paragraph(&mut self, style: Option<&str>, runs: &str) {
+        self.paragraph_with(style, false, None, runs);
+    }
+
+    // pPr 子元素需按架构顺序排列：pStyle、pageBreakBefore、numPr、pBdr、ind、jc
+    fn paragraph_with(&mut self, style: Option<&str>, border: bool, align: Option<&str>, runs: &str) {
+        let mut props = String::new();
+        if let Some(style) = style.or(self.default_style()) {
+            props.push_str(&format!("<w:pStyle w:val=\"{}\"/>", style));
+        }
+        if std::mem::take(&mut self.page_break) {
+            props.push_str("<w:pageBreakBefore/>");
+        }
+        let mut prefix = String::new();
+        let mut numbered = false;
+        if !self.in_table {
+            if let Some((num_id, level)) = self.pending_num.take() {
+                numbered = true;
+                props.push_str(&format!("<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>", level, num_id));
+                if let Some(checked) = self.task_marker.take() {
+                    prefix.push_str(&run(if checked { "☒ " } else { "☐ " }, Marks::default()));
+                }
+            }
+        }
+        if border {
+            props.push_str(r#"<w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="D0D7DE"/></w:pBdr>"#);
+        }
+        if !self.in_table && !self.in_footnote && !self.list_levels.is_empty() && !numbered {
+            // 列表项中的后续段落与列表文字对齐
+            props.push_str(&format!("<w:ind w:left=\"{}\"/>", LIST_INDENT * self.list_levels.len() as u32));
+        }
+        if let Some(align) = align {
+            props.push_str(&format!("<w:jc w:val=\"{}\"/>", align));
+        }
+        if std::mem::take(&mut self.note_ref_pending) {
+            prefix.push_str(r#"<w:r><w:rPr><w:rStyle w:val="FootnoteReference"/></w:rPr><w:footnoteRef/></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r>"#);
+        }
+
+        self.body.push_str("<w:p>");
+        if !props.is_empty() {
+            self.body.push_str(&format!("<w:pPr>{}</w:pPr>", props));
+        }
+        self.body.push_str(&prefix);
+        self.body.push_str(runs);
+        self.body.push_str("</w:p>");
+    }
+
+    fn plain_text(&mut self, text: &str) {
+        for paragraph in text.replace("\r\n", "\n").split("\n\n") {
+            let paragraph = paragraph.trim_matches('\n');
+            if paragraph.trim().is_empty() {
+                continue;
+            }
+            self.paragraph(None, &run(paragraph.trim_end(), Marks::default()));
+        }
+    }
+
+    // ----- 文档结构 -----
+
+    fn cover(&mut self, title: &str, description: &str) {
+        self.paragraph(Some("Title"), &run(title, Marks::default()));
+        if !description.trim().is_empty() {
+            self.paragraph(Some("Subtitle"), &run(description.trim(), Marks::default()));
+        }
+    }
+
+    fn document(&mut self, document: &Document, in_project: bool) {
+        if in_project {
+            // 项目中每篇文档另起一页，文档标题作为一级标题，正文标题依次降一级
+            self.page_break = true;
+            self.bookmark_count += 1;
+            let title = format!(
+                "<w:bookmarkStart w:id=\"{id}\" w:name=\"{name}\"/>{run}<w:bookmarkEnd w:id=\"{id}\"/>",
+                id = self.bookmark_count,
+                name = bookmark_name(&document.id),
+                run = run(&document.title, Marks::default())
+            );
+            self.paragraph(Some("Heading1"), &title);
+        } else {
+            self.paragraph(Some("Title"), &run(&document.title, Marks::default()));
+        }
+        self.heading_offset = u8::from(in_project);
+        match document.content_type {
+            DocumentType::Markdown => self.markdown(&document.content),
+            DocumentType::PlainText => self.plain_text(&document.content),
+            DocumentType::RichText => self.plain_text(&html_to_text(&document.content)),
+        }
+    }
+
+    fn markdown(&mut self, content: &str) {
+        let arena = Arena::new();
+        let root = parse_document(&arena, content, &markdown_options());
+        let mut definitions = HashMap::new();
+        for node in root.descendants() {
+            if let NodeValue::FootnoteDefinition(definition) = &node.data.borrow().value {
+                definitions.insert(definition.name.clone(), node);
+            }
+        }
+
+        self.note_ids.clear();
+        self.blocks(root);
+        // 脚注正文写入 footnotes.xml，脚注里还可能引用其他脚注
+        while !self.pending_notes.is_empty() {
+            for (name, id) in std::mem::take(&mut self.pending_notes) {
+                if let Some(node) = definitions.get(&name) {
+                    self.footnote(node, id);
+                }
+            }
+        }
+    }
+
+    fn blocks<'n>(&mut self, node: &'n AstNode<'n>) {
+        for child in node.children() {
+            self.block(child);
+        }
+    }
+
+    fn block<'n>(&mut self, node: &'n AstNode<'n>) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Paragraph => {
+                let mut runs = String::new();
+                self.inlines(node, Marks::default(), &mut runs);
+                if !runs.is_empty() || self.pending_num.is_some() {
+                    self.paragraph(None, &runs);
+                }
+            }
+            NodeValue::Heading(heading) => {
+                let mut runs = String::new();
+                self.inlines(node, Marks::default(), &mut runs);
+                let level = heading.level.saturating_add(self.heading_offset).min(6);
+                self.paragraph(Some(&format!("Heading{}", level)), &runs);
+            }
+            NodeValue::List(list) => self.list(node, &list),
+            NodeValue::CodeBlock(code) => {
+                self.paragraph(Some("SourceCode"), &run(code.literal.trim_end_matches('\n'), Marks::default()));
+            }
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) | NodeValue::Alert(_) => {
+                self.quote_depth += 1;
+                self.blocks(node);
+                self.quote_depth -= 1;
+            }
+            NodeValue::ThematicBreak => self.paragraph_with(None, true, None, ""),
+            NodeValue::Table(table) => self.table(node, &table.alignments),
+            NodeValue::FootnoteDefinition(_) | NodeValue::HtmlBlock(_) | NodeValue::FrontMatter(_) => {}
+            _ => self.blocks(node),
+        }
+    }
+
+    fn inlines<'n>(&mut self, node: &'n AstNode<'n>, marks: Marks, out: &mut String) {
+        for child in node.children() {
+            let value = child.data.borrow().value.clone();
+            match value {
+                NodeValue::Text(text) => out.push_str(&run(&text, marks)),
+                NodeValue::Code(code) => out.push_str(&run(&code.literal, Marks { code: true, ..marks })),
+                NodeValue::SoftBreak => out.push_str(&run(" ", marks)),
+                NodeValue::LineBreak => out.push_str(&run("\n", marks)),
+                NodeValue::HtmlInline(html) if html.to_lowercase().starts_with("<br") => out.push_str(&run("\n", marks)),
+                NodeValue::HtmlInline(_) => {}
+                NodeValue::Emph => self.inlines(child, Marks { italic: true, ..marks }, out),
+                NodeValue::Strong => self.inlines(child, Marks { bold: true, ..marks }, out),
+                NodeValue::Strikethrough => self.inlines(child, Marks { strike: true, ..marks }, out),
+                NodeValue::Link(link) => {
+                    let mut inner = String::new();
+                    self.inlines(child, Marks { link: true, ..marks }, &mut inner);
+                    // 文档链接指向项目中对应文档的书签，其他文档的链接只保留文字；附件链接改为本机文件地址
+                    let url = match links::document_id(&link.url) {
+                        Some(id) if self.documents.contains(id) => format!("#{}", bookmark_name(id)),
+                        Some(_) => String::new(),
+                        None => links::attachment_file_url(&link.url).unwrap_or(link.url),
+                    };
+                    if let Some(anchor) = url.strip_prefix('#') {
+                        out.push_str(&format!("<w:hyperlink w:anchor=\"{}\">{}</w:hyperlink>", escape_xml(anchor), inner));
+                    } else if url.is_empty() {
+                        out.push_str(&inner);
+                    } else {
+                        let id = match self.link_ids.get(&url) {
+                            Some(id) => id.clone(),
+                            None => {
+                                let id = self.relationship("hyperlink", &url, true);
+                                self.link_ids.insert(url, id.clone());
+                                id
+                            }
+                        };
+                        out.push_str(&format!("<w:hyperlink r:id=\"{}\" w:history=\"1\">{}</w:hyperlink>", id, inner));
+                    }
+                }
+                NodeValue::Image(link) => {
+                    let alt = alt_text(child);
+                    out.push_str(&self.image(&link.url, &alt));
+                }
+                NodeValue::FootnoteReference(reference) => {
+                    let id = match self.note_ids.get(&reference.name) {
+                        Some(&id) => id,
+                        None => {
+                            self.footnote_count += 1;
+                            let id = self.footnote_count;
+                            self.note_ids.insert(reference.name.clone(), id);
+                            self.pending_notes.push((reference.name.clone(), id));
+                            id
+                        }
+                    };
+                    out.push_str(&format!(
+                        "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:footnoteReference w:id=\"{}\"/></w:r>",
+                        id
+                    ));
+                }
+                _ => self.inlines(child, marks, out),
+            }
+        }
+    }
+
+    fn list<'n>(&mut self, node: &'n AstNode<'n>, list: &NodeList) {
+        // 列表项直接以子列表开头时，先给外层列表项留出一个空段落
+        if self.pending_num.is_some() {
+            self.paragraph(None, "");
+        }
+        let level = self.list_levels.len().min(8) as u32;
+        let num_id = if list.list_type == ListType::Ordered {
+            self.ordered_lists.push((level, list.start));
+            BULLET_NUM_ID + self.ordered_lists.len() as u32
+        } else {
+            BULLET_NUM_ID
+        };
+
+        self.list_levels.push(num_id);
+        for item in node.children() {
+            self.pending_num = Some((num_id, level));
+            self.task_marker = match &item.data.borrow().value {
+                NodeValue::TaskItem(checked) => Some(checked.is_some()),
+                _ => None,
+            };
+            self.blocks(item);
+            if self.pending_num.is_some() {
+                self.paragraph(None, "");
+            }
+        }
+        self.list_levels.pop();
+        self.task_marker = None;
+    }
+
+    fn footnote<'n>(&mut self, node: &'n AstNode<'n>, id: u32) {
+        let body = std::mem::take(&mut self.body);
+        self.in_footnote = true;
+        self.note_ref_pending = true;
+        self.blocks(node);
+        if self.note_ref_pending {
+            self.paragraph(None, "");
+        }
+        self.in_footnote = false;
+        let content = std::mem::replace(&mut self.body, body);
+        self.footnotes.push_str(&format!("<w:footnote w:id=\"{}\">{}</w:footnote>", id, content));
+    }
+
+    fn table<'n>(&mut self, node: &'n AstNode<'n>, alignments: &[TableAlignment]) {
+        let columns = alignments.len().max(1) as u32;
+        let column_width = TEXT_WIDTH / columns;
+        let mut xml = String::from(
+            r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="0" w:type="auto"/><w:tblLook w:val="04A0" w:firstRow="1" w:lastRow="0" w:firstColumn="0" w:lastColumn="0" w:noHBand="0" w:noVBand="1"/></w:tblPr><w:tblGrid>"#,
+        );
+        for _ in 0..columns {
+            xml.push_str(&format!("<w:gridCol w:w=\"{}\"/>", column_width));
+        }
+        xml.push_str("</w:tblGrid>");
+
+        let body = std::mem::take(&mut self.body);
+        self.in_table = true;
+        for row in node.children() {
+            let header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+            self.body.push_str("<w:tr>");
+            if header {
+                self.body.push_str("<w:trPr><w:tblHeader/></w:trPr>");
+            }
+            for (column, cell) in row.children().enumerate() {
+                self.body.push_str(&format!("<w:tc><w:tcPr><w:tcW w:w=\"{}\" w:type=\"dxa\"/></w:tcPr>", column_width));
+                let mut runs = String::new();
+                self.inlines(cell, Marks::default(), &mut runs);
+                let align = match alignments.get(column) {
+                    Some(TableAlignment::Center) => Some("center"),
+                    Some(TableAlignment::Right) => Some("right"),
+                    _ => None,
+                };
+                self.paragraph_with(None, false, align, &runs);
+                self.body.push_str("</w:tc>");
+            }
+            self.body.push_str("</w:tr>");
+        }
+        self.in_table = false;
+        let rows = std::mem::replace(&mut self.body, body);
+        self.body.push_str(&xml);
+        self.body.push_str(&rows);
+        self.body.push_str("</w:tbl>");
+    }
+
+    fn image(&mut self, url: &str, alt: &str) -> String {
+        let entry = match self.image_cache.get(url) {
+            Some(entry) => entry.clone(),
+            None => {
+                let entry = self.load_image(url);
+                self.image_cache.insert(url.to_string(), entry.clone());
+                entry
+            }
+        };
+        let Some((id, width, height)) = entry else {
+            // 无法嵌入的图片（远程地址、文件缺失等）以替代文本占位
+            let label = if alt.is_empty() { url } else { alt };
+            return run(&format!("[图片: {}]", label), Marks { italic: true, ..Marks::default() });
+        };
+
+        self.drawing_count += 1;
+        let number = self.drawing_count;
+        let alt = escape_xml(alt);
+        format!(
+            "<w:r><w:drawing><wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\"><wp:extent cx=\"{w}\" cy=\"{h}\"/>\
+             <wp:docPr id=\"{n}\" name=\"Picture {n}\" descr=\"{alt}\"/><wp:cNvGraphicFramePr><a:graphicFrameLocks noChangeAspect=\"1\"/></wp:cNvGraphicFramePr>\
+             <a:graphic><a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\"><pic:pic>\
+             <pic:nvPicPr><pic:cNvPr id=\"{n}\" name=\"Picture {n}\" descr=\"{alt}\"/><pic:cNvPicPr/></pic:nvPicPr>\
+             <pic:blipFill><a:blip r:embed=\"{id}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill>\
+             <pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{w}\" cy=\"{h}\"/></a:xfrm><a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr>\
+             </pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>",
+            w = width,
+            h = height,
+            n = number,
+            alt = alt,
+            id = id,
+        )
+    }
+
+    fn load_image(&mut self, url: &str) -> Option<(String, u64, u64)> {
+        // Word 不支持 WebP 等格式，统一转成 PNG
+        let (data, extension, width, height) = load_portable_image(url, self.base_dir).ok()?;
+        let (mut width, mut height) = (width as u64 * EMU_PER_PX, height as u64 * EMU_PER_PX);
+        if width > MAX_IMAGE_WIDTH {
+            height = height * MAX_IMAGE_WIDTH / width;
+            width = MAX_IMAGE_WIDTH;
+        }
+        let name = format!("image{}.{}", self.images.len() + 1, extension);
+        let id = self.relationship("image", &format!("media/{}", name), false);
+        self.images.push(DocxImage { name, data });
+        Some((id, width, height))
+    }
+
+    // ----- 打包 -----
+
+    fn numbering_xml(&self) -> String {
+        let mut xml = format!("{}<w:numbering xmlns:w=\"{}\">", XML_HEADER, NS_W);
+        xml.push_str("<w:abstractNum w:abstractNumId=\"0\"><w:multiLevelType w:val=\"hybridMultilevel\"/>");
+        for level in 0..9u32 {
+            let bullet = ["•", "◦", "▪"][level as usize % 3];
+            xml.push_str(&format!(
+                "<w:lvl w:ilvl=\"{l}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"bullet\"/><w:lvlText w:val=\"{b}\"/><w:lvlJc w:val=\"left\"/>\
+                 <w:pPr><w:ind w:left=\"{ind}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                l = level,
+                b = bullet,
+                ind = LIST_INDENT * (level + 1),
+            ));
+        }
+        xml.push_str("</w:abstractNum><w:abstractNum w:abstractNumId=\"1\"><w:multiLevelType w:val=\"hybridMultilevel\"/>");
+        for level in 0..9u32 {
+            let format = ["decimal", "lowerLetter", "lowerRoman"][level as usize % 3];
+            xml.push_str(&format!(
+                "<w:lvl w:ilvl=\"{l}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{f}\"/><w:lvlText w:val=\"%{n}.\"/><w:lvlJc w:val=\"left\"/>\
+                 <w:pPr><w:ind w:left=\"{ind}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                l = level,
+                f = format,
+                n = level + 1,
+                ind = LIST_INDENT * (level + 1),
+            ));
+        }
+        xml.push_str("</w:abstractNum>");
+        xml.push_str(&format!("<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"0\"/></w:num>", BULLET_NUM_ID));
+        for (index, (level, start)) in self.ordered_lists.iter().enumerate() {
+            xml.push_str(&format!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"1\"/><w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride></w:num>",
+                BULLET_NUM_ID as usize + index + 1,
+                level,
+                start
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+
+    fn package(self, info: &CoreInfo, reference: Option<&ReferenceDocx>) -> Result<Vec<u8>> {
+        let theme = reference.and_then(|r| r.theme.as_deref());
+        let mut parts: Vec<(String, Vec<u8>)> = Vec::new();
+
+        let mut content_types = format!(
+            "{}<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Default Extension=\"png\" ContentType=\"image/png\"/><Default Extension=\"jpeg\" ContentType=\"image/jpeg\"/>\
+             <Default Extension=\"gif\" ContentType=\"image/gif\"/>\
+             <Override PartName=\"/word/document.xml\" ContentType=\"{ct}.document.main+xml\"/>\
+             <Override PartName=\"/word/styles.xml\" ContentType=\"{ct}.styles+xml\"/>\
+             <Override PartName=\"/word/numbering.xml\" ContentType=\"{ct}.numbering+xml\"/>\
+             <Override PartName=\"/word/footnotes.xml\" ContentType=\"{ct}.footnotes+xml\"/>\
+             <Override PartName=\"/word/settings.xml\" ContentType=\"{ct}.settings+xml\"/>\
+             <Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\
+             <Override PartName=\"/docProps/app.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.extended-properties+xml\"/>",
+            XML_HEADER,
+            ct = CT_BASE,
+        );
+        if theme.is_some() {
+            content_types.push_str(
+                "<Override PartName=\"/word/theme/theme1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.theme+xml\"/>",
+            );
+        }
+        content_types.push_str("</Types>");
+        parts.push(("[Content_Types].xml".into(), content_types.into_bytes()));
+
+        parts.push((
+            "_rels/.rels".into(),
+            format!(
+                "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+                 <Relationship Id=\"rId1\" Type=\"{base}/officeDocument\" Target=\"word/document.xml\"/>\
+                 <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties\" Target=\"docProps/core.xml\"/>\
+                 <Relationship Id=\"rId3\" Type=\"{base}/extended-properties\" Target=\"docProps/app.xml\"/>\
+                 </Relationships>",
+                XML_HEADER,
+                base = REL_BASE,
+            )
+            .into_bytes(),
+        ));
+        parts.push(("docProps/core.xml".into(), core_xml(info).into_bytes()));
+        parts.push((
+            "docProps/app.xml".into(),
+            format!(
+                "{}<Properties xmlns=\"http://schemas.openxmlformats.org/officeDocument/2006/extended-properties\"><Application>{}</Application></Properties>",
+                XML_HEADER,
+                escape_xml(GENERATOR)
+            )
+            .into_bytes(),
+        ));
+
+        let mut rels = format!(
+            "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"{base}/styles\" Target=\"styles.xml\"/>\
+             <Relationship Id=\"rId2\" Type=\"{base}/numbering\" Target=\"numbering.xml\"/>\
+             <Relationship Id=\"rId3\" Type=\"{base}/footnotes\" Target=\"footnotes.xml\"/>\
+             <Relationship Id=\"rId4\" Type=\"{base}/settings\" Target=\"settings.xml\"/>",
+            XML_HEADER,
+            base = REL_BASE,
+        );
+        if theme.is_some() {
+            rels.push_str(&format!("<Relationship Id=\"rId5\" Type=\"{}/theme\" Target=\"theme/theme1.xml\"/>", REL_BASE));
+        }
+        for (index, relationship) in self.relationships.iter().enumerate() {
+            rels.push_str(&format!(
+                "<Relationship Id=\"rId{}\" Type=\"{}/{}\" Target=\"{}\"{}/>",
+                FIRST_DYNAMIC_RID + index,
+                REL_BASE,
+                relationship.kind,
+                escape_xml(&relationship.target),
+                if relationship.external { " TargetMode=\"External\"" } else { "" }
+            ));
+        }
+        rels.push_str("</Relationships>");
+        parts.push(("word/_rels/document.xml.rels".into(), rels.into_bytes()));
+
+        let document = format!(
+            "{}<w:document xmlns:w=\"{}\" xmlns:r=\"{}\" \
+             xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\" \
+             xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" \
+             xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\"><w:body>{}\
+             <w:sectPr><w:pgSz w:w=\"{}\" w:h=\"{}\"/><w:pgMar w:top=\"{m}\" w:right=\"{m}\" w:bottom=\"{m}\" w:left=\"{m}\" \
+             w:header=\"709\" w:footer=\"709\" w:gutter=\"0\"/></w:sectPr></w:body></w:document>",
+            XML_HEADER,
+            NS_W,
+            NS_R,
+            self.body,
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            m = PAGE_MARGIN,
+        );
+        parts.push(("word/document.xml".into(), document.into_bytes()));
+        parts.push(("word/styles.xml".into(), styles_xml(reference.map(|r| r.styles.as_str())).into_bytes()));
+        parts.push(("word/numbering.xml".into(), self.numbering_xml().into_bytes()));
+        parts.push((
+            "word/footnotes.xml".into(),
+            format!(
+                "{}<w:footnotes xmlns:w=\"{}\" xmlns:r=\"{}\" \
+                 xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\" \
+                 xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" \
+                 xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+                 <w:footnote w:type=\"separator\" w:id=\"-1\"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>\
+                 <w:footnote w:type=\"continuationSeparator\" w:id=\"0\"><w:p><w:r><w:continuationSeparator/></w:r></w:p></w:footnote>\
+                 {}</w:footnotes>",
+                XML_HEADER,
+                NS_W,
+                NS_R,
+                self.footnotes
+            )
+            .into_bytes(),
+        ));
+        parts.push((
+            "word/settings.xml".into(),
+            format!(
+                "{}<w:settings xmlns:w=\"{}\"><w:footnotePr><w:footnote w:id=\"-1\"/><w:footnote w:id=\"0\"/></w:footnotePr>\
+                 <w:compat><w:compatSetting w:name=\"compatibilityMode\" w:uri=\"http://schemas.microsoft.com/office/word\" w:val=\"15\"/></w:compat>\
+                 </w:settings>",
+                XML_HEADER, NS_W
+            )
+            .into_bytes(),
+        ));
+        if let Some(theme) = theme {
+            parts.push(("word/theme/theme1.xml".into(), theme.to_vec()));
+        }
+        for image in self.images {
+            parts.push((format!("word/media/{}", image.name), image.data));
+        }
+
+        // 不写入修改时间，相同输入得到相同的文件
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, data) in parts {
+            zip.start_file(name, options)?;
+            zip.write_all(&data)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+// 书签名最长 40 个字符且须以字母开头，uuid 去掉连字符后正好放得下
+fn bookmark_name(document_id: &str) -> String {
+    format!("doc_{}", document_id.replace('-', ""))
+}
+
+fn run(text: &str, marks: Marks) -> String {
+    let mut props = String::new();
+    if marks.code {
+        props.push_str("<w:rStyle w:val=\"VerbatimChar\"/>");
+    } else if marks.link {
+        props.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+    }
+    if marks.bold {
+        props.push_str("<w:b/><w:bCs/>");
+    }
+    if marks.italic {
+        props.push_str("<w:i/><w:iCs/>");
+    }
+    if marks.strike {
+        props.push_str("<w:strike/>");
+    }
+
+    let mut content = String::new();
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            content.push_str("<w:br/>");
+        }
+        for (index, part) in line.split('\t').enumerate() {
+            if index > 0 {
+                content.push_str("<w:tab/>");
+            }
+            if !part.is_empty() {
+                content.push_str(&format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_xml(part)));
+            }
+        }
+    }
+    if content.is_empty() {
+        return String::new();
+    }
+    if props.is_empty() {
+        format!("<w:r>{}</w:r>", content)
+    } else {
+        format!("<w:r><w:rPr>{}</w:rPr>{}</w:r>", props, content)
+    }
+}
+
+fn alt_text<'n>(node: &'n AstNode<'n>) -> String {
+    node.descendants()
+        .filter_map(|child| match &child.data.borrow().value {
+            NodeValue::Text(text) => Some(text.clone()),
+            NodeValue::Code(code) => Some(code.literal.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn core_xml(info: &CoreInfo) -> String {
+    let mut xml = format!(
+        "{}<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"><dc:title>{}</dc:title>",
+        XML_HEADER,
+        escape_xml(&info.title)
+    );
+    if let Some(author) = &info.author {
+        xml.push_str(&format!("<dc:creator>{}</dc:creator>", escape_xml(author)));
+    }
+    if !info.keywords.is_empty() {
+        xml.push_str(&format!("<cp:keywords>{}</cp:keywords>", escape_xml(&info.keywords.join(", "))));
+    }
+    if let Some(language) = &info.language {
+        xml.push_str(&format!("<dc:language>{}</dc:language>", escape_xml(language)));
+    }
+    xml.push_str(&format!(
+        "<dcterms:created xsi:type=\"dcterms:W3CDTF\">{}</dcterms:created><dcterms:modified xsi:type=\"dcterms:W3CDTF\">{}</dcterms:modified></cp:coreProperties>",
+        format_time(&info.created_at),
+        format_time(&info.updated_at)
+    ));
+    xml
+}
+
+// 有参考文档时以它的样式表为准，只补上其中缺少的导出样式
+fn styles_xml(reference: Option<&str>) -> String {
+    let Some(reference) = reference else {
+        let styles: String = DEFAULT_STYLES.iter().map(|(_, xml)| *xml).collect();
+        return format!("{}<w:styles xmlns:w=\"{}\">{}{}</w:styles>", XML_HEADER, NS_W, DOC_DEFAULTS, styles);
+    };
+    let existing = style_ids(reference);
+    let missing: String = DEFAULT_STYLES
+        .iter()
+        .filter(|(id, _)| !existing.contains(*id))
+        .map(|(_, xml)| *xml)
+        .collect();
+    match reference.rfind("</w:styles>") {
+        Some(end) => format!("{}{}{}", &reference[..end], missing, &reference[end..]),
+        None => reference.to_string(),
+    }
+}
+
+fn style_ids(styles: &str) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    let mut reader = Reader::from_str(styles);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"style" => {
+                if let Some(id) = attribute(&e, b"styleId") {
+                    ids.insert(id);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    ids
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn load_reference(options: &DocxExportOptions) -> Result<Option<ReferenceDocx>> {
+    let Some(path) = options.reference_docx.as_deref().filter(|p| !p.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let bytes = std::fs::read(path)?;
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| AppError::validation(format!("Reference document is not a valid .docx file: {}", e)))?;
+    let styles = read_part(&mut archive, "word/styles.xml")?
+        .ok_or_else(|| AppError::validation("Reference document has no word/styles.xml"))?;
+    Ok(Some(ReferenceDocx {
+        styles: String::from_utf8(styles).map_err(|_| AppError::validation("Reference styles.xml is not UTF-8"))?,
+        theme: read_part(&mut archive, "word/theme/theme1.xml")?,
+    }))
+}
+
+/// 导出单个文档
+pub fn document_to_docx(document: &Document, options: &DocxExportOptions, base_dir: Option<&Path>) -> Result<Vec<u8>> {
+    let reference = load_reference(options)?;
+    let info = CoreInfo {
+        title: document.title.clone(),
+        author: document.metadata.author.clone().filter(|a| !a.trim().is_empty()),
+        keywords: document.tags.clone(),
+        language: Some(document.metadata.language.clone()).filter(|l| !l.trim().is_empty()),
+        created_at: document.created_at,
+        updated_at: document.updated_at,
+    };
+    let mut writer = DocxWriter::new(base_dir);
+    writer.document(document, false);
+    writer.package(&info, reference.as_ref())
+}
+
+/// 把整个项目导出为一个 DOCX：标题页之后每篇文档另起一页。documents 需已按目录顺序排好
+pub fn project_to_docx(
+    project: &Project,
+    documents: &[Document],
+    options: &DocxExportOptions,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>> {
+    let reference = load_reference(options)?;
+    let mut keywords: Vec<String> = Vec::new();
+    for tag in documents.iter().flat_map(|d| &d.tags) {
+        if !keywords.contains(tag) {
+            keywords.push(tag.clone());
+        }
+    }
+    let info = CoreInfo {
+        title: project.name.clone(),
+        author: documents
+            .iter()
+            .find_map(|d| d.metadata.author.clone())
+            .filter(|a| !a.trim().is_empty()),
+        keywords,
+        language: documents
+            .first()
+            .map(|d| d.metadata.language.clone())
+            .filter(|l| !l.trim().is_empty()),
+        created_at: project.created_at,
+        updated_at: project.updated_at,
+    };
+    let mut writer = DocxWriter::new(base_dir);
+    writer.documents = documents.iter().map(|document| document.id.clone()).collect();
+    writer.cover(&project.name, &project.description);
+    for document in documents {
+        writer.document(document, true);
+    }
+    writer.package(&info, reference.as_ref())
+}
+
+// ---------- 导入 ----------
+
+/// 从 .docx 转换得到的内容
+#[derive(Debug, Clone)]
+pub struct DocxImport {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub markdown: String,
+}
+
+// 导入只需要按名字查找元素和属性，解析成一棵简单的树即可；名字均去掉命名空间前缀
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+#[derive(Debug)]
+enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+impl XmlElement {
+    fn from_start(start: &BytesStart) -> Result<Self> {
+        let mut attributes = Vec::new();
+        for attr in start.attributes() {
+            let attr = attr?;
+            let name = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+            attributes.push((name, attr.unescape_value()?.into_owned()));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    // 深度优先查找第一个同名后代
+    fn find(&self, name: &str) -> Option<&XmlElement> {
+        self.elements()
+            .find_map(|element| if element.name == name { Some(element) } else { element.find(name) })
+    }
+
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .map(|child| match child {
+                XmlNode::Text(text) => text.clone(),
+                XmlNode::Element(element) => element.text(),
+            })
+            .collect()
+    }
+}
+
+fn parse_xml(data: &[u8]) -> Result<XmlElement> {
+    let mut reader = Reader::from_reader(data);
+    let mut stack = vec![XmlElement::default()];
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(XmlElement::from_start(&start)?),
+            Event::Empty(start) => {
+                let element = XmlElement::from_start(&start)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(XmlNode::Element(element));
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(|| anyhow!("Unbalanced XML"))?;
+                let parent = stack.last_mut().ok_or_else(|| anyhow!("Unbalanced XML"))?;
+                parent.children.push(XmlNode::Element(element));
+            }
+            Event::Text(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(XmlNode::Text(text.unescape()?.into_owned()));
+                }
+            }
+            Event::CData(data) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(XmlNode::Text(String::from_utf8_lossy(&data).into_owned()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if stack.len() != 1 {
+        return Err(anyhow!("Unbalanced XML"));
+    }
+    stack
+        .pop()
+        .and_then(|root| {
+            root.children.into_iter().find_map(|child| match child {
+                XmlNode::Element(element) => Some(element),
+                XmlNode::Text(_) => None,
+            })
+        })
+        .ok_or_else(|| anyhow!("Empty XML document"))
+}
+
+// 把关系目标解析为包内路径
+fn resolve_part(base_dir: &str, target: &str) -> String {
+    let joined = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None if base_dir.is_empty() => target.to_string(),
+        None => format!("{}/{}", base_dir, target),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[derive(Debug, Clone)]
+struct PartRelationship {
+    kind: String,
+    target: String,
+    external: bool,
+}
+
+fn parse_relationships(data: &[u8]) -> Result<HashMap<String, PartRelationship>> {
+    let root = parse_xml(data)?;
+    Ok(root
+        .elements()
+        .filter(|element| element.name == "Relationship")
+        .filter_map(|element| {
+            let kind = element.attr("Type")?;
+            Some((
+                element.attr("Id")?.to_string(),
+                PartRelationship {
+                    kind: kind.rsplit('/').next().unwrap_or(kind).to_string(),
+                    target: element.attr("Target")?.to_string(),
+                    external: element.attr("TargetMode") == Some("External"),
+                },
+            ))
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ListLevel {
+    ordered: bool,
+    start: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParagraphKind {
+    Normal,
+    Title,
+    Heading(u8),
+    Quote,
+    Code,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct RunStyle {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    text: String,
+    style: RunStyle,
+    link: Option<String>,
+    // 已经是 Markdown 的片段（图片、脚注引用），不再转义
+    raw: bool,
+}
+
+enum Block {
+    Paragraph(String),
+    Heading(u8, String),
+    Quote(String),
+    Code(String),
+    // list 为 Word 中的编号 id，用来区分相邻的两个列表
+    Item { list: String, level: usize, marker: String, text: String },
+    Table(String),
+}
+
+// 按样式名（其次是 id）识别 Word 内置和常见的自定义样式，支持中文版 Word 的样式名
+fn classify_style(label: &str) -> Option<ParagraphKind> {
+    let label: String = label.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let heading = label.strip_prefix("heading").or_else(|| label.strip_prefix("标题"));
+    if let Some(level) = heading.and_then(|rest| rest.parse::<u8>().ok()).filter(|l| (1..=9).contains(l)) {
+        return Some(ParagraphKind::Heading(level.min(6)));
+    }
+    match label.as_str() {
+        "title" | "标题" => Some(ParagraphKind::Title),
+        "quote" | "intensequote" | "blocktext" | "引用" | "明显引用" => Some(ParagraphKind::Quote),
+        _ if ["code", "preformatted", "verbatim", "plaintext", "代码"].iter().any(|k| label.contains(k)) => {
+            Some(ParagraphKind::Code)
+        }
+        _ => None,
+    }
+}
+
+fn flag(props: Option<&XmlElement>, name: &str) -> Option<bool> {
+    props
+        .and_then(|p| p.child(name))
+        .map(|element| !matches!(element.attr("val"), Some("0") | Some("false") | Some("off") | Some("none")))
+}
+
+fn is_monospace(props: Option<&XmlElement>) -> bool {
+    props
+        .and_then(|p| p.child("rFonts"))
+        .and_then(|fonts| fonts.attr("ascii").or(fonts.attr("hAnsi")))
+        .is_some_and(|font| MONOSPACE_FONTS.contains(&font.to_lowercase().as_str()))
+}
+
+struct DocxReader {
+    paragraph_styles: HashMap<String, ParagraphKind>,
+    character_styles: HashMap<String, RunStyle>,
+    // numId -> 每一级的编号方式
+    numbering: HashMap<String, Vec<ListLevel>>,
+    relationships: HashMap<String, PartRelationship>,
+    media: HashMap<String, Vec<u8>>,
+    notes: HashMap<String, XmlElement>,
+    note_order: Vec<String>,
+    counters: HashMap<(String, usize), u32>,
+    title: Option<String>,
+}
+
+impl DocxReader {
+    fn load_styles(&mut self, styles: &XmlElement) {
+        let mut based_on: HashMap<String, String> = HashMap::new();
+        for style in styles.elements().filter(|e| e.name == "style") {
+            let Some(id) = style.attr("styleId") else { continue };
+            let name = style.child("name").and_then(|n| n.attr("val")).unwrap_or(id);
+            if let Some(parent) = style.child("basedOn").and_then(|b| b.attr("val")) {
+                based_on.insert(id.to_string(), parent.to_string());
+            }
+            match style.attr("type") {
+                Some("character") => {
+                    let props = style.child("rPr");
+                    let label = name.to_lowercase();
+                    let run_style = RunStyle {
+                        bold: flag(props, "b").unwrap_or(label == "strong"),
+                        italic: flag(props, "i").unwrap_or(label == "emphasis"),
+                        strike: flag(props, "strike").unwrap_or(false),
+                        code: classify_style(name).or_else(|| classify_style(id)) == Some(ParagraphKind::Code)
+                            || is_monospace(props),
+                    };
+                    self.character_styles.insert(id.to_string(), run_style);
+                }
+                Some("paragraph") | None => {
+                    let outline = style
+                        .child("pPr")
+                        .and_then(|p| p.child("outlineLvl"))
+                        .and_then(|o| o.attr("val"))
+                        .and_then(|v| v.parse::<u8>().ok())
+                        .filter(|l| *l < 6)
+                        .map(|l| ParagraphKind::Heading(l + 1));
+                    if let Some(kind) = classify_style(name).or_else(|| classify_style(id)).or(outline) {
+                        self.paragraph_styles.insert(id.to_string(), kind);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // 未识别的样式沿 basedOn 继承父样式的类别
+        for id in based_on.keys() {
+            let mut current = id.as_str();
+            for _ in 0..10 {
+                if self.paragraph_styles.contains_key(current) {
+                    break;
+                }
+                match based_on.get(current) {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if current != id {
+                if let Some(kind) = self.paragraph_styles.get(current).copied() {
+                    // 由标题样式派生的样式一般是标题的变体
+                    self.paragraph_styles.entry(id.clone()).or_insert(kind);
+                }
+            }
+        }
+    }
+
+    fn load_numbering(&mut self, numbering: &XmlElement) {
+        let start_of = |element: &XmlElement| element.attr("val").and_then(|v| v.parse::<u32>().ok());
+        let mut abstracts: HashMap<&str, Vec<ListLevel>> = HashMap::new();
+        for definition in numbering.elements().filter(|e| e.name == "abstractNum") {
+            let Some(id) = definition.attr("abstractNumId") else { continue };
+            let mut levels = vec![ListLevel { ordered: false, start: 1 }; 9];
+            for level in definition.elements().filter(|e| e.name == "lvl") {
+                let index = level.attr("ilvl").and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+                let format = level.child("numFmt").and_then(|f| f.attr("val")).unwrap_or("bullet");
+                if let Some(entry) = levels.get_mut(index) {
+                    entry.ordered = !matches!(format, "bullet" | "none");
+                    entry.start = level.child("start").and_then(start_of).unwrap_or(1);
+                }
+            }
+            abstracts.insert(id, levels);
+        }
+        for num in numbering.elements().filter(|e| e.name == "num") {
+            let abstract_id = num.child("abstractNumId").and_then(|a| a.attr("val"));
+            let (Some(id), Some(levels)) = (num.attr("numId"), abstract_id.and_then(|a| abstracts.get(a))) else {
+                continue;
+            };
+            let mut levels = levels.clone();
+            for overridden in num.elements().filter(|e| e.name == "lvlOverride") {
+                let index = overridden.attr("ilvl").and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+                if let (Some(entry), Some(start)) = (levels.get_mut(index), overridden.child("startOverride").and_then(start_of)) {
+                    entry.start = start;
+                }
+            }
+            self.numbering.insert(id.to_string(), levels);
+        }
+    }
+
+    fn load_notes(&mut self, notes: XmlElement, prefix: char) {
+        for child in notes.children {
+            let XmlNode::Element(note) = child else { continue };
+            // 分隔线等特殊脚注没有正文
+            if note.attr("type").is_some_and(|t| t != "normal") {
+                continue;
+            }
+            if let Some(id) = note.attr("id") {
+                self.notes.insert(format!("{}{}", prefix, id), note);
+            }
+        }
+    }
+
+    fn note_label(&mut self, key: String) -> usize {
+        match self.note_order.iter().position(|k| *k == key) {
+            Some(index) => index + 1,
+            None => {
+                self.note_order.push(key);
+                self.note_order.len()
+            }
+        }
+    }
+
+    fn blocks(&mut self, container: &XmlElement, out: &mut Vec<Block>) {
+        for element in container.elements() {
+            match element.name.as_str() {
+                "p" => self.paragraph(element, out),
+                "tbl" => {
+                    let table = self.table(element);
+                    if !table.is_empty() {
+                        out.push(Block::Table(table));
+                    }
+                }
+                "sdt" | "sdtContent" | "customXml" | "ins" | "smartTag" => self.blocks(element, out),
+                _ => {}
+            }
+        }
+    }
+
+    fn paragraph(&mut self, paragraph: &XmlElement, out: &mut Vec<Block>) {
+        let props = paragraph.child("pPr");
+        let style = props.and_then(|p| p.child("pStyle")).and_then(|s| s.attr("val"));
+        let mut kind = style
+            .and_then(|s| self.paragraph_styles.get(s))
+            .copied()
+            .unwrap_or(ParagraphKind::Normal);
+        if kind == ParagraphKind::Normal {
+            if let Some(level) = props
+                .and_then(|p| p.child("outlineLvl"))
+                .and_then(|o| o.attr("val"))
+                .and_then(|v| v.parse::<u8>().ok())
+                .filter(|l| *l < 6)
+            {
+                kind = ParagraphKind::Heading(level + 1);
+            }
+        }
+
+        if kind == ParagraphKind::Code {
+            let mut text = String::new();
+            collect_text(paragraph, &mut text);
+            out.push(Block::Code(text));
+            return;
+        }
+
+        let mut segments = Vec::new();
+        self.inlines(paragraph, None, &mut segments);
+        let text = trim_breaks(&render_inlines(&segments));
+        if text.is_empty() {
+            return;
+        }
+
+        let numbering = props
+            .and_then(|p| p.child("numPr"))
+            .and_then(|n| Some((n.child("numId")?.attr("val")?, n.child("ilvl").and_then(|l| l.attr("val")).unwrap_or("0"))))
+            .filter(|(id, _)| *id != "0");
+
+        match kind {
+            ParagraphKind::Title if self.title.is_none() => {
+                let plain: String = segments.iter().filter(|s| !s.raw).map(|s| s.text.as_str()).collect();
+                self.title = Some(plain.split_whitespace().collect::<Vec<_>>().join(" "));
+            }
+            ParagraphKind::Title => out.push(Block::Heading(1, text)),
+            ParagraphKind::Heading(level) => out.push(Block::Heading(level, text)),
+            ParagraphKind::Quote => out.push(Block::Quote(text)),
+            _ => match numbering {
+                Some((num_id, level)) => {
+                    let level: usize = level.parse().unwrap_or(0).min(8);
+                    let list_level = self.numbering.get(num_id).and_then(|l| l.get(level)).copied().unwrap_or_default();
+                    let marker = if list_level.ordered {
+                        // 回到上层时，下层编号重新计数
+                        self.counters.retain(|(id, l), _| id != num_id || *l <= level);
+                        let counter = self
+                            .counters
+                            .entry((num_id.to_string(), level))
+                            .or_insert(list_level.start.saturating_sub(1));
+                        *counter += 1;
+                        format!("{}.", counter)
+                    } else {
+                        "-".to_string()
+                    };
+                    // 导出时任务列表的勾选框写成了文字，这里还原
+                    let text = match (text.strip_prefix("☒ "), text.strip_prefix("☐ ")) {
+                        (Some(rest), _) => format!("[x] {}", rest),
+                        (_, Some(rest)) => format!("[ ] {}", rest),
+                        _ => text,
+                    };
+                    out.push(Block::Item { list: num_id.to_string(), level, marker, text });
+                }
+                None => out.push(Block::Paragraph(text)),
+            },
+        }
+    }
+
+    fn inlines(&mut self, node: &XmlElement, link: Option<&str>, out: &mut Vec<Segment>) {
+        for child in node.elements() {
+            match child.name.as_str() {
+                "r" => self.run(child, link, out),
+                "hyperlink" => {
+                    let target = child
+                        .attr("id")
+                        .and_then(|id| self.relationships.get(id))
+                        .map(|r| r.target.clone())
+                        .or_else(|| child.attr("anchor").map(|anchor| format!("#{}", anchor)));
+                    self.inlines(child, target.as_deref().or(link), out);
+                }
+                "ins" | "smartTag" | "sdt" | "sdtContent" | "fldSimple" | "customXml" | "bdo" | "dir" => {
+                    self.inlines(child, link, out)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn run(&mut self, run: &XmlElement, link: Option<&str>, out: &mut Vec<Segment>) {
+        let props = run.child("rPr");
+        let base = props
+            .and_then(|p| p.child("rStyle"))
+            .and_then(|s| s.attr("val"))
+            .and_then(|id| self.character_styles.get(id))
+            .copied()
+            .unwrap_or_default();
+        let style = RunStyle {
+            bold: flag(props, "b").unwrap_or(base.bold),
+            italic: flag(props, "i").unwrap_or(base.italic),
+            strike: flag(props, "strike").or_else(|| flag(props, "dstrike")).unwrap_or(base.strike),
+            code: base.code || is_monospace(props),
+        };
+        let segment = |text: String, raw: bool| Segment { text, style, link: link.map(str::to_string), raw };
+
+        for child in run.elements() {
+            match child.name.as_str() {
+                "t" => out.push(segment(child.text(), false)),
+                "tab" | "ptab" => out.push(segment("\t".into(), false)),
+                "br" | "cr" if child.attr("type").is_none_or(|t| t == "textWrapping") => out.push(segment("\n".into(), false)),
+                "noBreakHyphen" => out.push(segment("-".into(), false)),
+                "drawing" | "pict" | "object" => {
+                    if let Some(image) = self.image(child) {
+                        out.push(segment(image, true));
+                    }
+                }
+                "footnoteReference" | "endnoteReference" => {
+                    let prefix = if child.name == "footnoteReference" { 'f' } else { 'e' };
+                    if let Some(id) = child.attr("id") {
+                        let label = self.note_label(format!("{}{}", prefix, id));
+                        out.push(Segment { text: format!("[^{}]", label), style: RunStyle::default(), link: None, raw: true });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 内嵌图片转成 data URI，外链图片保留原地址
+    fn image(&self, node: &XmlElement) -> Option<String> {
+        let (id, alt) = match node.find("blip") {
+            Some(blip) => {
+                let description = node.find("docPr").and_then(|d| d.attr("descr").filter(|v| !v.is_empty()).or(d.attr("title")));
+                (blip.attr("embed").or(blip.attr("link"))?, description)
+            }
+            None => {
+                let data = node.find("imagedata")?;
+                (data.attr("id")?, data.attr("title"))
+            }
+        };
+        let alt = escape_markdown(alt.unwrap_or_default()).replace('\n', " ");
+        if let Some(bytes) = self.media.get(id) {
+            let mime = match image::guess_format(bytes).ok()? {
+                ImageFormat::Png => "image/png",
+                ImageFormat::Jpeg => "image/jpeg",
+                ImageFormat::Gif => "image/gif",
+                ImageFormat::WebP => "image/webp",
+                ImageFormat::Bmp => "image/bmp",
+                ImageFormat::Tiff => "image/tiff",
+                _ => return None,
+            };
+            let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+            return Some(format!("![{}](data:{};base64,{})", alt, mime, data));
+        }
+        match self.relationships.get(id) {
+            Some(relationship) if relationship.external => Some(format!("![{}]({})", alt, link_destination(&relationship.target))),
+            _ => None,
+        }
+    }
+
+    fn table(&mut self, table: &XmlElement) -> String {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut alignments: Vec<&str> = Vec::new();
+        for row in table.elements().filter(|e| e.name == "tr") {
+            let mut cells = Vec::new();
+            for cell in row.elements().filter(|e| e.name == "tc") {
+                // 列的对齐方式取自首行单元格的段落对齐
+                if rows.is_empty() {
+                    let align = cell
+                        .child("p")
+                        .and_then(|p| p.child("pPr"))
+                        .and_then(|p| p.child("jc"))
+                        .and_then(|j| j.attr("val"));
+                    alignments.push(match align {
+                        Some("center") => " :---: |",
+                        Some("right") | Some("end") => " ---: |",
+                        _ => " --- |",
+                    });
+                }
+                let mut parts = Vec::new();
+                for element in cell.elements() {
+                    let text = match element.name.as_str() {
+                        "p" => {
+                            let mut segments = Vec::new();
+                            self.inlines(element, None, &mut segments);
+                            trim_breaks(&render_inlines(&segments)).replace("\\\n", "<br>")
+                        }
+                        // 表格不能嵌套，内层表格只保留文字
+                        "tbl" => {
+                            let mut text = String::new();
+                            collect_text(element, &mut text);
+                            escape_markdown(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+                        }
+                        _ => continue,
+                    };
+                    if !text.trim().is_empty() {
+                        parts.push(text);
+                    }
+                }
+                cells.push(parts.join("<br>").replace('\n', " ").replace('|', "\\|"));
+                let span = cell
+                    .child("tcPr")
+                    .and_then(|p| p.child("gridSpan"))
+                    .and_then(|s| s.attr("val"))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(1);
+                cells.extend(std::iter::repeat_n(String::new(), span.saturating_sub(1)));
+            }
+            rows.push(cells);
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+        let line = |cells: &[String]| {
+            let padded: Vec<&str> = (0..columns).map(|i| cells.get(i).map(String::as_str).unwrap_or("")).collect();
+            format!("| {} |", padded.join(" | "))
+        };
+        let separator: String = (0..columns).map(|i| alignments.get(i).copied().unwrap_or(" --- |")).collect();
+        let mut lines = vec![line(&rows[0]), format!("|{}", separator)];
+        lines.extend(rows[1..].iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    fn note_definitions(&mut self) -> String {
+        let mut definitions = Vec::new();
+        let mut index = 0;
+        // 脚注里的引用会继续追加到 note_order
+        while index < self.note_order.len() {
+            let key = self.note_order[index].clone();
+            index += 1;
+            let Some(note) = self.notes.remove(&key) else { continue };
+            let mut blocks = Vec::new();
+            self.blocks(&note, &mut blocks);
+            let body = render_blocks(&blocks);
+            let body = body.trim();
+            definitions.push(format!("[^{}]: {}", index, body.replace('\n', "\n    ").replace("\n    \n", "\n\n")));
+        }
+        definitions.join("\n\n")
+    }
+}
+
+// 代码段落只取文字，跳过修订中删除的内容
+fn collect_text(element: &XmlElement, out: &mut String) {
+    for child in element.elements() {
+        match child.name.as_str() {
+            "t" => out.push_str(&child.text()),
+            "tab" | "ptab" => out.push('\t'),
+            "br" | "cr" => out.push('\n'),
+            "del" | "pPr" | "rPr" | "instrText" => {}
+            _ => collect_text(child, out),
+        }
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// 行首的 #、>、-、+、= 和 "1." 会被当成块级语法，需要转义
+fn escape_line_start(line: &str) -> String {
+    let content = line.trim_start();
+    let indent = &line[..line.len() - content.len()];
+    if content.starts_with(['#', '>', '-', '+', '=']) {
+        return format!("{}\\{}", indent, content);
+    }
+    let digits = content.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && content[digits..].starts_with(['.', ')']) {
+        return format!("{}{}\\{}", indent, &content[..digits], &content[digits..]);
+    }
+    line.to_string()
+}
+
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+// 去掉段落首尾的空白和多余的硬换行
+fn trim_breaks(text: &str) -> String {
+    let mut text = text.trim();
+    loop {
+        let trimmed = text
+            .strip_suffix("\\\n")
+            .or_else(|| text.strip_prefix("\\\n"))
+            .map(str::trim);
+        match trimmed {
+            Some(rest) => text = rest,
+            None => break,
+        }
+    }
+    text.to_string()
+}
+
+fn styled(text: &str, style: RunStyle) -> String {
+    if style.code {
+        let content = text.replace('\n', " ");
+        if content.trim().is_empty() {
+            return content;
+        }
+        let mut longest = 0;
+        let mut current = 0;
+        for c in content.chars() {
+            current = if c == '`' { current + 1 } else { 0 };
+            longest = longest.max(current);
+        }
+        let fence = "`".repeat(longest + 1);
+        let pad = if content.starts_with('`') || content.ends_with('`') { " " } else { "" };
+        return format!("{fence}{pad}{content}{pad}{fence}");
+    }
+
+    let escaped = escape_markdown(text);
+    let core = escaped.trim();
+    if core.is_empty() || !(style.bold || style.italic || style.strike) {
+        return escaped.replace('\n', "\\\n");
+    }
+    // 强调标记不能紧贴空白，首尾空白移到标记外面
+    let start = escaped.len() - escaped.trim_start().len();
+    let end = escaped.trim_end().len();
+    let mut marked = core.to_string();
+    if style.strike {
+        marked = format!("~~{}~~", marked);
+    }
+    if style.italic {
+        marked = format!("*{}*", marked);
+    }
+    if style.bold {
+        marked = format!("**{}**", marked);
+    }
+    format!("{}{}{}", &escaped[..start], marked, &escaped[end..]).replace('\n', "\\\n")
+}
+
+fn render_styled(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    let mut pending: Option<(String, RunStyle)> = None;
+    for segment in segments {
+        if segment.raw {
+            if let Some((text, style)) = pending.take() {
+                out.push_str(&styled(&text, style));
+            }
+            out.push_str(&segment.text);
+            continue;
+        }
+        match pending.as_mut() {
+            Some((text, style)) if *style == segment.style => text.push_str(&segment.text),
+            _ => {
+                if let Some((text, style)) = pending.take() {
+                    out.push_str(&styled(&text, style));
+                }
+                pending = Some((segment.text.clone(), segment.style));
+            }
+        }
+    }
+    if let Some((text, style)) = pending {
+        out.push_str(&styled(&text, style));
+    }
+    out
+}
+
+fn render_inlines(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    while start < segments.len() {
+        let link = &segments[start].link;
+        let end = segments[start..]
+            .iter()
+            .position(|s| s.link != *link)
+            .map_or(segments.len(), |offset| start + offset);
+        let inner = render_styled(&segments[start..end]);
+        match link {
+            Some(url) if !inner.trim().is_empty() => out.push_str(&format!("[{}]({})", inner, link_destination(url))),
+            _ => out.push_str(&inner),
+        }
+        start = end;
+    }
+    out
+}
+
+fn escape_lines(text: &str) -> String {
+    text.split('\n').map(escape_line_start).collect::<Vec<_>>().join("\n")
+}
+
+fn render_blocks(blocks: &[Block]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < blocks.len() {
+        match &blocks[index] {
+            Block::Code(_) => {
+                // 连续的代码段落合并为一个代码块
+                let mut lines = Vec::new();
+                while let Some(Block::Code(line)) = blocks.get(index) {
+                    lines.push(line.as_str());
+                    index += 1;
+                }
+                let code = lines.join("\n");
+                let mut fence = "```".to_string();
+                while code.contains(&fence) {
+                    fence.push('`');
+                }
+                parts.push(format!("{}\n{}\n{}", fence, code, fence));
+                continue;
+            }
+            Block::Item { .. } => {
+                let mut lines = Vec::new();
+                let mut previous: Option<usize> = None;
+                let first_list = match &blocks[index] {
+                    Block::Item { list, .. } => list.clone(),
+                    _ => String::new(),
+                };
+                while let Some(Block::Item { list, level, marker, text }) = blocks.get(index) {
+                    // 顶层换了编号就是另一个列表，空行分开，避免合并成同一个列表
+                    if previous.is_some() && *level == 0 && *list != first_list {
+                        break;
+                    }
+                    // 层级不能跳级，否则缩进过深会变成代码块
+                    let level = match previous {
+                        Some(previous) => (*level).min(previous + 1),
+                        None => 0,
+                    };
+                    previous = Some(level);
+                    let indent = " ".repeat(level * 4);
+                    let continuation = format!("\n{}{}", indent, " ".repeat(marker.len() + 1));
+                    lines.push(format!("{}{} {}", indent, marker, escape_lines(text).replace('\n', &continuation)));
+                    index += 1;
+                }
+                parts.push(lines.join("\n"));
+                continue;
+            }
+            Block::Quote(_) => {
+                let mut quotes = Vec::new();
+                while let Some(Block::Quote(text)) = blocks.get(index) {
+                    quotes.push(format!("> {}", escape_lines(text).replace('\n', "\n> ")));
+                    index += 1;
+                }
+                parts.push(quotes.join("\n>\n"));
+                continue;
+            }
+            Block::Paragraph(text) => parts.push(escape_lines(text)),
+            Block::Heading(level, text) => {
+                parts.push(format!("{} {}", "#".repeat(*level as usize), text.replace("\\\n", " ")));
+            }
+            Block::Table(table) => parts.push(table.clone()),
+        }
+        index += 1;
+    }
+    parts.join("\n\n")
+}
+
+/// 把 .docx 转换为 Markdown：标题、列表、引用、代码、表格、脚注和尾注映射为对应的 Markdown 语法，
+/// 内嵌图片转为 data URI。标题优先取文档中第一个标题样式段落，其次是文档属性
+pub fn docx_to_markdown(bytes: &[u8]) -> Result<DocxImport> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| AppError::validation(format!("Not a valid .docx file: {}", e)))?;
+
+    let package_rels = match read_part(&mut archive, "_rels/.rels")? {
+        Some(data) => parse_relationships(&data)?,
+        None => HashMap::new(),
+    };
+    let main_part = package_rels
+        .values()
+        .find(|r| r.kind == "officeDocument")
+        .map(|r| resolve_part("", &r.target))
+        .unwrap_or_else(|| "word/document.xml".to_string());
+    let document = read_part(&mut archive, &main_part)?
+        .ok_or_else(|| AppError::validation(format!("Not a valid .docx file: missing {}", main_part)))?;
+    let document = parse_xml(&document)?;
+
+    let (part_dir, part_file) = main_part.rsplit_once('/').unwrap_or(("", main_part.as_str()));
+    let rels_path = resolve_part(part_dir, &format!("_rels/{}.rels", part_file));
+    let relationships = match read_part(&mut archive, &rels_path)? {
+        Some(data) => parse_relationships(&data)?,
+        None => HashMap::new(),
+    };
+
+    let mut reader = DocxReader {
+        paragraph_styles: HashMap::new(),
+        character_styles: HashMap::new(),
+        numbering: HashMap::new(),
+        relationships: HashMap::new(),
+        media: HashMap::new(),
+        notes: HashMap::new(),
+        note_order: Vec::new(),
+        counters: HashMap::new(),
+        title: None,
+    };
+    for (id, relationship) in &relationships {
+        if relationship.external {
+            continue;
+        }
+        let path = resolve_part(part_dir, &relationship.target);
+        match relationship.kind.as_str() {
+            "image" => {
+                if let Some(data) = read_part(&mut archive, &path)? {
+                    reader.media.insert(id.clone(), data);
+                }
+            }
+            "styles" => {
+                if let Some(data) = read_part(&mut archive, &path)? {
+                    reader.load_styles(&parse_xml(&data)?);
+                }
+            }
+            "numbering" => {
+                if let Some(data) = read_part(&mut archive, &path)? {
+                    reader.load_numbering(&parse_xml(&data)?);
+                }
+            }
+            "footnotes" | "endnotes" => {
+                if let Some(data) = read_part(&mut archive, &path)? {
+                    let prefix = if relationship.kind == "footnotes" { 'f' } else { 'e' };
+                    reader.load_notes(parse_xml(&data)?, prefix);
+                }
+            }
+            _ => {}
+        }
+    }
+    reader.relationships = relationships;
+
+    let mut blocks = Vec::new();
+    if let Some(body) = document.child("body") {
+        reader.blocks(body, &mut blocks);
+    }
+    let mut markdown = render_blocks(&blocks);
+    let notes = reader.note_definitions();
+    if !notes.is_empty() {
+        markdown.push_str("\n\n");
+        markdown.push_str(&notes);
+    }
+    markdown.push('\n');
+
+    let core = read_part(&mut archive, "docProps/core.xml")?
+        .and_then(|data| parse_xml(&data).ok());
+    let property = |name: &str| {
+        core.as_ref()
+            .and_then(|c| c.child(name))
+            .map(|e| e.text().trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    Ok(DocxImport {
+        title: reader.title.filter(|t| !t.is_empty()).or_else(|| property("title")),
+        author: property("creator"),
+        markdown,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::document::CreateDocumentData;
+    use std::path::PathBuf;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/docx").join(name)
+    }
+
+    fn read_fixture(name: &str) -> Vec<u8> {
+        let path = fixture_path(name);
+        std::fs::read(&path).unwrap_or_else(|e| panic!("cannot read fixture {}: {}", path.display(), e))
+    }
+
+    // 期望的 Markdown 与 .docx 放在一起，设置 UPDATE_SNAPSHOTS=1 运行测试可重新生成
+    fn assert_markdown(name: &str, actual: &str) {
+        let path = fixture_path(name);
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {} (run with UPDATE_SNAPSHOTS=1)", path.display(), e));
+        assert_eq!(actual, expected, "{} differs", name);
+    }
+
+    fn markdown_document(content: &str) -> Document {
+        let mut document = Document::new(CreateDocumentData {
+            title: "往返测试".to_string(),
+            content: Some(content.to_string()),
+            content_type: DocumentType::Markdown,
+            project_id: "project-1".to_string(),
+            folder_path: None,
+            folder_id: None,
+            tags: None,
+            template_id: None,
+            template_variables: None,
+        });
+        document.metadata.author = Some("张三".to_string());
+        document
+    }
+
+    // 中文版 Word 的样式名、编号列表、修订、超链接、表格、脚注和内嵌图片
+    #[test]
+    fn imports_word_document() {
+        let imported = docx_to_markdown(&read_fixture("word-styles.docx")).unwrap();
+        assert_eq!(imported.title.as_deref(), Some("季度写作 报告"));
+        assert_eq!(imported.author.as_deref(), Some("李四"));
+        assert_markdown("word-styles.md", &imported.markdown);
+    }
+
+    // 没有关系和样式部件时按默认路径读取正文，标题取自文档属性
+    #[test]
+    fn imports_minimal_document() {
+        let imported = docx_to_markdown(&read_fixture("minimal.docx")).unwrap();
+        assert_eq!(imported.title.as_deref(), Some("最简文档"));
+        assert_eq!(imported.author.as_deref(), Some("王五"));
+        assert_markdown("minimal.md", &imported.markdown);
+    }
+
+    #[test]
+    fn rejects_non_docx_input() {
+        let error = docx_to_markdown(b"not a zip file").unwrap_err();
+        assert!(matches!(error.downcast_ref::<AppError>(), Some(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn round_trips_markdown() {
+        let source = read_fixture("round-trip.md");
+        let source = String::from_utf8(source).unwrap();
+        let document = markdown_document(&source);
+        let bytes = document_to_docx(&document, &DocxExportOptions::default(), None).unwrap();
+        assert_eq!(bytes, document_to_docx(&document, &DocxExportOptions::default(), None).unwrap(), "export must be deterministic");
+
+        let imported = docx_to_markdown(&bytes).unwrap();
+        assert_eq!(imported.author.as_deref(), Some("张三"));
+        assert_eq!(imported.markdown, source);
+    }
+}
@@ -0,0 +1,494 @@
+// EPUB 3 导出
+// 把项目按目录树的顺序编成电子书：每篇文档一个 XHTML 章节，文件夹成为导航中的分组。
+// 生成导航文档、OPF 元数据以及兼容旧阅读器的 toc.ncx，封面和正文图片一并打包。
+// 打包前逐个检查 XHTML 是否为格式良好的 XML，避免产出阅读器打不开的文件
+
+use crate::error::AppError;
+use crate::models::config::EpubExportOptions;
+use crate::models::document::{Document, DocumentType};
+use crate::models::folder::{FolderNode, ProjectTree, TreeDocument};
+use crate::models::project::Project;
+use crate::services::export::{escape_html, highlighter, language_of, markdown_options, stylesheet, DEFAULT_LANGUAGE, GENERATOR};
+use crate::services::links;
+use crate::services::pdf::{html_to_text, load_portable_image};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use comrak::nodes::NodeValue;
+use comrak::{format_html_with_plugins, parse_document, Arena, Plugins};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+const EPUB_CSS: &str = r#"body { max-width: none; margin: 0; padding: 0 0.5em; }
+.chapter-title { margin-top: 0; }
+.cover { text-align: center; padding-top: 30%; }
+.cover-title { font-size: 2em; }
+.cover-author, .cover-description { color: #57606a; }
+.cover-image { text-align: center; margin: 0; padding: 0; }
+.cover-image img { max-width: 100%; max-height: 100vh; }
+nav ol { list-style: none; padding-left: 1.2em; }
+"#;
+
+struct Chapter {
+    id: String,
+    href: String,
+    title: String,
+}
+
+enum NavEntry {
+    Chapter(usize),
+    Folder { name: String, entries: Vec<NavEntry> },
+}
+
+struct Resource {
+    id: String,
+    href: String,
+    media_type: &'static str,
+    properties: Option<&'static str>,
+    data: Vec<u8>,
+}
+
+fn media_type(extension: &str) -> &'static str {
+    match extension {
+        "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        _ => "image/png",
+    }
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn xhtml_page(title: &str, lang: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"utf-8\" />\n<title>{title}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"{css}\" />\n</head>\n<body>\n{body}</body>\n</html>\n",
+        lang = escape_html(lang),
+        title = escape_html(title),
+        css = stylesheet,
+        body = body,
+    )
+}
+
+// comrak 输出 HTML5：给布尔属性补上空值，表格对齐改用样式，使其成为合法的 XHTML
+fn to_xhtml(html: &str) -> String {
+    html.replace(" data-footnotes>", " data-footnotes=\"\">")
+        .replace(" data-footnote-ref>", " data-footnote-ref=\"\">")
+        .replace(" data-footnote-backref ", " data-footnote-backref=\"\" ")
+        .replace(" align=\"left\"", " style=\"text-align: left\"")
+        .replace(" align=\"center\"", " style=\"text-align: center\"")
+        .replace(" align=\"right\"", " style=\"text-align: right\"")
+}
+
+fn check_well_formed(name: &str, xml: &str) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => return Err(anyhow!("{} is not well-formed XHTML: {}", name, e)),
+        }
+    }
+}
+
+struct EpubBuilder<'a> {
+    base_dir: Option<&'a Path>,
+    chapters: Vec<Chapter>,
+    pages: Vec<(String, String)>,
+    images: Vec<Resource>,
+    // url -> 包内路径，加载失败的记为 None，避免重复读取
+    image_cache: HashMap<String, Option<String>>,
+}
+
+impl<'a> EpubBuilder<'a> {
+    fn image(&mut self, url: &str) -> Option<String> {
+        if let Some(cached) = self.image_cache.get(url) {
+            return cached.clone();
+        }
+        let href = load_portable_image(url, self.base_dir).ok().map(|(data, extension, _, _)| {
+            let number = self.images.len() + 1;
+            let href = format!("images/image-{:03}.{}", number, extension);
+            self.images.push(Resource {
+                id: format!("image-{:03}", number),
+                href: href.clone(),
+                media_type: media_type(extension),
+                properties: None,
+                data,
+            });
+            href
+        });
+        self.image_cache.insert(url.to_string(), href.clone());
+        href
+    }
+
+    fn markdown(&mut self, content: &str) -> Result<String> {
+        let arena = Arena::new();
+        let mut options = markdown_options();
+        // 原始 HTML 不一定是合法的 XHTML，电子书中略去
+        options.render.unsafe_ = false;
+        let root = parse_document(&arena, content, &options);
+
+        // 章节标题占用 h1，正文标题依次降一级；图片改为包内路径，无法嵌入的只保留替代文本
+        for node in root.descendants() {
+            let mut data = node.data.borrow_mut();
+            let replacement = match &mut data.value {
+                NodeValue::Heading(heading) => {
+                    heading.level = (heading.level + 1).min(6);
+                    None
+                }
+                NodeValue::Image(link) => match self.image(&link.url) {
+                    Some(href) => {
+                        link.url = format!("../{}", href);
+                        None
+                    }
+                    None => Some(NodeValue::Emph),
+                },
+                _ => None,
+            };
+            if let Some(value) = replacement {
+                data.value = value;
+            }
+        }
+
+        let mut plugins = Plugins::default();
+        plugins.render.codefence_syntax_highlighter = Some(highlighter());
+        let mut html = Vec::new();
+        format_html_with_plugins(root, &options, &mut html, &plugins)?;
+        Ok(to_xhtml(&String::from_utf8(html)?))
+    }
+
+    fn chapter(&mut self, document: &Document) -> Result<usize> {
+        let content = match document.content_type {
+            DocumentType::Markdown => self.markdown(&document.content)?,
+            DocumentType::PlainText => plain_paragraphs(&document.content),
+            // 富文本正文不一定是合法的 XHTML，转成纯文本段落
+            DocumentType::RichText => plain_paragraphs(&html_to_text(&document.content)),
+        };
+        let body = format!(
+            "<section epub:type=\"chapter\" id=\"doc-{}\">\n<h1 class=\"chapter-title\">{}</h1>\n{}</section>\n",
+            escape_html(&document.id),
+            escape_html(&document.title),
+            content
+        );
+
+        let href = format!("text/chapter-{:03}.xhtml", self.chapters.len() + 1);
+        let page = xhtml_page(&document.title, language_of(document), "../styles.css", &body);
+        check_well_formed(&format!("Chapter \"{}\"", document.title), &page)?;
+        self.pages.push((href.clone(), page));
+        self.chapters.push(Chapter { id: document.id.clone(), href, title: document.title.clone() });
+        Ok(self.chapters.len() - 1)
+    }
+
+    // 每一层先放文档，再放子文件夹；没有文档的文件夹不出现在目录中
+    fn collect(
+        &mut self,
+        documents: &HashMap<&str, &Document>,
+        used: &mut HashSet<String>,
+        tree_documents: &[TreeDocument],
+        folders: &[FolderNode],
+    ) -> Result<Vec<NavEntry>> {
+        let mut entries = Vec::new();
+        for tree_document in tree_documents {
+            if let Some(document) = documents.get(tree_document.id.as_str()) {
+                if used.insert(document.id.clone()) {
+                    entries.push(NavEntry::Chapter(self.chapter(document)?));
+                }
+            }
+        }
+        for folder in folders {
+            let children = self.collect(documents, used, &folder.documents, &folder.folders)?;
+            if !children.is_empty() {
+                entries.push(NavEntry::Folder { name: folder.folder.name.clone(), entries: children });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn plain_paragraphs(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| format!("<p>{}</p>\n", escape_html(paragraph.trim_matches('\n')).replace('\n', "<br />\n")))
+        .collect()
+}
+
+fn nav_list(entries: &[NavEntry], chapters: &[Chapter], out: &mut String) {
+    out.push_str("<ol>\n");
+    for entry in entries {
+        match entry {
+            NavEntry::Chapter(index) => {
+                let chapter = &chapters[*index];
+                out.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", chapter.href, escape_html(&chapter.title)));
+            }
+            NavEntry::Folder { name, entries } => {
+                out.push_str(&format!("<li><span>{}</span>\n", escape_html(name)));
+                nav_list(entries, chapters, out);
+                out.push_str("</li>\n");
+            }
+        }
+    }
+    out.push_str("</ol>\n");
+}
+
+fn first_chapter(entries: &[NavEntry]) -> Option<usize> {
+    entries.iter().find_map(|entry| match entry {
+        NavEntry::Chapter(index) => Some(*index),
+        NavEntry::Folder { entries, .. } => first_chapter(entries),
+    })
+}
+
+fn nav_depth(entries: &[NavEntry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| match entry {
+            NavEntry::Chapter(_) => 1,
+            NavEntry::Folder { entries, .. } => 1 + nav_depth(entries),
+        })
+        .max()
+        .unwrap_or(1)
+}
+
+// NCX 中指向同一文件的条目必须使用相同的 playOrder，文件夹条目指向其中第一章
+fn ncx_points(entries: &[NavEntry], chapters: &[Chapter], counter: &mut usize, out: &mut String) {
+    for entry in entries {
+        let (label, index, children) = match entry {
+            NavEntry::Chapter(index) => (chapters[*index].title.as_str(), *index, None),
+            NavEntry::Folder { name, entries } => match first_chapter(entries) {
+                Some(index) => (name.as_str(), index, Some(entries)),
+                None => continue,
+            },
+        };
+        *counter += 1;
+        out.push_str(&format!(
+            "<navPoint id=\"navpoint-{}\" playOrder=\"{}\"><navLabel><text>{}</text></navLabel><content src=\"{}\"/>\n",
+            counter,
+            index + 1,
+            escape_html(label),
+            chapters[index].href
+        ));
+        if let Some(children) = children {
+            ncx_points(children, chapters, counter, out);
+        }
+        out.push_str("</navPoint>\n");
+    }
+}
+
+/// 把项目导出为 EPUB 3。章节顺序取自目录树（文件夹和手动排序），tree 与 documents 需属于同一项目
+pub fn project_to_epub(
+    project: &Project,
+    tree: &ProjectTree,
+    documents: &[Document],
+    options: &EpubExportOptions,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>> {
+    let mut builder = EpubBuilder {
+        base_dir,
+        chapters: Vec::new(),
+        pages: Vec::new(),
+        images: Vec::new(),
+        image_cache: HashMap::new(),
+    };
+
+    let by_id: HashMap<&str, &Document> = documents.iter().map(|d| (d.id.as_str(), d)).collect();
+    let mut used = HashSet::new();
+    let mut entries = builder.collect(&by_id, &mut used, &tree.documents, &tree.folders)?;
+    // 目录树之外的文档（理论上不会出现）按原顺序放在最后
+    for document in documents.iter().filter(|d| !used.contains(&d.id)) {
+        entries.push(NavEntry::Chapter(builder.chapter(document)?));
+    }
+    // 指向书中其他文档的链接改为对应章节；章节页面都在 text/ 下，使用同级相对路径
+    let targets: HashMap<&str, &str> = builder
+        .chapters
+        .iter()
+        .map(|chapter| (chapter.id.as_str(), chapter.href.trim_start_matches("text/")))
+        .collect();
+    for (_, page) in &mut builder.pages {
+        *page = links::rewrite_html_links(page, |url| {
+            let id = links::document_id(url)?;
+            targets.get(id).map(|href| format!("{}#doc-{}", href, id))
+        });
+    }
+
+    let lang = documents.first().map(language_of).unwrap_or(DEFAULT_LANGUAGE);
+    let mut authors: Vec<&str> = Vec::new();
+    for author in documents.iter().filter_map(|d| d.metadata.author.as_deref()).map(str::trim) {
+        if !author.is_empty() && !authors.contains(&author) {
+            authors.push(author);
+        }
+    }
+    let mut subjects: Vec<&str> = Vec::new();
+    for tag in documents.iter().flat_map(|d| &d.tags) {
+        if !subjects.contains(&tag.as_str()) {
+            subjects.push(tag);
+        }
+    }
+
+    // 封面：指定了图片就用图片，否则生成文字封面
+    let cover_image = match options.cover_image.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(path) => {
+            let (data, extension, _, _) = load_portable_image(path, base_dir)
+                .map_err(|e| AppError::validation(format!("Cover image cannot be loaded: {}", e)))?;
+            Some(Resource {
+                id: "cover-image".into(),
+                href: format!("images/cover.{}", extension),
+                media_type: media_type(extension),
+                properties: Some("cover-image"),
+                data,
+            })
+        }
+        None => None,
+    };
+    let cover_body = match &cover_image {
+        Some(image) => format!(
+            "<section epub:type=\"cover\" class=\"cover-image\">\n<img src=\"{}\" alt=\"{}\" />\n</section>\n",
+            image.href,
+            escape_html(&project.name)
+        ),
+        None => {
+            let mut body = format!(
+                "<section epub:type=\"cover\" class=\"cover\">\n<h1 class=\"cover-title\">{}</h1>\n",
+                escape_html(&project.name)
+            );
+            if !authors.is_empty() {
+                body.push_str(&format!("<p class=\"cover-author\">{}</p>\n", escape_html(&authors.join("、"))));
+            }
+            if !project.description.trim().is_empty() {
+                body.push_str(&format!("<p class=\"cover-description\">{}</p>\n", escape_html(project.description.trim())));
+            }
+            body.push_str("</section>\n");
+            body
+        }
+    };
+    let cover = xhtml_page(&project.name, lang, "styles.css", &cover_body);
+
+    let toc_title = "目录";
+    let mut nav_body = format!("<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n", toc_title);
+    nav_list(&entries, &builder.chapters, &mut nav_body);
+    nav_body.push_str("</nav>\n<nav epub:type=\"landmarks\" hidden=\"hidden\">\n<ol>\n");
+    nav_body.push_str("<li><a epub:type=\"cover\" href=\"cover.xhtml\">封面</a></li>\n");
+    nav_body.push_str(&format!("<li><a epub:type=\"toc\" href=\"nav.xhtml#toc\">{}</a></li>\n", toc_title));
+    if let Some(index) = first_chapter(&entries) {
+        nav_body.push_str(&format!("<li><a epub:type=\"bodymatter\" href=\"{}\">正文</a></li>\n", builder.chapters[index].href));
+    }
+    nav_body.push_str("</ol>\n</nav>\n");
+    let nav = xhtml_page(toc_title, lang, "styles.css", &nav_body);
+    check_well_formed("Navigation document", &nav)?;
+
+    let identifier = format!("urn:uuid:{}", project.id);
+    let mut ncx = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\" xml:lang=\"{}\">\n\
+         <head><meta name=\"dtb:uid\" content=\"{}\"/><meta name=\"dtb:depth\" content=\"{}\"/>\
+         <meta name=\"dtb:totalPageCount\" content=\"0\"/><meta name=\"dtb:maxPageNumber\" content=\"0\"/></head>\n\
+         <docTitle><text>{}</text></docTitle>\n<navMap>\n",
+        escape_html(lang),
+        escape_html(&identifier),
+        nav_depth(&entries),
+        escape_html(&project.name)
+    );
+    ncx_points(&entries, &builder.chapters, &mut 0, &mut ncx);
+    ncx.push_str("</navMap>\n</ncx>\n");
+
+    let mut metadata = format!(
+        "<dc:identifier id=\"book-id\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n",
+        escape_html(&identifier),
+        escape_html(&project.name),
+        escape_html(lang)
+    );
+    for (index, author) in authors.iter().enumerate() {
+        metadata.push_str(&format!(
+            "<dc:creator id=\"creator-{n}\">{}</dc:creator>\n<meta refines=\"#creator-{n}\" property=\"role\" scheme=\"marc:relators\">aut</meta>\n",
+            escape_html(author),
+            n = index + 1
+        ));
+    }
+    if !project.description.trim().is_empty() {
+        metadata.push_str(&format!("<dc:description>{}</dc:description>\n", escape_html(project.description.trim())));
+    }
+    for subject in &subjects {
+        metadata.push_str(&format!("<dc:subject>{}</dc:subject>\n", escape_html(subject)));
+    }
+    metadata.push_str(&format!("<dc:date>{}</dc:date>\n", project.created_at.format("%Y-%m-%d")));
+    metadata.push_str(&format!("<meta property=\"dcterms:modified\">{}</meta>\n", format_time(&project.updated_at)));
+    metadata.push_str(&format!("<meta name=\"generator\" content=\"{}\"/>\n", escape_html(GENERATOR)));
+    if cover_image.is_some() {
+        metadata.push_str("<meta name=\"cover\" content=\"cover-image\"/>\n");
+    }
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+         <item id=\"css\" href=\"styles.css\" media-type=\"text/css\"/>\n\
+         <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+    );
+    let mut spine = String::from("<itemref idref=\"cover\"/>\n<itemref idref=\"nav\"/>\n");
+    for (index, chapter) in builder.chapters.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{:03}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            index + 1,
+            chapter.href
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{:03}\"/>\n", index + 1));
+    }
+    for resource in cover_image.iter().chain(builder.images.iter()) {
+        manifest.push_str(&format!(
+            "<item id=\"{}\" href=\"{}\" media-type=\"{}\"{}/>\n",
+            resource.id,
+            resource.href,
+            resource.media_type,
+            resource.properties.map(|p| format!(" properties=\"{}\"", p)).unwrap_or_default()
+        ));
+    }
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}</metadata>\n\
+         <manifest>\n{}</manifest>\n<spine toc=\"ncx\">\n{}</spine>\n</package>\n",
+        escape_html(lang),
+        metadata,
+        manifest,
+        spine
+    );
+
+    // mimetype 必须是第一个条目且不压缩；不写入修改时间，相同输入得到相同的文件
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    let mut parts: Vec<(String, Vec<u8>)> = vec![
+        ("META-INF/container.xml".into(), CONTAINER_XML.as_bytes().to_vec()),
+        ("OEBPS/content.opf".into(), opf.into_bytes()),
+        ("OEBPS/nav.xhtml".into(), nav.into_bytes()),
+        ("OEBPS/toc.ncx".into(), ncx.into_bytes()),
+        ("OEBPS/styles.css".into(), format!("{}{}", stylesheet(), EPUB_CSS).into_bytes()),
+        ("OEBPS/cover.xhtml".into(), cover.into_bytes()),
+    ];
+    for (href, page) in builder.pages {
+        parts.push((format!("OEBPS/{}", href), page.into_bytes()));
+    }
+    for resource in cover_image.into_iter().chain(builder.images) {
+        parts.push((format!("OEBPS/{}", resource.href), resource.data));
+    }
+    for (name, data) in parts {
+        zip.start_file(name, deflated)?;
+        zip.write_all(&data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
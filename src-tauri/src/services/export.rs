@@ -1,25 +1,408 @@
 // 导出服务模块
-// 用于导出文档到各种格式
+// 把文档渲染为 HTML：CommonMark + GFM（表格、任务列表、脚注、删除线、自动链接），代码块按语言着色。
+// 输出只取决于文档内容和导出选项，不写入导出时间等易变信息，便于做快照测试
 
-#[allow(dead_code)]
-pub struct ExportService;
+use crate::models::config::HtmlExportOptions;
+use crate::models::document::{Document, DocumentType};
+use crate::models::project::Project;
+use crate::services::{links, templates};
+use chrono::{DateTime, SecondsFormat, Utc};
+use comrak::plugins::syntect::{SyntectAdapter, SyntectAdapterBuilder};
+use comrak::{markdown_to_html_with_plugins, Options, Plugins};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle};
+use std::collections::HashSet;
+use std::sync::OnceLock;
 
-#[allow(dead_code)]
-impl ExportService {
-    pub fn new() -> Self {
-        Self
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+pub(crate) const DEFAULT_LANGUAGE: &str = "zh-CN";
+pub(crate) const GENERATOR: &str = "WriteFlow Studio";
+
+const BASE_CSS: &str = r#"body { max-width: 46em; margin: 2em auto; padding: 0 1em; font-family: -apple-system, "Segoe UI", "PingFang SC", "Microsoft YaHei", sans-serif; line-height: 1.7; color: #24292f; }
+h1, h2, h3, h4, h5, h6 { line-height: 1.3; margin: 1.4em 0 0.6em; }
+a { color: #0969da; }
+img { max-width: 100%; }
+blockquote { margin: 0; padding: 0 1em; color: #57606a; border-left: 0.25em solid #d0d7de; }
+code { font-family: "JetBrains Mono", Consolas, monospace; font-size: 0.9em; background: #f6f8fa; padding: 0.1em 0.3em; border-radius: 4px; }
+pre { background: #f6f8fa; padding: 1em; overflow-x: auto; border-radius: 6px; }
+pre code { background: none; padding: 0; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #d0d7de; padding: 0.4em 0.8em; }
+th { background: #f6f8fa; }
+ul.contains-task-list { list-style: none; padding-left: 1.2em; }
+.footnotes { font-size: 0.9em; color: #57606a; border-top: 1px solid #d0d7de; margin-top: 2em; }
+.plain-text { white-space: pre-wrap; font-family: inherit; background: none; padding: 0; }
+.document + .document { margin-top: 3em; padding-top: 2em; border-top: 1px solid #d0d7de; }
+.document-metadata { display: flex; flex-wrap: wrap; gap: 0.2em 1.2em; margin: 0 0 1.5em; font-size: 0.85em; color: #57606a; }
+.document-metadata dt { font-weight: 600; }
+.document-metadata dd { margin: 0 0 0 -0.8em; }
+.project-toc ol { padding-left: 1.4em; }
+"#;
+
+pub(crate) fn highlighter() -> &'static SyntectAdapter {
+    static ADAPTER: OnceLock<SyntectAdapter> = OnceLock::new();
+    // 使用 class 而不是内联样式，配色由样式表决定
+    ADAPTER.get_or_init(|| SyntectAdapterBuilder::new().css().build())
+}
+
+pub(crate) fn markdown_options() -> Options<'static> {
+    let mut options = Options::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    options.extension.footnotes = true;
+    options.render.tasklist_classes = true;
+    // 导出的是用户自己的文档，保留其中的原始 HTML
+    options.render.unsafe_ = true;
+    options
+}
+
+/// 把 Markdown 渲染为 HTML 片段
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut plugins = Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(highlighter());
+    markdown_to_html_with_plugins(markdown, &markdown_options(), &plugins)
+}
+
+/// 导出使用的样式表，包含代码着色规则
+pub fn stylesheet() -> &'static str {
+    static CSS: OnceLock<String> = OnceLock::new();
+    CSS.get_or_init(|| {
+        let themes = ThemeSet::load_defaults();
+        let highlight = themes
+            .themes
+            .get(HIGHLIGHT_THEME)
+            .and_then(|theme| css_for_theme_with_class_style(theme, ClassStyle::Spaced).ok())
+            .unwrap_or_default();
+        format!("{}{}", BASE_CSS, highlight)
+    })
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn document_body(document: &Document) -> String {
+    match document.content_type {
+        DocumentType::Markdown => markdown_to_html(&document.content),
+        // 富文本正文本身就是 HTML
+        DocumentType::RichText => format!("{}\n", document.content),
+        DocumentType::PlainText => format!("<pre class=\"plain-text\">{}</pre>\n", escape_html(&document.content)),
     }
-    
-    // TODO: 实现导出功能
-    pub async fn export_to_pdf(&self, _content: &str) -> anyhow::Result<Vec<u8>> {
-        Ok(vec![])
+}
+
+fn document_article(document: &Document) -> String {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    if let Some(author) = document.metadata.author.as_deref().filter(|a| !a.is_empty()) {
+        fields.push(("作者", escape_html(author)));
     }
-    
-    pub async fn export_to_html(&self, _content: &str) -> anyhow::Result<String> {
-        Ok("".to_string())
+    fields.push(("字数", document.word_count.to_string()));
+    fields.push(("创建", document.created_at.format("%Y-%m-%d").to_string()));
+    fields.push(("更新", document.updated_at.format("%Y-%m-%d").to_string()));
+    if !document.tags.is_empty() {
+        let tags: Vec<String> = document.tags.iter().map(|t| escape_html(t)).collect();
+        fields.push(("标签", tags.join(", ")));
     }
-    
-    pub async fn export_to_markdown(&self, _content: &str) -> anyhow::Result<String> {
-        Ok("".to_string())
+    let metadata: String = fields
+        .iter()
+        .map(|(name, value)| format!("<dt>{}</dt><dd>{}</dd>", name, value))
+        .collect();
+
+    format!(
+        "<article class=\"document\" id=\"doc-{id}\" data-status=\"{status:?}\" data-version=\"{version}\" \
+         data-created=\"{created}\" data-updated=\"{updated}\">\n\
+         <header>\n<h1 class=\"document-title\">{title}</h1>\n<dl class=\"document-metadata\">{metadata}</dl>\n</header>\n\
+         {body}</article>\n",
+        id = escape_html(&document.id),
+        status = document.status,
+        version = document.metadata.version,
+        created = format_time(&document.created_at),
+        updated = format_time(&document.updated_at),
+        title = escape_html(&document.title),
+        metadata = metadata,
+        body = document_body(document),
+    )
+}
+
+fn meta_tag(name: &str, content: &str) -> String {
+    format!("<meta name=\"{}\" content=\"{}\">\n", name, escape_html(content))
+}
+
+fn document_meta_tags(document: &Document) -> String {
+    let mut tags = meta_tag("generator", GENERATOR);
+    if let Some(author) = document.metadata.author.as_deref().filter(|a| !a.is_empty()) {
+        tags.push_str(&meta_tag("author", author));
+    }
+    if !document.tags.is_empty() {
+        tags.push_str(&meta_tag("keywords", &document.tags.join(", ")));
+    }
+    tags.push_str(&meta_tag("dcterms.created", &format_time(&document.created_at)));
+    tags.push_str(&meta_tag("dcterms.modified", &format_time(&document.updated_at)));
+    tags
+}
+
+pub(crate) fn language_of(document: &Document) -> &str {
+    match document.metadata.language.trim() {
+        "" => DEFAULT_LANGUAGE,
+        lang => lang,
+    }
+}
+
+// 按选项组装最终输出：自定义模板优先，其次是完整 HTML 页面，否则只输出片段
+fn wrap(title: &str, lang: &str, meta_tags: &str, body: &str, options: &HtmlExportOptions) -> String {
+    let style = if options.include_css {
+        format!("<style>\n{}</style>\n", stylesheet())
+    } else {
+        String::new()
+    };
+
+    if let Some(template) = options.custom_template.as_deref().filter(|t| !t.trim().is_empty()) {
+        // 一次替换全部占位符，标题或正文中出现的 {{content}} 等文字不会被再次替换
+        return templates::substitute(template, |placeholder| match placeholder {
+            "title" => Some(escape_html(title)),
+            "lang" => Some(escape_html(lang)),
+            "metadata" => Some(meta_tags.to_string()),
+            "css" => Some(style.clone()),
+            "content" => Some(body.to_string()),
+            _ => None,
+        });
+    }
+
+    if options.standalone {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n{}{}</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(lang),
+            escape_html(title),
+            meta_tags,
+            style,
+            body
+        )
+    } else {
+        format!("{}{}", style, body)
+    }
+}
+
+/// 导出单个文档，附件链接改为本机文件地址
+pub fn document_to_html(document: &Document, options: &HtmlExportOptions) -> String {
+    let article = links::rewrite_html_links(&document_article(document), links::attachment_file_url);
+    wrap(&document.title, language_of(document), &document_meta_tags(document), &article, options)
+}
+
+/// 导出整个项目，documents 需已按目录顺序排好
+pub fn project_to_html(project: &Project, documents: &[Document], options: &HtmlExportOptions) -> String {
+    let mut body = format!(
+        "<main class=\"project\" id=\"project-{}\">\n<header>\n<h1 class=\"project-title\">{}</h1>\n",
+        escape_html(&project.id),
+        escape_html(&project.name)
+    );
+    if !project.description.trim().is_empty() {
+        body.push_str(&format!("<p class=\"project-description\">{}</p>\n", escape_html(&project.description)));
+    }
+    body.push_str("</header>\n");
+
+    if !documents.is_empty() {
+        body.push_str("<nav class=\"project-toc\">\n<ol>\n");
+        for document in documents {
+            body.push_str(&format!(
+                "<li><a href=\"#doc-{}\">{}</a></li>\n",
+                escape_html(&document.id),
+                escape_html(&document.title)
+            ));
+        }
+        body.push_str("</ol>\n</nav>\n");
+    }
+    // 指向本次导出中文档的链接改为页内锚点，附件链接改为本机文件地址
+    let exported: HashSet<&str> = documents.iter().map(|document| document.id.as_str()).collect();
+    let target = |url: &str| match links::document_id(url) {
+        Some(id) if exported.contains(id) => Some(format!("#doc-{}", id)),
+        Some(_) => None,
+        None => links::attachment_file_url(url),
+    };
+    for document in documents {
+        body.push_str(&links::rewrite_html_links(&document_article(document), target));
+    }
+    body.push_str("</main>\n");
+
+    let mut meta_tags = meta_tag("generator", GENERATOR);
+    if !project.description.trim().is_empty() {
+        meta_tags.push_str(&meta_tag("description", &project.description));
+    }
+    meta_tags.push_str(&meta_tag("dcterms.created", &format_time(&project.created_at)));
+    meta_tags.push_str(&meta_tag("dcterms.modified", &format_time(&project.updated_at)));
+
+    let lang = documents.first().map(language_of).unwrap_or(DEFAULT_LANGUAGE);
+    wrap(&project.name, lang, &meta_tags, &body, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::document::CreateDocumentData;
+    use crate::models::project::CreateProjectData;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    const CUSTOM_TEMPLATE: &str = "<html lang=\"{{lang}}\">\n<title>{{title}}</title>\n{{metadata}}{{css}}<div class=\"custom\">\n{{content}}</div>\n</html>\n";
+
+    // 快照位于 tests/snapshots/html，设置 UPDATE_SNAPSHOTS=1 运行测试可重新生成。
+    // 样式表单独存一份快照，其余快照中用占位注释代替，避免每个文件都重复一遍
+    fn assert_snapshot(name: &str, actual: &str) {
+        let actual = if name.ends_with(".css") {
+            actual.to_string()
+        } else {
+            actual.replace(stylesheet(), "/* stylesheet() */\n")
+        };
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/html").join(name);
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("cannot read snapshot {}: {} (run with UPDATE_SNAPSHOTS=1)", path.display(), e));
+        assert_eq!(actual, expected, "snapshot {} differs", name);
+    }
+
+    fn fixed_time(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 8, 30, 0).unwrap()
+    }
+
+    fn document(id: &str, title: &str, content: &str, content_type: DocumentType) -> Document {
+        let mut document = Document::new(CreateDocumentData {
+            title: title.to_string(),
+            content: Some(content.to_string()),
+            content_type,
+            project_id: "project-1".to_string(),
+            folder_path: None,
+            folder_id: None,
+            tags: Some(vec!["草稿".to_string(), "a&b".to_string()]),
+            template_id: None,
+            template_variables: None,
+        });
+        document.id = id.to_string();
+        document.word_count = 42;
+        document.metadata.author = Some("张三".to_string());
+        document.metadata.language = "zh-CN".to_string();
+        document.metadata.version = 3;
+        document.created_at = fixed_time(1);
+        document.updated_at = fixed_time(2);
+        document.last_accessed = fixed_time(2);
+        document
+    }
+
+    fn markdown_document() -> Document {
+        let content = "# 第一章 <起点>\n\n\
+            **粗体**、*斜体*、~~删除线~~ 和 https://example.com 自动链接。\n\n\
+            | 名称 | 数量 |\n| :--- | ---: |\n| 苹果 | 3 |\n\n\
+            - [x] 已完成\n- [ ] 未完成\n\n\
+            正文中的脚注[^1]，另见[纯文本](writeflow://document/doc-2)和[别的项目](writeflow://document/doc-9)。\n\n\
+            ```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+            [^1]: 脚注内容。\n";
+        document("doc-1", "第一章 \"起点\" & <草稿>", content, DocumentType::Markdown)
+    }
+
+    fn project_documents() -> Vec<Document> {
+        vec![
+            markdown_document(),
+            document("doc-2", "纯文本", "第一行 <b>\n第二行 & 结尾", DocumentType::PlainText),
+            document("doc-3", "富文本", "<p>已经是 <em>HTML</em></p>", DocumentType::RichText),
+        ]
+    }
+
+    fn project() -> Project {
+        let mut project = Project::new(CreateProjectData {
+            name: "长篇 <小说>".to_string(),
+            description: "一个 \"测试\" 项目".to_string(),
+            icon: String::new(),
+            color: String::new(),
+            workspace_id: "workspace-1".to_string(),
+            template_id: None,
+        });
+        project.id = "project-1".to_string();
+        project.created_at = fixed_time(1);
+        project.updated_at = fixed_time(3);
+        project
+    }
+
+    // 覆盖 include_css、standalone 和自定义模板的全部组合
+    fn option_sets() -> Vec<(String, HtmlExportOptions)> {
+        let mut sets = Vec::new();
+        for include_css in [false, true] {
+            for standalone in [false, true] {
+                for template in [false, true] {
+                    let name = format!(
+                        "{}-{}-{}",
+                        if include_css { "css" } else { "nocss" },
+                        if standalone { "standalone" } else { "fragment" },
+                        if template { "template" } else { "default" }
+                    );
+                    let options = HtmlExportOptions {
+                        include_css,
+                        standalone,
+                        custom_template: template.then(|| CUSTOM_TEMPLATE.to_string()),
+                    };
+                    sets.push((name, options));
+                }
+            }
+        }
+        sets
+    }
+
+    #[test]
+    fn stylesheet_snapshot() {
+        assert_snapshot("stylesheet.css", stylesheet());
+    }
+
+    #[test]
+    fn document_snapshots() {
+        let document = markdown_document();
+        for (name, options) in option_sets() {
+            let html = document_to_html(&document, &options);
+            assert_eq!(html, document_to_html(&document, &options), "output must be deterministic");
+            assert_snapshot(&format!("document-{}.html", name), &html);
+        }
+    }
+
+    #[test]
+    fn project_snapshots() {
+        let project = project();
+        let documents = project_documents();
+        for (name, options) in option_sets() {
+            let html = project_to_html(&project, &documents, &options);
+            assert_eq!(html, project_to_html(&project, &documents, &options), "output must be deterministic");
+            assert_snapshot(&format!("project-{}.html", name), &html);
+        }
+    }
+
+    #[test]
+    fn blank_template_falls_back_to_default_layout() {
+        let document = markdown_document();
+        let blank = HtmlExportOptions { include_css: false, standalone: true, custom_template: Some("  \n".to_string()) };
+        let default = HtmlExportOptions { include_css: false, standalone: true, custom_template: None };
+        assert_eq!(document_to_html(&document, &blank), document_to_html(&document, &default));
+    }
+
+    #[test]
+    fn template_placeholders_in_title_and_body_are_not_expanded() {
+        let document = document("doc-4", "标题 {{content}}", "正文 {{title}} {{css}}", DocumentType::PlainText);
+        let options = HtmlExportOptions {
+            include_css: false,
+            standalone: false,
+            custom_template: Some("<title>{{title}}</title>{{content}}{{unknown}}".to_string()),
+        };
+        let html = document_to_html(&document, &options);
+        assert!(html.starts_with("<title>标题 {{content}}</title>"), "{}", html);
+        assert!(html.contains("正文 {{title}} {{css}}"), "{}", html);
+        assert!(html.ends_with("{{unknown}}"), "{}", html);
     }
 }
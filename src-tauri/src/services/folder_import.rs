@@ -0,0 +1,278 @@
+// 从已有目录导入项目
+// 先扫描目录生成导入计划（预览到此为止），再按计划创建项目、文件夹和文档。
+// 文档逐个写入，单个文件失败只记入报告；重新导入同一目录时按 id 或“文件夹 + 标题”识别已有文档
+
+use crate::error::AppError;
+use crate::models::document::{Document, DocumentType};
+use crate::models::folder::FolderNode;
+use crate::models::import::{DuplicateStrategy, FolderImportOptions, FolderImportReport, ImportAction, ImportFileResult};
+use crate::models::project::{CreateProjectData, Project};
+use crate::services::database::Database;
+use crate::services::markdown;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_ICON: &str = "folder";
+const DEFAULT_COLOR: &str = "#6b7280";
+
+// 新建项目的名称：优先使用指定的名称，否则取目录名或 zip 文件名
+pub(crate) fn project_name(source: &Path, name: Option<&str>) -> String {
+    name.map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            let name = if source.is_dir() { source.file_name() } else { source.file_stem() };
+            name.map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "导入的项目".to_string())
+}
+
+pub(crate) async fn create_project(database: &Database, workspace_id: &str, name: &str) -> Result<Project> {
+    database
+        .create_project(CreateProjectData {
+            name: name.to_string(),
+            description: String::new(),
+            icon: DEFAULT_ICON.to_string(),
+            color: DEFAULT_COLOR.to_string(),
+            workspace_id: workspace_id.to_string(),
+            template_id: None,
+        })
+        .await
+}
+
+struct PlannedFile {
+    document: Option<Document>,
+    existing: Option<Document>,
+}
+
+/// 导入计划：report 即预览结果，execute 按其中的动作执行
+pub struct FolderImportPlan {
+    pub report: FolderImportReport,
+    folders: Vec<String>,
+    files: Vec<PlannedFile>,
+}
+
+// 与 ensure_folder_path 一致：按 / 或 \ 拆分并去掉空白，比较时不受写法差异影响
+fn normalize_folder_path(path: Option<&str>) -> String {
+    path.unwrap_or_default()
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn collect_folder_paths(prefix: &str, folders: &[FolderNode], paths: &mut HashSet<String>) {
+    for node in folders {
+        let path = format!("{}{}", prefix, node.folder.name.trim());
+        collect_folder_paths(&format!("{}/", path), &node.folders, paths);
+        paths.insert(path);
+    }
+}
+
+fn file_result(document: &Document, path: &str, action: ImportAction) -> ImportFileResult {
+    ImportFileResult {
+        path: path.to_string(),
+        title: document.title.clone(),
+        folder_path: document.folder_path.clone(),
+        content_type: document.content_type.clone(),
+        action,
+        document_id: None,
+        message: None,
+    }
+}
+
+/// 扫描目录并与目标项目比对，不写入任何数据
+pub async fn plan(database: &Database, options: &FolderImportOptions) -> Result<FolderImportPlan> {
+    let source = PathBuf::from(&options.source_path);
+    if !source.is_dir() {
+        return Err(AppError::validation(format!("{} is not a directory", options.source_path)).into());
+    }
+    if database.get_workspace_by_id(&options.workspace_id).await?.is_none() {
+        return Err(AppError::not_found("Workspace", &options.workspace_id).into());
+    }
+
+    let (project_id, project_name, existing, existing_folders) = match &options.project_id {
+        Some(project_id) => {
+            let project = database
+                .get_project_by_id(project_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Project", project_id))?;
+            let tree = database.get_project_tree(project_id).await?;
+            let mut folders = HashSet::new();
+            collect_folder_paths("", &tree.folders, &mut folders);
+            let documents = database.get_project_documents_ordered(project_id).await?;
+            (Some(project.id), project.name, documents, folders)
+        }
+        None => {
+            let name = project_name(&source, options.project_name.as_deref());
+            (None, name, Vec::new(), HashSet::new())
+        }
+    };
+
+    let scan_root = source.clone();
+    let archive = tokio::task::spawn_blocking(move || markdown::read_dir_archive(&scan_root))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    let by_id: HashMap<&str, &Document> = existing.iter().map(|d| (d.id.as_str(), d)).collect();
+    let by_title: HashMap<(String, &str), &Document> = existing
+        .iter()
+        .map(|d| ((normalize_folder_path(d.folder_path.as_deref()), d.title.as_str()), d))
+        .collect();
+
+    let mut report = FolderImportReport {
+        dry_run: true,
+        project_id: project_id.clone(),
+        project_name,
+        new_folders: archive
+            .folders
+            .iter()
+            .map(|folder| normalize_folder_path(Some(folder)))
+            .filter(|folder| !folder.is_empty() && !existing_folders.contains(folder))
+            .collect(),
+        files: Vec::new(),
+        created: 0,
+        updated: 0,
+        skipped: 0,
+        failed: 0,
+    };
+    let mut files = Vec::new();
+
+    let mut documents: Vec<(String, Document)> = archive
+        .files
+        .iter()
+        .map(|file| (file.path.clone(), markdown::file_to_document(file, project_id.as_deref().unwrap_or_default())))
+        .collect();
+    // front matter 中有 order 时按其排列，否则保持文件名顺序
+    documents.sort_by_key(|(_, document)| document.sort_order);
+
+    for (path, document) in documents {
+        let folder_path = normalize_folder_path(document.folder_path.as_deref());
+        let duplicate = by_id
+            .get(document.id.as_str())
+            .or_else(|| by_title.get(&(folder_path, document.title.as_str())))
+            .copied();
+
+        let mut result = file_result(&document, &path, ImportAction::Create);
+        let planned = match duplicate {
+            None => PlannedFile { document: Some(document), existing: None },
+            Some(existing) => {
+                result.document_id = Some(existing.id.clone());
+                if existing.content == document.content && existing.title == document.title {
+                    result.action = ImportAction::Skip;
+                    result.message = Some("Unchanged since the last import".to_string());
+                    PlannedFile { document: None, existing: None }
+                } else {
+                    match options.duplicates {
+                        DuplicateStrategy::Skip => {
+                            result.action = ImportAction::Skip;
+                            result.message = Some(format!("Document \"{}\" already exists", existing.title));
+                            PlannedFile { document: None, existing: None }
+                        }
+                        DuplicateStrategy::Update => {
+                            result.action = ImportAction::Update;
+                            PlannedFile { document: Some(document), existing: Some(existing.clone()) }
+                        }
+                        DuplicateStrategy::KeepBoth => {
+                            result.document_id = None;
+                            PlannedFile { document: Some(document), existing: None }
+                        }
+                    }
+                }
+            }
+        };
+        report.files.push(result);
+        files.push(planned);
+    }
+
+    for (path, reason) in archive.errors {
+        let title = Path::new(&path).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let is_text = Path::new(&path).extension().is_some_and(|e| e.eq_ignore_ascii_case("txt"));
+        report.files.push(ImportFileResult {
+            folder_path: path.rsplit_once('/').map(|(folder, _)| folder.to_string()),
+            path,
+            title,
+            content_type: if is_text { DocumentType::PlainText } else { DocumentType::Markdown },
+            action: ImportAction::Failed,
+            document_id: None,
+            message: Some(reason),
+        });
+        files.push(PlannedFile { document: None, existing: None });
+    }
+
+    report.tally();
+    Ok(FolderImportPlan { report, folders: archive.folders, files })
+}
+
+/// 执行导入计划。progress 在每个文件处理后调用，参数为已处理数、总数和文件路径
+pub async fn execute(
+    database: &Database,
+    options: &FolderImportOptions,
+    plan: FolderImportPlan,
+    progress: impl Fn(u32, u32, &str),
+) -> Result<FolderImportReport> {
+    let FolderImportPlan { mut report, folders, files } = plan;
+    report.dry_run = false;
+
+    let project_id = match report.project_id.clone() {
+        Some(project_id) => project_id,
+        None => {
+            let project = create_project(database, &options.workspace_id, &report.project_name).await?;
+            report.project_id = Some(project.id.clone());
+            project.id
+        }
+    };
+    // 先按目录顺序建好文件夹，空目录也会保留
+    database.import_documents(&project_id, &folders, Vec::new()).await?;
+
+    let total = files.len() as u32;
+    for (index, (result, planned)) in report.files.iter_mut().zip(files).enumerate() {
+        let outcome = match (planned.document, planned.existing) {
+            (Some(document), Some(mut existing)) => {
+                existing.title = document.title;
+                existing.word_count = document.content.split_whitespace().count() as u32;
+                existing.char_count = document.content.chars().count() as u32;
+                existing.content = document.content;
+                existing.status = document.status;
+                existing.tags = document.tags;
+                existing.metadata.author = document.metadata.author.or(existing.metadata.author);
+                existing.metadata.language = document.metadata.language;
+                existing.metadata.properties.extend(document.metadata.properties);
+                database.update_imported_document(&existing.id, existing.clone()).await.map(|_| existing.id)
+            }
+            (Some(mut document), None) => {
+                document.project_id = project_id.clone();
+                database
+                    .import_documents(&project_id, &[], vec![document])
+                    .await
+                    .map(|mut imported| imported.remove(0).id)
+            }
+            _ => Ok(result.document_id.clone().unwrap_or_default()),
+        };
+        match outcome {
+            Ok(document_id) if !document_id.is_empty() => result.document_id = Some(document_id),
+            Ok(_) => {}
+            Err(e) => {
+                result.action = ImportAction::Failed;
+                result.document_id = None;
+                result.message = Some(e.to_string());
+            }
+        }
+        progress(index as u32 + 1, total, &result.path);
+    }
+
+    report.tally();
+    Ok(report)
+}
+
+impl FolderImportReport {
+    fn tally(&mut self) {
+        let count = |action| self.files.iter().filter(|f| f.action == action).count() as u32;
+        self.created = count(ImportAction::Create);
+        self.updated = count(ImportAction::Update);
+        self.skipped = count(ImportAction::Skip);
+        self.failed = count(ImportAction::Failed);
+    }
+}
@@ -0,0 +1,74 @@
+// 项目目标服务
+// 根据目标字数、截止日期和最近的写作速度预测完成时间
+
+use crate::models::project::{GoalStatus, Project, ProjectForecast};
+use crate::models::writing::WritingStats;
+use chrono::{Duration, NaiveDate};
+
+// 计算平均写作速度时参考的天数
+pub const RECENT_WINDOW_DAYS: i64 = 14;
+
+pub fn recent_window_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(RECENT_WINDOW_DAYS - 1)
+}
+
+fn div_ceil(value: u32, divisor: u32) -> u32 {
+    value.div_ceil(divisor.max(1))
+}
+
+/// `recent` 为最近 RECENT_WINDOW_DAYS 天的按日统计
+pub fn forecast(project: &Project, recent: &WritingStats, today: NaiveDate) -> ProjectForecast {
+    let average_words_per_day = recent.net_words.max(0) as f64 / RECENT_WINDOW_DAYS as f64;
+    let remaining_words = project
+        .target_word_count
+        .map(|target| target.saturating_sub(project.words_count));
+
+    // 包含今天在内距离截止日期的天数，已过期时为负数
+    let days_remaining = project.deadline.map(|deadline| (deadline - today).num_days() + 1);
+
+    let required_words_per_day = match (remaining_words, days_remaining) {
+        (Some(remaining), Some(days)) if days > 0 => Some(div_ceil(remaining, days as u32)),
+        (Some(remaining), Some(_)) => Some(remaining),
+        _ => None,
+    };
+
+    let projected_completion_date = match remaining_words {
+        Some(0) => Some(today),
+        Some(remaining) if average_words_per_day > 0.0 => {
+            Some(today + Duration::days((remaining as f64 / average_words_per_day).ceil() as i64))
+        }
+        _ => None,
+    };
+
+    let behind_quota = project
+        .daily_word_quota
+        .is_some_and(|quota| average_words_per_day < quota as f64);
+
+    let status = match (remaining_words, project.deadline) {
+        (Some(0), _) => GoalStatus::Completed,
+        (None, None) => GoalStatus::NoGoal,
+        (_, Some(deadline)) if deadline < today => GoalStatus::Overdue,
+        (Some(_), Some(deadline)) => match projected_completion_date {
+            Some(projected) if projected <= deadline && !behind_quota => GoalStatus::OnTrack,
+            _ => GoalStatus::AtRisk,
+        },
+        _ if behind_quota => GoalStatus::AtRisk,
+        _ => GoalStatus::OnTrack,
+    };
+
+    ProjectForecast {
+        project_id: project.id.clone(),
+        project_name: project.name.clone(),
+        progress: project.progress,
+        words_count: project.words_count,
+        target_word_count: project.target_word_count,
+        remaining_words,
+        deadline: project.deadline,
+        days_remaining,
+        daily_word_quota: project.daily_word_quota,
+        required_words_per_day,
+        average_words_per_day,
+        projected_completion_date,
+        status,
+    }
+}
@@ -0,0 +1,102 @@
+// 应用内部链接
+// 导入时改写的链接使用 writeflow:// 协议：writeflow://document/<id>[#标题] 指向文档，
+// writeflow://attachment/<相对路径> 指向附件目录中的文件。附件只存相对于附件目录的路径，数据目录搬迁后链接仍然有效
+
+use crate::error::AppError;
+use crate::services::database::Database;
+use crate::services::vault_import::percent_decode;
+use anyhow::Result;
+use base64::Engine;
+use std::path::{Component, Path, PathBuf};
+
+pub const DOCUMENT_LINK_PREFIX: &str = "writeflow://document/";
+pub const ATTACHMENT_LINK_PREFIX: &str = "writeflow://attachment/";
+
+pub fn document_url(id: &str, anchor: Option<&str>) -> String {
+    match anchor.filter(|anchor| !anchor.is_empty()) {
+        Some(anchor) => format!("{}{}#{}", DOCUMENT_LINK_PREFIX, id, anchor),
+        None => format!("{}{}", DOCUMENT_LINK_PREFIX, id),
+    }
+}
+
+/// relative 为附件目录下的相对路径，使用 / 分隔
+pub fn attachment_url(relative: &str) -> String {
+    format!("{}{}", ATTACHMENT_LINK_PREFIX, relative)
+}
+
+/// 文档链接指向的文档 id，忽略 #标题
+pub fn document_id(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix(DOCUMENT_LINK_PREFIX)?;
+    let id = rest.split(['#', '?']).next().unwrap_or_default();
+    Some(id).filter(|id| !id.is_empty())
+}
+
+/// 附件链接对应的本地文件；不是附件链接时返回 None，路径跳出附件目录时报错
+pub fn attachment_path(url: &str) -> Result<Option<PathBuf>> {
+    let Some(relative) = url.strip_prefix(ATTACHMENT_LINK_PREFIX) else { return Ok(None) };
+    let relative = percent_decode(relative);
+    let relative = Path::new(&relative);
+    if relative.as_os_str().is_empty() || relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(AppError::validation(format!("Invalid attachment link: {}", url)).into());
+    }
+    Ok(Some(Database::attachments_directory()?.join(relative)))
+}
+
+/// 读取附件并转成 data URI，供编辑器预览
+pub fn attachment_data_uri(url: &str) -> Result<String> {
+    let path = attachment_path(url)?.ok_or_else(|| AppError::validation(format!("Not an attachment link: {}", url)))?;
+    let bytes = std::fs::read(&path).map_err(|_| AppError::not_found("Attachment", url))?;
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "avif" => "image/avif",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    };
+    Ok(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+/// 附件在本机的 file:// 地址，HTML 导出中用来代替 writeflow:// 链接
+pub fn attachment_file_url(url: &str) -> Option<String> {
+    let path = attachment_path(url).ok()??;
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut encoded = String::from("file://");
+    // Windows 盘符路径需要多一个 /
+    if !path.starts_with('/') {
+        encoded.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    Some(encoded)
+}
+
+/// 改写 HTML 中 href 和 src 属性里的 writeflow:// 地址，target 返回 None 时保持原样
+pub fn rewrite_html_links(html: &str, target: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = ["href=\"", "src=\""]
+        .iter()
+        .filter_map(|attribute| rest.find(&format!("{}writeflow://", attribute)).map(|index| index + attribute.len()))
+        .min()
+    {
+        let Some(length) = rest[start..].find('"') else { break };
+        let url = &rest[start..start + length];
+        out.push_str(&rest[..start]);
+        match target(url) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(url),
+        }
+        rest = &rest[start + length..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,800 @@
+// Markdown 归档导出与导入
+// 项目导出为与目录树同构的 .md/.txt 文件（zip 或目录），每个文件可带 YAML front matter，
+// 记录 id、标题、状态、标签、作者和时间戳等，导入时据此还原文档。
+// front matter 只用到扁平的键值和字符串列表，这里自带一个够用的小解析器，不引入完整的 YAML 库。
+// 文件夹名在文件系统中可能需要替换字符，目录也只能按文件名排序，所以另写一份文件夹清单记录原名和顺序
+
+use crate::models::config::MarkdownExportOptions;
+use crate::models::document::{CreateDocumentData, Document, DocumentStatus, DocumentType};
+use crate::models::folder::{FolderNode, ProjectTree, TreeDocument};
+use crate::utils::sanitize_filename;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const MARKDOWN_EXTENSIONS: [&str; 3] = ["md", "markdown", "txt"];
+
+// 文件夹清单，放在归档根目录，只在附带元数据导出时写出
+const FOLDER_MANIFEST: &str = ".writeflow-folders.json";
+
+// apply_front_matter 识别的字段，其余字段存入 metadata.properties
+const KNOWN_KEYS: [&str; 15] = [
+    "id", "title", "type", "status", "tags", "author", "language", "lang", "order", "line_ending", "created_at",
+    "created", "date", "updated_at", "updated",
+];
+
+/// 归档中的一个文件，path 使用 / 分隔的相对路径
+#[derive(Debug, Clone)]
+pub struct MarkdownFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// 文件夹清单中的一项：归档中的路径、原始名称和排序值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderEntry {
+    pub path: String,
+    pub name: String,
+    pub sort_order: i64,
+}
+
+/// 归档内容：按目录树顺序排列的文件夹路径（包括空文件夹）和文档文件
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownArchive {
+    pub folders: Vec<String>,
+    pub files: Vec<MarkdownFile>,
+    // 读取失败的文件：(路径, 原因)，不影响其他文件
+    pub errors: Vec<(String, String)>,
+    // 文件夹清单；读取时已据此还原 folders 和文件路径中的文件夹名
+    pub manifest: Vec<FolderEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontMatterValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+pub type FrontMatter = BTreeMap<String, FrontMatterValue>;
+
+fn uses_crlf(options: &MarkdownExportOptions) -> bool {
+    options.line_ending.eq_ignore_ascii_case("CRLF")
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+// 一律输出双引号字符串，避免标题中的冒号、井号等被当成 YAML 语法
+fn yaml_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn render_front_matter(document: &Document) -> String {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("id: {}\n", yaml_string(&document.id)));
+    out.push_str(&format!("title: {}\n", yaml_string(&document.title)));
+    if !matches!(document.content_type, DocumentType::Markdown) {
+        out.push_str(&format!("type: {:?}\n", document.content_type));
+    }
+    out.push_str(&format!("status: {:?}\n", document.status));
+    let tags: Vec<String> = document.tags.iter().map(|tag| yaml_string(tag)).collect();
+    out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    if let Some(author) = &document.metadata.author {
+        out.push_str(&format!("author: {}\n", yaml_string(author)));
+    }
+    if !document.metadata.language.is_empty() {
+        out.push_str(&format!("language: {}\n", yaml_string(&document.metadata.language)));
+    }
+    // 文件换行符由导出选项决定，正文原本的 CRLF 换行单独记下
+    if document.content.contains("\r\n") {
+        out.push_str("line_ending: CRLF\n");
+    }
+    out.push_str(&format!("order: {}\n", document.sort_order));
+    for (key, value) in &document.metadata.properties {
+        let plain = !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        let key = if plain { key.clone() } else { yaml_string(key) };
+        out.push_str(&format!("{}: {}\n", key, yaml_string(value)));
+    }
+    out.push_str(&format!("created_at: {}\n", format_time(&document.created_at)));
+    out.push_str(&format!("updated_at: {}\n", format_time(&document.updated_at)));
+    out.push_str("---\n");
+    out
+}
+
+/// 单个文档的 Markdown 文本，按配置附带 front matter 并转换换行符
+pub fn document_to_markdown(document: &Document, options: &MarkdownExportOptions) -> String {
+    let mut text = String::new();
+    if options.include_metadata {
+        text.push_str(&render_front_matter(document));
+    }
+    text.push_str(&document.content.replace("\r\n", "\n"));
+    if uses_crlf(options) {
+        text = text.replace('\n', "\r\n");
+    }
+    text
+}
+
+fn document_file_name(document: &Document) -> String {
+    let stem = sanitize_filename(document.title.trim());
+    // 去掉开头的点，避免生成隐藏文件
+    let stem = stem.trim_start_matches('.');
+    let stem = if stem.is_empty() { "未命名文档" } else { stem };
+    let extension = if matches!(document.content_type, DocumentType::PlainText) { "txt" } else { "md" };
+    format!("{}.{}", stem, extension)
+}
+
+// 同一目录下重名时追加序号；大小写不敏感的文件系统上也不会互相覆盖
+fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    if taken.insert(name.to_lowercase()) {
+        return name;
+    }
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name.as_str(), ""));
+    (2..)
+        .map(|n| {
+            if extension.is_empty() {
+                format!("{} ({})", stem, n)
+            } else {
+                format!("{} ({}).{}", stem, n, extension)
+            }
+        })
+        .find(|candidate| taken.insert(candidate.to_lowercase()))
+        .unwrap_or_default()
+}
+
+struct ArchiveBuilder<'a> {
+    documents: HashMap<&'a str, &'a Document>,
+    used: HashSet<&'a str>,
+    options: &'a MarkdownExportOptions,
+    archive: MarkdownArchive,
+}
+
+impl<'a> ArchiveBuilder<'a> {
+    fn collect(&mut self, prefix: &str, tree_documents: &[TreeDocument], folders: &[FolderNode]) {
+        let mut taken = HashSet::new();
+        for folder in folders {
+            let name = sanitize_filename(folder.folder.name.trim());
+            let name = unique_name(if name.is_empty() { "未命名文件夹".to_string() } else { name }, &mut taken);
+            let path = format!("{}{}", prefix, name);
+            self.archive.folders.push(path.clone());
+            if self.options.include_metadata {
+                self.archive.manifest.push(FolderEntry {
+                    path: path.clone(),
+                    name: folder.folder.name.trim().to_string(),
+                    sort_order: folder.folder.sort_order,
+                });
+            }
+            self.collect(&format!("{}/", path), &folder.documents, &folder.folders);
+        }
+        for tree_document in tree_documents {
+            if let Some(document) = self.documents.get(tree_document.id.as_str()).copied() {
+                if self.used.insert(document.id.as_str()) {
+                    self.push(prefix, document, &mut taken);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, prefix: &str, document: &Document, taken: &mut HashSet<String>) {
+        let name = unique_name(document_file_name(document), taken);
+        self.archive.files.push(MarkdownFile {
+            path: format!("{}{}", prefix, name),
+            contents: document_to_markdown(document, self.options),
+        });
+    }
+}
+
+/// 按目录树生成项目的 Markdown 归档；目录树之外的文档放在根目录
+pub fn project_to_markdown_archive(
+    tree: &ProjectTree,
+    documents: &[Document],
+    options: &MarkdownExportOptions,
+) -> MarkdownArchive {
+    let mut builder = ArchiveBuilder {
+        documents: documents.iter().map(|d| (d.id.as_str(), d)).collect(),
+        used: HashSet::new(),
+        options,
+        archive: MarkdownArchive::default(),
+    };
+    builder.collect("", &tree.documents, &tree.folders);
+
+    let mut taken: HashSet<String> = builder
+        .archive
+        .folders
+        .iter()
+        .chain(builder.archive.files.iter().map(|file| &file.path))
+        .filter(|path| !path.contains('/'))
+        .map(|path| path.to_lowercase())
+        .collect();
+    for document in documents {
+        if !builder.used.contains(document.id.as_str()) {
+            builder.push("", document, &mut taken);
+        }
+    }
+    builder.archive
+}
+
+/// 打包为 zip；文件夹单独写入目录条目，空文件夹也能保留
+pub fn archive_to_zip(archive: &MarkdownArchive) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for folder in &archive.folders {
+        zip.add_directory(format!("{}/", folder), options)?;
+    }
+    for file in &archive.files {
+        zip.start_file(file.path.as_str(), options)?;
+        zip.write_all(file.contents.as_bytes())?;
+    }
+    if !archive.manifest.is_empty() {
+        zip.start_file(FOLDER_MANIFEST, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&archive.manifest)?)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// 写出为目录树
+pub fn archive_to_dir(archive: &MarkdownArchive, dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for folder in &archive.folders {
+        std::fs::create_dir_all(dir.join(folder))?;
+    }
+    for file in &archive.files {
+        std::fs::write(dir.join(&file.path), file.contents.as_bytes())?;
+    }
+    if !archive.manifest.is_empty() {
+        std::fs::write(dir.join(FOLDER_MANIFEST), serde_json::to_vec_pretty(&archive.manifest)?)?;
+    }
+    Ok(())
+}
+
+fn is_markdown_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| MARKDOWN_EXTENSIONS.iter().any(|m| e.eq_ignore_ascii_case(m)))
+}
+
+fn is_hidden(path: &str) -> bool {
+    path.split('/').any(|part| part.starts_with('.') || part == "__MACOSX")
+}
+
+fn bytes_to_text(bytes: Vec<u8>) -> Result<String> {
+    let text = String::from_utf8(bytes).map_err(|_| anyhow!("File is not valid UTF-8 text"))?;
+    Ok(text.trim_start_matches('\u{feff}').replace("\r\n", "\n"))
+}
+
+impl MarkdownArchive {
+    fn push_file(&mut self, path: String, contents: Result<String>) {
+        match contents {
+            Ok(contents) => self.files.push(MarkdownFile { path, contents }),
+            Err(e) => self.errors.push((path, e.to_string())),
+        }
+    }
+
+    // 清单损坏时忽略，仍按文件名还原
+    fn read_manifest(&mut self, bytes: &[u8]) {
+        self.manifest = serde_json::from_slice(bytes).unwrap_or_default();
+    }
+
+    // 按清单把文件夹改回原名、按原顺序排列；清单之外的文件夹保持文件名顺序排在同级之后。
+    // 原名中含路径分隔符时无法用路径表示，沿用归档中的名称
+    fn restore_folders(&mut self) {
+        if self.manifest.is_empty() {
+            return;
+        }
+        let entries: HashMap<&str, &FolderEntry> = self.manifest.iter().map(|entry| (entry.path.as_str(), entry)).collect();
+        let prefixes = |path: &str| -> Vec<String> {
+            let parts: Vec<&str> = path.split('/').collect();
+            (1..=parts.len()).map(|n| parts[..n].join("/")).collect()
+        };
+        let sort_key = |path: &String| -> Vec<i64> {
+            prefixes(path)
+                .iter()
+                .map(|prefix| entries.get(prefix.as_str()).map_or(i64::MAX, |entry| entry.sort_order))
+                .collect()
+        };
+        let rename = |path: &str| -> String {
+            let names: Vec<&str> = prefixes(path)
+                .iter()
+                .zip(path.split('/'))
+                .map(|(prefix, part)| {
+                    entries
+                        .get(prefix.as_str())
+                        .map(|entry| entry.name.as_str())
+                        .filter(|name| !name.is_empty() && !name.contains(['/', '\\']))
+                        .unwrap_or(part)
+                })
+                .collect();
+            names.join("/")
+        };
+
+        self.folders.sort_by_cached_key(sort_key);
+        self.folders = self.folders.iter().map(|path| rename(path)).collect();
+        for file in &mut self.files {
+            file.path = rename(&file.path);
+        }
+        for (path, _) in &mut self.errors {
+            *path = rename(path);
+        }
+    }
+}
+
+/// 读取 zip 中的 Markdown 和文本文件，跳过隐藏文件和越出归档根目录的路径；单个文件读取失败记入 errors
+pub fn read_zip_archive(bytes: &[u8]) -> Result<MarkdownArchive> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+    let mut archive = MarkdownArchive::default();
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let Some(path) = entry.enclosed_name() else { continue };
+        let path: Vec<String> = path.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        let path = path.join("/");
+        if path == FOLDER_MANIFEST {
+            let mut bytes = Vec::new();
+            if entry.read_to_end(&mut bytes).is_ok() {
+                archive.read_manifest(&bytes);
+            }
+            continue;
+        }
+        if path.is_empty() || is_hidden(&path) {
+            continue;
+        }
+        if entry.is_dir() {
+            archive.folders.push(path);
+        } else if is_markdown_file(&path) {
+            let mut bytes = Vec::new();
+            let read = entry.read_to_end(&mut bytes).map_err(anyhow::Error::from);
+            archive.push_file(path, read.and_then(|_| bytes_to_text(bytes)));
+        }
+    }
+    archive.restore_folders();
+    Ok(archive)
+}
+
+fn walk_dir(root: &Path, prefix: &str, archive: &mut MarkdownArchive) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(root.join(prefix))?.collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = format!("{}{}", prefix, name);
+        if is_hidden(&name) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            archive.folders.push(path.clone());
+            walk_dir(root, &format!("{}/", path), archive)?;
+        } else if is_markdown_file(&path) {
+            let read = std::fs::read(entry.path()).map_err(anyhow::Error::from);
+            archive.push_file(path, read.and_then(bytes_to_text));
+        }
+    }
+    Ok(())
+}
+
+/// 读取目录树中的 Markdown 和文本文件，按文件名排序，有文件夹清单时按清单还原；单个文件读取失败记入 errors
+pub fn read_dir_archive(dir: &Path) -> Result<MarkdownArchive> {
+    let mut archive = MarkdownArchive::default();
+    walk_dir(dir, "", &mut archive)?;
+    if let Ok(bytes) = std::fs::read(dir.join(FOLDER_MANIFEST)) {
+        archive.read_manifest(&bytes);
+    }
+    archive.restore_folders();
+    Ok(archive)
+}
+
+fn parse_quoted(value: &str) -> Option<(String, &str)> {
+    let mut chars = value.char_indices();
+    let quote = chars.next()?.1;
+    let mut out = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' if quote == '"' => return Some((out, &value[index + 1..])),
+            '\'' if quote == '\'' => {
+                // 单引号字符串中 '' 表示一个单引号
+                if value[index + 1..].starts_with('\'') {
+                    chars.next();
+                    out.push('\'');
+                } else {
+                    return Some((out, &value[index + 1..]));
+                }
+            }
+            '\\' if quote == '"' => match chars.next()?.1 {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                '0' => out.push('\0'),
+                'u' => {
+                    let start = index + 2;
+                    let code = value.get(start..start + 4)?;
+                    out.push(char::from_u32(u32::from_str_radix(code, 16).ok()?)?);
+                    for _ in 0..4 {
+                        chars.next();
+                    }
+                }
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn parse_scalar(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('"') || value.starts_with('\'') {
+        if let Some((text, _)) = parse_quoted(value) {
+            return text;
+        }
+    }
+    // 未加引号的值中，空格后的 # 开始注释
+    let value = match value.find(" #") {
+        Some(index) => &value[..index],
+        None => value,
+    };
+    value.trim().to_string()
+}
+
+fn parse_flow_list(value: &str) -> Vec<String> {
+    let inner = value.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut items = Vec::new();
+    let mut rest = inner.trim_start();
+    while !rest.is_empty() {
+        let (item, remaining) = if rest.starts_with('"') || rest.starts_with('\'') {
+            match parse_quoted(rest) {
+                Some((text, remaining)) => (text, remaining),
+                None => (parse_scalar(rest), ""),
+            }
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            (parse_scalar(&rest[..end]), &rest[end..])
+        };
+        if !item.is_empty() {
+            items.push(item);
+        }
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+    items
+}
+
+fn is_null(value: &str) -> bool {
+    matches!(value, "~" | "null" | "Null" | "NULL")
+}
+
+fn parse_front_matter(block: &str) -> FrontMatter {
+    let mut map = FrontMatter::new();
+    let lines: Vec<&str> = block.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') || line.starts_with([' ', '\t', '-']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = parse_scalar(key);
+        let value = value.trim();
+        if key.is_empty() || is_null(value) {
+            continue;
+        }
+
+        // 缩进的后续行：块列表或 | > 多行文本；其他嵌套结构忽略
+        let start = index;
+        while index < lines.len() && (lines[index].starts_with([' ', '\t']) || lines[index].trim().is_empty()) {
+            index += 1;
+        }
+        let nested: Vec<&str> = lines[start..index].iter().map(|l| l.trim()).collect();
+
+        let parsed = if value.starts_with('[') {
+            FrontMatterValue::List(parse_flow_list(value))
+        } else if value.starts_with('|') || value.starts_with('>') {
+            let separator = if value.starts_with('|') { "\n" } else { " " };
+            let text: Vec<&str> = nested.iter().copied().filter(|l| !l.is_empty()).collect();
+            FrontMatterValue::Text(text.join(separator))
+        } else if value.is_empty() {
+            // 紧跟同级的 "- item" 行也视为块列表
+            while index < lines.len() && lines[index].starts_with('-') {
+                index += 1;
+            }
+            let items = lines[start..index]
+                .iter()
+                .map(|l| l.trim())
+                .filter_map(|l| l.strip_prefix('-'))
+                .map(parse_scalar)
+                .filter(|item| !item.is_empty() && !is_null(item))
+                .collect();
+            FrontMatterValue::List(items)
+        } else {
+            FrontMatterValue::Text(parse_scalar(value))
+        };
+        map.insert(key, parsed);
+    }
+    map
+}
+
+/// 拆出开头的 YAML front matter（--- 到 --- 或 ...），返回解析结果和正文；没有时返回空表和原文
+pub fn split_front_matter(text: &str) -> (FrontMatter, &str) {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (FrontMatter::new(), text);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return (parse_front_matter(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (FrontMatter::new(), text)
+}
+
+impl FrontMatterValue {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            FrontMatterValue::Text(text) if !text.is_empty() => Some(text),
+            _ => None,
+        }
+    }
+
+    // 标签既可以写成列表，也可以写成逗号或空格分隔的字符串
+    pub fn to_list(&self) -> Vec<String> {
+        match self {
+            FrontMatterValue::List(items) => items.clone(),
+            FrontMatterValue::Text(text) => text
+                .split([',', ' '])
+                .map(|item| item.trim().trim_start_matches('#'))
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+pub fn parse_status(value: &str) -> Option<DocumentStatus> {
+    let normalized: String = value.chars().filter(|c| !matches!(c, '_' | '-' | ' ')).collect::<String>().to_lowercase();
+    match normalized.as_str() {
+        "draft" | "草稿" => Some(DocumentStatus::Draft),
+        "inprogress" | "wip" | "doing" | "进行中" | "写作中" => Some(DocumentStatus::InProgress),
+        "review" | "inreview" | "审阅" | "审阅中" => Some(DocumentStatus::Review),
+        "final" | "done" | "published" | "定稿" | "完成" => Some(DocumentStatus::Final),
+        _ => None,
+    }
+}
+
+fn parse_content_type(value: &str) -> Option<DocumentType> {
+    match value.to_lowercase().as_str() {
+        "markdown" => Some(DocumentType::Markdown),
+        "plaintext" | "text" => Some(DocumentType::PlainText),
+        "richtext" | "html" => Some(DocumentType::RichText),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc())
+        })
+}
+
+/// 用 front matter 覆盖文档字段：id、title、type、status、tags、author、language、order 和时间戳，
+/// 无法识别的值保持原样，其他字段存入 metadata.properties
+pub fn apply_front_matter(document: &mut Document, front_matter: &FrontMatter) {
+    let text = |key: &str| front_matter.get(key).and_then(FrontMatterValue::as_text);
+    if let Some(id) = text("id") {
+        document.id = id.to_string();
+    }
+    if let Some(title) = text("title").map(str::trim).filter(|t| !t.is_empty()) {
+        document.title = title.to_string();
+    }
+    if let Some(content_type) = text("type").and_then(parse_content_type) {
+        document.content_type = content_type;
+    }
+    if let Some(status) = text("status").and_then(parse_status) {
+        document.status = status;
+    }
+    if let Some(tags) = front_matter.get("tags") {
+        document.tags = tags.to_list();
+    }
+    if let Some(author) = text("author") {
+        document.metadata.author = Some(author.to_string());
+    }
+    if let Some(language) = text("language").or_else(|| text("lang")) {
+        document.metadata.language = language.to_string();
+    }
+    if let Some(order) = text("order").and_then(|o| o.parse().ok()) {
+        document.sort_order = order;
+    }
+    if let Some(created_at) = text("created_at").or_else(|| text("created")).or_else(|| text("date")).and_then(parse_time) {
+        document.created_at = created_at;
+    }
+    if let Some(updated_at) = text("updated_at").or_else(|| text("updated")).and_then(parse_time) {
+        document.updated_at = updated_at;
+        document.last_accessed = updated_at;
+    }
+    for (key, value) in front_matter.iter().filter(|(key, _)| !KNOWN_KEYS.contains(&key.as_str())) {
+        let value = match value {
+            FrontMatterValue::Text(text) => text.clone(),
+            FrontMatterValue::List(items) => items.join(", "),
+        };
+        if !value.is_empty() {
+            document.metadata.properties.insert(key.clone(), value);
+        }
+    }
+}
+
+/// 把归档中的文件还原为文档：目录成为 folder_path，.txt 默认为纯文本，front matter 中的字段优先
+pub fn file_to_document(file: &MarkdownFile, project_id: &str) -> Document {
+    let path = Path::new(&file.path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("未命名文档").to_string();
+    let folder_path = file.path.rsplit_once('/').map(|(folder, _)| folder.to_string());
+    let is_text = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("txt"));
+
+    let (front_matter, body) = split_front_matter(&file.contents);
+    let mut document = Document::new(CreateDocumentData {
+        title: stem,
+        content: Some(body.to_string()),
+        content_type: if is_text { DocumentType::PlainText } else { DocumentType::Markdown },
+        project_id: project_id.to_string(),
+        folder_path,
+        folder_id: None,
+        tags: None,
+        template_id: None,
+        template_variables: None,
+    });
+    apply_front_matter(&mut document, &front_matter);
+    // 读取时换行已统一为 LF，正文原本是 CRLF 的改回来
+    if front_matter.get("line_ending").and_then(FrontMatterValue::as_text).is_some_and(|e| e.eq_ignore_ascii_case("CRLF")) {
+        document.content = document.content.replace('\n', "\r\n");
+    }
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::folder::Folder;
+
+    fn folder(id: &str, name: &str, sort_order: i64, folders: Vec<FolderNode>, documents: Vec<&Document>) -> FolderNode {
+        FolderNode {
+            folder: Folder {
+                id: id.to_string(),
+                project_id: "project-1".to_string(),
+                parent_id: None,
+                name: name.to_string(),
+                sort_order,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            folders,
+            documents: documents.into_iter().map(tree_document).collect(),
+        }
+    }
+
+    fn tree_document(document: &Document) -> TreeDocument {
+        TreeDocument {
+            id: document.id.clone(),
+            title: document.title.clone(),
+            status: document.status.clone(),
+            word_count: 0,
+            sort_order: document.sort_order,
+            updated_at: document.updated_at,
+        }
+    }
+
+    fn document(title: &str, content: &str, folder_path: Option<&str>, sort_order: i64) -> Document {
+        let mut document = Document::new(CreateDocumentData {
+            title: title.to_string(),
+            content: Some(content.to_string()),
+            content_type: DocumentType::Markdown,
+            project_id: "project-1".to_string(),
+            folder_path: folder_path.map(str::to_string),
+            folder_id: None,
+            tags: None,
+            template_id: None,
+            template_variables: None,
+        });
+        document.sort_order = sort_order;
+        document
+    }
+
+    // 手动排在前面的文件夹名按字母排在后面，名称中的冒号在文件名里会被替换
+    fn project() -> (ProjectTree, Vec<Document>) {
+        let documents = vec![
+            document("开端", "第一行\r\n第二行\r\n", Some("Zeta/Part 1: Origins"), 0),
+            document("附录", "只有 LF\n", Some("Appendix"), 0),
+        ];
+        let tree = ProjectTree {
+            project_id: "project-1".to_string(),
+            folders: vec![
+                folder(
+                    "folder-zeta",
+                    "Zeta",
+                    0,
+                    vec![
+                        folder("folder-origins", "Part 1: Origins", 0, Vec::new(), vec![&documents[0]]),
+                        folder("folder-later", "Later", 1, Vec::new(), Vec::new()),
+                    ],
+                    Vec::new(),
+                ),
+                folder("folder-appendix", "Appendix", 1, Vec::new(), vec![&documents[1]]),
+            ],
+            documents: Vec::new(),
+        };
+        (tree, documents)
+    }
+
+    fn options(line_ending: &str) -> MarkdownExportOptions {
+        MarkdownExportOptions { format: "markdown".to_string(), include_metadata: true, line_ending: line_ending.to_string() }
+    }
+
+    fn assert_restored(archive: &MarkdownArchive, documents: &[Document]) {
+        assert_eq!(archive.folders, ["Zeta", "Zeta/Part 1: Origins", "Zeta/Later", "Appendix"]);
+        let imported: Vec<Document> = archive.files.iter().map(|file| file_to_document(file, "project-2")).collect();
+        for original in documents {
+            let document = imported.iter().find(|d| d.id == original.id).expect("document is imported");
+            assert_eq!(document.title, original.title);
+            assert_eq!(document.content, original.content);
+            assert_eq!(document.folder_path, original.folder_path);
+        }
+    }
+
+    #[test]
+    fn zip_round_trip_keeps_folder_names_order_and_line_endings() {
+        let (tree, documents) = project();
+        for line_ending in ["LF", "CRLF"] {
+            let archive = project_to_markdown_archive(&tree, &documents, &options(line_ending));
+            assert!(archive.folders.contains(&"Zeta/Part 1_ Origins".to_string()));
+            let restored = read_zip_archive(&archive_to_zip(&archive).unwrap()).unwrap();
+            assert_restored(&restored, &documents);
+        }
+    }
+
+    #[test]
+    fn directory_round_trip_keeps_folder_names_and_order() {
+        let (tree, documents) = project();
+        let archive = project_to_markdown_archive(&tree, &documents, &options("LF"));
+        let dir = std::env::temp_dir().join(format!("writeflow-markdown-{}", uuid::Uuid::new_v4()));
+        archive_to_dir(&archive, &dir).unwrap();
+        let restored = read_dir_archive(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_restored(&restored.unwrap(), &documents);
+    }
+
+    #[test]
+    fn directory_without_manifest_uses_file_names() {
+        let (tree, documents) = project();
+        let mut options = options("LF");
+        options.include_metadata = false;
+        let archive = project_to_markdown_archive(&tree, &documents, &options);
+        assert!(archive.manifest.is_empty());
+        let dir = std::env::temp_dir().join(format!("writeflow-markdown-{}", uuid::Uuid::new_v4()));
+        archive_to_dir(&archive, &dir).unwrap();
+        let restored = read_dir_archive(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(restored.unwrap().folders, ["Appendix", "Zeta", "Zeta/Later", "Zeta/Part 1_ Origins"]);
+    }
+}
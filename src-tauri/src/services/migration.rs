@@ -0,0 +1,913 @@
+// 数据库迁移模块
+// 按版本号顺序执行增量迁移，每个迁移在独立事务中运行
+
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+type StepFuture<'c> = Pin<Box<dyn Future<Output = Result<(), sqlx::Error>> + Send + 'c>>;
+
+pub enum Step {
+    Sql(&'static str),
+    // 需要在 Rust 中处理数据的步骤，与其他步骤在同一事务中执行
+    Code(for<'c> fn(&'c mut SqliteConnection) -> StepFuture<'c>),
+    // SQLite 不支持 ADD COLUMN IF NOT EXISTS，执行前先检查列是否已存在
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("Failed to back up database before migrating: {0}")]
+    Backup(#[source] sqlx::Error),
+    #[error("Migration {version} ({name}) failed: {source}. Database was rolled back; a backup taken before migrating is at {}", backup.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "<none>".to_string()))]
+    Failed {
+        version: i64,
+        name: &'static str,
+        backup: Option<PathBuf>,
+        #[source]
+        source: sqlx::Error,
+    },
+    #[error("Database schema version {current} is newer than this build supports ({supported}). Please update the application")]
+    TooNew { current: i64, supported: i64 },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS workspaces (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    projects_count INTEGER NOT NULL DEFAULT 0,
+                    status TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    last_accessed TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS projects (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    icon TEXT NOT NULL,
+                    color TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    progress INTEGER NOT NULL DEFAULT 0,
+                    documents_count INTEGER NOT NULL DEFAULT 0,
+                    words_count INTEGER NOT NULL DEFAULT 0,
+                    workspace_id TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY (workspace_id) REFERENCES workspaces (id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS documents (
+                    id TEXT PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    content_type TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    word_count INTEGER NOT NULL DEFAULT 0,
+                    char_count INTEGER NOT NULL DEFAULT 0,
+                    project_id TEXT NOT NULL,
+                    folder_path TEXT,
+                    tags TEXT, -- JSON array
+                    metadata TEXT NOT NULL, -- JSON
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    last_accessed TEXT NOT NULL,
+                    FOREIGN KEY (project_id) REFERENCES projects (id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS config (
+                    id INTEGER PRIMARY KEY,
+                    config_data TEXT NOT NULL, -- JSON
+                    updated_at TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS agents (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    category TEXT NOT NULL,
+                    version TEXT NOT NULL,
+                    enabled INTEGER NOT NULL DEFAULT 1,
+                    description TEXT,
+                    tags TEXT NOT NULL -- JSON array
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS ai_providers (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    model_name TEXT NOT NULL,
+                    api_key TEXT NOT NULL,
+                    base_url TEXT,
+                    icon TEXT NOT NULL,
+                    bg_color TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    status_text TEXT NOT NULL,
+                    max_tokens INTEGER NOT NULL,
+                    context_length INTEGER NOT NULL,
+                    last_tested TEXT NOT NULL,
+                    description TEXT,
+                    priority INTEGER NOT NULL
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "ai_providers_model_pointer",
+        steps: &[Step::AddColumn {
+            table: "ai_providers",
+            column: "model_pointer",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 3,
+        name: "document_revisions",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS revision_blobs (
+                    hash TEXT PRIMARY KEY,
+                    content TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS document_revisions (
+                    id TEXT PRIMARY KEY,
+                    document_id TEXT NOT NULL,
+                    version INTEGER NOT NULL,
+                    title TEXT NOT NULL,
+                    content_hash TEXT NOT NULL,
+                    word_count INTEGER NOT NULL DEFAULT 0,
+                    char_count INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    UNIQUE (document_id, version),
+                    FOREIGN KEY (content_hash) REFERENCES revision_blobs (hash)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_document_revisions_document ON document_revisions (document_id, version)"),
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "documents_fts",
+        // 索引内容由 services::search 预先分词，启动时 Database::ensure_search_index 负责回填
+        steps: &[Step::Sql(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
+                title,
+                content,
+                document_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 5,
+        name: "soft_delete",
+        steps: &[
+            Step::AddColumn { table: "workspaces", column: "deleted_at", definition: "TEXT" },
+            Step::AddColumn { table: "projects", column: "deleted_at", definition: "TEXT" },
+            Step::AddColumn { table: "documents", column: "deleted_at", definition: "TEXT" },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_projects_workspace ON projects (workspace_id, deleted_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_documents_project ON documents (project_id, deleted_at)"),
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "recount_aggregates",
+        // 修正此前增量维护导致的计数偏差
+        steps: &[
+            Step::Sql(
+                r#"
+                UPDATE projects SET
+                    documents_count = (SELECT COUNT(*) FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL),
+                    words_count = (SELECT COALESCE(SUM(d.word_count), 0) FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL)
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                UPDATE workspaces SET
+                    projects_count = (SELECT COUNT(*) FROM projects p WHERE p.workspace_id = workspaces.id AND p.deleted_at IS NULL)
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "folders",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS folders (
+                    id TEXT PRIMARY KEY,
+                    project_id TEXT NOT NULL,
+                    parent_id TEXT,
+                    name TEXT NOT NULL,
+                    sort_order INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY (project_id) REFERENCES projects (id),
+                    FOREIGN KEY (parent_id) REFERENCES folders (id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_folders_parent ON folders (project_id, parent_id)"),
+            Step::AddColumn { table: "documents", column: "folder_id", definition: "TEXT REFERENCES folders (id)" },
+            Step::AddColumn { table: "documents", column: "sort_order", definition: "INTEGER NOT NULL DEFAULT 0" },
+            Step::Code(folders_from_paths),
+            Step::Sql(
+                r#"
+                UPDATE documents SET sort_order = (
+                    SELECT COUNT(*) FROM documents d
+                    WHERE d.project_id = documents.project_id
+                      AND d.folder_id IS documents.folder_id
+                      AND (d.created_at < documents.created_at OR (d.created_at = documents.created_at AND d.id < documents.id))
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "tags",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS tags (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    created_at TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS document_tags (
+                    document_id TEXT NOT NULL,
+                    tag_id TEXT NOT NULL,
+                    position INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (document_id, tag_id),
+                    FOREIGN KEY (document_id) REFERENCES documents (id),
+                    FOREIGN KEY (tag_id) REFERENCES tags (id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS project_tags (
+                    project_id TEXT NOT NULL,
+                    tag_id TEXT NOT NULL,
+                    position INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (project_id, tag_id),
+                    FOREIGN KEY (project_id) REFERENCES projects (id),
+                    FOREIGN KEY (tag_id) REFERENCES tags (id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_document_tags_tag ON document_tags (tag_id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_project_tags_tag ON project_tags (tag_id)"),
+            Step::Code(tags_from_json),
+        ],
+    },
+    Migration {
+        version: 9,
+        name: "writing_activity",
+        // 文档被彻底删除后统计历史仍然保留，因此冗余存储项目和工作区 ID
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS writing_activity (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    document_id TEXT NOT NULL,
+                    project_id TEXT NOT NULL,
+                    workspace_id TEXT NOT NULL,
+                    recorded_at TEXT NOT NULL,
+                    words_delta INTEGER NOT NULL,
+                    active_seconds INTEGER NOT NULL DEFAULT 0
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_writing_activity_recorded ON writing_activity (recorded_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_writing_activity_document ON writing_activity (document_id, recorded_at)"),
+        ],
+    },
+    Migration {
+        version: 10,
+        name: "project_goals",
+        steps: &[
+            Step::AddColumn { table: "projects", column: "target_word_count", definition: "INTEGER" },
+            Step::AddColumn { table: "projects", column: "deadline", definition: "TEXT" },
+            Step::AddColumn { table: "projects", column: "daily_word_quota", definition: "INTEGER" },
+            Step::AddColumn { table: "projects", column: "progress_mode", definition: "TEXT NOT NULL DEFAULT '\"WordCount\"'" },
+            // 原先手动填写的进度改为按文档状态计算
+            Step::Sql(
+                r#"
+                UPDATE projects SET progress = COALESCE((
+                    SELECT CAST(ROUND(AVG(CASE d.status WHEN '"Final"' THEN 100 WHEN '"Review"' THEN 80 WHEN '"InProgress"' THEN 50 ELSE 0 END)) AS INTEGER)
+                    FROM documents d WHERE d.project_id = projects.id AND d.deleted_at IS NULL
+                ), 0)
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 11,
+        name: "document_list_indexes",
+        steps: &[
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_documents_project_updated ON documents (project_id, deleted_at, updated_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_documents_folder_order ON documents (project_id, folder_id, sort_order)"),
+        ],
+    },
+    Migration {
+        version: 12,
+        name: "secrets",
+        // 迁移无法访问主密钥，已有的明文 API Key 在启动时由 Database 统一加密转存
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS secrets (
+                id TEXT PRIMARY KEY,
+                ciphertext TEXT NOT NULL,
+                nonce TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 13,
+        name: "unified_ai_providers",
+        steps: &[
+            Step::AddColumn { table: "ai_providers", column: "provider_type", definition: "TEXT NOT NULL DEFAULT 'custom'" },
+            Step::AddColumn { table: "ai_providers", column: "temperature", definition: "REAL" },
+            Step::AddColumn { table: "ai_providers", column: "enabled", definition: "INTEGER NOT NULL DEFAULT 1" },
+            Step::Code(providers_from_config),
+        ],
+    },
+    Migration {
+        version: 14,
+        name: "document_templates",
+        // 内置模板在启动时由 Database 同步，迁移只建表
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS document_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                category TEXT NOT NULL DEFAULT '',
+                title TEXT NOT NULL DEFAULT '',
+                content TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                variables TEXT NOT NULL DEFAULT '[]',
+                built_in INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 15,
+        name: "project_templates",
+        steps: &[
+            Step::AddColumn { table: "projects", column: "writing_preferences", definition: "TEXT NOT NULL DEFAULT '{}'" },
+            // 模板内容整体存为 JSON，内置模板同样在启动时同步
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS project_templates (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    data TEXT NOT NULL,
+                    built_in INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 16,
+        name: "search_index_cjk_unigrams",
+        // 索引格式变化（CJK 段尾增加单字词），清空后由 Database::ensure_search_index 在启动时重建
+        steps: &[Step::Sql("DELETE FROM documents_fts")],
+    },
+];
+
+// Code 步骤中的 SQL 固定为编写迁移时的写法，不调用 Database 中会继续演进的方法，
+// 否则日后修改这些方法会悄悄改变旧迁移在用户数据库上的行为
+
+// 把旧的自由格式 folder_path 转换为文件夹记录
+fn folders_from_paths(conn: &mut SqliteConnection) -> StepFuture<'_> {
+    Box::pin(async move {
+        let rows = sqlx::query("SELECT id, project_id, folder_path FROM documents WHERE folder_path IS NOT NULL AND folder_path != '' AND folder_id IS NULL")
+            .fetch_all(&mut *conn)
+            .await?;
+        for row in rows {
+            let project_id: String = row.get("project_id");
+            let path: String = row.get("folder_path");
+            let mut folder_id: Option<String> = None;
+            for name in path.split(['/', '\\']).map(str::trim).filter(|n| !n.is_empty()) {
+                let existing: Option<String> = sqlx::query("SELECT id FROM folders WHERE project_id = ?1 AND parent_id IS ?2 AND name = ?3")
+                    .bind(&project_id)
+                    .bind(&folder_id)
+                    .bind(name)
+                    .fetch_optional(&mut *conn)
+                    .await?
+                    .map(|r| r.get("id"));
+                let id = match existing {
+                    Some(id) => id,
+                    None => {
+                        let id = format!("folder-{}", uuid::Uuid::new_v4());
+                        sqlx::query(
+                            r#"
+                            INSERT INTO folders (id, project_id, parent_id, name, sort_order, created_at, updated_at)
+                            VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM folders WHERE project_id = ?2 AND parent_id IS ?3), ?5, ?5)
+                            "#,
+                        )
+                        .bind(&id)
+                        .bind(&project_id)
+                        .bind(&folder_id)
+                        .bind(name)
+                        .bind(chrono::Utc::now().to_rfc3339())
+                        .execute(&mut *conn)
+                        .await?;
+                        id
+                    }
+                };
+                folder_id = Some(id);
+            }
+            sqlx::query("UPDATE documents SET folder_id = ?2 WHERE id = ?1")
+                .bind(row.get::<String, _>("id"))
+                .bind(folder_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    })
+}
+
+// 把 documents.tags 中的 JSON 数组导入标签表，去掉空白和忽略大小写的重复后按原顺序写入
+fn tags_from_json(conn: &mut SqliteConnection) -> StepFuture<'_> {
+    Box::pin(async move {
+        let rows = sqlx::query("SELECT id, tags FROM documents WHERE tags IS NOT NULL AND tags NOT IN ('', '[]')")
+            .fetch_all(&mut *conn)
+            .await?;
+        for row in rows {
+            let document_id: String = row.get("id");
+            let tags: Vec<String> = serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default();
+            let mut seen = std::collections::HashSet::new();
+            let names = tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()));
+
+            sqlx::query("DELETE FROM document_tags WHERE document_id = ?1")
+                .bind(&document_id)
+                .execute(&mut *conn)
+                .await?;
+            for (position, name) in names.enumerate() {
+                // tags.name 使用 NOCASE 排序规则，大小写不同视为同一标签
+                sqlx::query("INSERT OR IGNORE INTO tags (id, name, created_at) VALUES (?1, ?2, ?3)")
+                    .bind(format!("tag-{}", uuid::Uuid::new_v4()))
+                    .bind(name)
+                    .bind(chrono::Utc::now().to_rfc3339())
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query("INSERT INTO document_tags (document_id, tag_id, position) SELECT ?1, id, ?3 FROM tags WHERE name = ?2")
+                    .bind(&document_id)
+                    .bind(name)
+                    .bind(position as i64)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            sqlx::query(
+                r#"
+                UPDATE documents SET tags = (
+                    SELECT json_group_array(name) FROM (
+                        SELECT t.name FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                        WHERE dt.document_id = documents.id ORDER BY dt.position
+                    )
+                )
+                WHERE id = ?1
+                "#,
+            )
+            .bind(&document_id)
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(())
+    })
+}
+
+// 把配置 JSON 中的 ai_providers.providers 合并进 ai_providers 表。
+// 同名（忽略大小写）的提供商视为同一个，只补充表中缺少的字段；默认提供商改为指向表中的 id
+fn providers_from_config(conn: &mut SqliteConnection) -> StepFuture<'_> {
+    Box::pin(async move {
+        let Some(row) = sqlx::query("SELECT config_data FROM config WHERE id = 1")
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Ok(mut config) = serde_json::from_str::<serde_json::Value>(&row.get::<String, _>("config_data")) else {
+            return Ok(());
+        };
+        let Some(section) = config.get_mut("ai_providers").and_then(|v| v.as_object_mut()) else {
+            return Ok(());
+        };
+
+        let providers = section.remove("providers").and_then(|v| v.as_object().cloned()).unwrap_or_default();
+        let mut default_provider = section.get("default_provider").and_then(|v| v.as_str()).map(str::to_string);
+
+        for (key, provider) in &providers {
+            let text = |field: &str| provider.get(field).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+            let name = text("name").unwrap_or(key);
+            let provider_type = text("provider_type").unwrap_or("custom");
+            let api_key = text("api_key").unwrap_or("");
+            let temperature = provider.get("temperature").and_then(|v| v.as_f64());
+            let enabled = provider.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+
+            let existing = sqlx::query("SELECT id, api_key FROM ai_providers WHERE name = ?1 COLLATE NOCASE")
+                .bind(name)
+                .fetch_optional(&mut *conn)
+                .await?;
+
+            let id = match existing {
+                Some(existing) => {
+                    let id: String = existing.get("id");
+                    let existing_key: String = existing.get("api_key");
+                    sqlx::query("UPDATE ai_providers SET provider_type = ?2, temperature = COALESCE(temperature, ?3), enabled = ?4, api_key = ?5 WHERE id = ?1")
+                        .bind(&id)
+                        .bind(provider_type)
+                        .bind(temperature)
+                        .bind(enabled)
+                        .bind(if existing_key.is_empty() { api_key } else { existing_key.as_str() })
+                        .execute(&mut *conn)
+                        .await?;
+                    // 表中已有密钥时配置里的密钥不再使用，删掉它已加密保存的副本，不在 secrets 表中留下孤立的密钥
+                    if !existing_key.is_empty() && existing_key != api_key {
+                        if let Some(secret_id) = api_key.strip_prefix("secret://") {
+                            sqlx::query("DELETE FROM secrets WHERE id = ?1")
+                                .bind(secret_id)
+                                .execute(&mut *conn)
+                                .await?;
+                        }
+                    }
+                    id
+                }
+                None => {
+                    let id = format!("prov-{}", uuid::Uuid::new_v4());
+                    sqlx::query(
+                        r#"INSERT INTO ai_providers (id, name, model_name, api_key, base_url, icon, bg_color, status, status_text, max_tokens, context_length, last_tested, description, priority, model_pointer, provider_type, temperature, enabled)
+                           VALUES (?1, ?2, ?3, ?4, ?5, '', '', 'disconnected', '未测试', ?6, 0, '从未', NULL,
+                                   (SELECT COALESCE(MAX(priority), 0) + 1 FROM ai_providers), NULL, ?7, ?8, ?9)"#,
+                    )
+                    .bind(&id)
+                    .bind(name)
+                    .bind(text("model").unwrap_or(""))
+                    .bind(api_key)
+                    .bind(text("api_base"))
+                    .bind(provider.get("max_tokens").and_then(|v| v.as_i64()).unwrap_or(4096))
+                    .bind(provider_type)
+                    .bind(temperature)
+                    .bind(enabled)
+                    .execute(&mut *conn)
+                    .await?;
+                    id
+                }
+            };
+
+            if default_provider.as_deref() == Some(key.as_str()) {
+                default_provider = Some(id);
+            }
+        }
+
+        section.insert("default_provider".to_string(), default_provider.into());
+        sqlx::query("UPDATE config SET config_data = ?1 WHERE id = 1")
+            .bind(config.to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    })
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub async fn current_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.get::<i64, _>("version"))
+}
+
+/// 执行所有未应用的迁移。已有数据的数据库在迁移前会先通过 `VACUUM INTO` 备份到 `backup_dir`。
+pub async fn run_migrations(pool: &SqlitePool, backup_dir: &Path) -> Result<(), MigrationError> {
+    create_version_table(pool).await?;
+
+    let current = current_version(pool).await?;
+    let latest = latest_version();
+    // 旧版本程序不能在新结构的数据库上运行，直接中止启动
+    if current > latest {
+        return Err(MigrationError::TooNew { current, supported: latest });
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let backup = if has_user_tables(pool).await? {
+        Some(backup_before_migrate(pool, backup_dir, current).await?)
+    } else {
+        None
+    };
+
+    for migration in pending {
+        println!("Applying migration {} ({})", migration.version, migration.name);
+        apply(pool, migration).await.map_err(|source| MigrationError::Failed {
+            version: migration.version,
+            name: migration.name,
+            backup: backup.clone(),
+            source,
+        })?;
+    }
+
+    Ok(())
+}
+
+async fn create_version_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn apply(pool: &SqlitePool, migration: &Migration) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for step in migration.steps {
+        match step {
+            Step::Sql(sql) => {
+                sqlx::query(sql).execute(&mut *tx).await?;
+            }
+            Step::Code(run) => {
+                run(&mut tx).await?;
+            }
+            Step::AddColumn { table, column, definition } => {
+                let exists = sqlx::query("SELECT COUNT(*) AS count FROM pragma_table_info(?1) WHERE name = ?2")
+                    .bind(table)
+                    .bind(column)
+                    .fetch_one(&mut *tx)
+                    .await?
+                    .get::<i64, _>("count") > 0;
+                if !exists {
+                    let sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
+                    sqlx::query(&sql).execute(&mut *tx).await?;
+                }
+            }
+        }
+    }
+
+    sqlx::query("INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+async fn has_user_tables(pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT COUNT(*) AS count FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_version'",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.get::<i64, _>("count") > 0)
+}
+
+async fn backup_before_migrate(pool: &SqlitePool, backup_dir: &Path, from_version: i64) -> Result<PathBuf, MigrationError> {
+    tokio::fs::create_dir_all(backup_dir)
+        .await
+        .map_err(|e| MigrationError::Backup(sqlx::Error::Io(e)))?;
+
+    let file_name = format!(
+        "pre-migration-v{}-{}.db",
+        from_version,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let path = backup_dir.join(file_name);
+    let target = path.display().to_string().replace('\'', "''");
+
+    sqlx::query(&format!("VACUUM INTO '{}'", target))
+        .execute(pool)
+        .await
+        .map_err(MigrationError::Backup)?;
+
+    println!("Database backed up to {} before migrating", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 临时目录中的数据库文件，返回连接池和目录，迁移前的备份写到目录下的 backups
+    async fn temp_database() -> (SqlitePool, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("writeflow-migration-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", dir.join("writeflow.db").display())).await.unwrap();
+        (pool, dir)
+    }
+
+    async fn migrate_to(pool: &SqlitePool, version: i64) {
+        create_version_table(pool).await.unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            apply(pool, migration).await.unwrap();
+        }
+    }
+
+    // 迁移框架之前 Database::init_tables 建出的结构（版本 0），固定为当时的写法
+    const V0_SCHEMA: &[&str] = &[
+        "CREATE TABLE workspaces (id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT NOT NULL, projects_count INTEGER NOT NULL DEFAULT 0, status TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, last_accessed TEXT NOT NULL)",
+        "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT NOT NULL, icon TEXT NOT NULL, color TEXT NOT NULL, status TEXT NOT NULL, progress INTEGER NOT NULL DEFAULT 0, documents_count INTEGER NOT NULL DEFAULT 0, words_count INTEGER NOT NULL DEFAULT 0, workspace_id TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, FOREIGN KEY (workspace_id) REFERENCES workspaces (id))",
+        "CREATE TABLE documents (id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, content_type TEXT NOT NULL, status TEXT NOT NULL, word_count INTEGER NOT NULL DEFAULT 0, char_count INTEGER NOT NULL DEFAULT 0, project_id TEXT NOT NULL, folder_path TEXT, tags TEXT, metadata TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, last_accessed TEXT NOT NULL, FOREIGN KEY (project_id) REFERENCES projects (id))",
+        "CREATE TABLE config (id INTEGER PRIMARY KEY, config_data TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE agents (id TEXT PRIMARY KEY, name TEXT NOT NULL, category TEXT NOT NULL, version TEXT NOT NULL, enabled INTEGER NOT NULL DEFAULT 1, description TEXT, tags TEXT NOT NULL)",
+        "CREATE TABLE ai_providers (id TEXT PRIMARY KEY, name TEXT NOT NULL, model_name TEXT NOT NULL, api_key TEXT NOT NULL, base_url TEXT, icon TEXT NOT NULL, bg_color TEXT NOT NULL, status TEXT NOT NULL, status_text TEXT NOT NULL, max_tokens INTEGER NOT NULL, context_length INTEGER NOT NULL, last_tested TEXT NOT NULL, description TEXT, priority INTEGER NOT NULL)",
+    ];
+
+    // 计数故意写错，迁移后应按实际数据重新计算
+    const V0_DATA: &[&str] = &[
+        "INSERT INTO workspaces VALUES ('ws-1', 'Workspace', '', 7, '\"Active\"', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00')",
+        "INSERT INTO projects VALUES ('proj-1', 'Novel', '', '', '', '\"Active\"', 0, 9, 9, 'ws-1', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00')",
+        "INSERT INTO documents VALUES ('doc-1', 'One', 'one two three four five', '\"Markdown\"', '\"Draft\"', 5, 23, 'proj-1', ' Part 1 / Chapter ', '[\"draft\", \" Draft \", \"intro\", \"\"]', '{}', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00')",
+        "INSERT INTO documents VALUES ('doc-2', 'Two', 'six seven eight', '\"Markdown\"', '\"Draft\"', 3, 15, 'proj-1', 'Part 1', NULL, '{}', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00')",
+        r#"INSERT INTO config VALUES (1, '{"ai_providers": {"default_provider": "openai", "providers": {"openai": {"name": "OpenAI", "api_key": "sk-config", "model": "gpt"}}}}', '2024-01-01T00:00:00+00:00')"#,
+    ];
+
+    async fn column(pool: &SqlitePool, sql: &str) -> Vec<String> {
+        sqlx::query(sql).fetch_all(pool).await.unwrap().iter().map(|r| r.get(0)).collect()
+    }
+
+    #[tokio::test]
+    async fn merging_config_providers_drops_sealed_keys_that_are_not_used() {
+        let (pool, dir) = temp_database().await;
+        migrate_to(&pool, 12).await;
+        for id in ["sec-table", "sec-config", "sec-new"] {
+            sqlx::query("INSERT INTO secrets (id, ciphertext, nonce, created_at, updated_at) VALUES (?1, '', '', '', '')")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            r#"INSERT INTO ai_providers (id, name, model_name, api_key, icon, bg_color, status, status_text, max_tokens, context_length, last_tested, priority)
+               VALUES ('prov-openai', 'OpenAI', 'gpt', 'secret://sec-table', '', '', '', '', 0, 0, '', 1)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let config = serde_json::json!({
+            "ai_providers": {
+                "default_provider": "claude",
+                "providers": {
+                    "openai": { "name": "openai", "api_key": "secret://sec-config" },
+                    "claude": { "name": "Claude", "api_key": "secret://sec-new" },
+                },
+            },
+        });
+        sqlx::query("INSERT INTO config (id, config_data, updated_at) VALUES (1, ?1, '')")
+            .bind(config.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run_migrations(&pool, &dir.join("backups")).await.unwrap();
+
+        // 同名提供商保留表中的密钥，配置里那份被删除；新加入的提供商接管配置中的密钥
+        assert_eq!(column(&pool, "SELECT id FROM secrets ORDER BY id").await, ["sec-new", "sec-table"]);
+        assert_eq!(
+            column(&pool, "SELECT name || '=' || api_key FROM ai_providers ORDER BY name").await,
+            ["Claude=secret://sec-new", "OpenAI=secret://sec-table"]
+        );
+        let config: serde_json::Value =
+            serde_json::from_str(&column(&pool, "SELECT config_data FROM config").await[0]).unwrap();
+        let claude = column(&pool, "SELECT id FROM ai_providers WHERE name = 'Claude'").await;
+        assert_eq!(config["ai_providers"]["default_provider"], claude[0]);
+        assert!(config["ai_providers"].get("providers").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn migration_versions_are_consecutive() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1, "migration {} is out of order", migration.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn migrates_a_version_0_database_to_the_latest_version() {
+        let (pool, dir) = temp_database().await;
+        for sql in V0_SCHEMA.iter().chain(V0_DATA) {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        let backups = dir.join("backups");
+        run_migrations(&pool, &backups).await.unwrap();
+
+        let versions: Vec<i64> = sqlx::query("SELECT version FROM schema_version ORDER BY applied_at, version")
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.get(0))
+            .collect();
+        assert_eq!(versions, (1..=latest_version()).collect::<Vec<_>>());
+
+        // 已有数据的库在迁移前备份，再次运行时没有待执行的迁移，也不再备份
+        let backup_names = || -> Vec<String> {
+            std::fs::read_dir(&backups).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect()
+        };
+        let names = backup_names();
+        assert_eq!(names.len(), 1);
+        assert!(names[0].starts_with("pre-migration-v0-"), "{}", names[0]);
+        run_migrations(&pool, &backups).await.unwrap();
+        assert_eq!(backup_names().len(), 1);
+
+        assert_eq!(column(&pool, "SELECT documents_count || '/' || words_count FROM projects").await, ["2/8"]);
+        assert_eq!(column(&pool, "SELECT CAST(projects_count AS TEXT) FROM workspaces").await, ["1"]);
+
+        assert_eq!(
+            column(&pool, "SELECT COALESCE(p.name || '/', '') || f.name FROM folders f LEFT JOIN folders p ON p.id = f.parent_id ORDER BY 1").await,
+            ["Part 1", "Part 1/Chapter"]
+        );
+        assert_eq!(
+            column(&pool, "SELECT d.id || ':' || f.name FROM documents d JOIN folders f ON f.id = d.folder_id ORDER BY d.id").await,
+            ["doc-1:Chapter", "doc-2:Part 1"]
+        );
+
+        assert_eq!(
+            column(&pool, "SELECT t.name FROM document_tags dt JOIN tags t ON t.id = dt.tag_id WHERE dt.document_id = 'doc-1' ORDER BY dt.position").await,
+            ["draft", "intro"]
+        );
+        assert_eq!(column(&pool, "SELECT tags FROM documents WHERE id = 'doc-1'").await, [r#"["draft","intro"]"#]);
+
+        assert_eq!(column(&pool, "SELECT name || '=' || api_key FROM ai_providers").await, ["OpenAI=sk-config"]);
+        let config: serde_json::Value =
+            serde_json::from_str(&column(&pool, "SELECT config_data FROM config").await[0]).unwrap();
+        let openai = column(&pool, "SELECT id FROM ai_providers").await;
+        assert_eq!(config["ai_providers"]["default_provider"], openai[0]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,23 @@
 pub mod database;
+pub mod goals;
+pub mod migration;
+pub mod provider;
+pub mod revision;
+pub mod search;
+pub mod secrets;
+pub mod writing_stats;
 pub mod config;
 pub mod backup;
 pub mod export;
 pub mod writeflow;
-pub mod environment;
\ No newline at end of file
+pub mod environment;
+pub mod pdf;
+pub mod docx;
+pub mod epub;
+pub mod markdown;
+pub mod folder_import;
+pub mod links;
+pub mod vault_import;
+pub mod obsidian;
+pub mod notion;
+pub mod templates;
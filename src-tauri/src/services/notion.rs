@@ -0,0 +1,314 @@
+// Notion 导出导入（Markdown & CSV 格式，目录或 zip）
+// 文件名和目录名末尾的 32 位页面 id 会被去掉，子页面目录成为文件夹。
+// 数据库导出为 CSV 加同名目录，目录中的每个页面对应一行：行中各列写入状态、标签、作者、时间，其余列存入 metadata.properties；
+// 没有对应页面的行也会创建一篇空文档。页面之间的相对链接和 notion.so 链接改写为文档链接，图片和附件复制到附件目录
+
+use crate::models::document::{CreateDocumentData, Document, DocumentType};
+use crate::models::import::{VaultImportOptions, VaultImportReport};
+use crate::services::database::Database;
+use crate::services::markdown::{parse_status, parse_time};
+use crate::services::vault_import::{
+    self, extension, file_stem, is_external, join_path, parent_dir, percent_decode, rewrite_links, AttachmentsDir,
+    Link, Prepared, Resolver,
+};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+
+const UNTITLED: &str = "Untitled";
+
+fn is_notion_id(text: &str) -> bool {
+    text.len() == 32 && text.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+// "Page Title 0123…cdef" -> ("Page Title", Some("0123…cdef"))
+fn split_notion_id(name: &str) -> (&str, Option<&str>) {
+    let len = name.len();
+    if len > 32 && name.is_char_boundary(len - 32) {
+        let (head, id) = name.split_at(len - 32);
+        let head = head.trim_end();
+        if is_notion_id(id) && !head.is_empty() {
+            return (head, Some(id));
+        }
+    }
+    (name, None)
+}
+
+fn clean_folder_path(dir: &str) -> Option<String> {
+    let path = dir
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| split_notion_id(segment).0)
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(path).filter(|path| !path.is_empty())
+}
+
+// https://www.notion.so/workspace/Page-Title-0123…cdef?pvs=4 中的页面 id
+fn notion_url_id(url: &str) -> Option<&str> {
+    if !url.contains("notion.so/") && !url.contains("notion.site/") {
+        return None;
+    }
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let last = path.trim_end_matches('/').rsplit('/').next()?;
+    let id = last.rsplit('-').next()?;
+    is_notion_id(id).then_some(id)
+}
+
+// RFC 4180：逗号分隔，双引号包裹的字段中可以有逗号、换行和 "" 转义
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+// Notion 导出中的日期形如 "March 5, 2024 3:07 PM"，按地区设置也可能是 "2024/03/05 15:07"
+fn parse_notion_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    // 日期范围 "March 5, 2024 → March 8, 2024" 取开始日期
+    let value = value.split(" → ").next().unwrap_or(value);
+    parse_time(value)
+        .or_else(|| {
+            ["%B %d, %Y %I:%M %p", "%Y/%m/%d %H:%M", "%Y-%m-%d %H:%M"]
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+                .map(|time| time.and_utc())
+        })
+        .or_else(|| {
+            ["%B %d, %Y", "%Y/%m/%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc())
+        })
+}
+
+// 把数据库行中的一列写入文档，列名不区分大小写；无法识别的值和其他列存入 properties
+fn apply_property(document: &mut Document, column: &str, value: &str) {
+    let (column, value) = (column.trim(), value.trim());
+    if value.is_empty() {
+        return;
+    }
+    let handled = match column.to_lowercase().as_str() {
+        "tags" | "tag" | "标签" => {
+            for tag in value.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+                if !document.tags.iter().any(|t| t == tag) {
+                    document.tags.push(tag.to_string());
+                }
+            }
+            true
+        }
+        "status" | "状态" => parse_status(value).map(|status| document.status = status).is_some(),
+        "author" | "created by" | "作者" | "创建者" => {
+            document.metadata.author = Some(value.to_string());
+            true
+        }
+        "created" | "created time" | "创建时间" => {
+            parse_notion_time(value).map(|time| document.created_at = time).is_some()
+        }
+        "last edited time" | "updated" | "最后编辑时间" => parse_notion_time(value)
+            .map(|time| {
+                document.updated_at = time;
+                document.last_accessed = time;
+            })
+            .is_some(),
+        _ => false,
+    };
+    if !handled {
+        document.metadata.properties.insert(column.to_string(), value.to_string());
+    }
+}
+
+fn new_document(title: &str, content: &str, dir: &str) -> Document {
+    let mut document = Document::new(CreateDocumentData {
+        title: title.to_string(),
+        content: Some(content.to_string()),
+        content_type: DocumentType::Markdown,
+        // 写入时设置为目标项目
+        project_id: String::new(),
+        folder_path: clean_folder_path(dir),
+        folder_id: None,
+        tags: None,
+        template_id: None,
+        template_variables: None,
+    });
+    document.id = uuid::Uuid::new_v4().to_string();
+    document
+}
+
+// 页面以 "# 标题" 开头，标题作为文档标题并从正文中去掉
+fn page_to_document(path: &str, text: &str) -> Document {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let (title, body) = match text.strip_prefix("# ") {
+        Some(rest) => {
+            let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (title.trim().to_string(), body.trim_start_matches('\n'))
+        }
+        None => (split_notion_id(file_stem(path)).0.to_string(), text.as_str()),
+    };
+    let title = if title.is_empty() { UNTITLED.to_string() } else { title };
+    new_document(&title, body, parent_dir(path))
+}
+
+// 数据库页面的正文开头是 "列名: 值" 形式的属性列表，已经写入文档字段，这里去掉
+fn strip_property_lines(content: &str, columns: &[String]) -> String {
+    let mut rest = content;
+    while !rest.is_empty() {
+        let (line, tail) = rest.split_once('\n').unwrap_or((rest, ""));
+        let is_property = line
+            .split_once(": ")
+            .is_some_and(|(name, _)| columns.iter().any(|column| column.trim() == name.trim()));
+        if !is_property {
+            break;
+        }
+        rest = tail;
+    }
+    rest.trim_start_matches('\n').to_string()
+}
+
+fn resolve(resolver: &mut Resolver, pages: &HashMap<String, String>, path: &str, link: &Link) -> Option<String> {
+    let Link::Inline { text, url, title, image } = *link else { return None };
+    if let Some(id) = notion_url_id(url) {
+        return match pages.get(id) {
+            Some(found) => resolver.link_to(path, found, None, false, text, title),
+            None => resolver.unresolved(path, url),
+        };
+    }
+    if is_external(url) {
+        return None;
+    }
+    let decoded = percent_decode(url);
+    let (target, heading) = match decoded.split_once('#') {
+        Some((target, heading)) => (target, Some(heading)),
+        None => (decoded.as_str(), None),
+    };
+    let found = resolver.index.by_path(&join_path(parent_dir(path), target)).map(str::to_string);
+    match found {
+        Some(found) => resolver.link_to(path, &found, heading, image, text, title),
+        None => resolver.unresolved(path, url),
+    }
+}
+
+fn prepare(source: &Path, attachments: AttachmentsDir) -> Result<Prepared> {
+    let files = vault_import::load_source(source)?;
+    let mut resolver = Resolver::new(&files, attachments);
+    let mut documents: Vec<(String, Document)> = Vec::new();
+    let mut failures = Vec::new();
+    // Notion 页面 id -> 页面路径
+    let mut pages = HashMap::new();
+    // 数据库目录 -> CSV 路径，同时导出了 "_all" 版本时以它为准；按目录排序，行的导入顺序保持稳定
+    let mut databases: BTreeMap<String, &str> = BTreeMap::new();
+
+    for file in &files {
+        let page = extension(&file.path) == "md";
+        resolver.index.add(&file.path, page);
+        if extension(&file.path) == "csv" {
+            let stem = file_stem(&file.path);
+            let (table, all) = match stem.strip_suffix("_all") {
+                Some(table) => (table, true),
+                None => (stem, false),
+            };
+            let dir = join_path(parent_dir(&file.path), table);
+            if all || !databases.contains_key(&dir) {
+                databases.insert(dir, &file.path);
+            }
+        }
+        if !page {
+            continue;
+        }
+        match std::str::from_utf8(&file.bytes) {
+            Ok(text) => {
+                let document = page_to_document(&file.path, text);
+                resolver.add_document(&file.path, &document.id);
+                if let (_, Some(id)) = split_notion_id(file_stem(&file.path)) {
+                    pages.insert(id.to_string(), file.path.clone());
+                }
+                documents.push((file.path.clone(), document));
+            }
+            Err(_) => failures.push((file.path.clone(), "File is not valid UTF-8 text".to_string())),
+        }
+    }
+
+    // 按数据库目录和标题把行对应到页面，同名的行按出现顺序依次对应
+    let mut by_title: HashMap<(String, String), VecDeque<usize>> = HashMap::new();
+    for (index, (path, document)) in documents.iter().enumerate() {
+        by_title.entry((parent_dir(path).to_string(), document.title.clone())).or_default().push_back(index);
+    }
+    let mut rows = Vec::new();
+    for (dir, csv_path) in &databases {
+        let Some(bytes) = files.iter().find(|file| file.path == *csv_path).map(|file| &file.bytes) else { continue };
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            failures.push((csv_path.to_string(), "File is not valid UTF-8 text".to_string()));
+            continue;
+        };
+        let mut table = parse_csv(text).into_iter();
+        let Some(columns) = table.next() else { continue };
+        for row in table {
+            let title = row.first().map(|title| title.trim()).filter(|title| !title.is_empty()).unwrap_or(UNTITLED);
+            let matched = by_title.get_mut(&(dir.clone(), title.to_string())).and_then(VecDeque::pop_front);
+            rows.push((dir.clone(), csv_path.to_string(), title.to_string(), matched, columns.clone(), row));
+        }
+    }
+    for (dir, csv_path, title, matched, columns, row) in rows {
+        let document = match matched {
+            Some(index) => {
+                let document = &mut documents[index].1;
+                document.content = strip_property_lines(&document.content, &columns[1..]);
+                document
+            }
+            None => {
+                documents.push((csv_path, new_document(&title, "", &dir)));
+                &mut documents.last_mut().expect("document was just pushed").1
+            }
+        };
+        for (column, value) in columns.iter().zip(&row).skip(1) {
+            apply_property(document, column, value);
+        }
+    }
+
+    for (path, document) in &mut documents {
+        document.content = rewrite_links(&document.content, |link| resolve(&mut resolver, &pages, path, link));
+    }
+
+    Ok(Prepared {
+        documents,
+        failures,
+        attachments_copied: resolver.attachments_copied(),
+        unresolved: std::mem::take(&mut resolver.unresolved),
+    })
+}
+
+/// 导入 Notion 导出的 Markdown & CSV（目录或 zip）到指定项目，未指定项目时新建项目
+pub async fn import_export(database: &Database, options: &VaultImportOptions) -> Result<VaultImportReport> {
+    vault_import::import(database, options, prepare).await
+}
@@ -0,0 +1,199 @@
+// Obsidian 库导入
+// 每个 .md 笔记成为一篇文档，子目录成为文件夹；front matter 映射为标签、状态和元数据，aliases 参与链接解析，
+// 正文中的 #标签 也并入标签。[[wikilink]]、![[嵌入]] 和指向笔记的普通链接改写为文档链接，
+// 引用的图片和附件复制到附件目录
+
+use crate::models::document::{CreateDocumentData, Document, DocumentType};
+use crate::models::import::{VaultImportOptions, VaultImportReport};
+use crate::services::database::Database;
+use crate::services::markdown::{self, FrontMatterValue};
+use crate::services::vault_import::{
+    self, extension, file_name, file_stem, is_external, is_image, join_path, parent_dir, percent_decode, rewrite_links,
+    AttachmentsDir, FileIndex, Link, Prepared, Resolver,
+};
+use anyhow::Result;
+use std::path::Path;
+
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        // 只认行首或空白后的 #，标题（# 后跟空格）和纯数字（如 #1）不算标签
+        let mut previous = ' ';
+        for (index, c) in line.char_indices() {
+            if c == '#' && previous.is_whitespace() {
+                let tag: String = line[index + 1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                    .collect();
+                if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            previous = c;
+        }
+    }
+    tags
+}
+
+// 返回文档和它的别名
+fn note_to_document(path: &str, text: &str) -> (Document, Vec<String>) {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let (front_matter, body) = markdown::split_front_matter(&text);
+    let mut document = Document::new(CreateDocumentData {
+        title: file_stem(path).to_string(),
+        content: Some(body.to_string()),
+        content_type: DocumentType::Markdown,
+        // 写入时设置为目标项目
+        project_id: String::new(),
+        folder_path: Some(parent_dir(path).to_string()).filter(|dir| !dir.is_empty()),
+        folder_id: None,
+        tags: None,
+        template_id: None,
+        template_variables: None,
+    });
+    markdown::apply_front_matter(&mut document, &front_matter);
+    // 链接按新分配的 id 改写，front matter 中的 id 只作为普通属性保留
+    if let Some(id) = front_matter.get("id").and_then(FrontMatterValue::as_text) {
+        document.metadata.properties.insert("id".to_string(), id.to_string());
+    }
+    document.id = uuid::Uuid::new_v4().to_string();
+    for tag in inline_tags(body) {
+        if !document.tags.contains(&tag) {
+            document.tags.push(tag);
+        }
+    }
+
+    // 别名中可能有空格，只按逗号拆分
+    let aliases = ["aliases", "alias"]
+        .iter()
+        .filter_map(|key| front_matter.get(*key))
+        .flat_map(|value| match value {
+            FrontMatterValue::List(items) => items.clone(),
+            FrontMatterValue::Text(text) => text.split(',').map(|alias| alias.trim().to_string()).collect(),
+        })
+        .filter(|alias| !alias.is_empty())
+        .collect();
+    (document, aliases)
+}
+
+// [[folder/Note]] 先按库内路径查找，[[Note]] 按文件名或别名查找
+fn find_note<'i>(index: &'i FileIndex, from_dir: &str, name: &str) -> Option<&'i str> {
+    if name.contains('/') {
+        index
+            .by_path(&join_path("", name))
+            .or_else(|| index.by_path(&join_path(from_dir, name)))
+            .or_else(|| index.by_name(from_dir, file_name(name)))
+    } else {
+        index.by_name(from_dir, name)
+    }
+}
+
+fn split_heading(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('#') {
+        Some((name, heading)) => (name.trim(), Some(heading.trim()).filter(|h| !h.is_empty())),
+        None => (target.trim(), None),
+    }
+}
+
+// ![[image.png|300]] 和 ![[image.png|300x200]] 中的别名是显示尺寸
+fn is_size(alias: &str) -> bool {
+    !alias.is_empty() && alias.chars().all(|c| c.is_ascii_digit() || c == 'x')
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+fn resolve(resolver: &mut Resolver, path: &str, link: &Link) -> Option<String> {
+    let from_dir = parent_dir(path);
+    match *link {
+        Link::Wiki { target, alias, embed } => {
+            let (name, heading) = split_heading(target);
+            // [[#标题]] 指向当前笔记
+            let found = if name.is_empty() {
+                Some(path.to_string())
+            } else {
+                find_note(&resolver.index, from_dir, name).map(str::to_string)
+            };
+            let Some(found) = found else { return resolver.unresolved(path, target) };
+            let image = embed && is_image(&found);
+            let text = match (alias, heading) {
+                (Some(alias), _) if !(image && is_size(alias)) => alias.to_string(),
+                _ if image => file_stem(&found).to_string(),
+                (_, Some(heading)) if name.is_empty() => heading.to_string(),
+                (_, Some(heading)) => format!("{} > {}", file_name(name), heading),
+                _ => file_name(name).to_string(),
+            };
+            // 嵌入的笔记无法内联展示，改为普通的文档链接
+            resolver.link_to(path, &found, heading, image, &escape_text(&text), None)
+        }
+        Link::Inline { text, url, title, image } => {
+            if is_external(url) {
+                return None;
+            }
+            let decoded = percent_decode(url);
+            let (target, heading) = split_heading(&decoded);
+            let found = resolver
+                .index
+                .by_path(&join_path(from_dir, target))
+                .or_else(|| resolver.index.by_path(&join_path("", target)))
+                .or_else(|| resolver.index.by_name(from_dir, file_name(target)))
+                .map(str::to_string);
+            match found {
+                Some(found) => resolver.link_to(path, &found, heading, image, text, title),
+                None => resolver.unresolved(path, url),
+            }
+        }
+    }
+}
+
+fn prepare(source: &Path, attachments: AttachmentsDir) -> Result<Prepared> {
+    let files = vault_import::load_source(source)?;
+    let mut resolver = Resolver::new(&files, attachments);
+    let mut documents = Vec::new();
+    let mut failures = Vec::new();
+
+    for file in &files {
+        let note = extension(&file.path) == "md";
+        resolver.index.add(&file.path, note);
+        if !note {
+            continue;
+        }
+        match std::str::from_utf8(&file.bytes) {
+            Ok(text) => {
+                let (document, aliases) = note_to_document(&file.path, text);
+                resolver.add_document(&file.path, &document.id);
+                for alias in aliases {
+                    resolver.index.add_name(&alias, &file.path);
+                }
+                documents.push((file.path.clone(), document));
+            }
+            Err(_) => failures.push((file.path.clone(), "File is not valid UTF-8 text".to_string())),
+        }
+    }
+
+    for (path, document) in &mut documents {
+        document.content = rewrite_links(&document.content, |link| resolve(&mut resolver, path, link));
+    }
+
+    Ok(Prepared {
+        documents,
+        failures,
+        attachments_copied: resolver.attachments_copied(),
+        unresolved: std::mem::take(&mut resolver.unresolved),
+    })
+}
+
+/// 导入 Obsidian 库（目录或 zip）到指定项目，未指定项目时以库名新建项目
+pub async fn import_vault(database: &Database, options: &VaultImportOptions) -> Result<VaultImportReport> {
+    vault_import::import(database, options, prepare).await
+}
@@ -0,0 +1,1880 @@
+// PDF 导出
+// 不依赖浏览器或 LaTeX：用 comrak 解析 Markdown 后自行分行分页，再用 pdf-writer 输出。
+// 字体取自系统（优先含中文的字体），只嵌入实际用到的字形子集
+
+use crate::error::AppError;
+use crate::models::config::PdfExportOptions;
+use crate::models::document::{Document, DocumentType};
+use crate::models::project::Project;
+use crate::services::export::{markdown_options, GENERATOR};
+use crate::services::links;
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use comrak::nodes::{AstNode, ListType, NodeList, NodeValue, TableAlignment};
+use comrak::{parse_document, Arena};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::{ColorType, ImageFormat};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, TextRenderingMode, UnicodeCmap};
+use pdf_writer::{Content, Date, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use ttf_parser::{Face, GlyphId, Tag};
+
+const PT_PER_MM: f32 = 72.0 / 25.4;
+// 目录只收录前三级标题
+const TOC_DEPTH: u8 = 3;
+// 文字基线距行顶的位置（相对字号）
+const BASELINE_RATIO: f32 = 0.8;
+
+// 依次查找的正文字体，前面几个都带有中文字形
+const TEXT_FAMILIES: &[&str] = &[
+    "Noto Sans CJK SC", "Noto Sans SC", "Source Han Sans SC", "Source Han Sans CN", "PingFang SC",
+    "Hiragino Sans GB", "Microsoft YaHei", "WenQuanYi Micro Hei", "WenQuanYi Zen Hei", "Droid Sans Fallback",
+    "SimHei", "SimSun",
+];
+const LATIN_FAMILIES: &[&str] = &["Noto Sans", "Helvetica Neue", "Helvetica", "Arial", "Segoe UI", "DejaVu Sans", "Liberation Sans"];
+const MONO_FAMILIES: &[&str] = &[
+    "Noto Sans Mono", "JetBrains Mono", "SF Mono", "Menlo", "Consolas", "DejaVu Sans Mono", "Liberation Mono", "Courier New",
+];
+
+const CFF: Tag = Tag::from_bytes(b"CFF ");
+const SYSTEM_INFO: SystemInfo = SystemInfo {
+    registry: Str(b"Adobe"),
+    ordering: Str(b"Identity"),
+    supplement: 0,
+};
+
+// ---------- 页面与样式 ----------
+
+#[derive(Debug, Clone, Copy)]
+struct PageGeometry {
+    width: f32,
+    height: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+    left: f32,
+}
+
+impl PageGeometry {
+    fn from_options(options: &PdfExportOptions) -> Result<Self> {
+        let (width, height) = parse_page_size(&options.page_size)?;
+        let [top, right, bottom, left] = parse_margins(&options.margins)?;
+        if left + right >= width * 0.8 || top + bottom >= height * 0.8 {
+            return Err(AppError::validation(format!("Margins \"{}\" leave no room on the page", options.margins)).into());
+        }
+        Ok(Self { width, height, top, right, bottom, left })
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - self.left - self.right
+    }
+
+    fn content_height(&self) -> f32 {
+        self.height - self.top - self.bottom
+    }
+}
+
+/// 解析长度，支持 pt、mm、cm、in、px，不带单位时按 pt 处理
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let number: f32 = value[..split].parse().ok()?;
+    let factor = match value[split..].trim().to_lowercase().as_str() {
+        "" | "pt" => 1.0,
+        "mm" => PT_PER_MM,
+        "cm" => PT_PER_MM * 10.0,
+        "in" => 72.0,
+        "px" => 0.75,
+        _ => return None,
+    };
+    Some(number * factor)
+}
+
+/// 支持 A3/A4/A5/B5/Letter/Legal/Tabloid（可加 landscape），或 "210mm x 297mm" 形式的自定义尺寸
+fn parse_page_size(value: &str) -> Result<(f32, f32)> {
+    let invalid = || AppError::validation(format!("Unsupported page size: {}", value));
+    let lower = value.trim().to_lowercase();
+    let (name, landscape) = match lower.strip_suffix("landscape") {
+        Some(rest) => (rest.trim(), true),
+        None => (lower.strip_suffix("portrait").unwrap_or(&lower).trim(), false),
+    };
+    let (width, height) = match name {
+        "a3" => (841.89, 1190.55),
+        "a4" => (595.28, 841.89),
+        "a5" => (419.53, 595.28),
+        "b5" => (498.9, 708.66),
+        "letter" => (612.0, 792.0),
+        "legal" => (612.0, 1008.0),
+        "tabloid" => (792.0, 1224.0),
+        custom => {
+            let parts: Vec<&str> = custom
+                .split(|c: char| c.is_whitespace() || c == '×' || c == '*')
+                .filter(|part| !part.is_empty() && *part != "x")
+                .collect();
+            match parts.as_slice() {
+                [w, h] => (parse_length(w).ok_or_else(invalid)?, parse_length(h).ok_or_else(invalid)?),
+                _ => return Err(invalid().into()),
+            }
+        }
+    };
+    if width < 72.0 || height < 72.0 {
+        return Err(invalid().into());
+    }
+    Ok(if landscape { (height, width) } else { (width, height) })
+}
+
+/// 按 CSS margin 简写解析：1 到 4 个值，顺序为上、右、下、左
+fn parse_margins(value: &str) -> Result<[f32; 4]> {
+    let values: Option<Vec<f32>> = value.split_whitespace().map(parse_length).collect();
+    let invalid = || AppError::validation(format!("Unsupported margins: {}", value));
+    Ok(match values.ok_or_else(invalid)?.as_slice() {
+        [all] => [*all; 4],
+        [vertical, horizontal] => [*vertical, *horizontal, *vertical, *horizontal],
+        [top, horizontal, bottom] => [*top, *horizontal, *bottom, *horizontal],
+        [top, right, bottom, left] => [*top, *right, *bottom, *left],
+        _ => return Err(invalid().into()),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Color(f32, f32, f32);
+
+impl Color {
+    const fn hex(value: u32) -> Self {
+        Color(
+            ((value >> 16) & 0xff) as f32 / 255.0,
+            ((value >> 8) & 0xff) as f32 / 255.0,
+            (value & 0xff) as f32 / 255.0,
+        )
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return None,
+        };
+        return u32::from_str_radix(&hex, 16).ok().map(Color::hex);
+    }
+    if let Some(args) = value.strip_prefix("rgb(").and_then(|v| v.strip_suffix(')')) {
+        let channels: Vec<f32> = args.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+        if let [r, g, b] = channels.as_slice() {
+            return Some(Color(r / 255.0, g / 255.0, b / 255.0));
+        }
+        return None;
+    }
+    match value.as_str() {
+        "black" => Some(Color::hex(0x000000)),
+        "white" => Some(Color::hex(0xffffff)),
+        "gray" | "grey" => Some(Color::hex(0x808080)),
+        "red" => Some(Color::hex(0xff0000)),
+        "green" => Some(Color::hex(0x008000)),
+        "blue" => Some(Color::hex(0x0000ff)),
+        _ => None,
+    }
+}
+
+// 相对正文字号的默认标题字号
+const HEADING_SCALES: [f32; 6] = [2.0, 1.6, 1.35, 1.18, 1.05, 1.0];
+
+/// PDF 排版样式。custom_css 只支持下列选择器与属性：
+/// body/p 的 font-size、line-height、color；h1-h6 的 font-size、color；
+/// code/pre 的 font-size、color、background；a 和 blockquote 的 color
+#[derive(Debug, Clone)]
+struct PdfStyle {
+    font_size: f32,
+    line_height: f32,
+    color: Color,
+    heading_sizes: [Option<f32>; 6],
+    heading_colors: [Color; 6],
+    code_size: Option<f32>,
+    code_color: Color,
+    code_background: Color,
+    link_color: Color,
+    muted_color: Color,
+    border_color: Color,
+}
+
+impl Default for PdfStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 11.0,
+            line_height: 1.6,
+            color: Color::hex(0x24292f),
+            heading_sizes: [None; 6],
+            heading_colors: [Color::hex(0x1f2328); 6],
+            code_size: None,
+            code_color: Color::hex(0x24292f),
+            code_background: Color::hex(0xf6f8fa),
+            link_color: Color::hex(0x0969da),
+            muted_color: Color::hex(0x57606a),
+            border_color: Color::hex(0xd0d7de),
+        }
+    }
+}
+
+impl PdfStyle {
+    fn from_css(css: Option<&str>) -> Self {
+        let mut style = Self::default();
+        if let Some(css) = css {
+            style.apply_css(css);
+        }
+        style
+    }
+
+    fn heading_size(&self, level: u8) -> f32 {
+        let index = (level.clamp(1, 6) - 1) as usize;
+        self.heading_sizes[index].unwrap_or(self.font_size * HEADING_SCALES[index])
+    }
+
+    fn heading_color(&self, level: u8) -> Color {
+        self.heading_colors[(level.clamp(1, 6) - 1) as usize]
+    }
+
+    fn code_size(&self) -> f32 {
+        self.code_size.unwrap_or(self.font_size * 0.88)
+    }
+
+    fn apply_css(&mut self, css: &str) {
+        let mut source = String::new();
+        let mut rest = css;
+        while let Some(start) = rest.find("/*") {
+            source.push_str(&rest[..start]);
+            rest = rest[start..].find("*/").map_or("", |end| &rest[start + end + 2..]);
+        }
+        source.push_str(rest);
+
+        for rule in source.split('}') {
+            let Some((selectors, body)) = rule.split_once('{') else { continue };
+            for declaration in body.split(';') {
+                let Some((property, value)) = declaration.split_once(':') else { continue };
+                let property = property.trim().to_lowercase();
+                let value = value.trim().trim_end_matches("!important").trim();
+                for selector in selectors.split(',') {
+                    self.apply_declaration(&selector.trim().to_lowercase(), &property, value);
+                }
+            }
+        }
+    }
+
+    // 字号支持绝对长度以及相对正文的 em/rem/%
+    fn font_size_value(&self, value: &str) -> Option<f32> {
+        let relative = |number: &str, scale: f32| number.trim().parse::<f32>().ok().map(|n| n * scale * self.font_size);
+        if let Some(number) = value.strip_suffix("rem").or_else(|| value.strip_suffix("em")) {
+            return relative(number, 1.0);
+        }
+        if let Some(number) = value.strip_suffix('%') {
+            return relative(number, 0.01);
+        }
+        parse_length(value).filter(|size| *size > 0.0)
+    }
+
+    fn apply_declaration(&mut self, selector: &str, property: &str, value: &str) {
+        let heading = selector
+            .strip_prefix('h')
+            .and_then(|level| level.parse::<usize>().ok())
+            .filter(|level| (1..=6).contains(level));
+
+        match (selector, property) {
+            ("body" | "html" | "p", "font-size") => {
+                if let Some(size) = self.font_size_value(value) {
+                    self.font_size = size;
+                }
+            }
+            ("body" | "html" | "p", "line-height") => {
+                let ratio = match value.parse::<f32>() {
+                    Ok(ratio) => Some(ratio),
+                    Err(_) => self.font_size_value(value).map(|size| size / self.font_size),
+                };
+                if let Some(ratio) = ratio.filter(|r| *r >= 1.0) {
+                    self.line_height = ratio;
+                }
+            }
+            ("body" | "html" | "p", "color") => self.color = parse_color(value).unwrap_or(self.color),
+            (_, "font-size") if heading.is_some() => {
+                self.heading_sizes[heading.unwrap_or(1) - 1] = self.font_size_value(value);
+            }
+            (_, "color") if heading.is_some() => {
+                let index = heading.unwrap_or(1) - 1;
+                self.heading_colors[index] = parse_color(value).unwrap_or(self.heading_colors[index]);
+            }
+            ("code" | "pre" | "pre code", "font-size") => self.code_size = self.font_size_value(value),
+            ("code" | "pre" | "pre code", "color") => self.code_color = parse_color(value).unwrap_or(self.code_color),
+            ("code" | "pre" | "pre code", "background" | "background-color") => {
+                self.code_background = parse_color(value).unwrap_or(self.code_background)
+            }
+            ("a", "color") => self.link_color = parse_color(value).unwrap_or(self.link_color),
+            ("blockquote", "color") => self.muted_color = parse_color(value).unwrap_or(self.muted_color),
+            _ => {}
+        }
+    }
+}
+
+// ---------- 字体 ----------
+
+struct FontFile {
+    data: Vec<u8>,
+    index: u32,
+}
+
+struct FontBook {
+    files: Vec<FontFile>,
+    // 正文与代码各自的回退链，元素为 files 下标
+    text: Vec<usize>,
+    mono: Vec<usize>,
+}
+
+fn font_book() -> Result<&'static FontBook> {
+    static BOOK: OnceLock<Option<FontBook>> = OnceLock::new();
+    BOOK.get_or_init(load_font_book)
+        .as_ref()
+        .ok_or_else(|| anyhow!("No usable font was found on this system for PDF export"))
+}
+
+fn load_font_book() -> Option<FontBook> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let mut loaded: Vec<fontdb::ID> = Vec::new();
+    let mut files: Vec<FontFile> = Vec::new();
+    let mut load = |id: fontdb::ID| -> Option<usize> {
+        if let Some(position) = loaded.iter().position(|l| *l == id) {
+            return Some(position);
+        }
+        let file = db
+            .with_face_data(id, |data, index| {
+                Face::parse(data, index).ok().map(|_| FontFile { data: data.to_vec(), index })
+            })
+            .flatten()?;
+        loaded.push(id);
+        files.push(file);
+        Some(files.len() - 1)
+    };
+
+    let cjk = find_family(&db, TEXT_FAMILIES).or_else(|| find_covering(&db, '中', false));
+    let latin = find_family(&db, LATIN_FAMILIES).or_else(|| find_covering(&db, 'a', false));
+    let mono = find_family(&db, MONO_FAMILIES).or_else(|| find_covering(&db, 'a', true));
+
+    let mut text: Vec<usize> = Vec::new();
+    for id in [cjk, latin].into_iter().flatten() {
+        if let Some(index) = load(id).filter(|i| !text.contains(i)) {
+            text.push(index);
+        }
+    }
+    if text.is_empty() {
+        return None;
+    }
+    let mut mono_chain: Vec<usize> = mono.and_then(&mut load).into_iter().collect();
+    for index in &text {
+        if !mono_chain.contains(index) {
+            mono_chain.push(*index);
+        }
+    }
+
+    Some(FontBook { files, text, mono: mono_chain })
+}
+
+fn find_family(db: &fontdb::Database, families: &[&str]) -> Option<fontdb::ID> {
+    families.iter().find_map(|name| {
+        db.query(&fontdb::Query {
+            families: &[fontdb::Family::Name(name)],
+            ..Default::default()
+        })
+    })
+}
+
+// 首选字体都不存在时，找一个常规字重、包含指定字符的字体
+fn find_covering(db: &fontdb::Database, c: char, monospaced: bool) -> Option<fontdb::ID> {
+    let mut candidates: Vec<&fontdb::FaceInfo> = db
+        .faces()
+        .filter(|face| face.style == fontdb::Style::Normal && (!monospaced || face.monospaced))
+        .collect();
+    candidates.sort_by_key(|face| ((face.weight.0 as i32 - 400).abs(), face.post_script_name.clone()));
+    candidates
+        .into_iter()
+        .find(|face| {
+            db.with_face_data(face.id, |data, index| {
+                Face::parse(data, index).is_ok_and(|parsed| parsed.glyph_index(c).is_some())
+            })
+            .unwrap_or(false)
+        })
+        .map(|face| face.id)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    face: usize,
+    id: u16,
+    ch: char,
+    advance: f32,
+}
+
+struct Fonts<'a> {
+    book: &'a FontBook,
+    faces: Vec<Face<'a>>,
+}
+
+impl<'a> Fonts<'a> {
+    fn new(book: &'a FontBook) -> Result<Self> {
+        let faces = book
+            .files
+            .iter()
+            .map(|file| Face::parse(&file.data, file.index))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { book, faces })
+    }
+
+    fn glyph(&self, c: char, mono: bool, size: f32) -> Glyph {
+        let chain = if mono { &self.book.mono } else { &self.book.text };
+        let c = if c == '\t' { ' ' } else { c };
+        let (face, id) = chain
+            .iter()
+            .find_map(|&index| self.faces[index].glyph_index(c).map(|glyph| (index, glyph.0)))
+            .unwrap_or((chain[0], 0));
+        let parsed = &self.faces[face];
+        let advance = parsed.glyph_hor_advance(GlyphId(id)).unwrap_or(0) as f32 / parsed.units_per_em() as f32 * size;
+        Glyph { face, id, ch: c, advance }
+    }
+
+    fn has(&self, c: char) -> bool {
+        self.book.text.iter().any(|&index| self.faces[index].glyph_index(c).is_some())
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.glyph(c, false, size).advance).sum()
+    }
+}
+
+// CID 字体写入的是 CID 而不是字形编号，CID-keyed CFF 字体两者不同
+fn glyph_cid(face: &Face, glyph: u16) -> u16 {
+    face.tables()
+        .cff
+        .and_then(|cff| cff.glyph_cid(GlyphId(glyph)))
+        .unwrap_or(glyph)
+}
+
+// ---------- 排版 ----------
+
+#[derive(Debug, Clone)]
+enum Item {
+    // y 为基线到页面顶部的距离
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        face: usize,
+        glyphs: Vec<(u16, char)>,
+        color: Color,
+        bold: bool,
+        italic: bool,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        fill: Color,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        width: f32,
+        color: Color,
+        dashed: bool,
+    },
+    Image {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        image: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Marks {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    strike: bool,
+    link: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    marks: Marks,
+}
+
+impl Span {
+    fn plain(text: impl Into<String>) -> Self {
+        Self { text: text.into(), marks: Marks::default() }
+    }
+}
+
+enum Inline {
+    Text(Vec<Span>),
+    Image { url: String, alt: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Word,
+    Space,
+    Break,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    glyphs: Vec<Glyph>,
+    marks: Marks,
+    width: f32,
+}
+
+impl Token {
+    fn new(kind: TokenKind, marks: Marks) -> Self {
+        Self { kind, glyphs: Vec::new(), marks, width: 0.0 }
+    }
+
+    fn push(&mut self, glyph: Glyph) {
+        self.width += glyph.advance;
+        self.glyphs.push(glyph);
+    }
+}
+
+// 同一字体、同一样式的一段连续字形
+struct Run {
+    face: usize,
+    marks: Marks,
+    x: f32,
+    width: f32,
+    glyphs: Vec<(u16, char)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TextOpts {
+    size: f32,
+    line_height: f32,
+    color: Color,
+    bold: bool,
+    italic: bool,
+    align: Align,
+}
+
+// 中日韩文字和全角标点可以在任意字符间断行
+fn breaks_anywhere(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x303F     // CJK 部首、符号和标点
+        | 0x3040..=0x30FF   // 平假名、片假名
+        | 0x3400..=0x4DBF   // CJK 扩展 A
+        | 0x4E00..=0x9FFF   // CJK 统一表意文字
+        | 0xAC00..=0xD7AF   // 韩文音节
+        | 0xF900..=0xFAFF   // CJK 兼容表意文字
+        | 0xFF00..=0xFFEF   // 全角字符
+        | 0x20000..=0x2FA1F // CJK 扩展 B-F
+    )
+}
+
+fn tokenize(fonts: &Fonts, spans: &[Span], size: f32) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for span in spans {
+        let mono = span.marks.code;
+        let mut word: Option<Token> = None;
+        for c in span.text.chars() {
+            if c == '\n' {
+                tokens.extend(word.take());
+                tokens.push(Token::new(TokenKind::Break, span.marks));
+            } else if c.is_whitespace() {
+                tokens.extend(word.take());
+                let mut space = Token::new(TokenKind::Space, span.marks);
+                space.push(fonts.glyph(' ', mono, size));
+                tokens.push(space);
+            } else if breaks_anywhere(c) {
+                tokens.extend(word.take());
+                let mut single = Token::new(TokenKind::Word, span.marks);
+                single.push(fonts.glyph(c, mono, size));
+                tokens.push(single);
+            } else {
+                word.get_or_insert_with(|| Token::new(TokenKind::Word, span.marks))
+                    .push(fonts.glyph(c, mono, size));
+            }
+        }
+        tokens.extend(word);
+    }
+    tokens
+}
+
+fn trim_line(line: &mut Vec<Token>) {
+    while line.last().is_some_and(|t| t.kind == TokenKind::Space) {
+        line.pop();
+    }
+}
+
+// 贪心断行；单个词比一行还宽时按字符拆开
+fn break_lines(tokens: Vec<Token>, width: f32) -> Vec<Vec<Token>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<Token> = Vec::new();
+    let mut used = 0.0;
+
+    for token in tokens {
+        match token.kind {
+            TokenKind::Break => {
+                trim_line(&mut line);
+                lines.push(std::mem::take(&mut line));
+                used = 0.0;
+            }
+            TokenKind::Space => {
+                if !line.is_empty() {
+                    used += token.width;
+                    line.push(token);
+                }
+            }
+            TokenKind::Word => {
+                if used + token.width > width && !line.is_empty() {
+                    trim_line(&mut line);
+                    lines.push(std::mem::take(&mut line));
+                    used = 0.0;
+                }
+                if token.width <= width {
+                    used += token.width;
+                    line.push(token);
+                    continue;
+                }
+                let mut piece = Token::new(TokenKind::Word, token.marks);
+                for glyph in token.glyphs {
+                    if piece.width + glyph.advance > width && !piece.glyphs.is_empty() {
+                        lines.push(vec![std::mem::replace(&mut piece, Token::new(TokenKind::Word, token.marks))]);
+                    }
+                    piece.push(glyph);
+                }
+                used = piece.width;
+                line.push(piece);
+            }
+        }
+    }
+    trim_line(&mut line);
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn line_width(line: &[Token]) -> f32 {
+    line.iter().map(|t| t.width).sum()
+}
+
+fn plain_text(spans: &[Span]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect::<String>().trim().to_string()
+}
+
+#[derive(Debug, Clone)]
+struct HeadingEntry {
+    level: u8,
+    title: String,
+    page: usize,
+}
+
+struct PdfImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    jpeg: bool,
+    gray: bool,
+    alpha: Option<Vec<u8>>,
+}
+
+struct Layout<'a> {
+    fonts: &'a Fonts<'a>,
+    style: &'a PdfStyle,
+    page: PageGeometry,
+    pages: Vec<Vec<Item>>,
+    // 当前位置到内容区顶部的距离
+    y: f32,
+    indent: f32,
+    quote_depth: usize,
+    list_depth: usize,
+    tight: bool,
+    pending_marker: Option<(String, f32)>,
+    heading_offset: u8,
+    headings: Vec<HeadingEntry>,
+    images: Vec<PdfImage>,
+    image_cache: HashMap<String, usize>,
+    footnotes: HashMap<String, u32>,
+    base_dir: Option<PathBuf>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(fonts: &'a Fonts<'a>, style: &'a PdfStyle, page: PageGeometry, base_dir: Option<&Path>) -> Self {
+        Self {
+            fonts,
+            style,
+            page,
+            pages: vec![Vec::new()],
+            y: 0.0,
+            indent: 0.0,
+            quote_depth: 0,
+            list_depth: 0,
+            tight: false,
+            pending_marker: None,
+            heading_offset: 0,
+            headings: Vec::new(),
+            images: Vec::new(),
+            image_cache: HashMap::new(),
+            footnotes: HashMap::new(),
+            base_dir: base_dir.map(Path::to_path_buf),
+        }
+    }
+
+    fn x(&self) -> f32 {
+        self.page.left + self.indent
+    }
+
+    fn width(&self) -> f32 {
+        self.page.content_width() - self.indent
+    }
+
+    fn top(&self) -> f32 {
+        self.page.top + self.y
+    }
+
+    fn push(&mut self, item: Item) {
+        if let Some(page) = self.pages.last_mut() {
+            page.push(item);
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = 0.0;
+    }
+
+    fn ensure(&mut self, height: f32) {
+        if self.y > 0.0 && self.y + height > self.page.content_height() {
+            self.new_page();
+        }
+    }
+
+    // 页首不留空白
+    fn gap(&mut self, height: f32) {
+        if self.y > 0.0 {
+            self.y += height;
+        }
+    }
+
+    fn body_opts(&self) -> TextOpts {
+        TextOpts {
+            size: self.style.font_size,
+            line_height: self.style.font_size * self.style.line_height,
+            color: if self.quote_depth > 0 { self.style.muted_color } else { self.style.color },
+            bold: false,
+            italic: false,
+            align: Align::Left,
+        }
+    }
+
+    fn marker_or(&self, preferred: &str, fallback: &str) -> String {
+        if preferred.chars().all(|c| self.fonts.has(c)) { preferred } else { fallback }.to_string()
+    }
+
+    fn text_block(&mut self, spans: &[Span], opts: TextOpts) {
+        let (x, width) = (self.x(), self.width());
+        let tokens = tokenize(self.fonts, spans, opts.size);
+        for line in break_lines(tokens, width) {
+            self.ensure(opts.line_height);
+            let baseline = self.top() + (opts.line_height - opts.size) / 2.0 + opts.size * BASELINE_RATIO;
+            if let Some((marker, marker_x)) = self.pending_marker.take() {
+                let marker = tokenize(self.fonts, &[Span::plain(marker)], opts.size);
+                self.draw_line(&marker, &TextOpts { align: Align::Left, ..opts }, marker_x, 0.0, baseline);
+            }
+            self.draw_line(&line, &opts, x, width, baseline);
+            self.y += opts.line_height;
+        }
+    }
+
+    fn draw_line(&mut self, line: &[Token], opts: &TextOpts, x: f32, width: f32, baseline: f32) {
+        let mut cursor = x + match opts.align {
+            Align::Left => 0.0,
+            Align::Center => ((width - line_width(line)) / 2.0).max(0.0),
+            Align::Right => (width - line_width(line)).max(0.0),
+        };
+
+        let mut run: Option<Run> = None;
+        for token in line {
+            for glyph in &token.glyphs {
+                let same = run
+                    .as_ref()
+                    .is_some_and(|r| r.face == glyph.face && r.marks == token.marks);
+                if !same {
+                    if let Some(finished) = run.take() {
+                        self.flush_run(finished, opts, baseline);
+                    }
+                    run = Some(Run { face: glyph.face, marks: token.marks, x: cursor, width: 0.0, glyphs: Vec::new() });
+                }
+                if let Some(current) = run.as_mut() {
+                    current.width += glyph.advance;
+                    current.glyphs.push((glyph.id, glyph.ch));
+                }
+                cursor += glyph.advance;
+            }
+        }
+        if let Some(finished) = run {
+            self.flush_run(finished, opts, baseline);
+        }
+    }
+
+    fn flush_run(&mut self, run: Run, opts: &TextOpts, baseline: f32) {
+        let Run { face, marks, x, width, glyphs } = run;
+        let size = opts.size;
+        if marks.code {
+            self.push(Item::Rect {
+                x: x - 1.5,
+                y: baseline - size * 0.85,
+                width: width + 3.0,
+                height: size * 1.1,
+                fill: self.style.code_background,
+            });
+        }
+        let color = if marks.link {
+            self.style.link_color
+        } else if marks.code {
+            self.style.code_color
+        } else {
+            opts.color
+        };
+        self.push(Item::Text {
+            x,
+            y: baseline,
+            size,
+            face,
+            glyphs,
+            color,
+            bold: opts.bold || marks.bold,
+            italic: opts.italic || marks.italic,
+        });
+        let rule = |offset: f32| Item::Line {
+            x1: x,
+            y1: baseline + offset,
+            x2: x + width,
+            y2: baseline + offset,
+            width: (size * 0.05).max(0.4),
+            color,
+            dashed: false,
+        };
+        if marks.strike {
+            let item = rule(-size * 0.3);
+            self.push(item);
+        }
+        if marks.link {
+            let item = rule(size * 0.12);
+            self.push(item);
+        }
+    }
+
+    // ----- 文档结构 -----
+
+    fn cover(&mut self, title: &str, description: &str) {
+        let size = self.style.heading_size(1) * 1.4;
+        self.y = self.page.content_height() * 0.3;
+        self.text_block(
+            &[Span::plain(title)],
+            TextOpts { size, line_height: size * 1.3, color: self.style.heading_color(1), bold: true, italic: false, align: Align::Center },
+        );
+        if !description.trim().is_empty() {
+            self.y += self.style.font_size * 1.5;
+            let opts = TextOpts { color: self.style.muted_color, align: Align::Center, ..self.body_opts() };
+            self.text_block(&[Span::plain(description.trim())], opts);
+        }
+        self.new_page();
+    }
+
+    fn document(&mut self, document: &Document, in_project: bool) {
+        if self.y > 0.0 {
+            self.new_page();
+        }
+        let size = self.style.heading_size(1) * 1.15;
+        if in_project {
+            self.headings.push(HeadingEntry { level: 1, title: document.title.clone(), page: self.pages.len() - 1 });
+        }
+        self.text_block(
+            &[Span::plain(document.title.as_str())],
+            TextOpts { size, line_height: size * 1.3, color: self.style.heading_color(1), bold: true, italic: false, align: Align::Left },
+        );
+
+        let mut meta: Vec<String> = Vec::new();
+        if let Some(author) = document.metadata.author.as_deref().filter(|a| !a.trim().is_empty()) {
+            meta.push(author.trim().to_string());
+        }
+        meta.push(document.updated_at.format("%Y-%m-%d").to_string());
+        meta.push(format!("{} 字", document.word_count));
+        if !document.tags.is_empty() {
+            meta.push(document.tags.join(", "));
+        }
+        let opts = TextOpts {
+            size: self.style.font_size * 0.85,
+            line_height: self.style.font_size * 1.4,
+            color: self.style.muted_color,
+            ..self.body_opts()
+        };
+        self.text_block(&[Span::plain(meta.join(" · "))], opts);
+        self.gap(self.style.font_size * 1.5);
+
+        self.heading_offset = u8::from(in_project);
+        match document.content_type {
+            DocumentType::Markdown => self.markdown(&document.content),
+            DocumentType::PlainText => self.plain_text(&document.content),
+            DocumentType::RichText => self.plain_text(&html_to_text(&document.content)),
+        }
+    }
+
+    fn plain_text(&mut self, text: &str) {
+        let gap = self.style.font_size * 0.6;
+        for paragraph in text.replace("\r\n", "\n").split("\n\n") {
+            if paragraph.trim().is_empty() {
+                continue;
+            }
+            let opts = self.body_opts();
+            self.text_block(&[Span::plain(paragraph.trim_end())], opts);
+            self.gap(gap);
+        }
+    }
+
+    fn markdown(&mut self, content: &str) {
+        let arena = Arena::new();
+        let root = parse_document(&arena, content, &markdown_options());
+        self.footnotes.clear();
+        self.blocks(root);
+    }
+
+    fn blocks<'n>(&mut self, node: &'n AstNode<'n>) {
+        for child in node.children() {
+            self.block(child);
+        }
+    }
+
+    fn block<'n>(&mut self, node: &'n AstNode<'n>) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Paragraph => self.paragraph(node),
+            NodeValue::Heading(heading) => {
+                let spans = self.inline_spans(node);
+                self.heading(heading.level, &spans);
+            }
+            NodeValue::List(list) => self.list(node, &list),
+            NodeValue::CodeBlock(code) => self.code_block(&code.literal),
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) | NodeValue::Alert(_) => self.block_quote(node),
+            NodeValue::ThematicBreak => self.rule(),
+            NodeValue::Table(table) => self.table(node, &table.alignments),
+            NodeValue::FootnoteDefinition(definition) => {
+                if let Some(&number) = self.footnotes.get(&definition.name) {
+                    self.footnote(node, number);
+                }
+            }
+            NodeValue::HtmlBlock(_) | NodeValue::FrontMatter(_) => {}
+            _ => self.blocks(node),
+        }
+    }
+
+    fn collect_inlines<'n>(&mut self, node: &'n AstNode<'n>, marks: Marks, out: &mut Vec<Inline>) {
+        for child in node.children() {
+            let value = child.data.borrow().value.clone();
+            match value {
+                NodeValue::Text(text) => push_text(out, &text, marks),
+                NodeValue::Code(code) => push_text(out, &code.literal, Marks { code: true, ..marks }),
+                NodeValue::SoftBreak => push_text(out, " ", marks),
+                NodeValue::LineBreak => push_text(out, "\n", marks),
+                NodeValue::HtmlInline(html) if html.to_lowercase().starts_with("<br") => push_text(out, "\n", marks),
+                NodeValue::HtmlInline(_) => {}
+                NodeValue::Emph => self.collect_inlines(child, Marks { italic: true, ..marks }, out),
+                NodeValue::Strong => self.collect_inlines(child, Marks { bold: true, ..marks }, out),
+                NodeValue::Strikethrough => self.collect_inlines(child, Marks { strike: true, ..marks }, out),
+                NodeValue::Link(_) => self.collect_inlines(child, Marks { link: true, ..marks }, out),
+                NodeValue::Image(link) => {
+                    let mut alt = Vec::new();
+                    self.collect_inlines(child, marks, &mut alt);
+                    out.push(Inline::Image { url: link.url, alt: inline_text(&alt) });
+                }
+                NodeValue::FootnoteReference(reference) => {
+                    self.footnotes.insert(reference.name, reference.ix);
+                    push_text(out, &format!("[{}]", reference.ix), marks);
+                }
+                _ => self.collect_inlines(child, marks, out),
+            }
+        }
+    }
+
+    // 标题、表格单元格等只取文字，图片以替代文本代替
+    fn inline_spans<'n>(&mut self, node: &'n AstNode<'n>) -> Vec<Span> {
+        let mut inlines = Vec::new();
+        self.collect_inlines(node, Marks::default(), &mut inlines);
+        let mut spans = Vec::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => spans.extend(text),
+                Inline::Image { alt, .. } => spans.push(Span::plain(format!("[{}]", alt))),
+            }
+        }
+        spans
+    }
+
+    fn paragraph<'n>(&mut self, node: &'n AstNode<'n>) {
+        let mut inlines = Vec::new();
+        self.collect_inlines(node, Marks::default(), &mut inlines);
+        for inline in inlines {
+            match inline {
+                Inline::Text(spans) => {
+                    if spans.iter().any(|s| !s.text.trim().is_empty()) || self.pending_marker.is_some() {
+                        let opts = self.body_opts();
+                        self.text_block(&spans, opts);
+                    }
+                }
+                Inline::Image { url, alt } => self.image(&url, &alt),
+            }
+        }
+        let factor = if self.tight { 0.25 } else { 0.6 };
+        self.gap(self.style.font_size * factor);
+    }
+
+    fn heading(&mut self, level: u8, spans: &[Span]) {
+        let size = self.style.heading_size(level);
+        let line_height = size * 1.3;
+        self.gap(size * 0.8);
+        // 标题至少与后面两行正文在同一页
+        self.ensure(line_height + self.style.font_size * self.style.line_height * 2.0);
+        self.headings.push(HeadingEntry {
+            level: level.saturating_add(self.heading_offset),
+            title: plain_text(spans),
+            page: self.pages.len() - 1,
+        });
+        let opts = TextOpts { size, line_height, color: self.style.heading_color(level), bold: true, italic: false, align: Align::Left };
+        self.text_block(spans, opts);
+        self.gap(size * 0.4);
+    }
+
+    fn list<'n>(&mut self, node: &'n AstNode<'n>, list: &NodeList) {
+        let indent = self.style.font_size * 1.8;
+        let saved_tight = std::mem::replace(&mut self.tight, list.tight);
+        let bullet = match self.list_depth % 3 {
+            0 => self.marker_or("•", "-"),
+            1 => self.marker_or("◦", "-"),
+            _ => self.marker_or("▪", "-"),
+        };
+        self.list_depth += 1;
+
+        for (index, item) in node.children().enumerate() {
+            let marker = match &item.data.borrow().value {
+                NodeValue::TaskItem(checked) => {
+                    if checked.is_some() {
+                        self.marker_or("☑", "[x]")
+                    } else {
+                        self.marker_or("☐", "[ ]")
+                    }
+                }
+                _ if list.list_type == ListType::Ordered => format!("{}.", list.start + index),
+                _ => bullet.clone(),
+            };
+            self.indent += indent;
+            self.pending_marker = Some((marker, self.x() - indent * 0.8));
+            self.blocks(item);
+            if self.pending_marker.is_some() {
+                let opts = self.body_opts();
+                self.text_block(&[], opts);
+            }
+            self.indent -= indent;
+        }
+
+        self.list_depth -= 1;
+        self.tight = saved_tight;
+        if list.tight {
+            self.gap(self.style.font_size * 0.4);
+        }
+    }
+
+    fn footnote<'n>(&mut self, node: &'n AstNode<'n>, number: u32) {
+        let indent = self.style.font_size * 1.8;
+        self.indent += indent;
+        self.pending_marker = Some((format!("[{}]", number), self.x() - indent));
+        self.blocks(node);
+        self.indent -= indent;
+    }
+
+    fn code_block(&mut self, code: &str) {
+        let size = self.style.code_size();
+        let line_height = size * 1.45;
+        let padding = size * 0.7;
+        let (x, width) = (self.x(), self.width());
+        let inner = width - padding * 2.0;
+
+        let mut lines: Vec<Vec<Glyph>> = Vec::new();
+        for source in code.trim_end_matches('\n').split('\n') {
+            let mut line: Vec<Glyph> = Vec::new();
+            let mut used = 0.0;
+            for c in source.replace('\t', "    ").trim_end().chars() {
+                let glyph = self.fonts.glyph(c, true, size);
+                if used + glyph.advance > inner && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    used = 0.0;
+                }
+                used += glyph.advance;
+                line.push(glyph);
+            }
+            lines.push(line);
+        }
+
+        let background = self.style.code_background;
+        let opts = TextOpts { size, line_height, color: self.style.code_color, bold: false, italic: false, align: Align::Left };
+        self.ensure(line_height + padding * 2.0);
+        self.push(Item::Rect { x, y: self.top(), width, height: padding, fill: background });
+        self.y += padding;
+        for line in lines {
+            self.ensure(line_height);
+            self.push(Item::Rect { x, y: self.top(), width, height: line_height, fill: background });
+            let baseline = self.top() + (line_height - size) / 2.0 + size * BASELINE_RATIO;
+            let mut token = Token::new(TokenKind::Word, Marks::default());
+            line.into_iter().for_each(|glyph| token.push(glyph));
+            self.draw_line(&[token], &opts, x + padding, inner, baseline);
+            self.y += line_height;
+        }
+        self.push(Item::Rect { x, y: self.top(), width, height: padding, fill: background });
+        self.y += padding;
+        self.gap(self.style.font_size * 0.8);
+    }
+
+    fn block_quote<'n>(&mut self, node: &'n AstNode<'n>) {
+        let bar_x = self.x() + 1.5;
+        let start = (self.pages.len() - 1, self.top());
+        self.indent += self.style.font_size * 1.2;
+        self.quote_depth += 1;
+        self.blocks(node);
+        self.quote_depth -= 1;
+        self.indent -= self.style.font_size * 1.2;
+
+        // 引用跨页时每页各画一段竖线
+        let last_page = self.pages.len() - 1;
+        let end = (self.top() - self.style.font_size * 0.6).max(self.page.top);
+        for page in start.0..=last_page {
+            let top = if page == start.0 { start.1 } else { self.page.top };
+            let bottom = if page == last_page { end } else { self.page.height - self.page.bottom };
+            if bottom > top {
+                self.pages[page].push(Item::Line {
+                    x1: bar_x,
+                    y1: top,
+                    x2: bar_x,
+                    y2: bottom,
+                    width: 3.0,
+                    color: self.style.border_color,
+                    dashed: false,
+                });
+            }
+        }
+    }
+
+    fn rule(&mut self) {
+        let size = self.style.font_size;
+        self.gap(size * 0.4);
+        self.ensure(size);
+        let y = self.top() + size / 2.0;
+        let (x, width) = (self.x(), self.width());
+        self.push(Item::Line { x1: x, y1: y, x2: x + width, y2: y, width: 1.0, color: self.style.border_color, dashed: false });
+        self.y += size * 1.4;
+    }
+
+    fn table<'n>(&mut self, node: &'n AstNode<'n>, alignments: &[TableAlignment]) {
+        let mut rows: Vec<(bool, Vec<Vec<Span>>)> = Vec::new();
+        for row in node.children() {
+            let header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+            let cells = row.children().map(|cell| self.inline_spans(cell)).collect();
+            rows.push((header, cells));
+        }
+        let columns = alignments.len().max(1);
+        let size = self.style.font_size * 0.95;
+        let line_height = size * 1.45;
+        let padding = size * 0.5;
+        let (x, available) = (self.x(), self.width());
+
+        // 列宽按内容自然宽度分配，总宽超出时等比缩小
+        let mut natural = vec![size * 2.0; columns];
+        for (header, cells) in &rows {
+            for (column, cell) in cells.iter().enumerate().take(columns) {
+                let spans: Vec<Span> = cell.iter().map(|s| Span { marks: Marks { bold: *header || s.marks.bold, ..s.marks }, ..s.clone() }).collect();
+                let width = line_width(&tokenize(self.fonts, &spans, size)) + padding * 2.0;
+                natural[column] = natural[column].max(width);
+            }
+        }
+        let total: f32 = natural.iter().sum();
+        let widths: Vec<f32> = if total <= available {
+            natural
+        } else {
+            natural.iter().map(|w| w * available / total).collect()
+        };
+        let table_width: f32 = widths.iter().sum();
+
+        for (header, cells) in rows {
+            let opts = TextOpts { size, line_height, color: self.body_opts().color, bold: header, italic: false, align: Align::Left };
+            let laid_out: Vec<Vec<Vec<Token>>> = (0..columns)
+                .map(|column| {
+                    let spans = cells.get(column).map(Vec::as_slice).unwrap_or(&[]);
+                    break_lines(tokenize(self.fonts, spans, size), widths[column] - padding * 2.0)
+                })
+                .collect();
+            let row_height = laid_out.iter().map(Vec::len).max().unwrap_or(1) as f32 * line_height + padding * 2.0;
+            self.ensure(row_height);
+            let top = self.top();
+            if header {
+                self.push(Item::Rect { x, y: top, width: table_width, height: row_height, fill: self.style.code_background });
+            }
+
+            let mut cell_x = x;
+            for (column, lines) in laid_out.into_iter().enumerate() {
+                let align = match alignments.get(column) {
+                    Some(TableAlignment::Center) => Align::Center,
+                    Some(TableAlignment::Right) => Align::Right,
+                    _ => Align::Left,
+                };
+                let inner = widths[column] - padding * 2.0;
+                for (index, line) in lines.iter().enumerate() {
+                    let baseline = top + padding + index as f32 * line_height + (line_height - size) / 2.0 + size * BASELINE_RATIO;
+                    self.draw_line(line, &TextOpts { align, ..opts }, cell_x + padding, inner, baseline);
+                }
+                cell_x += widths[column];
+            }
+
+            let border = self.style.border_color;
+            let bottom = top + row_height;
+            for y in [top, bottom] {
+                self.push(Item::Line { x1: x, y1: y, x2: x + table_width, y2: y, width: 0.6, color: border, dashed: false });
+            }
+            let mut edge = x;
+            for width in std::iter::once(0.0).chain(widths.iter().copied()) {
+                edge += width;
+                self.push(Item::Line { x1: edge, y1: top, x2: edge, y2: bottom, width: 0.6, color: border, dashed: false });
+            }
+            self.y += row_height;
+        }
+        self.gap(self.style.font_size * 0.8);
+    }
+
+    fn image(&mut self, url: &str, alt: &str) {
+        let index = match self.image_cache.get(url) {
+            Some(&index) => Some(index),
+            None => match load_image(url, self.base_dir.as_deref()) {
+                Ok(image) => {
+                    self.images.push(image);
+                    self.image_cache.insert(url.to_string(), self.images.len() - 1);
+                    Some(self.images.len() - 1)
+                }
+                Err(_) => None,
+            },
+        };
+        let Some(index) = index else {
+            // 无法嵌入的图片（远程地址、文件缺失等）以替代文本占位
+            let label = if alt.is_empty() { url } else { alt };
+            let opts = TextOpts { color: self.style.muted_color, italic: true, ..self.body_opts() };
+            self.text_block(&[Span::plain(format!("[图片: {}]", label))], opts);
+            return;
+        };
+
+        // 按 96 DPI 换算，再缩放到版心之内
+        let image = &self.images[index];
+        let (mut width, mut height) = (image.width as f32 * 0.75, image.height as f32 * 0.75);
+        let max_height = self.page.content_height() * 0.9;
+        let scale = (self.width() / width).min(max_height / height).min(1.0);
+        width *= scale;
+        height *= scale;
+
+        self.ensure(height);
+        let x = self.x();
+        let y = self.top();
+        self.push(Item::Image { x, y, width, height, image: index });
+        self.y += height;
+        self.gap(self.style.font_size * 0.6);
+    }
+
+    // ----- 目录与页码 -----
+
+    fn toc_title(&mut self) {
+        let size = self.style.heading_size(1);
+        let opts = TextOpts { size, line_height: size * 1.3, color: self.style.heading_color(1), bold: true, italic: false, align: Align::Left };
+        let title = self.marker_or("目录", "Contents");
+        self.text_block(&[Span::plain(title)], opts);
+        self.gap(size * 0.6);
+    }
+
+    fn toc_entry(&mut self, entry: &HeadingEntry, number: usize) {
+        let opts = TextOpts { bold: entry.level == 1, ..self.body_opts() };
+        let indent = (entry.level.saturating_sub(1)) as f32 * opts.size * 1.5;
+        let column = opts.size * 3.0;
+        let x = self.x() + indent;
+        let available = self.width() - indent - column;
+        let number_text = number.to_string();
+        let number_width = self.fonts.width(&number_text, opts.size);
+        let right = self.page.width - self.page.right;
+
+        let lines = break_lines(tokenize(self.fonts, &[Span::plain(entry.title.as_str())], opts.size), available);
+        let count = lines.len();
+        for (index, line) in lines.into_iter().enumerate() {
+            self.ensure(opts.line_height);
+            let baseline = self.top() + (opts.line_height - opts.size) / 2.0 + opts.size * BASELINE_RATIO;
+            self.draw_line(&line, &opts, x, available, baseline);
+            if index + 1 == count {
+                let leader_start = x + line_width(&line) + opts.size * 0.4;
+                let leader_end = right - number_width - opts.size * 0.4;
+                if leader_end > leader_start {
+                    self.push(Item::Line {
+                        x1: leader_start,
+                        y1: baseline,
+                        x2: leader_end,
+                        y2: baseline,
+                        width: 0.8,
+                        color: self.style.muted_color,
+                        dashed: true,
+                    });
+                }
+                let number_tokens = tokenize(self.fonts, &[Span::plain(number_text.as_str())], opts.size);
+                self.draw_line(&number_tokens, &opts, right - number_width, number_width, baseline);
+            }
+            self.y += opts.line_height;
+        }
+    }
+
+    fn page_number(&mut self, number: usize) -> Vec<Item> {
+        self.pages = vec![Vec::new()];
+        let size = self.style.font_size * 0.85;
+        let opts = TextOpts { size, line_height: size, color: self.style.muted_color, bold: false, italic: false, align: Align::Center };
+        let tokens = tokenize(self.fonts, &[Span::plain(number.to_string())], size);
+        let baseline = self.page.height - self.page.bottom / 2.0 + size * 0.3;
+        self.draw_line(&tokens, &opts, self.page.left, self.page.content_width(), baseline);
+        self.pages.pop().unwrap_or_default()
+    }
+}
+
+fn push_text(out: &mut Vec<Inline>, text: &str, marks: Marks) {
+    if let Some(Inline::Text(spans)) = out.last_mut() {
+        match spans.last_mut() {
+            Some(last) if last.marks == marks => last.text.push_str(text),
+            _ => spans.push(Span { text: text.to_string(), marks }),
+        }
+        return;
+    }
+    out.push(Inline::Text(vec![Span { text: text.to_string(), marks }]));
+}
+
+fn inline_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(spans) => plain_text(spans),
+            Inline::Image { alt, .. } => alt.clone(),
+        })
+        .collect()
+}
+
+// 富文本正文粗略转为纯文本：去掉标签，块级标签处换行
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim_start_matches('/').to_lowercase();
+        let name: String = tag.chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        match name.as_str() {
+            "br" => text.push('\n'),
+            "p" | "div" | "li" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "pre" | "tr" => text.push_str("\n\n"),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// ---------- 图片 ----------
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // 写入内存缓冲区不会失败
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+/// 读取本地文件或 data URI 图片；相对路径基于导出目录解析，远程图片不下载
+pub(crate) fn read_image_source(url: &str, base_dir: Option<&Path>) -> Result<Vec<u8>> {
+    Ok(if let Some(data) = url.strip_prefix("data:") {
+        let (meta, payload) = data.split_once(',').ok_or_else(|| anyhow!("Malformed data URI"))?;
+        if !meta.ends_with(";base64") {
+            bail!("Only base64 data URIs are supported");
+        }
+        base64::engine::general_purpose::STANDARD.decode(payload.trim())?
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        bail!("Remote images are not embedded: {}", url);
+    } else if let Some(path) = links::attachment_path(url)? {
+        std::fs::read(path)?
+    } else {
+        let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+        let path = match base_dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+        std::fs::read(path)?
+    })
+}
+
+/// 读取图片并统一为 PNG、JPEG 或 GIF，其余格式（如 WebP）转成 PNG。返回数据、扩展名和像素尺寸
+pub(crate) fn load_portable_image(url: &str, base_dir: Option<&Path>) -> Result<(Vec<u8>, &'static str, u32, u32)> {
+    let bytes = read_image_source(url, base_dir)?;
+    let format = image::guess_format(&bytes)?;
+    let decoded = image::load_from_memory_with_format(&bytes, format)?;
+    let (width, height) = (decoded.width(), decoded.height());
+    Ok(match format {
+        ImageFormat::Png => (bytes, "png", width, height),
+        ImageFormat::Jpeg => (bytes, "jpeg", width, height),
+        ImageFormat::Gif => (bytes, "gif", width, height),
+        _ => {
+            let mut png = std::io::Cursor::new(Vec::new());
+            decoded.write_to(&mut png, ImageFormat::Png)?;
+            (png.into_inner(), "png", width, height)
+        }
+    })
+}
+
+fn load_image(url: &str, base_dir: Option<&Path>) -> Result<PdfImage> {
+    let bytes = read_image_source(url, base_dir)?;
+    let format = image::guess_format(&bytes)?;
+    let decoded = image::load_from_memory_with_format(&bytes, format)?;
+    let (width, height) = (decoded.width(), decoded.height());
+
+    // JPEG 原样嵌入，其余格式解码后压缩
+    if format == ImageFormat::Jpeg && matches!(decoded.color(), ColorType::L8 | ColorType::Rgb8) {
+        return Ok(PdfImage { width, height, data: bytes, jpeg: true, gray: decoded.color() == ColorType::L8, alpha: None });
+    }
+    let alpha = decoded.color().has_alpha().then(|| {
+        let alpha: Vec<u8> = decoded.to_rgba8().pixels().map(|p| p[3]).collect();
+        deflate(&alpha)
+    });
+    Ok(PdfImage {
+        width,
+        height,
+        data: deflate(&decoded.to_rgb8().into_raw()),
+        jpeg: false,
+        gray: false,
+        alpha,
+    })
+}
+
+// ---------- 输出 ----------
+
+struct PdfInfo {
+    title: String,
+    author: Option<String>,
+    keywords: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+fn pdf_date(time: &DateTime<Utc>) -> Date {
+    Date::new(time.year() as u16)
+        .month(time.month() as u8)
+        .day(time.day() as u8)
+        .hour(time.hour() as u8)
+        .minute(time.minute() as u8)
+        .second(time.second() as u8)
+        .utc_offset_hour(0)
+}
+
+// 子集字体名前缀：由字形集合决定的 6 个大写字母
+fn subset_tag(glyphs: &BTreeMap<u16, char>) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for glyph in glyphs.keys() {
+        for byte in glyph.to_be_bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+    (0..6)
+        .map(|i| (b'A' + ((hash >> (i * 8)) % 26) as u8) as char)
+        .collect()
+}
+
+fn write_font(pdf: &mut Pdf, alloc: &mut Ref, type0_ref: Ref, face: &Face, file: &FontFile, glyphs: &BTreeMap<u16, char>) -> Result<()> {
+    let glyph_ids: Vec<u16> = std::iter::once(0).chain(glyphs.keys().copied()).collect();
+    let subset = subsetter::subset(&file.data, file.index, subsetter::Profile::pdf(&glyph_ids)).ok();
+
+    let cid_ref = alloc.bump();
+    let descriptor_ref = alloc.bump();
+    let cmap_ref = alloc.bump();
+    let data_ref = alloc.bump();
+
+    let is_cff = face.tables().cff.is_some();
+    let postscript_name = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+        .find_map(|name| name.to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+        .replace(' ', "");
+    // 只有子集字体才加子集标记前缀
+    let base_font = match subset {
+        Some(_) => format!("{}+{}", subset_tag(glyphs), postscript_name),
+        None => postscript_name,
+    };
+    let type0_name = if is_cff { format!("{}-Identity-H", base_font) } else { base_font.clone() };
+
+    pdf.type0_font(type0_ref)
+        .base_font(Name(type0_name.as_bytes()))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid_ref)
+        .to_unicode(cmap_ref);
+
+    let units = |value: f32| value * 1000.0 / face.units_per_em() as f32;
+    let mut cid = pdf.cid_font(cid_ref);
+    cid.subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 });
+    cid.base_font(Name(base_font.as_bytes()));
+    cid.system_info(SYSTEM_INFO);
+    cid.font_descriptor(descriptor_ref);
+    cid.default_width(0.0);
+    if !is_cff {
+        cid.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    let mut widths = cid.widths();
+    for &glyph in glyphs.keys() {
+        let advance = face.glyph_hor_advance(GlyphId(glyph)).unwrap_or(0) as f32;
+        widths.consecutive(glyph_cid(face, glyph), [units(advance)]);
+    }
+    widths.finish();
+    cid.finish();
+
+    let bbox = face.global_bounding_box();
+    let mut flags = FontFlags::SYMBOLIC;
+    flags.set(FontFlags::FIXED_PITCH, face.is_monospaced());
+    let mut descriptor = pdf.font_descriptor(descriptor_ref);
+    descriptor
+        .name(Name(base_font.as_bytes()))
+        .flags(flags)
+        .bbox(Rect::new(
+            units(bbox.x_min as f32),
+            units(bbox.y_min as f32),
+            units(bbox.x_max as f32),
+            units(bbox.y_max as f32),
+        ))
+        .italic_angle(face.italic_angle().unwrap_or(0.0))
+        .ascent(units(face.ascender() as f32))
+        .descent(units(face.descender() as f32))
+        .cap_height(units(face.capital_height().unwrap_or(face.ascender()) as f32))
+        .stem_v(80.0);
+    if is_cff {
+        descriptor.font_file3(data_ref);
+    } else {
+        descriptor.font_file2(data_ref);
+    }
+    descriptor.finish();
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), SYSTEM_INFO);
+    for (&glyph, &c) in glyphs.iter().filter(|(glyph, _)| **glyph != 0) {
+        cmap.pair(glyph_cid(face, glyph), c);
+    }
+    pdf.cmap(cmap_ref, &cmap.finish());
+
+    // 子集化失败时退回嵌入整个字体；字体集合（.ttc）整体嵌入不是合法的字体程序，直接报错
+    let mut data: &[u8] = match &subset {
+        Some(subset) => subset,
+        None if ttf_parser::fonts_in_collection(&file.data).is_some() => {
+            bail!("Font \"{}\" could not be subset for PDF export", base_font);
+        }
+        None => &file.data,
+    };
+    if is_cff {
+        if let Some(cff) = ttf_parser::RawFace::parse(data, 0).ok().and_then(|raw| raw.table(CFF)) {
+            data = cff;
+        }
+    }
+    let compressed = deflate(data);
+    let mut stream = pdf.stream(data_ref, &compressed);
+    stream.filter(Filter::FlateDecode);
+    if is_cff {
+        stream.pair(Name(b"Subtype"), Name(b"CIDFontType0C"));
+    }
+    stream.finish();
+    Ok(())
+}
+
+fn page_content(items: &[Item], page: &PageGeometry, fonts: &Fonts) -> Vec<u8> {
+    let mut content = Content::new();
+    let flip = |y: f32| page.height - y;
+    for item in items {
+        match item {
+            Item::Text { x, y, size, face, glyphs, color, bold, italic } => {
+                let parsed = &fonts.faces[*face];
+                let encoded: Vec<u8> = glyphs
+                    .iter()
+                    .flat_map(|(glyph, _)| glyph_cid(parsed, *glyph).to_be_bytes())
+                    .collect();
+                let font_name = format!("F{}", face);
+                content.save_state();
+                content.set_fill_rgb(color.0, color.1, color.2);
+                if *bold {
+                    // 系统字体通常只有常规字重，粗体用描边加粗模拟
+                    content.set_stroke_rgb(color.0, color.1, color.2);
+                    content.set_line_width(size * 0.035);
+                }
+                content.begin_text();
+                content.set_font(Name(font_name.as_bytes()), *size);
+                if *bold {
+                    content.set_text_rendering_mode(TextRenderingMode::FillStroke);
+                }
+                let skew = if *italic { 0.2 } else { 0.0 };
+                content.set_text_matrix([1.0, 0.0, skew, 1.0, *x, flip(*y)]);
+                content.show(Str(&encoded));
+                content.end_text();
+                content.restore_state();
+            }
+            Item::Rect { x, y, width, height, fill } => {
+                content.save_state();
+                content.set_fill_rgb(fill.0, fill.1, fill.2);
+                content.rect(*x, flip(*y + *height), *width, *height);
+                content.fill_nonzero();
+                content.restore_state();
+            }
+            Item::Line { x1, y1, x2, y2, width, color, dashed } => {
+                content.save_state();
+                content.set_stroke_rgb(color.0, color.1, color.2);
+                content.set_line_width(*width);
+                if *dashed {
+                    content.set_dash_pattern([1.0, 2.5], 0.0);
+                }
+                content.move_to(*x1, flip(*y1));
+                content.line_to(*x2, flip(*y2));
+                content.stroke();
+                content.restore_state();
+            }
+            Item::Image { x, y, width, height, image } => {
+                let name = format!("Im{}", image);
+                content.save_state();
+                content.transform([*width, 0.0, 0.0, *height, *x, flip(*y + *height)]);
+                content.x_object(Name(name.as_bytes()));
+                content.restore_state();
+            }
+        }
+    }
+    content.finish()
+}
+
+fn write_pdf(fonts: &Fonts, page: &PageGeometry, pages: &[Vec<Item>], images: &[PdfImage], info: &PdfInfo) -> Result<Vec<u8>> {
+    let mut alloc = Ref::new(1);
+    let catalog_ref = alloc.bump();
+    let tree_ref = alloc.bump();
+    let info_ref = alloc.bump();
+
+    // 收集每个字体实际用到的字形，用于子集化和 ToUnicode 映射
+    let mut used: BTreeMap<usize, BTreeMap<u16, char>> = BTreeMap::new();
+    for item in pages.iter().flatten() {
+        if let Item::Text { face, glyphs, .. } = item {
+            let set = used.entry(*face).or_default();
+            for &(glyph, c) in glyphs {
+                set.entry(glyph).or_insert(c);
+            }
+        }
+    }
+
+    let mut pdf = Pdf::new();
+    let font_refs: BTreeMap<usize, Ref> = used.keys().map(|&face| (face, alloc.bump())).collect();
+    for (face, glyphs) in &used {
+        write_font(&mut pdf, &mut alloc, font_refs[face], &fonts.faces[*face], &fonts.book.files[*face], glyphs)?;
+    }
+
+    let mut image_refs = Vec::new();
+    for image in images {
+        let image_ref = alloc.bump();
+        let mask_ref = image.alpha.as_ref().map(|_| alloc.bump());
+        let mut xobject = pdf.image_xobject(image_ref, &image.data);
+        xobject.filter(if image.jpeg { Filter::DctDecode } else { Filter::FlateDecode });
+        xobject.width(image.width as i32);
+        xobject.height(image.height as i32);
+        if image.gray {
+            xobject.color_space().device_gray();
+        } else {
+            xobject.color_space().device_rgb();
+        }
+        xobject.bits_per_component(8);
+        if let Some(mask_ref) = mask_ref {
+            xobject.s_mask(mask_ref);
+        }
+        xobject.finish();
+        if let (Some(mask_ref), Some(alpha)) = (mask_ref, image.alpha.as_ref()) {
+            let mut mask = pdf.image_xobject(mask_ref, alpha);
+            mask.filter(Filter::FlateDecode);
+            mask.width(image.width as i32);
+            mask.height(image.height as i32);
+            mask.color_space().device_gray();
+            mask.bits_per_component(8);
+        }
+        image_refs.push(image_ref);
+    }
+
+    let font_names: Vec<(String, Ref)> = font_refs.iter().map(|(face, r)| (format!("F{}", face), *r)).collect();
+    let image_names: Vec<(String, Ref)> = image_refs.iter().enumerate().map(|(i, r)| (format!("Im{}", i), *r)).collect();
+    let mut page_refs = Vec::new();
+    for items in pages {
+        let page_ref = alloc.bump();
+        let content_ref = alloc.bump();
+        page_refs.push(page_ref);
+
+        let content = deflate(&page_content(items, page, fonts));
+        pdf.stream(content_ref, &content).filter(Filter::FlateDecode);
+
+        let mut pdf_page = pdf.page(page_ref);
+        pdf_page.media_box(Rect::new(0.0, 0.0, page.width, page.height));
+        pdf_page.parent(tree_ref);
+        pdf_page.contents(content_ref);
+        let mut resources = pdf_page.resources();
+        let mut font_dict = resources.fonts();
+        for (name, font_ref) in &font_names {
+            font_dict.pair(Name(name.as_bytes()), *font_ref);
+        }
+        font_dict.finish();
+        let mut xobjects = resources.x_objects();
+        for (name, image_ref) in &image_names {
+            xobjects.pair(Name(name.as_bytes()), *image_ref);
+        }
+        xobjects.finish();
+    }
+
+    pdf.pages(tree_ref).kids(page_refs.iter().copied()).count(page_refs.len() as i32);
+    pdf.catalog(catalog_ref).pages(tree_ref);
+
+    let keywords = info.keywords.join(", ");
+    let mut document_info = pdf.document_info(info_ref);
+    document_info
+        .title(TextStr(&info.title))
+        .creator(TextStr(GENERATOR))
+        .producer(TextStr(GENERATOR))
+        .creation_date(pdf_date(&info.created_at))
+        .modified_date(pdf_date(&info.updated_at));
+    if let Some(author) = info.author.as_deref() {
+        document_info.author(TextStr(author));
+    }
+    if !keywords.is_empty() {
+        document_info.keywords(TextStr(&keywords));
+    }
+    document_info.finish();
+
+    Ok(pdf.finish())
+}
+
+// 排版正文后按需插入目录（位于 toc_at 页之前）并加页码
+fn render(
+    options: &PdfExportOptions,
+    base_dir: Option<&Path>,
+    info: &PdfInfo,
+    toc_at: usize,
+    build: impl FnOnce(&mut Layout),
+) -> Result<Vec<u8>> {
+    let page = PageGeometry::from_options(options)?;
+    let style = PdfStyle::from_css(options.custom_css.as_deref());
+    let fonts = Fonts::new(font_book()?)?;
+
+    let mut body = Layout::new(&fonts, &style, page, base_dir);
+    build(&mut body);
+    let mut pages = std::mem::take(&mut body.pages);
+    while pages.len() > 1 && pages.last().is_some_and(Vec::is_empty) {
+        pages.pop();
+    }
+
+    let entries: Vec<&HeadingEntry> = body.headings.iter().filter(|e| e.level <= TOC_DEPTH).collect();
+    if options.include_toc && !entries.is_empty() {
+        // 目录页数会影响页码，先按估计页数排一次，页数变化时再排
+        let mut toc_pages = 1;
+        let toc = loop {
+            let mut toc = Layout::new(&fonts, &style, page, None);
+            toc.toc_title();
+            for entry in &entries {
+                let index = if entry.page >= toc_at { entry.page + toc_pages } else { entry.page };
+                toc.toc_entry(entry, index + 1);
+            }
+            if toc.pages.len() == toc_pages {
+                break toc.pages;
+            }
+            toc_pages = toc.pages.len();
+        };
+        let toc_at = toc_at.min(pages.len());
+        pages.splice(toc_at..toc_at, toc);
+    }
+
+    if options.include_page_numbers {
+        let mut footer = Layout::new(&fonts, &style, page, None);
+        for (index, items) in pages.iter_mut().enumerate() {
+            items.extend(footer.page_number(index + 1));
+        }
+    }
+
+    write_pdf(&fonts, &page, &pages, &body.images, info)
+}
+
+/// 导出单个文档
+pub fn document_to_pdf(document: &Document, options: &PdfExportOptions, base_dir: Option<&Path>) -> Result<Vec<u8>> {
+    let info = PdfInfo {
+        title: document.title.clone(),
+        author: document.metadata.author.clone().filter(|a| !a.trim().is_empty()),
+        keywords: document.tags.clone(),
+        created_at: document.created_at,
+        updated_at: document.updated_at,
+    };
+    render(options, base_dir, &info, 0, |layout| layout.document(document, false))
+}
+
+/// 把整个项目编排成一个 PDF：封面、目录，之后每篇文档另起一页。documents 需已按目录顺序排好
+pub fn project_to_pdf(
+    project: &Project,
+    documents: &[Document],
+    options: &PdfExportOptions,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>> {
+    let mut keywords: Vec<String> = Vec::new();
+    for tag in documents.iter().flat_map(|d| &d.tags) {
+        if !keywords.contains(tag) {
+            keywords.push(tag.clone());
+        }
+    }
+    let info = PdfInfo {
+        title: project.name.clone(),
+        author: documents
+            .iter()
+            .find_map(|d| d.metadata.author.clone())
+            .filter(|a| !a.trim().is_empty()),
+        keywords,
+        created_at: project.created_at,
+        updated_at: project.updated_at,
+    };
+    render(options, base_dir, &info, 1, |layout| {
+        layout.cover(&project.name, &project.description);
+        for document in documents {
+            layout.document(document, true);
+        }
+    })
+}
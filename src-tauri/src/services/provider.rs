@@ -0,0 +1,27 @@
+// AI 提供商连接测试
+
+use crate::models::provider::AIProvider;
+
+/// 使用已解密的 API Key 测试提供商是否可用
+pub async fn test_connection(provider: &AIProvider, api_key: &str) -> bool {
+    // 基本验证
+    if api_key.is_empty() {
+        return false;
+    }
+
+    // 简单的 HTTP 请求测试（这里可以根据具体提供商实现更详细的测试）
+    match provider.provider_type.as_str() {
+        "openai" => {
+            let base_url = provider.base_url.as_deref().unwrap_or("https://api.openai.com/v1");
+            let response = reqwest::Client::new()
+                .get(format!("{}/models", base_url.trim_end_matches('/')))
+                .header("Authorization", format!("Bearer {}", api_key))
+                .send()
+                .await;
+
+            matches!(response, Ok(resp) if resp.status().is_success())
+        }
+        // 对于其他提供商，暂时只检查 API key 是否存在
+        _ => true,
+    }
+}
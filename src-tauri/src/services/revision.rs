@@ -0,0 +1,120 @@
+// 文档修订服务
+// 计算修订内容的哈希与行级差异
+
+use crate::models::revision::{DiffHunk, DiffLine, DiffLineKind, RevisionDiff};
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashSet;
+
+// 无条件保留的最近修订数量，更早的修订按时间分桶抽稀
+pub const MAX_RECENT_REVISIONS: usize = 50;
+// 超过该天数的旧修订会被清理
+pub const REVISION_RETENTION_DAYS: i64 = 90;
+
+// 按修订的时间远近决定分桶粒度（秒）：一天内每 10 分钟、一周内每小时、保留期内每天
+const REVISION_BUCKETS: [(i64, i64); 3] = [(1, 10 * 60), (7, 60 * 60), (REVISION_RETENTION_DAYS, 24 * 60 * 60)];
+
+const DIFF_CONTEXT_LINES: usize = 3;
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// 返回需要清理的修订 id。revisions 按版本从新到旧排列。
+/// 每个时间桶保留最早的一个修订，即该时段开始编辑前的内容，连续的自动保存不会挤掉当天更早的版本
+pub fn revisions_to_prune(revisions: &[(String, DateTime<Utc>)], now: DateTime<Utc>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut prune = Vec::new();
+    for (index, (id, created_at)) in revisions.iter().enumerate().rev() {
+        if index < MAX_RECENT_REVISIONS {
+            continue;
+        }
+        let age = now - *created_at;
+        let bucket = REVISION_BUCKETS
+            .iter()
+            .find(|(days, _)| age < Duration::days(*days))
+            .map(|(_, seconds)| (*seconds, created_at.timestamp().div_euclid(*seconds)));
+        match bucket {
+            Some(bucket) if seen.insert(bucket) => {}
+            _ => prune.push(id.clone()),
+        }
+    }
+    prune
+}
+
+pub fn diff_texts(old: &str, new: &str, from_version: u32, to_version: u32, to_current: bool) -> RevisionDiff {
+    let diff = TextDiff::from_lines(old, new);
+    let mut insertions = 0;
+    let mut deletions = 0;
+    let mut hunks = Vec::new();
+
+    for group in diff.grouped_ops(DIFF_CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else { continue };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => DiffLineKind::Equal,
+                    ChangeTag::Insert => {
+                        insertions += 1;
+                        DiffLineKind::Insert
+                    }
+                    ChangeTag::Delete => {
+                        deletions += 1;
+                        DiffLineKind::Delete
+                    }
+                };
+                lines.push(DiffLine {
+                    kind,
+                    old_line: change.old_index().map(|i| i as u32 + 1),
+                    new_line: change.new_index().map(|i| i as u32 + 1),
+                    content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                });
+            }
+        }
+
+        hunks.push(DiffHunk {
+            old_start: old_range.start as u32 + 1,
+            old_lines: old_range.len() as u32,
+            new_start: new_range.start as u32 + 1,
+            new_lines: new_range.len() as u32,
+            lines,
+        });
+    }
+
+    RevisionDiff {
+        from_version,
+        to_version,
+        to_current,
+        insertions,
+        deletions,
+        hunks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn recent_revisions_are_kept_and_older_ones_are_thinned_per_bucket() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let mut revisions: Vec<(String, DateTime<Utc>)> = (0..MAX_RECENT_REVISIONS as i64)
+            .map(|i| (format!("r{i}"), now - Duration::seconds(i)))
+            .collect();
+        // 同一个 10 分钟桶里的三次自动保存只保留最早的一次
+        for (index, minutes) in [(50, 178), (51, 179), (52, 180)] {
+            revisions.push((format!("r{index}"), now - Duration::minutes(minutes)));
+        }
+        revisions.push(("r53".to_string(), now - Duration::days(3)));
+        revisions.push(("r54".to_string(), now - Duration::days(REVISION_RETENTION_DAYS + 10)));
+
+        assert_eq!(revisions_to_prune(&revisions, now), ["r54", "r51", "r50"]);
+        assert!(revisions_to_prune(&revisions[..MAX_RECENT_REVISIONS], now).is_empty());
+    }
+}
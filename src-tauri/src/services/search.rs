@@ -0,0 +1,210 @@
+// 全文搜索服务
+// FTS5 自带的 unicode61 分词器会把连续的中日韩字符当作一个词，
+// 这里在写入索引和构造查询前把 CJK 文本切成重叠的二元组（bigram）
+
+const SNIPPET_CHARS: usize = 120;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // 平假名、片假名
+        | 0x3400..=0x4DBF   // CJK 扩展 A
+        | 0x4E00..=0x9FFF   // CJK 统一表意文字
+        | 0xAC00..=0xD7AF   // 韩文音节
+        | 0xF900..=0xFAFF   // CJK 兼容表意文字
+        | 0x20000..=0x2FA1F // CJK 扩展 B-F
+    )
+}
+
+enum Token {
+    Word(String),
+    // 连续 CJK 字符切分出的 bigram；单个字符时只有一个元素
+    Cjk(Vec<String>),
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+
+    let flush_word = |word: &mut String, tokens: &mut Vec<Token>| {
+        if !word.is_empty() {
+            tokens.push(Token::Word(std::mem::take(word)));
+        }
+    };
+    let flush_cjk = |cjk: &mut Vec<char>, tokens: &mut Vec<Token>| {
+        if cjk.is_empty() {
+            return;
+        }
+        let grams = if cjk.len() == 1 {
+            vec![cjk[0].to_string()]
+        } else {
+            cjk.windows(2).map(|w| w.iter().collect()).collect()
+        };
+        tokens.push(Token::Cjk(grams));
+        cjk.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush_word(&mut word, &mut tokens);
+            cjk.push(c);
+        } else if c.is_alphanumeric() {
+            flush_cjk(&mut cjk, &mut tokens);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut cjk, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut cjk, &mut tokens);
+
+    tokens
+}
+
+/// 生成写入 FTS 表的文本。
+/// 每段 CJK 文本末尾另加最后一个字的单字词，单字查询的前缀匹配才能命中位于段尾的字（如「恐龙。」中的「龙」）
+pub fn index_text(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for token in tokenize(text) {
+        match token {
+            Token::Word(w) => out.push(w),
+            Token::Cjk(grams) => {
+                let tail = grams
+                    .last()
+                    .filter(|gram| gram.chars().count() > 1)
+                    .and_then(|gram| gram.chars().last())
+                    .map(String::from);
+                out.extend(grams);
+                out.extend(tail);
+            }
+        }
+    }
+    out.join(" ")
+}
+
+/// 把用户输入转成 FTS5 MATCH 表达式，所有词之间为 AND 关系。
+/// CJK 片段转成 bigram 短语以实现子串匹配，单字和最后一个词使用前缀匹配。
+pub fn match_query(query: &str) -> Option<String> {
+    let tokens = tokenize(query);
+    let last = tokens.len().checked_sub(1)?;
+    let terms: Vec<String> = tokens
+        .into_iter()
+        .enumerate()
+        .map(|(i, token)| match token {
+            Token::Word(w) if i == last => format!("\"{}\"*", w),
+            Token::Word(w) => format!("\"{}\"", w),
+            Token::Cjk(grams) if grams.len() == 1 && grams[0].chars().count() == 1 => format!("\"{}\"*", grams[0]),
+            Token::Cjk(grams) => format!("\"{}\"", grams.join(" ")),
+        })
+        .collect();
+    Some(terms.join(" AND "))
+}
+
+/// 查询中用于高亮的原始词
+pub fn highlight_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|t| t.to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// 返回 (起始字符下标, 长度) 形式的所有命中位置，已按起点排序且互不重叠
+fn find_matches(chars: &[char], terms: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < lower.len() {
+        let hit = terms
+            .iter()
+            .filter(|t| !t.is_empty() && lower[i..].starts_with(t))
+            .map(|t| t.len())
+            .max();
+        match hit {
+            Some(len) => {
+                matches.push((i, len));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+fn mark(chars: &[char], matches: &[(usize, usize)], start: usize, end: usize) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for &(m_start, m_len) in matches {
+        let m_end = m_start + m_len;
+        if m_end <= start || m_start >= end {
+            continue;
+        }
+        let (m_start, m_end) = (m_start.max(start), m_end.min(end));
+        out.push_str(&escape_html(&chars[pos..m_start].iter().collect::<String>()));
+        out.push_str("<mark>");
+        out.push_str(&escape_html(&chars[m_start..m_end].iter().collect::<String>()));
+        out.push_str("</mark>");
+        pos = m_end;
+    }
+    out.push_str(&escape_html(&chars[pos..end].iter().collect::<String>()));
+    out
+}
+
+/// 对整段文本做 HTML 转义并用 `<mark>` 标出命中的词
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let terms: Vec<Vec<char>> = terms.iter().map(|t| t.chars().collect()).collect();
+    let matches = find_matches(&chars, &terms);
+    mark(&chars, &matches, 0, chars.len())
+}
+
+/// 截取第一个命中位置附近的片段并高亮
+pub fn snippet(content: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = content.chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+    let terms: Vec<Vec<char>> = terms.iter().map(|t| t.chars().collect()).collect();
+    let matches = find_matches(&chars, &terms);
+
+    let first = matches.first().map(|m| m.0).unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_CHARS / 4);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut out = mark(&chars, &matches, start, end);
+    if start > 0 {
+        out.insert(0, '…');
+    }
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_text_splits_cjk_into_bigrams_with_a_trailing_unigram() {
+        assert_eq!(index_text("我喜欢恐龙。Rust 编程"), "我喜 喜欢 欢恐 恐龙 龙 rust 编程 程");
+        assert_eq!(index_text("龙"), "龙");
+    }
+
+    #[test]
+    fn match_query_uses_phrases_for_cjk_and_prefixes_for_single_characters() {
+        assert_eq!(match_query("龙").as_deref(), Some("\"龙\"*"));
+        assert_eq!(match_query("恐龙").as_deref(), Some("\"恐龙\""));
+        assert_eq!(match_query("喜欢恐龙").as_deref(), Some("\"喜欢 欢恐 恐龙\""));
+        assert_eq!(match_query("Rust 编程 pro").as_deref(), Some("\"rust\" AND \"编程\" AND \"pro\"*"));
+        assert_eq!(match_query("  。，"), None);
+    }
+
+    #[test]
+    fn highlight_escapes_html_and_marks_terms() {
+        let terms = highlight_terms("恐龙 A&B");
+        assert_eq!(highlight("<p>恐龙 a&b</p>", &terms), "&lt;p&gt;<mark>恐龙</mark> <mark>a&amp;b</mark>&lt;/p&gt;");
+    }
+}
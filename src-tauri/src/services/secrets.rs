@@ -0,0 +1,154 @@
+// 密钥存储服务
+// API Key 等敏感信息使用本地主密钥以 ChaCha20-Poly1305 加密后存入 secrets 表，
+// 业务表中只保存 `secret://<id>` 形式的引用。主密钥优先保存在系统钥匙串中，
+// 钥匙串不可用时退回到数据目录下权限为 0600 的密钥文件
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const SECRET_REF_PREFIX: &str = "secret://";
+
+const KEYRING_SERVICE: &str = "writeflow-studio";
+const KEYRING_USER: &str = "master-key";
+const KEY_FILE_NAME: &str = "master.key";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum KeySource {
+    Keyring,
+    File,
+}
+
+#[derive(Clone)]
+pub struct MasterKey {
+    key: Key,
+    pub source: KeySource,
+}
+
+// 不在日志中输出密钥内容
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey").field("source", &self.source).finish_non_exhaustive()
+    }
+}
+
+pub struct EncryptedSecret {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(SECRET_REF_PREFIX)
+}
+
+pub fn reference_for(id: &str) -> String {
+    format!("{}{}", SECRET_REF_PREFIX, id)
+}
+
+pub fn reference_id(reference: &str) -> Option<&str> {
+    reference.strip_prefix(SECRET_REF_PREFIX).filter(|id| !id.is_empty())
+}
+
+fn decode_key(encoded: &str) -> Result<Key> {
+    let bytes = BASE64.decode(encoded.trim()).context("Master key is not valid base64")?;
+    if bytes.len() != 32 {
+        anyhow::bail!("Master key has invalid length {}", bytes.len());
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+fn keyring_entry() -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+}
+
+fn write_key_file(path: &Path, encoded: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(encoded.as_bytes())?;
+    }
+    #[cfg(not(unix))]
+    std::fs::write(path, encoded)?;
+    Ok(())
+}
+
+/// 读取或创建主密钥。会访问系统钥匙串，可能阻塞，应在阻塞线程中调用。
+/// 已经存在密钥文件时始终使用文件，避免钥匙串后来变为可用时生成新的密钥导致旧数据无法解密
+pub fn load_or_create_master_key(data_dir: &Path) -> Result<MasterKey> {
+    let key_file = data_dir.join(KEY_FILE_NAME);
+    if key_file.exists() {
+        let encoded = std::fs::read_to_string(&key_file)?;
+        return Ok(MasterKey { key: decode_key(&encoded)?, source: KeySource::File });
+    }
+
+    let entry = keyring_entry();
+    match entry.as_ref().map(|e| e.get_password()) {
+        Ok(Ok(encoded)) => return Ok(MasterKey { key: decode_key(&encoded)?, source: KeySource::Keyring }),
+        Ok(Err(keyring::Error::NoEntry)) => {}
+        Ok(Err(e)) => println!("System keyring unavailable, using key file: {}", e),
+        Err(e) => println!("System keyring unavailable, using key file: {}", e),
+    }
+
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let encoded = BASE64.encode(key.as_slice());
+
+    // 写入后再读回一次，确认钥匙串确实可用
+    if let Ok(entry) = entry {
+        let stored = entry
+            .set_password(&encoded)
+            .and_then(|_| entry.get_password())
+            .map(|read_back| read_back == encoded);
+        if let Ok(true) = stored {
+            return Ok(MasterKey { key, source: KeySource::Keyring });
+        }
+    }
+
+    write_key_file(&key_file, &encoded)?;
+    Ok(MasterKey { key, source: KeySource::File })
+}
+
+/// 只在内存中使用的随机主密钥，测试时不访问钥匙串
+#[cfg(test)]
+pub fn ephemeral_master_key() -> MasterKey {
+    MasterKey { key: ChaCha20Poly1305::generate_key(&mut OsRng), source: KeySource::File }
+}
+
+/// 加密时把密钥 ID 作为附加数据，密文不能被挪用到其他引用下
+pub fn encrypt(master_key: &MasterKey, id: &str, plaintext: &str) -> Result<EncryptedSecret> {
+    let cipher = ChaCha20Poly1305::new(&master_key.key);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: id.as_bytes() })
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+    Ok(EncryptedSecret {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+pub fn decrypt(master_key: &MasterKey, id: &str, secret: &EncryptedSecret) -> Result<String> {
+    let cipher = ChaCha20Poly1305::new(&master_key.key);
+    let nonce_bytes = BASE64.decode(&secret.nonce)?;
+    if nonce_bytes.len() != 12 {
+        anyhow::bail!("Secret {} has an invalid nonce", id);
+    }
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce_bytes),
+            Payload { msg: &BASE64.decode(&secret.ciphertext)?, aad: id.as_bytes() },
+        )
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt secret {}: wrong master key or corrupted data", id))?;
+    Ok(String::from_utf8(plaintext)?)
+}
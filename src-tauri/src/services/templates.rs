@@ -0,0 +1,519 @@
+// 文档模板和项目模板
+// 模板的标题和正文中可以使用 {{name}} 占位符：声明过的变量取用户填写的值或默认值，
+// 其余名称按内置变量替换（日期、时间、周次、文档标题、项目和工作区名称），无法识别的占位符保持原样。
+// {{date:%Y年%m月%d日}} 按 strftime 格式输出当前日期
+
+use crate::error::AppError;
+use crate::models::document::{CreateDocumentData, Document, DocumentStatus, DocumentType};
+use crate::models::folder::{FolderNode, ProjectTree};
+use crate::models::project::{ProgressMode, Project, ProjectWritingPreferences};
+use crate::models::template::{
+    DocumentTemplate, ProjectTemplateData, TemplateBundle, TemplateData, TemplateSeedDocument, TemplateVariable,
+};
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local};
+use std::collections::HashMap;
+
+pub const BUILT_IN_PREFIX: &str = "builtin-";
+
+const BUILT_IN_VARIABLES: [&str; 11] =
+    ["date", "time", "datetime", "year", "month", "day", "week", "weekday", "title", "project", "workspace"];
+
+const WEEKDAYS: [&str; 7] = ["星期一", "星期二", "星期三", "星期四", "星期五", "星期六", "星期日"];
+
+/// 实例化模板时可用的内置变量
+pub struct TemplateContext {
+    pub title: String,
+    pub project: String,
+    pub workspace: String,
+    pub now: DateTime<Local>,
+}
+
+fn variable(name: &str, prompt: &str, default_value: Option<&str>) -> TemplateVariable {
+    TemplateVariable {
+        name: name.to_string(),
+        prompt: prompt.to_string(),
+        default_value: default_value.map(str::to_string),
+    }
+}
+
+fn built_in(name: &str, category: &str, description: &str, title: &str, content: &str, tags: &[&str], variables: Vec<TemplateVariable>) -> TemplateData {
+    TemplateData {
+        name: name.to_string(),
+        description: description.to_string(),
+        category: category.to_string(),
+        title: title.to_string(),
+        content: content.to_string(),
+        content_type: DocumentType::Markdown,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        variables,
+    }
+}
+
+/// 内置模板及其固定 id，启动时同步到数据库
+pub fn built_in_templates() -> Vec<(String, TemplateData)> {
+    let templates = [
+        (
+            "journal",
+            built_in(
+                "日记",
+                "日常",
+                "按日期记录当天的事情和想法",
+                "{{date}} 日记",
+                "# {{date}} {{weekday}}\n\n## 今天做了什么\n\n\n## 想法与感受\n\n\n## 明天的计划\n\n- [ ] \n",
+                &["日记"],
+                Vec::new(),
+            ),
+        ),
+        (
+            "meeting-notes",
+            built_in(
+                "会议纪要",
+                "工作",
+                "记录会议议题、讨论和待办事项",
+                "{{topic}} 会议纪要",
+                "# {{topic}}\n\n- 时间：{{datetime}}\n- 地点：{{location}}\n- 参会人：{{attendees}}\n\n## 议题\n\n1. \n\n## 讨论记录\n\n\n## 决议\n\n\n## 待办事项\n\n- [ ] \n",
+                &["会议"],
+                vec![
+                    variable("topic", "会议主题", None),
+                    variable("location", "会议地点", Some("线上")),
+                    variable("attendees", "参会人", Some("")),
+                ],
+            ),
+        ),
+        (
+            "reading-notes",
+            built_in(
+                "读书笔记",
+                "学习",
+                "整理一本书的要点、摘录和思考",
+                "《{{book}}》读书笔记",
+                "# 《{{book}}》\n\n- 作者：{{author}}\n- 阅读日期：{{date}}\n\n## 内容概要\n\n\n## 精彩摘录\n\n> \n\n## 我的思考\n\n",
+                &["读书笔记"],
+                vec![variable("book", "书名", None), variable("author", "作者", Some("未知"))],
+            ),
+        ),
+        (
+            "novel-chapter",
+            built_in(
+                "小说章节",
+                "写作",
+                "包含章节目标、场景和正文的小说章节",
+                "第{{chapter}}章 {{chapter_title}}",
+                "# 第{{chapter}}章 {{chapter_title}}\n\n> 本章目标：{{goal}}\n\n## 场景\n\n- 时间：\n- 地点：\n- 出场人物：\n\n## 正文\n\n",
+                &["章节"],
+                vec![
+                    variable("chapter", "章节序号", Some("一")),
+                    variable("chapter_title", "章节标题", Some("")),
+                    variable("goal", "本章要完成的情节", Some("")),
+                ],
+            ),
+        ),
+        (
+            "weekly-report",
+            built_in(
+                "周报",
+                "工作",
+                "总结本周工作并规划下周",
+                "{{year}} 年第 {{week}} 周周报",
+                "# {{project}} 周报（{{date}}）\n\n## 本周完成\n\n- \n\n## 遇到的问题\n\n- \n\n## 下周计划\n\n- \n",
+                &["周报"],
+                Vec::new(),
+            ),
+        ),
+    ];
+    templates
+        .into_iter()
+        .map(|(id, template)| (format!("{}{}", BUILT_IN_PREFIX, id), template))
+        .collect()
+}
+
+// 去掉空白后的占位符内容；包含花括号或换行的不是占位符
+fn parse_placeholder(inner: &str) -> Option<&str> {
+    let inner = inner.trim();
+    (!inner.is_empty() && !inner.contains(['{', '}', '\n'])).then_some(inner)
+}
+
+// 逐个替换 {{...}}，value 返回 None 时保留原文。只扫描一遍，替换进来的内容不会再被当作占位符
+pub(crate) fn substitute(text: &str, mut value: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        let end = start + 2 + len + 2;
+        out.push_str(&rest[..start]);
+        match parse_placeholder(&rest[start + 2..end - 2]).and_then(&mut value) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 文本中出现的占位符，按首次出现的顺序
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    substitute(text, |placeholder| {
+        if !names.iter().any(|name| name == placeholder) {
+            names.push(placeholder.to_string());
+        }
+        None
+    });
+    names
+}
+
+fn is_built_in(placeholder: &str) -> bool {
+    let name = placeholder.split_once(':').map(|(name, _)| name).unwrap_or(placeholder);
+    BUILT_IN_VARIABLES.contains(&name)
+}
+
+fn built_in_value(placeholder: &str, context: &TemplateContext) -> Option<String> {
+    let now = &context.now;
+    let value = match placeholder.split_once(':') {
+        // 格式无效时保留原文，避免 chrono 在格式化时 panic
+        Some(("date", format)) => {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return None;
+            }
+            now.format(format).to_string()
+        }
+        Some(_) => return None,
+        None => match placeholder {
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H:%M").to_string(),
+            "datetime" => now.format("%Y-%m-%d %H:%M").to_string(),
+            "year" => now.year().to_string(),
+            "month" => now.month().to_string(),
+            "day" => now.day().to_string(),
+            "week" => now.iso_week().week().to_string(),
+            "weekday" => WEEKDAYS[now.weekday().num_days_from_monday() as usize].to_string(),
+            "title" => context.title.clone(),
+            "project" => context.project.clone(),
+            "workspace" => context.workspace.clone(),
+            _ => return None,
+        },
+    };
+    Some(value)
+}
+
+fn is_valid_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 校验模板数据并补全变量：标题和正文中未声明的占位符（内置变量除外）自动加入变量列表
+pub fn normalize(mut data: TemplateData) -> Result<TemplateData> {
+    data.name = data.name.trim().to_string();
+    if data.name.is_empty() {
+        return Err(AppError::validation("Template name cannot be empty").into());
+    }
+    data.category = data.category.trim().to_string();
+
+    let mut variables: Vec<TemplateVariable> = Vec::new();
+    for mut variable in std::mem::take(&mut data.variables) {
+        variable.name = variable.name.trim().to_string();
+        if !is_valid_variable_name(&variable.name) {
+            return Err(AppError::validation(format!("Invalid template variable name: \"{}\"", variable.name)).into());
+        }
+        if variables.iter().any(|v| v.name == variable.name) {
+            return Err(AppError::validation(format!("Duplicate template variable: {}", variable.name)).into());
+        }
+        variables.push(variable);
+    }
+    for name in placeholders(&format!("{}\n{}", data.title, data.content)) {
+        if is_valid_variable_name(&name) && !is_built_in(&name) && !variables.iter().any(|v| v.name == name) {
+            variables.push(TemplateVariable { prompt: name.clone(), name, default_value: None });
+        }
+    }
+    data.variables = variables;
+    Ok(data)
+}
+
+/// 替换文本中的占位符。声明过的变量既没有填写也没有默认值时报错
+pub fn render(text: &str, variables: &[TemplateVariable], values: &HashMap<String, String>, context: &TemplateContext) -> Result<String> {
+    let mut missing: Vec<&str> = Vec::new();
+    let rendered = substitute(text, |placeholder| {
+        match variables.iter().find(|variable| variable.name == placeholder) {
+            Some(variable) => match values.get(&variable.name).or(variable.default_value.as_ref()) {
+                Some(value) => Some(value.clone()),
+                None => {
+                    if !missing.contains(&variable.name.as_str()) {
+                        missing.push(&variable.name);
+                    }
+                    None
+                }
+            },
+            None => built_in_value(placeholder, context),
+        }
+    });
+    if !missing.is_empty() {
+        return Err(AppError::validation(format!("Missing values for template variables: {}", missing.join(", "))).into());
+    }
+    Ok(rendered)
+}
+
+/// 用模板生成新文档的数据：标题未填写时取模板标题，正文和类型来自模板，标签合并
+pub fn instantiate(template: &DocumentTemplate, mut data: CreateDocumentData, context: &mut TemplateContext) -> Result<CreateDocumentData> {
+    let values = data.template_variables.take().unwrap_or_default();
+    let title = if !data.title.trim().is_empty() {
+        data.title.clone()
+    } else if !template.title.trim().is_empty() {
+        template.title.clone()
+    } else {
+        template.name.clone()
+    };
+    data.title = render(&title, &template.variables, &values, context)?.trim().to_string();
+    context.title = data.title.clone();
+    data.content = Some(render(&template.content, &template.variables, &values, context)?);
+    data.content_type = template.content_type.clone();
+
+    let mut tags = template.tags.clone();
+    for tag in data.tags.take().unwrap_or_default() {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    data.tags = Some(tags);
+    Ok(data)
+}
+
+const BUNDLE_VERSION: u32 = 1;
+
+pub fn to_bundle(templates: Vec<DocumentTemplate>) -> Result<String> {
+    let bundle = TemplateBundle { version: BUNDLE_VERSION, templates: templates.into_iter().map(TemplateData::from).collect() };
+    Ok(serde_json::to_string_pretty(&bundle)?)
+}
+
+/// 读取导出的模板文件，也接受单个模板对象
+pub fn from_bundle(text: &str) -> Result<Vec<TemplateData>> {
+    if let Ok(bundle) = serde_json::from_str::<TemplateBundle>(text) {
+        if bundle.version > BUNDLE_VERSION {
+            return Err(AppError::validation(format!("Template file version {} is not supported", bundle.version)).into());
+        }
+        return Ok(bundle.templates);
+    }
+    let template = serde_json::from_str::<TemplateData>(text)
+        .map_err(|e| AppError::validation(format!("Not a template file: {}", e)))?;
+    Ok(vec![template])
+}
+
+// 项目模板
+// 模板定义文件夹、初始文档、项目标签、字数目标和项目级写作偏好，初始文档的标题和正文可以使用内置变量
+
+fn seed(folder: Option<&str>, title: &str, hint: &str) -> TemplateSeedDocument {
+    TemplateSeedDocument {
+        title: title.to_string(),
+        folder_path: folder.map(str::to_string),
+        content: format!("# {}\n\n> {}\n", title, hint),
+        content_type: DocumentType::Markdown,
+        status: DocumentStatus::Draft,
+        tags: Vec::new(),
+    }
+}
+
+fn preferences(writing_style: &str, tone: &str, target_audience: &str) -> ProjectWritingPreferences {
+    ProjectWritingPreferences {
+        language: None,
+        writing_style: Some(writing_style.to_string()),
+        tone: Some(tone.to_string()),
+        target_audience: Some(target_audience.to_string()),
+    }
+}
+
+/// 内置项目模板及其固定 id，启动时同步到数据库
+pub fn built_in_project_templates() -> Vec<(String, ProjectTemplateData)> {
+    let academic = ProjectTemplateData {
+        name: "学术论文".to_string(),
+        description: "按摘要、引言、方法、结果、讨论和结论组织的研究论文".to_string(),
+        icon: "graduation-cap".to_string(),
+        color: "#10b981".to_string(),
+        folders: vec!["正文".to_string(), "资料".to_string()],
+        documents: vec![
+            seed(None, "摘要", "概述研究背景、目的、方法、主要结果和结论，并列出 3～5 个关键词。"),
+            seed(Some("正文"), "引言", "说明研究问题的背景和意义，指出现有研究的不足，提出本文的研究目标和贡献。"),
+            seed(Some("正文"), "相关工作", "梳理与本文相关的已有研究，说明本文与它们的联系和区别。"),
+            seed(Some("正文"), "研究方法", "描述研究设计、数据来源、实验设置和分析方法，使他人能够复现。"),
+            seed(Some("正文"), "实验与结果", "客观呈现实验结果，配合图表说明主要发现。"),
+            seed(Some("正文"), "讨论", "解释结果的含义，与已有研究比较，说明研究的局限性。"),
+            seed(Some("正文"), "结论", "总结主要结论和贡献，提出未来的研究方向。"),
+            seed(None, "参考文献", "按目标期刊要求的格式列出引用的文献。"),
+            seed(Some("资料"), "文献笔记", "记录阅读文献时的要点和可引用的内容。"),
+        ],
+        tags: vec!["学术论文".to_string()],
+        target_word_count: Some(8000),
+        daily_word_quota: None,
+        progress_mode: ProgressMode::DocumentStatus,
+        writing_preferences: preferences("academic", "formal", "researchers"),
+    };
+    let business = ProjectTemplateData {
+        name: "商业计划书".to_string(),
+        description: "面向投资人的商业计划书，涵盖市场、产品、运营和财务".to_string(),
+        icon: "briefcase".to_string(),
+        color: "#3b82f6".to_string(),
+        folders: vec!["市场分析".to_string(), "财务".to_string()],
+        documents: vec![
+            seed(None, "执行摘要", "用一页篇幅概括项目亮点、商业模式、市场机会和融资需求。"),
+            seed(None, "公司介绍", "介绍公司的使命、发展历程和现状。"),
+            seed(None, "产品与服务", "说明产品解决的问题、核心功能和竞争优势。"),
+            seed(Some("市场分析"), "行业概况", "描述行业规模、增长趋势和驱动因素。"),
+            seed(Some("市场分析"), "目标客户", "刻画目标客户群体及其需求。"),
+            seed(Some("市场分析"), "竞争分析", "列出主要竞争对手，比较各自的优劣势。"),
+            seed(None, "营销策略", "说明定价、渠道和推广计划。"),
+            seed(None, "运营计划", "说明关键里程碑、生产和交付安排。"),
+            seed(None, "团队介绍", "介绍核心团队成员的背景和分工。"),
+            seed(Some("财务"), "财务预测", "给出未来三年的收入、成本和利润预测。"),
+            seed(Some("财务"), "融资需求", "说明融资金额、资金用途和股权安排。"),
+            seed(None, "风险分析", "识别主要风险并给出应对措施。"),
+        ],
+        tags: vec!["商业计划".to_string()],
+        target_word_count: Some(10000),
+        daily_word_quota: None,
+        progress_mode: ProgressMode::DocumentStatus,
+        writing_preferences: preferences("professional", "persuasive", "investors"),
+    };
+    let novel = ProjectTemplateData {
+        name: "小说大纲".to_string(),
+        description: "从设定、大纲到正文的长篇小说创作结构".to_string(),
+        icon: "pen-tool".to_string(),
+        color: "#ec4899".to_string(),
+        folders: vec!["设定".to_string(), "大纲".to_string(), "正文".to_string()],
+        documents: vec![
+            seed(Some("设定"), "故事梗概", "用几段话讲清楚故事的主线、核心冲突和结局。"),
+            seed(Some("设定"), "人物小传", "为主要人物记录外貌、性格、动机、经历和人物弧光。"),
+            seed(Some("设定"), "世界观", "记录故事发生的时代、地点、社会规则和重要设定。"),
+            seed(Some("大纲"), "分卷大纲", "划分故事的主要阶段，写出每一卷的目标和转折。"),
+            seed(Some("大纲"), "章节大纲", "逐章列出情节要点、出场人物和伏笔。"),
+            seed(Some("正文"), "第一章", "从这里开始写作。"),
+        ],
+        tags: vec!["小说".to_string()],
+        target_word_count: Some(100000),
+        daily_word_quota: Some(2000),
+        progress_mode: ProgressMode::WordCount,
+        writing_preferences: preferences("creative", "narrative", "general readers"),
+    };
+    [("academic-paper", academic), ("business-plan", business), ("novel-outline", novel)]
+        .into_iter()
+        .map(|(id, template)| (format!("{}{}", BUILT_IN_PREFIX, id), template))
+        .collect()
+}
+
+fn normalize_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 校验项目模板：名称和初始文档标题不能为空，文件夹路径统一为 / 分隔并去重
+pub fn normalize_project(mut data: ProjectTemplateData) -> Result<ProjectTemplateData> {
+    data.name = data.name.trim().to_string();
+    if data.name.is_empty() {
+        return Err(AppError::validation("Template name cannot be empty").into());
+    }
+    let mut folders: Vec<String> = Vec::new();
+    for folder in data.folders.iter().map(|folder| normalize_path(folder)) {
+        if !folder.is_empty() && !folders.contains(&folder) {
+            folders.push(folder);
+        }
+    }
+    data.folders = folders;
+    for document in &mut data.documents {
+        document.title = document.title.trim().to_string();
+        if document.title.is_empty() {
+            return Err(AppError::validation("Document title in a project template cannot be empty").into());
+        }
+        document.folder_path = document.folder_path.as_deref().map(normalize_path).filter(|path| !path.is_empty());
+    }
+    Ok(data)
+}
+
+/// 把模板中的图标、颜色、字数目标和写作偏好应用到新项目
+pub fn apply_project_template(project: &mut Project, template: &ProjectTemplateData) {
+    if project.icon.trim().is_empty() {
+        project.icon = template.icon.clone();
+    }
+    if project.color.trim().is_empty() {
+        project.color = template.color.clone();
+    }
+    project.target_word_count = template.target_word_count;
+    project.daily_word_quota = template.daily_word_quota;
+    project.progress_mode = template.progress_mode;
+    project.writing_preferences = template.writing_preferences.clone();
+}
+
+/// 生成项目模板中的初始文档，替换其中的内置变量
+pub fn seed_documents(template: &ProjectTemplateData, project_id: &str, context: &mut TemplateContext) -> Result<Vec<Document>> {
+    let values = HashMap::new();
+    let mut documents = Vec::with_capacity(template.documents.len());
+    for seed in &template.documents {
+        let title = render(&seed.title, &[], &values, context)?;
+        context.title = title.clone();
+        let mut document = Document::new(CreateDocumentData {
+            title,
+            content: Some(render(&seed.content, &[], &values, context)?),
+            content_type: seed.content_type.clone(),
+            project_id: project_id.to_string(),
+            folder_path: seed.folder_path.clone(),
+            folder_id: None,
+            tags: Some(seed.tags.clone()),
+            template_id: None,
+            template_variables: None,
+        });
+        document.status = seed.status.clone();
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// 由已有项目生成模板：保留文件夹结构、文档顺序、项目标签、字数目标和写作偏好，
+/// include_content 为 false 时只保留文档标题
+pub fn project_to_template(
+    project: &Project,
+    tree: &ProjectTree,
+    documents: Vec<Document>,
+    tags: Vec<String>,
+    include_content: bool,
+) -> ProjectTemplateData {
+    fn walk(prefix: &str, nodes: &[FolderNode], folders: &mut Vec<String>, order: &mut Vec<String>) {
+        for node in nodes {
+            let path = format!("{}{}", prefix, node.folder.name.trim());
+            folders.push(path.clone());
+            order.extend(node.documents.iter().map(|document| document.id.clone()));
+            walk(&format!("{}/", path), &node.folders, folders, order);
+        }
+    }
+    let mut folders = Vec::new();
+    let mut order: Vec<String> = tree.documents.iter().map(|document| document.id.clone()).collect();
+    walk("", &tree.folders, &mut folders, &mut order);
+
+    let mut by_id: HashMap<String, Document> = documents.into_iter().map(|document| (document.id.clone(), document)).collect();
+    let documents = order
+        .iter()
+        .filter_map(|id| by_id.remove(id))
+        .map(|document| TemplateSeedDocument {
+            title: document.title,
+            folder_path: document.folder_path,
+            content: if include_content { document.content } else { String::new() },
+            content_type: document.content_type,
+            status: if include_content { document.status } else { DocumentStatus::Draft },
+            tags: document.tags,
+        })
+        .collect();
+
+    ProjectTemplateData {
+        name: project.name.clone(),
+        description: project.description.clone(),
+        icon: project.icon.clone(),
+        color: project.color.clone(),
+        folders,
+        documents,
+        tags,
+        target_word_count: project.target_word_count,
+        daily_word_quota: project.daily_word_quota,
+        progress_mode: project.progress_mode,
+        writing_preferences: project.writing_preferences.clone(),
+    }
+}
@@ -0,0 +1,578 @@
+// Obsidian 库和 Notion 导出的公共导入流程
+// 读取源目录（或 zip）中的全部文件，按路径和文件名建立索引；扫描正文中的链接并改写：
+// 指向其他笔记的链接改为 writeflow://document/<id>，引用的图片和附件复制到附件目录后改为 writeflow://attachment/ 链接。
+// 代码块和行内代码中的内容保持原样。先解析和复制附件，成功后才新建项目，失败时删除本次复制的附件
+
+use crate::error::AppError;
+use crate::models::document::Document;
+use crate::models::import::{ImportAction, ImportFileResult, UnresolvedLink, VaultImportOptions, VaultImportReport};
+use crate::models::project::Project;
+use crate::services::database::Database;
+use crate::services::folder_import;
+use crate::services::links;
+use crate::utils::sanitize_filename;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+pub(crate) struct SourceFile {
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') || name == "__MACOSX"
+}
+
+fn walk_dir(root: &Path, prefix: &str, files: &mut Vec<SourceFile>) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(root.join(prefix))?.collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if is_hidden(&name) {
+            continue;
+        }
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            walk_dir(root, &format!("{}/", path), files)?;
+        } else {
+            files.push(SourceFile { path, bytes: std::fs::read(entry.path())? });
+        }
+    }
+    Ok(())
+}
+
+// Notion 的大型导出会把各部分再打成 zip 放在外层 zip 中，这里展开一层
+fn read_zip(bytes: &[u8], prefix: &str, nested: bool, files: &mut Vec<SourceFile>) -> Result<()> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let Some(path) = entry.enclosed_name() else { continue };
+        let parts: Vec<String> = path.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        if entry.is_dir() || parts.iter().any(|part| is_hidden(part)) {
+            continue;
+        }
+        let path = format!("{}{}", prefix, parts.join("/"));
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        if !nested && path.to_lowercase().ends_with(".zip") {
+            read_zip(&bytes, prefix, true, files)?;
+        } else {
+            files.push(SourceFile { path, bytes });
+        }
+    }
+    Ok(())
+}
+
+/// 读取目录或 zip 中的全部文件，跳过隐藏文件（包括 .obsidian、.trash）
+pub(crate) fn load_source(source: &Path) -> Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    if source.is_dir() {
+        walk_dir(source, "", &mut files)?;
+    } else {
+        read_zip(&std::fs::read(source)?, "", false, &mut files)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    Ok(files)
+}
+
+pub(crate) fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+pub(crate) fn is_image(path: &str) -> bool {
+    IMAGE_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+pub(crate) fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+pub(crate) fn file_name(path: &str) -> &str {
+    path.rsplit_once('/').map(|(_, name)| name).unwrap_or(path)
+}
+
+pub(crate) fn file_stem(path: &str) -> &str {
+    let name = file_name(path);
+    name.rsplit_once('.').map(|(stem, _)| stem).filter(|stem| !stem.is_empty()).unwrap_or(name)
+}
+
+// 外部链接和页内锚点不改写
+pub(crate) fn is_external(url: &str) -> bool {
+    url.starts_with('#') || url.starts_with("mailto:") || url.contains("://")
+}
+
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(value) = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(value);
+                index += 3;
+                continue;
+            }
+        }
+        out.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 以 dir 为起点解析相对路径，处理 . 和 ..；以 / 开头的路径从根目录算起
+pub(crate) fn join_path(dir: &str, relative: &str) -> String {
+    let mut parts: Vec<&str> = if relative.starts_with('/') {
+        Vec::new()
+    } else {
+        dir.split('/').filter(|part| !part.is_empty()).collect()
+    };
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+// 与 comrak 生成标题 id 的规则一致：小写，去掉标点，空格换成连字符
+fn heading_anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn markdown_link(image: bool, text: &str, url: &str, title: Option<&str>) -> String {
+    let url = if url.contains([' ', '(', ')', '<', '>']) { format!("<{}>", url) } else { url.to_string() };
+    let title = title.map(|t| format!(" \"{}\"", t.replace('"', "\\\""))).unwrap_or_default();
+    format!("{}[{}]({}{})", if image { "!" } else { "" }, text, url, title)
+}
+
+/// 正文中的一个链接
+pub(crate) enum Link<'a> {
+    // [[target|alias]] 或 ![[target|alias]]，target 可以带 #标题
+    Wiki { target: &'a str, alias: Option<&'a str>, embed: bool },
+    // [text](url "title") 或 ![text](url)
+    Inline { text: &'a str, url: &'a str, title: Option<&'a str>, image: bool },
+}
+
+struct InlineLink<'a> {
+    text: &'a str,
+    url: &'a str,
+    title: Option<&'a str>,
+    end: usize,
+}
+
+// 解析从 open（指向 '['）开始的行内链接，只处理单行内的链接
+fn parse_inline_link(line: &str, open: usize) -> Option<InlineLink<'_>> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut index = open;
+    let close = loop {
+        match *bytes.get(index)? {
+            b'\\' => {
+                index += 2;
+                continue;
+            }
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break index;
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    };
+    if bytes.get(close + 1) != Some(&b'(') {
+        return None;
+    }
+
+    let mut index = close + 2;
+    while bytes.get(index) == Some(&b' ') {
+        index += 1;
+    }
+    let (url_start, url_end) = if bytes.get(index) == Some(&b'<') {
+        let end = line[index + 1..].find('>')? + index + 1;
+        let range = (index + 1, end);
+        index = end + 1;
+        range
+    } else {
+        let start = index;
+        let mut parens = 0;
+        while let Some(&byte) = bytes.get(index) {
+            match byte {
+                b' ' | b'\t' => break,
+                b'(' => parens += 1,
+                b')' if parens == 0 => break,
+                b')' => parens -= 1,
+                b'\\' => index += 1,
+                _ => {}
+            }
+            index += 1;
+        }
+        index = index.min(bytes.len());
+        (start, index)
+    };
+    while bytes.get(index) == Some(&b' ') {
+        index += 1;
+    }
+    let mut title = None;
+    if let Some(&quote @ (b'"' | b'\'')) = bytes.get(index) {
+        let end = line[index + 1..].find(quote as char)? + index + 1;
+        title = Some(&line[index + 1..end]);
+        index = end + 1;
+        while bytes.get(index) == Some(&b' ') {
+            index += 1;
+        }
+    }
+    if bytes.get(index) != Some(&b')') {
+        return None;
+    }
+    Some(InlineLink { text: &line[open + 1..close], url: &line[url_start..url_end], title, end: index + 1 })
+}
+
+fn rewrite_line(line: &str, rewrite: &mut impl FnMut(&Link) -> Option<String>, out: &mut String) {
+    let bytes = line.as_bytes();
+    let mut copied = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'`' => {
+                let run = bytes[index..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[index..index + run];
+                index = match line[index + run..].find(fence) {
+                    Some(offset) => index + run + offset + run,
+                    None => index + run,
+                };
+            }
+            b'!' | b'[' => {
+                let start = index;
+                let embed = bytes[index] == b'!';
+                let open = if embed { index + 1 } else { index };
+                if bytes.get(open) != Some(&b'[') {
+                    index += 1;
+                    continue;
+                }
+                let mut matched = None;
+                if bytes.get(open + 1) == Some(&b'[') {
+                    if let Some(offset) = line[open + 2..].find("]]") {
+                        let inner = &line[open + 2..open + 2 + offset];
+                        if !inner.trim().is_empty() && !inner.contains('[') {
+                            // 表格中的 [[a\|b]] 用反斜杠转义竖线
+                            let (target, alias) = match inner.split_once('|') {
+                                Some((target, alias)) => (target.trim_end_matches('\\').trim(), Some(alias.trim())),
+                                None => (inner.trim(), None),
+                            };
+                            let link = Link::Wiki { target, alias, embed };
+                            matched = Some((rewrite(&link), open + 2 + offset + 2));
+                        }
+                    }
+                } else if let Some(inline) = parse_inline_link(line, open) {
+                    let link = Link::Inline { text: inline.text, url: inline.url, title: inline.title, image: embed };
+                    matched = Some((rewrite(&link), inline.end));
+                }
+                match matched {
+                    Some((replacement, end)) => {
+                        if let Some(replacement) = replacement {
+                            out.push_str(&line[copied..start]);
+                            out.push_str(&replacement);
+                            copied = end;
+                        }
+                        index = end;
+                    }
+                    None => index += 1,
+                }
+            }
+            _ => index += 1,
+        }
+    }
+    out.push_str(&line[copied..]);
+}
+
+/// 逐个改写正文中的链接：rewrite 返回替换后的完整文本，返回 None 时保留原文
+pub(crate) fn rewrite_links(content: &str, mut rewrite: impl FnMut(&Link) -> Option<String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut fence: Option<String> = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(marker) = &fence {
+            if trimmed.starts_with(marker.as_str()) {
+                fence = None;
+            }
+            out.push_str(line);
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let marker = trimmed.chars().next().unwrap_or('`');
+            let length = trimmed.chars().take_while(|&c| c == marker).count();
+            fence = Some(marker.to_string().repeat(length));
+            out.push_str(line);
+            continue;
+        }
+        rewrite_line(line, &mut rewrite, &mut out);
+    }
+    out
+}
+
+/// 源文件索引：按完整路径和文件名查找（大小写不敏感），笔记也可以省略扩展名
+#[derive(Default)]
+pub(crate) struct FileIndex {
+    paths: HashMap<String, String>,
+    names: HashMap<String, Vec<String>>,
+}
+
+impl FileIndex {
+    pub fn add(&mut self, path: &str, note: bool) {
+        let lower = path.to_lowercase();
+        self.paths.insert(lower.clone(), path.to_string());
+        self.add_name(file_name(&lower), path);
+        if note {
+            let stem = lower.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&lower);
+            self.paths.entry(stem.to_string()).or_insert_with(|| path.to_string());
+            self.add_name(file_stem(&lower), path);
+        }
+    }
+
+    // 额外的名称，如 Obsidian 的 aliases
+    pub fn add_name(&mut self, name: &str, path: &str) {
+        let candidates = self.names.entry(name.trim().to_lowercase()).or_default();
+        if !candidates.iter().any(|candidate| candidate == path) {
+            candidates.push(path.to_string());
+        }
+    }
+
+    pub fn by_path(&self, path: &str) -> Option<&str> {
+        self.paths.get(&path.to_lowercase()).map(String::as_str)
+    }
+
+    /// 同名文件优先取 from_dir 中的，其次取路径最短的
+    pub fn by_name(&self, from_dir: &str, name: &str) -> Option<&str> {
+        let candidates = self.names.get(&name.trim().to_lowercase())?;
+        candidates
+            .iter()
+            .find(|candidate| parent_dir(candidate) == from_dir)
+            .or_else(|| candidates.iter().min_by_key(|candidate| (candidate.matches('/').count(), candidate.len())))
+            .map(String::as_str)
+    }
+}
+
+/// 本次导入的附件目录：附件目录下以 key 命名的子目录，链接中只记录 key 和文件名
+#[derive(Debug, Clone)]
+pub(crate) struct AttachmentsDir {
+    pub key: String,
+    pub path: PathBuf,
+}
+
+// 同名文件追加序号
+struct Attachments {
+    dir: AttachmentsDir,
+    copied: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl Attachments {
+    fn copy(&mut self, path: &str, bytes: &[u8]) -> Result<String> {
+        if let Some(url) = self.copied.get(path) {
+            return Ok(url.clone());
+        }
+        std::fs::create_dir_all(&self.dir.path)?;
+        let name = sanitize_filename(file_name(path));
+        let (stem, extension) = name.rsplit_once('.').unwrap_or((name.as_str(), ""));
+        let mut candidate = name.clone();
+        let mut counter = 1;
+        while !self.taken.insert(candidate.to_lowercase()) {
+            counter += 1;
+            candidate = if extension.is_empty() {
+                format!("{} ({})", stem, counter)
+            } else {
+                format!("{} ({}).{}", stem, counter, extension)
+            };
+        }
+        std::fs::write(self.dir.path.join(&candidate), bytes)?;
+        let url = links::attachment_url(&format!("{}/{}", self.dir.key, candidate));
+        self.copied.insert(path.to_string(), url.clone());
+        Ok(url)
+    }
+}
+
+/// 链接解析上下文：笔记路径到文档 id 的映射、可复制的附件和无法解析的链接
+pub(crate) struct Resolver<'a> {
+    pub index: FileIndex,
+    documents: HashMap<String, String>,
+    files: HashMap<&'a str, &'a [u8]>,
+    attachments: Attachments,
+    pub unresolved: Vec<UnresolvedLink>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(files: &'a [SourceFile], attachments: AttachmentsDir) -> Self {
+        Resolver {
+            index: FileIndex::default(),
+            documents: HashMap::new(),
+            files: files.iter().map(|file| (file.path.as_str(), file.bytes.as_slice())).collect(),
+            attachments: Attachments { dir: attachments, copied: HashMap::new(), taken: HashSet::new() },
+            unresolved: Vec::new(),
+        }
+    }
+
+    pub fn add_document(&mut self, path: &str, id: &str) {
+        self.documents.insert(path.to_string(), id.to_string());
+    }
+
+    pub fn unresolved(&mut self, from: &str, target: &str) -> Option<String> {
+        self.unresolved.push(UnresolvedLink { path: from.to_string(), target: target.to_string() });
+        None
+    }
+
+    /// 生成指向已解析文件 path 的链接：笔记改为文档链接，其他文件复制后链接到副本
+    pub fn link_to(&mut self, from: &str, path: &str, heading: Option<&str>, image: bool, text: &str, title: Option<&str>) -> Option<String> {
+        if let Some(id) = self.documents.get(path) {
+            let anchor = heading.map(heading_anchor);
+            return Some(markdown_link(false, text, &links::document_url(id, anchor.as_deref()), title));
+        }
+        let bytes = *self.files.get(path)?;
+        match self.attachments.copy(path, bytes) {
+            Ok(url) => Some(markdown_link(image && is_image(path), text, &url, title)),
+            Err(e) => self.unresolved(from, &format!("{} ({})", path, e)),
+        }
+    }
+
+    pub fn attachments_copied(&self) -> u32 {
+        self.attachments.copied.len() as u32
+    }
+}
+
+/// 解析完成、等待写入的导入结果
+pub(crate) struct Prepared {
+    pub documents: Vec<(String, Document)>,
+    pub failures: Vec<(String, String)>,
+    pub attachments_copied: u32,
+    pub unresolved: Vec<UnresolvedLink>,
+}
+
+// 检查来源、工作区和指定的项目；未指定项目时返回 None，等解析成功后再新建
+async fn check_target(database: &Database, options: &VaultImportOptions) -> Result<Option<Project>> {
+    if !Path::new(&options.source_path).exists() {
+        return Err(AppError::validation(format!("{} does not exist", options.source_path)).into());
+    }
+    if database.get_workspace_by_id(&options.workspace_id).await?.is_none() {
+        return Err(AppError::not_found("Workspace", &options.workspace_id).into());
+    }
+    match &options.project_id {
+        Some(project_id) => Ok(Some(
+            database
+                .get_project_by_id(project_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Project", project_id))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// 导入流程：检查目标，在后台线程中解析来源并复制附件，需要时新建项目，最后写入文档。
+/// 任一步失败都会删除本次复制的附件；解析失败时不会留下空项目
+pub(crate) async fn import(
+    database: &Database,
+    options: &VaultImportOptions,
+    prepare: fn(&Path, AttachmentsDir) -> Result<Prepared>,
+) -> Result<VaultImportReport> {
+    let existing = check_target(database, options).await?;
+    let key = uuid::Uuid::new_v4().to_string();
+    let attachments = AttachmentsDir { path: Database::attachments_directory()?.join(&key), key };
+    let attachments_path = attachments.path.clone();
+
+    let result = async {
+        let source = PathBuf::from(&options.source_path);
+        let prepared = tokio::task::spawn_blocking(move || prepare(&source, attachments))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+        let project = match existing {
+            Some(project) => project,
+            None => {
+                let source = Path::new(&options.source_path);
+                let name = folder_import::project_name(source, options.project_name.as_deref());
+                folder_import::create_project(database, &options.workspace_id, &name).await?
+            }
+        };
+        write(database, project.id, project.name, prepared).await
+    }
+    .await;
+
+    if result.is_err() && attachments_path.exists() {
+        let _ = std::fs::remove_dir_all(&attachments_path);
+    }
+    result
+}
+
+/// 在一个事务中写入全部文档并生成报告
+async fn write(database: &Database, project_id: String, project_name: String, prepared: Prepared) -> Result<VaultImportReport> {
+    let paths: Vec<String> = prepared.documents.iter().map(|(path, _)| path.clone()).collect();
+    // 链接改写后正文有变化，重新统计字数
+    let documents = prepared
+        .documents
+        .into_iter()
+        .map(|(_, mut document)| {
+            document.word_count = document.content.split_whitespace().count() as u32;
+            document.char_count = document.content.chars().count() as u32;
+            document
+        })
+        .collect();
+    let imported = database.import_documents(&project_id, &[], documents).await?;
+
+    let mut files: Vec<ImportFileResult> = paths
+        .into_iter()
+        .zip(imported)
+        .map(|(path, document)| ImportFileResult {
+            path,
+            title: document.title,
+            folder_path: document.folder_path,
+            content_type: document.content_type,
+            action: ImportAction::Create,
+            document_id: Some(document.id),
+            message: None,
+        })
+        .collect();
+    let created = files.len() as u32;
+    for (path, reason) in &prepared.failures {
+        files.push(ImportFileResult {
+            path: path.clone(),
+            title: file_stem(path).to_string(),
+            folder_path: Some(parent_dir(path).to_string()).filter(|dir| !dir.is_empty()),
+            content_type: Default::default(),
+            action: ImportAction::Failed,
+            document_id: None,
+            message: Some(reason.clone()),
+        });
+    }
+
+    Ok(VaultImportReport {
+        project_id,
+        project_name,
+        files,
+        created,
+        failed: prepared.failures.len() as u32,
+        attachments_copied: prepared.attachments_copied,
+        unresolved_links: prepared.unresolved,
+    })
+}
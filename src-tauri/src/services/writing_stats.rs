@@ -0,0 +1,138 @@
+// 写作统计服务
+// 记录时间统一使用 UTC，统计时按用户本地时区划分日、周、月
+
+use crate::models::writing::{StatsPeriod, WritingActivity, WritingStats, WritingStatsBucket, WritingStreak};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+
+// 两次保存间隔不超过该秒数时计为连续编辑时间，超过则视为中途离开
+pub const IDLE_THRESHOLD_SECONDS: i64 = 300;
+
+// 默认统计区间包含的桶数量
+const DEFAULT_BUCKETS: u32 = 30;
+
+/// 根据同一文档上一次保存的时间估算本次保存前的编辑时长
+pub fn active_seconds_since(last_saved: Option<DateTime<Utc>>, now: DateTime<Utc>) -> i64 {
+    match last_saved {
+        Some(last) => {
+            let gap = (now - last).num_seconds();
+            if (0..=IDLE_THRESHOLD_SECONDS).contains(&gap) {
+                gap
+            } else {
+                0
+            }
+        }
+        None => 0,
+    }
+}
+
+pub fn local_day(ts: DateTime<Utc>) -> NaiveDate {
+    ts.with_timezone(&Local).date_naive()
+}
+
+/// 本地日期零点对应的 UTC 时间
+pub fn local_day_start(day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// 日期所在区间的起始日，周从周一开始
+pub fn period_start(day: NaiveDate, period: StatsPeriod) -> NaiveDate {
+    match period {
+        StatsPeriod::Day => day,
+        StatsPeriod::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        StatsPeriod::Month => day.with_day(1).unwrap_or(day),
+    }
+}
+
+fn next_period(start: NaiveDate, period: StatsPeriod) -> NaiveDate {
+    match period {
+        StatsPeriod::Day => start + Duration::days(1),
+        StatsPeriod::Week => start + Duration::weeks(1),
+        StatsPeriod::Month => start + Months::new(1),
+    }
+}
+
+/// 未指定起始日期时，返回截止日期往前若干个区间的起点
+pub fn default_from(to: NaiveDate, period: StatsPeriod) -> NaiveDate {
+    let end = period_start(to, period);
+    match period {
+        StatsPeriod::Day => end - Duration::days(DEFAULT_BUCKETS as i64 - 1),
+        StatsPeriod::Week => end - Duration::weeks(DEFAULT_BUCKETS as i64 - 1),
+        StatsPeriod::Month => end - Months::new(DEFAULT_BUCKETS - 1),
+    }
+}
+
+/// 把 [from, to] 范围内的活动记录汇总到各个区间，没有记录的区间也会返回
+pub fn aggregate(activities: &[WritingActivity], period: StatsPeriod, from: NaiveDate, to: NaiveDate) -> WritingStats {
+    let mut buckets: BTreeMap<NaiveDate, WritingStatsBucket> = BTreeMap::new();
+    let mut start = period_start(from, period);
+    while start <= to {
+        buckets.insert(
+            start,
+            WritingStatsBucket { start, words_added: 0, words_deleted: 0, net_words: 0, active_seconds: 0 },
+        );
+        start = next_period(start, period);
+    }
+
+    for activity in activities {
+        let day = local_day(activity.recorded_at);
+        if day < from || day > to {
+            continue;
+        }
+        if let Some(bucket) = buckets.get_mut(&period_start(day, period)) {
+            if activity.words_delta > 0 {
+                bucket.words_added += activity.words_delta;
+            } else {
+                bucket.words_deleted -= activity.words_delta;
+            }
+            bucket.net_words += activity.words_delta;
+            bucket.active_seconds += activity.active_seconds;
+        }
+    }
+
+    let buckets: Vec<WritingStatsBucket> = buckets.into_values().collect();
+    WritingStats {
+        period,
+        from,
+        to,
+        words_added: buckets.iter().map(|b| b.words_added).sum(),
+        words_deleted: buckets.iter().map(|b| b.words_deleted).sum(),
+        net_words: buckets.iter().map(|b| b.net_words).sum(),
+        active_seconds: buckets.iter().map(|b| b.active_seconds).sum(),
+        buckets,
+    }
+}
+
+/// 计算连续写作天数。当天新增过字数即算作写作日，今天还没写时从昨天开始计算当前连续天数
+pub fn streak(activities: &[WritingActivity], today: NaiveDate) -> WritingStreak {
+    let days: BTreeSet<NaiveDate> = activities
+        .iter()
+        .filter(|a| a.words_delta > 0)
+        .map(|a| local_day(a.recorded_at))
+        .collect();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = match previous {
+            Some(p) if day - p == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let last_writing_day = days.iter().next_back().copied();
+    let current = match last_writing_day {
+        Some(last) if last >= today - Duration::days(1) => run,
+        _ => 0,
+    };
+
+    WritingStreak { current_days: current, longest_days: longest, last_writing_day }
+}
@@ -16,7 +16,6 @@ pub fn get_file_extension(filename: &str) -> Option<&str> {
     Path::new(filename).extension()?.to_str()
 }
 
-#[allow(dead_code)]
 pub fn sanitize_filename(name: &str) -> String {
     name.chars()
         .map(|c| match c {
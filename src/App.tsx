@@ -20,7 +20,7 @@ import { WorkspacePage } from "@/pages/WorkspacePage";
 import { useAppStore, Config, Workspace, SystemInfo } from "@/store/app";
 
 function App() {
-  const { initializeApp, setLoading, loadProjectData } = useAppStore();
+  const { initializeApp, setLoading, loadProjectData, updateConfig } = useAppStore();
 
   useEffect(() => {
     const init = async () => {
@@ -51,6 +51,20 @@ function App() {
     init();
   }, [initializeApp, setLoading]);
 
+  // 任意窗口保存配置后，后端会广播 config-changed 事件
+  useEffect(() => {
+    const isTauri = typeof window !== 'undefined' &&
+      (('__TAURI__' in (window as any)) || ('__TAURI_INTERNALS__' in (window as any)));
+    if (!isTauri) return;
+
+    let unlisten: (() => void) | undefined;
+    import('@tauri-apps/api/event').then(({ listen }) =>
+      listen<Config>('config-changed', (event) => updateConfig(event.payload))
+    ).then((fn) => { unlisten = fn; });
+
+    return () => unlisten?.();
+  }, [updateConfig]);
+
   return (
     <ThemeProvider defaultTheme="system" storageKey="writeflow-ui-theme">
       <div className="min-h-screen font-sans antialiased bg-background">
@@ -3,6 +3,7 @@ import Editor from '@monaco-editor/react';
 import ReactMarkdown from 'react-markdown';
 import remarkGfm from 'remark-gfm';
 import rehypeHighlight from 'rehype-highlight';
+import { markdownComponents, urlTransform } from './markdownLinks';
 import { cn } from '@/lib/utils';
 import { 
   Eye,
@@ -94,6 +95,8 @@ const MarkdownEditor = memo(forwardRef<MarkdownEditorHandle, MarkdownEditorProps
                   <ReactMarkdown
                     remarkPlugins={[remarkGfm]}
                     rehypePlugins={[rehypeHighlight]}
+                    urlTransform={urlTransform}
+                    components={markdownComponents}
                   >
                     {value || placeholder}
                   </ReactMarkdown>
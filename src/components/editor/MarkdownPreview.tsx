@@ -1,6 +1,7 @@
 import React from 'react';
 import ReactMarkdown from 'react-markdown';
 import remarkGfm from 'remark-gfm';
+import { markdownComponents, urlTransform } from './markdownLinks';
 
 interface MarkdownPreviewProps {
   content: string;
@@ -10,7 +11,7 @@ interface MarkdownPreviewProps {
 export function MarkdownPreview({ content, className = "" }: MarkdownPreviewProps) {
   return (
     <div className={`max-w-none prose prose-sm dark:prose-invert ${className}`}>
-      <ReactMarkdown remarkPlugins={[remarkGfm]}>
+      <ReactMarkdown remarkPlugins={[remarkGfm]} urlTransform={urlTransform} components={markdownComponents}>
         {content}
       </ReactMarkdown>
     </div>
@@ -0,0 +1,64 @@
+import { useEffect, useState, type ComponentProps, type MouseEvent } from 'react';
+import { defaultUrlTransform, type Components, type ExtraProps } from 'react-markdown';
+import { useNavigate } from 'react-router-dom';
+import { invoke } from '@/lib/invokeCompat';
+import { useToast } from '@/components/ui/use-toast';
+import { errorMessage } from '@/lib/utils';
+
+// 导入时改写的内部链接：writeflow://document/<id>[#标题] 指向文档，writeflow://attachment/<路径> 指向附件
+const DOCUMENT_LINK_PREFIX = 'writeflow://document/';
+const ATTACHMENT_LINK_PREFIX = 'writeflow://attachment/';
+
+// react-markdown 默认会清空未知协议的地址，内部链接需要保留
+export function urlTransform(url: string): string {
+  return url.startsWith('writeflow://') ? url : defaultUrlTransform(url);
+}
+
+function MarkdownLink({ href, children, node: _node, ...props }: ComponentProps<'a'> & ExtraProps) {
+  const navigate = useNavigate();
+  const { toast } = useToast();
+
+  if (!href?.startsWith(DOCUMENT_LINK_PREFIX)) {
+    return <a href={href} {...props}>{children}</a>;
+  }
+
+  const documentId = href.slice(DOCUMENT_LINK_PREFIX.length).split(/[#?]/)[0];
+  const open = async (event: MouseEvent<HTMLAnchorElement>) => {
+    event.preventDefault();
+    try {
+      const document = await invoke("get_document_by_id", { documentId }) as { project_id: string } | null;
+      if (!document) {
+        toast({ title: "文档不存在", description: "链接指向的文档已被删除", variant: "destructive" });
+        return;
+      }
+      navigate(`/editor/${document.project_id}/${documentId}`);
+    } catch (error) {
+      toast({ title: "打开文档失败", description: errorMessage(error), variant: "destructive" });
+    }
+  };
+
+  return <a href={href} onClick={open} {...props}>{children}</a>;
+}
+
+// 附件由后端读取后以 data URI 显示
+function MarkdownImage({ src, node: _node, ...props }: ComponentProps<'img'> & ExtraProps) {
+  const attachment = typeof src === 'string' && src.startsWith(ATTACHMENT_LINK_PREFIX) ? src : null;
+  const [dataUri, setDataUri] = useState<string>();
+
+  useEffect(() => {
+    setDataUri(undefined);
+    if (!attachment) return;
+    let cancelled = false;
+    invoke("read_attachment", { url: attachment })
+      .then((uri: string) => { if (!cancelled) setDataUri(uri); })
+      .catch((error) => console.error("Failed to load attachment:", error));
+    return () => { cancelled = true; };
+  }, [attachment]);
+
+  return <img src={attachment ? dataUri : src} {...props} />;
+}
+
+export const markdownComponents: Components = {
+  a: MarkdownLink,
+  img: MarkdownImage,
+};
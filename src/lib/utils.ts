@@ -41,4 +41,22 @@ export function debounce<T extends (...args: any[]) => void>(
 
 export function generateId(): string {
   return Math.random().toString(36).substring(2) + Date.now().toString(36);
-}
\ No newline at end of file
+}
+// 后端命令失败时 reject 的是 { code, message, details } 对象而不是 Error
+export interface AppErrorPayload {
+  code: string;
+  message: string;
+  details?: unknown;
+}
+
+export function isAppError(error: unknown): error is AppErrorPayload {
+  return typeof error === 'object' && error !== null && typeof (error as AppErrorPayload).code === 'string';
+}
+
+// 取出可以展示给用户的错误信息，兼容后端错误对象、Error 和字符串
+export function errorMessage(error: unknown, fallback = "未知错误"): string {
+  if (isAppError(error)) return error.message || error.code;
+  if (error instanceof Error) return error.message || fallback;
+  if (typeof error === 'string' && error) return error;
+  return fallback;
+}
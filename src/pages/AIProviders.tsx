@@ -6,7 +6,8 @@ import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogTrigger } from
 import { Badge } from "@/components/ui/badge";
 import { Label } from "@/components/ui/label";
 import { toast } from "@/hooks/use-toast";
-import { useAppStore, AIProvider } from "@/store/app";
+import { errorMessage } from "@/lib/utils";
+import { AIProvider, CreateAIProviderInput } from "@/store/app";
 import { Brain, Download, Search, Sparkles, Zap, Bot, Star, Globe } from "lucide-react";
 
 type AIProviderCatalog = {
@@ -16,7 +17,7 @@ type AIProviderCatalog = {
   description: string;
   isInstalled: boolean;
   icon?: React.ComponentType<{ className?: string }>;
-  template: AIProvider;
+  template: Omit<CreateAIProviderInput, 'api_key'>;
 };
 
 // 精选 AI 提供商目录
@@ -31,11 +32,15 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "OpenAI",
       provider_type: "openai",
-      api_base: "https://api.openai.com/v1",
-      model: "gpt-4",
+      model_name: "gpt-4",
+      base_url: "https://api.openai.com/v1",
+      icon: "",
+      bg_color: "",
       max_tokens: 4096,
+      context_length: 4096,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
   {
@@ -48,11 +53,15 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "Anthropic",
       provider_type: "anthropic",
-      api_base: "https://api.anthropic.com",
-      model: "claude-3-sonnet-20240229",
+      model_name: "claude-3-sonnet-20240229",
+      base_url: "https://api.anthropic.com",
+      icon: "",
+      bg_color: "",
       max_tokens: 4096,
+      context_length: 4096,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
   {
@@ -65,11 +74,15 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "DeepSeek",
       provider_type: "deepseek",
-      api_base: "https://api.deepseek.com/v1",
-      model: "deepseek-chat",
+      model_name: "deepseek-chat",
+      base_url: "https://api.deepseek.com/v1",
+      icon: "",
+      bg_color: "",
       max_tokens: 4096,
+      context_length: 4096,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
   {
@@ -82,11 +95,15 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "Kimi",
       provider_type: "kimi",
-      api_base: "https://api.moonshot.cn/v1",
-      model: "moonshot-v1-8k",
+      model_name: "moonshot-v1-8k",
+      base_url: "https://api.moonshot.cn/v1",
+      icon: "",
+      bg_color: "",
       max_tokens: 8000,
+      context_length: 8000,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
   {
@@ -99,11 +116,15 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "通义千问",
       provider_type: "qwen",
-      api_base: "https://dashscope.aliyuncs.com/api/v1",
-      model: "qwen-turbo",
+      model_name: "qwen-turbo",
+      base_url: "https://dashscope.aliyuncs.com/api/v1",
+      icon: "",
+      bg_color: "",
       max_tokens: 2000,
+      context_length: 2000,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
   {
@@ -116,18 +137,22 @@ const AI_CATALOG_PRESETS: AIProviderCatalog[] = [
     template: {
       name: "智谱 GLM",
       provider_type: "zhipu",
-      api_base: "https://open.bigmodel.cn/api/paas/v4",
-      model: "glm-4",
+      model_name: "glm-4",
+      base_url: "https://open.bigmodel.cn/api/paas/v4",
+      icon: "",
+      bg_color: "",
       max_tokens: 4096,
+      context_length: 4096,
       temperature: 0.7,
       enabled: true,
+      priority: 0,
     },
   },
 ];
 
 export default function AIProviders() {
-  const { config, saveConfig, testAIProvider } = useAppStore();
   const [catalog, setCatalog] = useState<AIProviderCatalog[]>(AI_CATALOG_PRESETS);
+  const [providers, setProviders] = useState<AIProvider[]>([]);
   const [keyword, setKeyword] = useState('');
   const [apiKeyDialog, setApiKeyDialog] = useState<{ open: boolean; providerId: string | null }>({
     open: false,
@@ -135,19 +160,29 @@ export default function AIProviders() {
   });
   const [apiKey, setApiKey] = useState('');
 
-  const providers = config?.ai_providers?.providers || {};
-  const providersList = Object.entries(providers);
+  const loadProviders = async () => {
+    try {
+      const { invoke } = await import("@tauri-apps/api/core");
+      setProviders(await invoke("list_ai_providers") as AIProvider[]);
+    } catch (error) {
+      console.error("Failed to load AI providers:", error);
+    }
+  };
+
+  useEffect(() => {
+    loadProviders();
+  }, []);
 
   // 从后端获取已安装列表，与本地目录合并状态
   useEffect(() => {
-    const installedNames = new Set(providersList.map(([_, provider]) => provider.name));
+    const installedTypes = new Set(providers.map((provider) => provider.provider_type));
     setCatalog((prev) =>
       prev.map((item) => ({
         ...item,
-        isInstalled: installedNames.has(item.template.name),
+        isInstalled: installedTypes.has(item.template.provider_type),
       }))
     );
-  }, [config]);
+  }, [providers]);
 
   const filtered = useMemo(
     () =>
@@ -167,28 +202,18 @@ export default function AIProviders() {
     if (!providerId || !apiKey.trim()) return;
 
     const target = catalog.find((item) => item.id === providerId);
-    if (!target || !config) return;
+    if (!target) return;
 
     try {
-      const providerInstanceId = `${target.id}_${Date.now()}`;
-      const newProvider = { 
+      const { invoke } = await import("@tauri-apps/api/core");
+      const input: CreateAIProviderInput = {
         ...target.template,
         api_key: apiKey.trim(),
+        priority: providers.length,
       };
 
-      const updatedConfig = {
-        ...config,
-        ai_providers: {
-          ...config.ai_providers,
-          providers: {
-            ...config.ai_providers.providers,
-            [providerInstanceId]: newProvider,
-          },
-        },
-      };
-
-      await saveConfig(updatedConfig);
-      setCatalog((prev) => prev.map((item) => (item.id === providerId ? { ...item, isInstalled: true } : item)));
+      await invoke("create_ai_provider", { input });
+      await loadProviders();
       
       toast({
         title: "安装成功",
@@ -200,7 +225,7 @@ export default function AIProviders() {
     } catch (error) {
       toast({
         title: "安装失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -208,30 +233,16 @@ export default function AIProviders() {
 
   const uninstall = async (id: string) => {
     const target = catalog.find((item) => item.id === id);
-    if (!target || !config) return;
+    if (!target) return;
 
     try {
-      // 查找并删除匹配的提供商
-      const matchingProviders = providersList.filter(([_, provider]) => provider.name === target.template.name);
-      const updatedProviders = { ...config.ai_providers.providers };
-      
-      matchingProviders.forEach(([providerId]) => {
-        delete updatedProviders[providerId];
-      });
-
-      const updatedConfig = {
-        ...config,
-        ai_providers: {
-          ...config.ai_providers,
-          providers: updatedProviders,
-          default_provider: config.ai_providers.default_provider && 
-                           matchingProviders.some(([pid]) => pid === config.ai_providers.default_provider)
-                           ? null : config.ai_providers.default_provider,
-        },
-      };
-
-      await saveConfig(updatedConfig);
-      setCatalog((prev) => prev.map((item) => (item.id === id ? { ...item, isInstalled: false } : item)));
+      const { invoke } = await import("@tauri-apps/api/core");
+      // 查找并删除匹配的提供商，默认提供商由后端同步清除
+      const matchingProviders = providers.filter((provider) => provider.provider_type === target.template.provider_type);
+      for (const provider of matchingProviders) {
+        await invoke("delete_ai_provider", { id: provider.id });
+      }
+      await loadProviders();
       
       toast({
         title: "已卸载",
@@ -240,7 +251,7 @@ export default function AIProviders() {
     } catch (error) {
       toast({
         title: "卸载失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -344,7 +355,7 @@ export default function AIProviders() {
                 autoFocus
               />
               <p className="text-sm text-muted-foreground">
-                API Key 将安全存储在本地配置中
+                API Key 将加密存储在本机
               </p>
             </div>
 
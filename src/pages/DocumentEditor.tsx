@@ -8,6 +8,7 @@ import ImportExportDialog from "@/components/document/ImportExportDialog";
 import { useToast } from "@/components/ui/use-toast";
 import { Document, CreateDocumentData, createDefaultDocumentData } from "@/types/document";
 import { ProjectIcon } from "@/components/ui/project-icon";
+import { errorMessage } from "@/lib/utils";
 
 // 自定义 debounce 工具函数
 function debounce<T extends (...args: any[]) => any>(
@@ -127,7 +128,7 @@ export default function DocumentEditor() {
         // 显示错误提示
         toast({ 
           title: "保存失败", 
-          description: `文档保存时出现错误: ${error}`, 
+          description: `文档保存时出现错误: ${errorMessage(error)}`, 
           variant: "destructive" 
         });
       } finally {
@@ -152,8 +153,8 @@ export default function DocumentEditor() {
         
         setDocuments(projectDocuments);
         
-        // 只在初始加载时自动选择文档
-        if (projectDocuments.length > 0 && !selectedDocument) {
+        // 初始加载时自动选择文档；通过文档链接跳转时切换到链接指向的文档
+        if (projectDocuments.length > 0 && (!selectedDocument || (docId && selectedDocument.id !== docId))) {
           const target = (docId && projectDocuments.find(d => d.id === docId)) || projectDocuments[0];
           if (target) {
             handleDocumentSelect(target);
@@ -171,14 +172,17 @@ export default function DocumentEditor() {
   }, [projectId, docId]); // 保持简洁的依赖列表
 
   // 文档选择处理
-  const handleDocumentSelect = (document: Document) => {
+  const handleDocumentSelect = async (document: Document) => {
     // 如果有未保存的修改，先保存
     if (isModified && selectedDocument) {
       debouncedSave(selectedDocument.id, content, selectedDocument.title);
     }
     
-    setSelectedDocument(document);
-    setContent(document.content);
+    // 列表只包含文档摘要，正文需要单独加载
+    const full = await invoke("get_document_by_id", { documentId: document.id }) as Document | null;
+    const target = full ?? document;
+    setSelectedDocument(target);
+    setContent(target.content ?? "");
     setIsModified(false);
   };
 
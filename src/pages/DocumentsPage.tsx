@@ -248,7 +248,7 @@ export default function DocumentsPage() {
   };
 
   // 文档选择处理
-  const handleDocumentSelect = (document: Document) => {
+  const handleDocumentSelect = async (document: Document) => {
     if (document === selectedDocument) return;
 
     // 如果有未保存的修改，先保存
@@ -256,8 +256,11 @@ export default function DocumentsPage() {
       debouncedSave(selectedDocument.id, content);
     }
 
-    setSelectedDocument(document);
-    setContent(document.content);
+    // 列表只包含文档摘要，正文需要单独加载
+    const full = await invoke("get_document_by_id", { documentId: document.id }) as Document | null;
+    const target = full ?? document;
+    setSelectedDocument(target);
+    setContent(target.content ?? "");
     setIsModified(false);
 
     // 更新URL参数
@@ -4,6 +4,7 @@ import { Input } from "@/components/ui/input";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
 import { toast } from "@/hooks/use-toast";
+import { errorMessage } from "@/lib/utils";
 import { useAppStore, MCPServer } from "@/store/app";
 import { Server, Download, Search, Database, FileText, GitBranch, Globe, Code2, Terminal } from "lucide-react";
 
@@ -166,7 +167,7 @@ export default function MCPServers() {
     } catch (error) {
       toast({
         title: "安装失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -203,7 +204,7 @@ export default function MCPServers() {
     } catch (error) {
       toast({
         title: "卸载失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
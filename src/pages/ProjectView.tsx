@@ -166,10 +166,11 @@ export default function ProjectView() {
                   <DocumentTextIcon className="w-5 h-5 text-muted-foreground" />
                   <div className="flex-1 min-w-0">
                     <div className="mb-1 text-sm font-medium">{document.title}</div>
-                    <div className="mb-2 text-xs text-muted-foreground line-clamp-2">
-                      {document.content.substring(0, 100)}
-                      {document.content.length > 100 ? '...' : ''}
-                    </div>
+                    {(document.folder_path || document.tags?.length > 0) && (
+                      <div className="mb-2 text-xs text-muted-foreground line-clamp-1">
+                        {[document.folder_path, ...(document.tags ?? [])].filter(Boolean).join(' · ')}
+                      </div>
+                    )}
                     <div className="flex gap-4 items-center text-xs text-muted-foreground">
                       <span>{document.word_count} 字</span>
                       <span className="px-2 py-1 rounded-full bg-muted">
@@ -9,6 +9,7 @@ import { Switch } from "@/components/ui/switch";
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select";
 import { Separator } from "@/components/ui/separator";
 import { useToast } from "@/hooks/use-toast";
+import { errorMessage } from "@/lib/utils";
 import { Download, Upload, RotateCcw } from "lucide-react";
 
 export default function Settings() {
@@ -95,7 +96,7 @@ export default function Settings() {
     } catch (error) {
       toast({
         title: "导出失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     } finally {
@@ -124,7 +125,7 @@ export default function Settings() {
     } catch (error) {
       toast({
         title: "导入失败", 
-        description: error instanceof Error ? error.message : "请检查文件格式是否正确",
+        description: errorMessage(error, "请检查文件格式是否正确"),
         variant: "destructive",
       });
     } finally {
@@ -152,7 +153,7 @@ export default function Settings() {
     } catch (error) {
       toast({
         title: "重置失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     } finally {
@@ -13,6 +13,7 @@ import { useAppStore, WritingScenario } from "@/store/app";
 import { Save, RefreshCw, Settings, Eye, Zap, Globe, Brain, Plus, Edit2, Trash2 } from "lucide-react";
 import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogTrigger } from "@/components/ui/dialog";
 import { Textarea } from "@/components/ui/textarea";
+import { errorMessage } from "@/lib/utils";
 
 // 场景预设
 const SCENARIO_PRESETS = {
@@ -109,7 +110,7 @@ export default function WritingPreferences() {
     } catch (error) {
       toast({
         title: "保存失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     } finally {
@@ -133,7 +134,7 @@ export default function WritingPreferences() {
     saveConfig(updatedConfig).catch(error => {
       toast({
         title: "更新失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     });
@@ -174,7 +175,7 @@ export default function WritingPreferences() {
     } catch (error) {
       toast({
         title: "添加失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -218,7 +219,7 @@ export default function WritingPreferences() {
     } catch (error) {
       toast({
         title: "操作失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -248,7 +249,7 @@ export default function WritingPreferences() {
     } catch (error) {
       toast({
         title: "删除失败",
-        description: error instanceof Error ? error.message : "未知错误",
+        description: errorMessage(error),
         variant: "destructive",
       });
     }
@@ -49,7 +49,6 @@ export interface Config {
     custom_commands: Record<string, any>;
   };
   ai_providers: {
-    providers: Record<string, AIProvider>;
     default_provider: string | null;
   };
   mcp_servers: {
@@ -88,16 +87,28 @@ export interface SystemInfo {
 }
 
 export interface AIProvider {
+  id: string;
   name: string;
   provider_type: string;
-  api_key?: string;
-  api_base?: string;
-  model?: string;
-  max_tokens?: number;
-  temperature?: number;
+  model_name: string;
+  api_key: string;
+  base_url?: string | null;
+  icon: string;
+  bg_color: string;
+  status: string;
+  status_text: string;
+  max_tokens: number;
+  context_length: number;
+  temperature?: number | null;
   enabled: boolean;
+  last_tested: string;
+  description?: string | null;
+  priority: number;
+  model_pointer?: string | null;
 }
 
+export type CreateAIProviderInput = Omit<AIProvider, 'id' | 'status' | 'status_text' | 'last_tested'>;
+
 export interface MCPServer {
   name: string;
   connection_type: 'Stdio' | 'SSE';
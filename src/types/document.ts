@@ -19,6 +19,7 @@ export interface DocumentMetadata {
   reading_time: number;
   export_formats: string[];
   version: number;
+  properties?: Record<string, string>;
 }
 
 export interface Document {
@@ -46,6 +47,7 @@ export interface CreateDocumentData {
   folder_path?: string;
   tags?: string[];
   template_id?: string;
+  template_variables?: Record<string, string>;
 }
 
 export interface DocumentStats {